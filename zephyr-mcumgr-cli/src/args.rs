@@ -1,8 +1,8 @@
-use clap::{Args, Parser};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, parser::ValueSource};
 
-use crate::groups::Group;
+use crate::{groups::Group, smp_trace::TraceLevel};
 
-#[derive(Debug, Args)]
+#[derive(Debug, Clone, Args)]
 pub struct CommonArgs {
     /// Hide progress bar for data transfer commands
     #[arg(short, long)]
@@ -12,9 +12,13 @@ pub struct CommonArgs {
     #[arg(short, long)]
     pub verbose: bool,
 
-    /// Print command results as JSON, if possible
-    #[arg(long)]
+    /// Print command results, transfer summaries and errors as JSON
+    #[arg(long, global = true)]
     pub json: bool,
+
+    /// The device the command runs on, if it runs on multiple devices
+    #[arg(skip)]
+    pub device: Option<String>,
 }
 
 /// Command line client for Zephyr's MCUmgr SMP protocol
@@ -23,31 +27,117 @@ pub struct CommonArgs {
 #[command(disable_help_subcommand = true)]
 pub struct App {
     /// Use the given serial port as backend
-    #[arg(short, long)]
-    pub serial: Option<String>,
+    ///
+    /// Can be given multiple times to run the command on multiple devices in parallel.
+    #[arg(short, long, env = "MCUMGR_SERIAL")]
+    pub serial: Vec<String>,
 
     /// Use the given usb serial port as backend
     ///
     /// Must contain a regex that matches `vid:pid` or `vid:pid:iface`.
     /// If no argument provided, list all available ports and exit.
-    #[arg(short, long, verbatim_doc_comment, num_args = 0..=1, default_missing_value = "")]
-    pub usb_serial: Option<String>,
+    /// Can be given multiple times to run the command on multiple devices in parallel.
+    #[arg(
+        short,
+        long,
+        env = "MCUMGR_USB_SERIAL",
+        verbatim_doc_comment,
+        num_args = 0..=1,
+        default_missing_value = ""
+    )]
+    pub usb_serial: Vec<String>,
 
-    /// Serial port baud rate
-    #[arg(short, long, default_value_t = 115200)]
-    pub baud: u32,
+    /// Use every USB serial port that matches `--usb-serial`, instead of requiring a unique match
+    #[arg(long)]
+    pub all_matching: bool,
+
+    /// Serial port baud rate [default: 115200]
+    #[arg(short, long, env = "MCUMGR_BAUD")]
+    pub baud: Option<u32>,
 
-    /// Communication timeout (in ms)
-    #[arg(short, long, default_value_t = 2000)]
-    pub timeout: u64,
+    /// Communication timeout (in ms) [default: 2000]
+    ///
+    /// Can also be given after the subcommand, for commands that need more time,
+    /// like erasing large flash areas.
+    #[arg(short, long, global = true, env = "MCUMGR_TIMEOUT")]
+    pub timeout: Option<u64>,
+
+    /// Minimum time (in ms) between two writes to the serial port [default: 0]
+    ///
+    /// For UART bridges that lose bytes when frames are written back to back.
+    #[arg(long, global = true, env = "MCUMGR_FRAME_DELAY")]
+    pub frame_delay: Option<u64>,
+
+    /// Maximum number of bytes per write to the serial port
+    ///
+    /// Longer serial packets are split into multiple writes, separated by `--frame-delay`.
+    #[arg(long, global = true, env = "MCUMGR_WRITE_BURST")]
+    pub write_burst: Option<usize>,
+
+    /// How often requests get retransmitted if their response times out [default: 0]
+    ///
+    /// Upload chunks are only retransmitted if they are sent one at a time, which is the case
+    /// if the device reports at most two SMP buffers. Otherwise, multiple chunks are kept
+    /// in flight, which are never retransmitted.
+    #[arg(long, global = true, env = "MCUMGR_RETRIES")]
+    pub retries: Option<u32>,
+
+    /// Use the connection settings of the given profile from the configuration file
+    ///
+    /// If omitted, the `default_profile` of the configuration file is used.
+    /// Command line arguments take precedence over profile settings.
+    #[arg(short, long, env = "MCUMGR_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Capture all SMP frames into the given pcapng file
+    #[arg(long, value_name = "FILE")]
+    pub capture: Option<String>,
+
+    /// Append all SMP frames to the given text file, one timestamped frame per line
+    #[arg(long, value_name = "FILE")]
+    pub frame_log: Option<String>,
+
+    /// Print every SMP frame to stderr
+    ///
+    /// `frames` prints the decoded header and the hex payload of each frame,
+    /// `serial` additionally prints the raw serial chunks before base64 encoding.
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        verbatim_doc_comment,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "frames"
+    )]
+    pub trace_smp: Option<TraceLevel>,
 
     /// Settings that customize runtime behaviour
     #[command(flatten)]
     pub common: CommonArgs,
 
     /// Command group
-    ///
-    /// If missing, run a connection test
     #[command(subcommand)]
     pub group: Option<Group>,
 }
+
+impl App {
+    /// Parses the command line arguments and environment variables.
+    ///
+    /// A connection given on the command line replaces the one given via environment variable,
+    /// even if it is of a different type.
+    pub fn parse_with_env() -> Self {
+        let matches = Self::command().get_matches();
+        let mut app = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+        let from_env = |id| matches.value_source(id) == Some(ValueSource::EnvVariable);
+        let (serial_from_env, usb_serial_from_env) = (from_env("serial"), from_env("usb_serial"));
+        if serial_from_env && !usb_serial_from_env && !app.usb_serial.is_empty() {
+            app.serial.clear();
+        }
+        if usb_serial_from_env && !serial_from_env && !app.serial.is_empty() {
+            app.usb_serial.clear();
+        }
+
+        app
+    }
+}
@@ -0,0 +1,72 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::errors::CliError;
+
+/// The content of the configuration file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    /// The profile to use if none is given on the command line
+    default_profile: Option<String>,
+    /// The named device profiles
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+/// Connection settings of a device.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub serial: Option<String>,
+    pub usb_serial: Option<String>,
+    pub baud: Option<u32>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u32>,
+    /// The SMP frame size; detected automatically if omitted
+    pub mtu: Option<usize>,
+    /// The minimum time between two serial port writes, in ms
+    pub frame_delay: Option<u64>,
+    /// The maximum number of bytes per serial port write
+    pub write_burst: Option<usize>,
+}
+
+/// `~/.config/zephyr-mcumgr/config.toml` on Linux, the platform's config directory elsewhere.
+fn config_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("zephyr-mcumgr").join("config.toml"))
+}
+
+/// Loads the given profile, or the default profile if `name` is `None`.
+///
+/// Returns an empty profile if no profile is selected.
+pub fn load_profile(name: Option<&str>) -> Result<Profile, CliError> {
+    let path = config_file_path();
+
+    let config = match &path {
+        Some(path) if path.exists() => {
+            let content = std::fs::read_to_string(path).map_err(CliError::ConfigReadFailed)?;
+            toml::from_str::<Config>(&content).map_err(|e| CliError::ConfigParseFailed {
+                path: path.clone(),
+                source: e,
+            })?
+        }
+        _ => Config::default(),
+    };
+
+    let Some(name) = name.or(config.default_profile.as_deref()) else {
+        return Ok(Profile::default());
+    };
+
+    config
+        .profiles
+        .get(name)
+        .cloned()
+        .ok_or_else(|| CliError::ProfileNotFound {
+            name: name.to_string(),
+            config_file: path.map_or_else(
+                || "<no config directory>".to_string(),
+                |path| path.display().to_string(),
+            ),
+        })
+}
@@ -0,0 +1,157 @@
+use std::time::{Duration, Instant};
+
+use zephyr_mcumgr::{
+    MCUmgrClient,
+    client::{UsbSerialError, UsbSerialPorts},
+    transport::serial::SerialPacing,
+};
+
+use crate::{
+    errors::CliError,
+    formatting::{json_error, print_table, structured_print},
+};
+
+/// A device selected on the command line.
+#[derive(Debug)]
+pub enum Device {
+    /// A serial port, given by its name
+    Serial(String),
+    /// A USB serial port, given by its identifier regex
+    UsbSerial(String),
+}
+
+impl Device {
+    pub fn label(&self) -> &str {
+        match self {
+            Device::Serial(name) | Device::UsbSerial(name) => name,
+        }
+    }
+
+    pub fn connect(
+        &self,
+        baud: u32,
+        timeout: Duration,
+        pacing: SerialPacing,
+    ) -> Result<MCUmgrClient, CliError> {
+        let builder = MCUmgrClient::builder()
+            .timeout(timeout)
+            .serial_pacing(pacing);
+
+        match self {
+            Device::Serial(name) => {
+                let serial = serialport::new(name, baud)
+                    .timeout(timeout)
+                    .open()
+                    .map_err(CliError::OpenSerialFailed)?;
+                Ok(builder.build_from_serial(serial)?)
+            }
+            Device::UsbSerial(identifier) => Ok(builder.build_from_usb_serial(identifier, baud)?),
+        }
+    }
+}
+
+/// Collects the devices selected by `--serial` and `--usb-serial`.
+///
+/// With `all_matching`, every USB serial port that matches an identifier becomes a device.
+pub fn resolve(
+    serial: Vec<String>,
+    usb_serial: Vec<String>,
+    all_matching: bool,
+) -> Result<Vec<Device>, CliError> {
+    let mut devices = serial.into_iter().map(Device::Serial).collect::<Vec<_>>();
+
+    if all_matching {
+        let ports = UsbSerialPorts::available().map_err(CliError::ListPortsFailed)?;
+        for identifier in usb_serial {
+            let matches = ports.matching(&identifier).map_err(UsbSerialError::from)?;
+            if matches.0.is_empty() {
+                return Err(UsbSerialError::NoMatchingPort {
+                    identifier,
+                    available: ports,
+                }
+                .into());
+            }
+            devices.extend(
+                matches
+                    .0
+                    .into_iter()
+                    .map(|port| Device::Serial(port.port_name)),
+            );
+        }
+    } else {
+        devices.extend(usb_serial.into_iter().map(Device::UsbSerial));
+    }
+
+    Ok(devices)
+}
+
+/// Runs `action` on all devices in parallel, then prints the errors and a summary.
+pub fn run_parallel(
+    devices: &[Device],
+    json: bool,
+    action: impl Fn(&Device) -> Result<(), CliError> + Sync,
+) -> Result<(), CliError> {
+    let results = std::thread::scope(|scope| {
+        let handles = devices
+            .iter()
+            .map(|device| {
+                scope.spawn(|| {
+                    let start = Instant::now();
+                    let result = action(device);
+                    (start.elapsed(), result)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+
+    if json {
+        structured_print(None, true, |s| {
+            for (device, (elapsed, result)) in devices.iter().zip(&results) {
+                s.sublist(device.label(), |s| {
+                    s.key_value("success", result.is_ok());
+                    s.key_value("seconds", elapsed.as_secs_f64());
+                    s.key_value_maybe("error", result.as_ref().err().map(|e| json_error(e)));
+                });
+            }
+        })?;
+    } else {
+        let mut rows = vec![];
+        for (device, (elapsed, result)) in devices.iter().zip(results) {
+            let status = match result {
+                Ok(()) => "OK".to_string(),
+                Err(e) => {
+                    let status = format!("FAILED: {e}");
+                    eprintln!("{}:", device.label());
+                    eprintln!("{:?}", miette::Report::new(e));
+                    status
+                }
+            };
+            rows.push([
+                device.label().to_string(),
+                status,
+                format!("{:.1}s", elapsed.as_secs_f64()),
+            ]);
+        }
+        println!();
+        print_table(["DEVICE", "RESULT", "TIME"], &rows);
+    }
+
+    if failed > 0 {
+        return Err(CliError::DevicesFailed {
+            failed,
+            total: devices.len(),
+        });
+    }
+    Ok(())
+}
@@ -3,9 +3,13 @@ use thiserror::Error;
 
 use zephyr_mcumgr::{
     Errno,
-    client::{FileDownloadError, FileUploadError, ImageUploadError, UsbSerialError},
+    checksum::ChecksumError,
+    client::{
+        ClientBuildError, FileDownloadError, FileUploadError, FirmwareUpdateError,
+        ImageUploadError, UsbSerialError,
+    },
     connection::ExecuteError,
-    mcuboot::ImageParseError,
+    mcuboot::{IhexError, ImageParseError, ImageVerifyError, TrailerParseError},
 };
 
 /// Possible CLI errors.
@@ -14,46 +18,167 @@ pub enum CliError {
     #[error("Failed to open serial port")]
     #[diagnostic(code(zephyr_mcumgr::cli::open_serial_failed))]
     OpenSerialFailed(#[source] serialport::Error),
+    #[error("Failed to list serial ports")]
+    #[diagnostic(code(zephyr_mcumgr::cli::list_ports))]
+    ListPortsFailed(#[source] serialport::Error),
+    #[error("Failed to read the configuration file")]
+    #[diagnostic(code(zephyr_mcumgr::cli::config_read))]
+    ConfigReadFailed(#[source] std::io::Error),
+    #[error("Failed to parse the configuration file '{}'", .path.display())]
+    #[diagnostic(code(zephyr_mcumgr::cli::config_parse))]
+    ConfigParseFailed {
+        path: std::path::PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("Profile '{name}' not found")]
+    #[diagnostic(
+        code(zephyr_mcumgr::cli::profile_not_found),
+        help("Profiles are defined in `{config_file}`.")
+    )]
+    ProfileNotFound { name: String, config_file: String },
+    #[error("No command given")]
+    #[diagnostic(
+        code(zephyr_mcumgr::cli::missing_command),
+        help("Run `zephyr-mcumgr echo` to test the connection, or `--help` to list all commands.")
+    )]
+    MissingCommand,
+    #[error("Slot {slot} of image {image} does not accept uploads")]
+    #[diagnostic(
+        code(zephyr_mcumgr::cli::slot_not_uploadable),
+        help(
+            "See `image slot-info` for the available slots. Uploading to a specific slot requires `CONFIG_MCUMGR_GRP_IMG_DIRECT_UPLOAD` on the device."
+        )
+    )]
+    SlotNotUploadable { image: u32, slot: u32 },
     #[error("No backend selected")]
     #[diagnostic(code(zephyr_mcumgr::cli::no_backend))]
     NoBackendSelected,
+    #[error("Command failed on {failed} of {total} devices")]
+    #[diagnostic(code(zephyr_mcumgr::cli::devices_failed))]
+    DevicesFailed { failed: usize, total: usize },
     // #[error("Setting the timeout failed")]
     // #[diagnostic(code(zephyr_mcumgr::cli::set_timeout_failed))]
     // SetTimeoutFailed(#[source] Box<dyn miette::Diagnostic + Send + Sync + 'static>),
     #[error("Command execution failed")]
-    #[diagnostic(code(zephyr_mcumgr::cli::execution_failed))]
+    #[diagnostic(code(zephyr_mcumgr::cli::execution_failed), forward(0))]
     CommandExecutionFailed(#[from] ExecuteError),
     #[error("Json encode failed")]
     #[diagnostic(code(zephyr_mcumgr::cli::json_encode))]
     JsonEncodeError(#[source] serde_json::Error),
-    #[error("Shell command returned error exit code: {}", Errno::errno_to_string(*.0))]
+    #[error("Failed to convert the CBOR response: {0}")]
+    #[diagnostic(code(zephyr_mcumgr::cli::cbor_conversion))]
+    CborConversionFailed(String),
+    #[error("Shell command returned error exit code: {}", exit_code_to_string(*.0))]
     #[diagnostic(code(zephyr_mcumgr::cli::shell_exit_code))]
     ShellExitCode(i32),
+    #[error("Invalid shell script syntax in line {line}: {reason}")]
+    #[diagnostic(code(zephyr_mcumgr::cli::shell_script_syntax))]
+    ShellScriptSyntax { line: usize, reason: &'static str },
+    #[error("Failed to read from the terminal")]
+    #[diagnostic(code(zephyr_mcumgr::cli::readline))]
+    ReadlineFailed(#[from] rustyline::error::ReadlineError),
     #[error("Failed to read the input data")]
     #[diagnostic(code(zephyr_mcumgr::cli::input))]
     InputReadFailed(#[source] std::io::Error),
     #[error("Failed to write the output data")]
     #[diagnostic(code(zephyr_mcumgr::cli::output))]
     OutputWriteFailed(#[source] std::io::Error),
+    #[error("Failed to create the capture file")]
+    #[diagnostic(code(zephyr_mcumgr::cli::capture))]
+    CaptureFileCreateFailed(#[source] std::io::Error),
+    #[error("Failed to open the frame log file")]
+    #[diagnostic(code(zephyr_mcumgr::cli::frame_log))]
+    FrameLogOpenFailed(#[source] std::io::Error),
+    #[error("Capturing is only supported for a single device")]
+    #[diagnostic(code(zephyr_mcumgr::cli::capture_multiple_devices))]
+    CaptureMultipleDevices,
     #[error("Unable to determine output file name")]
     #[diagnostic(code(zephyr_mcumgr::cli::destination_unknown))]
     DestinationFilenameUnknown,
+    #[error("Resuming a download to stdout is not supported")]
+    #[diagnostic(code(zephyr_mcumgr::cli::resume_to_stdout))]
+    ResumeToStdoutUnsupported,
+    #[error("Downloaded file does not match the file on the device")]
+    #[diagnostic(
+        code(zephyr_mcumgr::cli::download_verify),
+        help(
+            "The local file was probably modified since the interrupted download. Download it again without `--resume`."
+        )
+    )]
+    DownloadVerificationFailed,
+    #[error("Unable to verify the resumed download: {0}")]
+    #[diagnostic(
+        code(zephyr_mcumgr::cli::download_verify_unavailable),
+        help(
+            "The device needs `CONFIG_MCUMGR_GRP_FS_CHECKSUM_HASH` for verifying resumed downloads. Download the file again without `--resume`."
+        )
+    )]
+    DownloadVerificationUnavailable(String),
+    #[error("Invalid interval: {0}")]
+    #[diagnostic(code(zephyr_mcumgr::cli::interval))]
+    InvalidInterval(String),
+    #[error("Invalid setting value: {0}")]
+    #[diagnostic(code(zephyr_mcumgr::cli::setting_value))]
+    SettingValueInvalid(String),
+    #[error("Setting value does not match the requested format: {0}")]
+    #[diagnostic(
+        code(zephyr_mcumgr::cli::setting_format),
+        help("Read the setting with `--hex` to see its raw data.")
+    )]
+    SettingFormatMismatch(&'static str),
+    #[error("Local file does not match '{0}' on the device")]
+    #[diagnostic(code(zephyr_mcumgr::cli::file_mismatch))]
+    FileMismatch(String),
+    #[error("Checksum type '{0}' is not supported locally")]
+    #[diagnostic(
+        code(zephyr_mcumgr::cli::unsupported_checksum),
+        help("Select a different algorithm, see `fs supported-checksums`.")
+    )]
+    UnsupportedChecksumType(String),
     #[error("File upload failed")]
-    #[diagnostic(code(zephyr_mcumgr::cli::file_upload))]
+    #[diagnostic(code(zephyr_mcumgr::cli::file_upload), forward(0))]
     FileUploadFailed(#[from] FileUploadError),
     #[error("File download failed")]
-    #[diagnostic(code(zephyr_mcumgr::cli::file_download))]
+    #[diagnostic(code(zephyr_mcumgr::cli::file_download), forward(0))]
     FileDownloadFailed(#[from] FileDownloadError),
     #[error("Image upload failed")]
-    #[diagnostic(code(zephyr_mcumgr::cli::image_upload))]
+    #[diagnostic(code(zephyr_mcumgr::cli::image_upload), forward(0))]
     ImageUploadFailed(#[from] ImageUploadError),
     #[error("Failed to parse datetime string")]
     #[diagnostic(code(zephyr_mcumgr::cli::chrono_parse))]
     ChronoParseFailed(#[from] chrono::ParseError),
     #[error("Failed to open USB serial port")]
-    #[diagnostic(code(zephyr_mcumgr::cli::usb_serial))]
+    #[diagnostic(code(zephyr_mcumgr::cli::usb_serial), forward(0))]
     UsbSerialOpenFailed(#[from] UsbSerialError),
+    #[error("Failed to connect to the device")]
+    #[diagnostic(code(zephyr_mcumgr::cli::connect), forward(0))]
+    ConnectFailed(#[from] ClientBuildError),
     #[error("Failed to parse MCUboot image")]
-    #[diagnostic(code(zephyr_mcumgr::cli::image_parse))]
+    #[diagnostic(code(zephyr_mcumgr::cli::image_parse), forward(0))]
     ImageParseFailed(#[from] ImageParseError),
+    #[error("Failed to verify MCUboot image signature")]
+    #[diagnostic(code(zephyr_mcumgr::cli::image_verify), forward(0))]
+    ImageVerifyFailed(#[from] ImageVerifyError),
+    #[error("Failed to convert Intel HEX file")]
+    #[diagnostic(code(zephyr_mcumgr::cli::ihex), forward(0))]
+    IhexConversionFailed(#[from] IhexError),
+    #[error("Failed to parse MCUboot trailer")]
+    #[diagnostic(code(zephyr_mcumgr::cli::trailer_parse), forward(0))]
+    TrailerParseFailed(#[from] TrailerParseError),
+    #[error("Firmware update failed")]
+    #[diagnostic(code(zephyr_mcumgr::cli::firmware_update), forward(0))]
+    FirmwareUpdateFailed(#[from] FirmwareUpdateError),
+    #[error("Checksum computation failed")]
+    #[diagnostic(code(zephyr_mcumgr::cli::checksum), forward(0))]
+    ChecksumFailed(#[from] ChecksumError),
+}
+
+/// Negative exit codes of shell commands are errno values, positive ones are command specific.
+fn exit_code_to_string(exit_code: i32) -> String {
+    if exit_code < 0 {
+        Errno::errno_to_string(exit_code)
+    } else {
+        exit_code.to_string()
+    }
 }
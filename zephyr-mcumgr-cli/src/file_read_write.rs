@@ -1,9 +1,11 @@
 use std::{
     fs::File,
-    io::{Read, Write},
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, StdinLock, StdoutLock, Write},
     path::{Path, PathBuf},
 };
 
+use tempfile::NamedTempFile;
+
 use crate::errors::CliError;
 
 /// Reads the input file, or stdin if '-'.
@@ -44,25 +46,278 @@ pub fn read_input_file(filename: &str) -> Result<(Box<[u8]>, Option<String>), Cl
     }
 }
 
-pub fn write_output_file(
-    output_path: &str,
-    source_filename: Option<&str>,
-    data: &[u8],
-) -> Result<(), CliError> {
-    if output_path == "-" {
-        return std::io::stdout()
-            .lock()
-            .write_all(data)
-            .map_err(CliError::OutputWriteFailed);
+/// Reads an MCUboot image file, or stdin if '-'.
+///
+/// Intel HEX files get converted to binary.
+///
+/// # Return
+///
+/// A tuple of (image_data, file_basename).
+///
+pub fn read_image_file(filename: &str) -> Result<(Box<[u8]>, Option<String>), CliError> {
+    let (data, source_filename) = read_input_file(filename)?;
+
+    if zephyr_mcumgr::mcuboot::is_ihex(&data) {
+        let data = zephyr_mcumgr::mcuboot::from_ihex(data.as_ref())?;
+        Ok((data.into_boxed_slice(), source_filename))
+    } else {
+        Ok((data, source_filename))
+    }
+}
+
+/// The reader of an opened input file.
+pub enum InputReader {
+    Stdin(StdinLock<'static>),
+    File(BufReader<File>),
+}
+
+impl Read for InputReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            InputReader::Stdin(stdin) => stdin.read(buf),
+            InputReader::File(file) => file.read(buf),
+        }
+    }
+}
+
+/// An opened input file.
+pub struct InputFile {
+    pub reader: InputReader,
+    /// The file size, unknown for stdin.
+    pub size: Option<u64>,
+    pub basename: Option<String>,
+}
+
+/// Opens the input file for reading, or stdin if '-'.
+pub fn open_input_file(filename: &str) -> Result<InputFile, CliError> {
+    if filename == "-" {
+        return Ok(InputFile {
+            reader: InputReader::Stdin(std::io::stdin().lock()),
+            size: None,
+            basename: None,
+        });
     }
 
+    let filename: &Path = filename.as_ref();
+    let file = File::open(filename).map_err(CliError::InputReadFailed)?;
+    let size = file.metadata().map_err(CliError::InputReadFailed)?.len();
+
+    Ok(InputFile {
+        reader: InputReader::File(BufReader::new(file)),
+        size: Some(size),
+        basename: filename
+            .file_name()
+            .map(|val| val.to_string_lossy().into_owned()),
+    })
+}
+
+/// Copies the input to a temporary file and returns it together with its size.
+///
+/// If `size` is given, exactly that many bytes get copied.
+pub fn spool_input(
+    reader: impl Read,
+    size: Option<u64>,
+) -> Result<(BufReader<File>, u64), CliError> {
+    let mut spool = tempfile::tempfile().map_err(CliError::InputReadFailed)?;
+    let spooled = match size {
+        Some(size) => std::io::copy(&mut SizedReader::new(reader, size), &mut spool),
+        None => std::io::copy(&mut { reader }, &mut spool),
+    }
+    .map_err(CliError::InputReadFailed)?;
+    spool
+        .seek(SeekFrom::Start(0))
+        .map_err(CliError::InputReadFailed)?;
+    Ok((BufReader::new(spool), spooled))
+}
+
+/// Reads exactly `size` bytes and fails if the input is longer.
+///
+/// The error gets raised while reading the last bytes, so they never reach the device.
+pub struct SizedReader<R> {
+    inner: R,
+    size: u64,
+    remaining: u64,
+}
+
+impl<R: Read> SizedReader<R> {
+    pub fn new(inner: R, size: u64) -> Self {
+        Self {
+            inner,
+            size,
+            remaining: size,
+        }
+    }
+
+    fn has_more_data(&mut self) -> std::io::Result<bool> {
+        let mut probe = [0u8];
+        loop {
+            match self.inner.read(&mut probe) {
+                Ok(len) => return Ok(len > 0),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for SizedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let limit = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        if limit == 0 {
+            return Ok(0);
+        }
+
+        let len = self.inner.read(&mut buf[..limit])?;
+        self.remaining -= len as u64;
+
+        if len > 0 && self.remaining == 0 && self.has_more_data()? {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "input is larger than the given size",
+            ));
+        }
+
+        Ok(len)
+    }
+}
+
+/// Positions are those of `inner`, which has to start at its beginning.
+impl<R: Seek> Seek for SizedReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.remaining = self.size.saturating_sub(position);
+        Ok(position)
+    }
+}
+
+/// Resolves the output path like [`open_output_file`].
+fn resolve_output_path(
+    output_path: &str,
+    source_filename: Option<&str>,
+) -> Result<PathBuf, CliError> {
     let mut output_path = PathBuf::from(output_path);
     if output_path.is_dir() {
         let filename = source_filename.ok_or_else(|| CliError::DestinationFilenameUnknown)?;
         output_path.push(filename);
     }
-    File::create(output_path)
-        .map_err(CliError::OutputWriteFailed)?
-        .write_all(data)
-        .map_err(CliError::OutputWriteFailed)
+    Ok(output_path)
+}
+
+/// A partially downloaded output file, opened for appending.
+pub struct ResumableOutputFile {
+    pub writer: BufWriter<File>,
+    pub path: PathBuf,
+    /// The length of the already existing data.
+    pub existing_len: u64,
+}
+
+/// Opens the output file for appending, creating it if it does not exist.
+///
+/// The path is resolved like in [`open_output_file`], except that stdout is not supported.
+pub fn open_output_file_for_resume(
+    output_path: &str,
+    source_filename: Option<&str>,
+) -> Result<ResumableOutputFile, CliError> {
+    if output_path == "-" {
+        return Err(CliError::ResumeToStdoutUnsupported);
+    }
+
+    let path = resolve_output_path(output_path, source_filename)?;
+    let file = File::options()
+        .append(true)
+        .create(true)
+        .open(&path)
+        .map_err(CliError::OutputWriteFailed)?;
+    let existing_len = file.metadata().map_err(CliError::OutputWriteFailed)?.len();
+
+    Ok(ResumableOutputFile {
+        writer: BufWriter::new(file),
+        path,
+        existing_len,
+    })
+}
+
+/// An output file that only replaces its destination once it is complete.
+pub enum OutputFile {
+    Stdout(StdoutLock<'static>),
+    /// The data gets written to a temporary file next to the destination,
+    /// so a failed download leaves an existing file untouched.
+    File {
+        writer: BufWriter<NamedTempFile>,
+        path: PathBuf,
+    },
+}
+
+impl OutputFile {
+    /// Flushes the written data and moves the file to its destination.
+    pub fn finish(self) -> Result<(), CliError> {
+        match self {
+            OutputFile::Stdout(mut stdout) => stdout.flush().map_err(CliError::OutputWriteFailed),
+            OutputFile::File { writer, path } => {
+                let file = writer
+                    .into_inner()
+                    .map_err(|e| CliError::OutputWriteFailed(e.into_error()))?;
+                file.persist(path)
+                    .map_err(|e| CliError::OutputWriteFailed(e.error))?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            OutputFile::Stdout(stdout) => stdout.write(buf),
+            OutputFile::File { writer, .. } => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            OutputFile::Stdout(stdout) => stdout.flush(),
+            OutputFile::File { writer, .. } => writer.flush(),
+        }
+    }
+}
+
+/// Opens the output file for writing, or stdout if '-'.
+///
+/// If `output_path` is a directory, the file will be created
+/// inside of it, named `source_filename`.
+///
+/// The destination only gets replaced by [`OutputFile::finish`].
+///
+pub fn open_output_file(
+    output_path: &str,
+    source_filename: Option<&str>,
+) -> Result<OutputFile, CliError> {
+    if output_path == "-" {
+        return Ok(OutputFile::Stdout(std::io::stdout().lock()));
+    }
+
+    let path = resolve_output_path(output_path, source_filename)?;
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".zephyr-mcumgr-").suffix(".part");
+    // Same permissions as a file created through `File::create`
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(std::fs::Permissions::from_mode(0o666));
+    }
+    let file = builder
+        .tempfile_in(directory)
+        .map_err(CliError::OutputWriteFailed)?;
+
+    Ok(OutputFile::File {
+        writer: BufWriter::new(file),
+        path,
+    })
 }
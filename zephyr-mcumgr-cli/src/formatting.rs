@@ -132,3 +132,59 @@ pub fn structured_print(
     }
     Ok(())
 }
+
+/// Prints rows as a table with aligned columns and a bold header.
+pub fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (width, column) in widths.iter_mut().zip(row) {
+            *width = (*width).max(column.chars().count());
+        }
+    }
+
+    let format_row = |row: [&str; N]| {
+        row.iter()
+            .zip(widths)
+            .map(|(column, width)| format!("{column:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", style(format_row(header)).bold());
+    for row in rows {
+        println!("{}", format_row(row.each_ref().map(String::as_str)));
+    }
+}
+
+/// Converts an error and its causes to a JSON object.
+pub fn json_error(error: &dyn miette::Diagnostic) -> serde_json::Value {
+    let mut causes = vec![];
+    let mut source = error.source();
+    while let Some(cause) = source {
+        causes.push(serde_json::Value::from(cause.to_string()));
+        source = cause.source();
+    }
+
+    let mut obj = serde_json::Map::new();
+    obj.insert("message".into(), error.to_string().into());
+    if let Some(code) = error.code() {
+        obj.insert("code".into(), code.to_string().into());
+    }
+    if let Some(help) = error.help() {
+        obj.insert("help".into(), help.to_string().into());
+    }
+    obj.insert("causes".into(), causes.into());
+
+    obj.into()
+}
+
+/// Prints an error and its causes as JSON to stderr.
+pub fn print_json_error(error: &dyn miette::Diagnostic) {
+    let json = serde_json::json!({ "error": json_error(error) });
+    eprintln!(
+        "{}",
+        serde_json::to_string_pretty(&json).unwrap_or_else(|_| json.to_string())
+    );
+}
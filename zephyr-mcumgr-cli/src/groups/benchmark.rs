@@ -0,0 +1,136 @@
+use std::time::{Duration, Instant};
+
+use indicatif::MultiProgress;
+use zephyr_mcumgr::{MCUmgrClient, client::FsUploadOptions};
+
+use crate::{
+    args::CommonArgs,
+    client::Client,
+    errors::CliError,
+    formatting::{StructuredPrint, structured_print},
+};
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct BenchmarkCommand {
+    /// The size of the echo payload, in bytes
+    #[arg(long, default_value_t = 64)]
+    payload_size: usize,
+    /// The duration of each measurement, in seconds
+    #[arg(long, default_value_t = 5.0)]
+    duration: f64,
+    /// The file path on the device to measure upload and download throughput with
+    ///
+    /// The file gets overwritten. If omitted, only the echo round trip gets measured.
+    #[arg(long)]
+    file: Option<String>,
+    /// The size of the file for the upload and download measurement, in bytes
+    #[arg(long, default_value_t = 65536)]
+    file_size: u64,
+}
+
+struct Measurement {
+    elapsed: Duration,
+    payload_bytes: u64,
+    packets: u64,
+    average_round_trip_time: Option<Duration>,
+    max_round_trip_time: Duration,
+}
+
+impl Measurement {
+    fn print(&self, s: &mut StructuredPrint) {
+        let seconds = self.elapsed.as_secs_f64();
+        let milliseconds =
+            |duration: Duration| (duration.as_secs_f64() * 100_000.0).round() / 100.0;
+
+        s.key_value("packets", self.packets);
+        s.key_value("packets/s", (self.packets as f64 / seconds).round() as u64);
+        s.key_value(
+            "bytes/s",
+            (self.payload_bytes as f64 / seconds).round() as u64,
+        );
+        s.key_value_maybe(
+            "average round trip (ms)",
+            self.average_round_trip_time.map(milliseconds),
+        );
+        s.key_value(
+            "max round trip (ms)",
+            milliseconds(self.max_round_trip_time),
+        );
+    }
+}
+
+/// Repeats `step` until `duration` is over and collects the connection statistics.
+///
+/// `step` returns the number of transferred payload bytes.
+fn measure(
+    client: &MCUmgrClient,
+    duration: Duration,
+    mut step: impl FnMut() -> Result<u64, CliError>,
+) -> Result<Measurement, CliError> {
+    client.reset_stats();
+    let start = Instant::now();
+
+    let mut payload_bytes = 0;
+    while start.elapsed() < duration {
+        payload_bytes += step()?;
+    }
+
+    let elapsed = start.elapsed();
+    let stats = client.stats();
+
+    Ok(Measurement {
+        elapsed,
+        payload_bytes,
+        packets: stats.responses,
+        average_round_trip_time: stats.average_round_trip_time(),
+        max_round_trip_time: stats.max_round_trip_time,
+    })
+}
+
+pub fn run(
+    client: &Client,
+    _multiprogress: &MultiProgress,
+    args: CommonArgs,
+    command: BenchmarkCommand,
+) -> Result<(), CliError> {
+    let client = client.get()?;
+    let duration = Duration::try_from_secs_f64(command.duration)
+        .map_err(|e| CliError::InvalidInterval(e.to_string()))?;
+
+    let payload = "x".repeat(command.payload_size);
+    let echo = measure(client, duration, || {
+        client.os_echo(&payload)?;
+        Ok(payload.len() as u64)
+    })?;
+
+    let transfers = if let Some(file) = &command.file {
+        let data = (0..command.file_size).map(|i| i as u8).collect::<Vec<_>>();
+
+        let upload = measure(client, duration, || {
+            client.fs_file_upload(
+                file,
+                data.as_slice(),
+                command.file_size,
+                &FsUploadOptions::default(),
+                None,
+            )?;
+            Ok(command.file_size)
+        })?;
+        let download = measure(client, duration, || {
+            client.fs_file_download(file, std::io::sink(), None, None)?;
+            Ok(command.file_size)
+        })?;
+
+        Some((upload, download))
+    } else {
+        None
+    };
+
+    structured_print(None, args.json, |s| {
+        s.sublist("echo", |s| echo.print(s));
+        if let Some((upload, download)) = &transfers {
+            s.sublist("upload", |s| upload.print(s));
+            s.sublist("download", |s| download.print(s));
+        }
+    })
+}
@@ -0,0 +1,96 @@
+use indicatif::MultiProgress;
+use zephyr_mcumgr::MCUmgrClient;
+
+use crate::{args::CommonArgs, client::Client, errors::CliError, formatting::structured_print};
+
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum DatetimeCommand {
+    /// Retrieve the device's RTC datetime
+    Get,
+    /// Set the device's RTC datetime
+    Set {
+        /// The datetime value, as RFC3339
+        #[arg(required_unless_present = "now")]
+        value: Option<String>,
+        /// Use the current host time
+        #[arg(long, conflicts_with = "value")]
+        now: bool,
+        /// Use UTC time instead of local time
+        #[arg(long)]
+        utc: bool,
+    },
+}
+
+pub fn run(
+    client: &Client,
+    _multiprogress: &MultiProgress,
+    args: CommonArgs,
+    command: DatetimeCommand,
+) -> Result<(), CliError> {
+    let client = client.get()?;
+
+    match command {
+        DatetimeCommand::Get => get_datetime(client, &args)?,
+        DatetimeCommand::Set { value, now: _, utc } => set_datetime(client, &args, value, utc)?,
+    }
+
+    Ok(())
+}
+
+/// Sets the device time to the given RFC3339 datetime, or to the host time if `None`.
+pub(super) fn set_datetime(
+    client: &MCUmgrClient,
+    args: &CommonArgs,
+    value: Option<String>,
+    utc: bool,
+) -> Result<(), CliError> {
+    use chrono::{DateTime, FixedOffset, NaiveDateTime};
+
+    let datetime_value = if let Some(value) = value {
+        value
+            .parse::<DateTime<FixedOffset>>()
+            .map(|datetime| {
+                if utc {
+                    datetime.naive_utc()
+                } else {
+                    datetime.naive_local()
+                }
+            })
+            .or_else(|_| value.parse::<NaiveDateTime>())?
+    } else {
+        let now = chrono::Local::now();
+        if utc {
+            now.naive_utc()
+        } else {
+            now.naive_local()
+        }
+    };
+
+    client.os_set_datetime(datetime_value)?;
+
+    if args.json {
+        structured_print(None, true, |s| {
+            s.key_value("datetime", datetime_value.format("%FT%T%.f").to_string())
+        })?;
+    } else if args.verbose {
+        println!("Set device time to: {}", datetime_value.format("%F %T"));
+    }
+
+    Ok(())
+}
+
+/// Prints the device time.
+pub(super) fn get_datetime(client: &MCUmgrClient, args: &CommonArgs) -> Result<(), CliError> {
+    let datetime = client.os_get_datetime()?;
+    if args.json {
+        structured_print(None, true, |s| {
+            s.key_value("datetime", datetime.format("%FT%T%.f").to_string())
+        })?;
+    } else if args.verbose {
+        println!("Device time: {}", datetime);
+    } else {
+        println!("{:?}", datetime);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+use indicatif::MultiProgress;
+use rand::distr::{Alphanumeric, SampleString};
+use zephyr_mcumgr::{connection::ExecuteError, transport::ReceiveError};
+
+use crate::{args::CommonArgs, client::Client, errors::CliError, formatting::structured_print};
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct EchoCommand {
+    /// The message to echo
+    #[arg(long, conflicts_with = "size")]
+    message: Option<String>,
+    /// The size of the random message to echo, in bytes
+    ///
+    /// Every repetition uses a new random message.
+    #[arg(long, default_value_t = 16)]
+    size: usize,
+    /// The number of echoes to send
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    count: u64,
+}
+
+pub fn run(
+    client: &Client,
+    _multiprogress: &MultiProgress,
+    args: CommonArgs,
+    command: EchoCommand,
+) -> Result<(), CliError> {
+    let client = client.get()?;
+    let milliseconds = |duration: Duration| (duration.as_secs_f64() * 100_000.0).round() / 100.0;
+
+    let mut round_trip_times = vec![];
+    let mut last_error = None;
+
+    for sequence in 0..command.count {
+        let message = command
+            .message
+            .clone()
+            .unwrap_or_else(|| Alphanumeric.sample_string(&mut rand::rng(), command.size));
+
+        let start = Instant::now();
+        let result = client.os_echo(&message).and_then(|response| {
+            if response == message {
+                Ok(())
+            } else {
+                Err(ExecuteError::ReceiveFailed(
+                    ReceiveError::UnexpectedResponse,
+                ))
+            }
+        });
+        let round_trip_time = start.elapsed();
+
+        match result {
+            Ok(()) => {
+                if !args.json {
+                    println!(
+                        "{} bytes: seq={sequence} time={} ms",
+                        message.len(),
+                        milliseconds(round_trip_time)
+                    );
+                }
+                round_trip_times.push(round_trip_time);
+            }
+            Err(e) => {
+                if !args.json {
+                    println!("seq={sequence}: {e}");
+                }
+                last_error = Some(e);
+            }
+        }
+    }
+
+    let (Some(min), Some(max)) = (
+        round_trip_times.iter().min().copied(),
+        round_trip_times.iter().max().copied(),
+    ) else {
+        // Nothing came back; the last error is the most useful explanation
+        return Err(last_error.expect("at least one echo gets sent").into());
+    };
+
+    let sent = command.count;
+    let received = round_trip_times.len() as u64;
+    let lost = sent - received;
+    let average = round_trip_times.iter().sum::<Duration>() / received as u32;
+
+    structured_print(Some("Echo statistics".to_string()), args.json, |s| {
+        s.key_value("sent", sent);
+        s.key_value("received", received);
+        s.key_value("lost", lost);
+        s.key_value(
+            "loss (%)",
+            (lost as f64 * 10_000.0 / sent as f64).round() / 100.0,
+        );
+        s.key_value("min round trip (ms)", milliseconds(min));
+        s.key_value("average round trip (ms)", milliseconds(average));
+        s.key_value("max round trip (ms)", milliseconds(max));
+    })
+}
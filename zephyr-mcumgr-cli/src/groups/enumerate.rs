@@ -0,0 +1,81 @@
+use indicatif::MultiProgress;
+use zephyr_mcumgr::{
+    MCUmgrGroup, commands::enumeration::GroupDetailsEntry, connection::ExecuteError,
+};
+
+use crate::{args::CommonArgs, client::Client, errors::CliError, formatting::print_table};
+
+/// The friendly name of a known group, otherwise the name reported by the device.
+fn group_name(entry: &GroupDetailsEntry) -> Option<String> {
+    const PERUSER: MCUmgrGroup = MCUmgrGroup::MGMT_GROUP_ID_PERUSER;
+    match MCUmgrGroup::from_repr(entry.group) {
+        Some(group) if group != PERUSER => Some(group.name().to_string()),
+        _ if entry.group >= PERUSER as u16 => entry
+            .name
+            .clone()
+            .or_else(|| Some(PERUSER.name().to_string())),
+        _ => entry.name.clone(),
+    }
+}
+
+pub fn run(
+    client: &Client,
+    _multiprogress: &MultiProgress,
+    args: CommonArgs,
+) -> Result<(), CliError> {
+    let client = client.get()?;
+
+    let groups = match client.enum_group_details(None) {
+        Ok(groups) => groups,
+        Err(ExecuteError::ErrorResponse(e)) => {
+            log::debug!("Group details unavailable, falling back to the group list: {e}");
+            client
+                .enum_list_groups()?
+                .into_iter()
+                .map(|group| GroupDetailsEntry {
+                    group,
+                    name: None,
+                    handlers: None,
+                })
+                .collect()
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if args.json {
+        let groups = groups
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "id": entry.group,
+                    "name": group_name(entry),
+                    "device_name": entry.name,
+                    "commands": entry.handlers,
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&groups).map_err(CliError::JsonEncodeError)?
+        );
+        return Ok(());
+    }
+
+    let rows = groups
+        .iter()
+        .map(|entry| {
+            [
+                entry.group.to_string(),
+                group_name(entry).unwrap_or_else(|| "---".to_string()),
+                entry
+                    .handlers
+                    .map(|handlers| handlers.to_string())
+                    .unwrap_or_else(|| "---".to_string()),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    print_table(["ID", "NAME", "COMMANDS"], &rows);
+
+    Ok(())
+}
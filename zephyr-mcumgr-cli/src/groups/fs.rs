@@ -1,17 +1,33 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
 
 use indicatif::MultiProgress;
+use zephyr_mcumgr::{
+    MCUmgrClient, checksum, client::FsUploadOptions, commands::fs::FileChecksumData,
+    connection::ExecuteError,
+};
 
 use crate::{
     args::CommonArgs,
     client::Client,
     errors::CliError,
-    file_read_write::{read_input_file, write_output_file},
+    file_read_write::{
+        InputFile, InputReader, ResumableOutputFile, SizedReader, open_input_file,
+        open_output_file, open_output_file_for_resume, spool_input,
+    },
     formatting::structured_print,
     progress::with_progress_bar,
+    remote_paths::{
+        DirTree, RemotePathCompleter, create_remote_dir, create_remote_dir_all, join_remote_path,
+        list_remote_dir, walk_remote_dir,
+    },
 };
 
-#[derive(Debug, clap::Subcommand)]
+#[derive(Debug, Clone, clap::Subcommand)]
 pub enum FsCommand {
     /// Downloads a file from the device
     Download {
@@ -19,6 +35,18 @@ pub enum FsCommand {
         remote: String,
         /// The target path. '-' for stdout.
         local: String,
+        /// Continue a previously interrupted download, appending to the existing local file.
+        ///
+        /// The result gets verified against the checksum of the file on the device,
+        /// which requires `CONFIG_MCUMGR_GRP_FS_CHECKSUM_HASH`.
+        #[arg(long, conflicts_with_all = ["offset", "length"])]
+        resume: bool,
+        /// The position in the remote file to start downloading from.
+        #[arg(long, default_value_t = 0)]
+        offset: u64,
+        /// The maximum number of bytes to download.
+        #[arg(long)]
+        length: Option<u64>,
     },
     /// Uploads a file to the device
     Upload {
@@ -26,6 +54,53 @@ pub enum FsCommand {
         local: String,
         /// The target path on the device.
         remote: String,
+        /// The number of bytes to upload.
+        ///
+        /// Allows streaming from stdin without `--resume`; otherwise stdin gets buffered
+        /// in a temporary file before uploading. Fails if the input is larger.
+        #[arg(long)]
+        size: Option<u64>,
+        /// Continue a previously interrupted upload if the existing data matches.
+        #[arg(long)]
+        resume: bool,
+        /// Compare the SHA256 hash of the uploaded file to the local file.
+        ///
+        /// Requires `CONFIG_MCUMGR_GRP_FS_HASH_SHA256` on the device.
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Uploads a directory and all of its content to the device
+    ///
+    /// Requires the device shell with file system commands
+    /// (`CONFIG_FILE_SYSTEM_SHELL` and `CONFIG_MCUMGR_GRP_SHELL`).
+    UploadDir {
+        /// The local directory to copy.
+        local: PathBuf,
+        /// The target directory on the device, gets created if missing.
+        remote: String,
+    },
+    /// Downloads a directory and all of its content from the device
+    ///
+    /// Requires the device shell with file system commands
+    /// (`CONFIG_FILE_SYSTEM_SHELL` and `CONFIG_MCUMGR_GRP_SHELL`).
+    DownloadDir {
+        /// The directory path on the device.
+        remote: String,
+        /// The local target directory, gets created if missing.
+        local: PathBuf,
+    },
+    /// Compares a local file with a file on the device, using the device's checksum
+    ///
+    /// Fails if the files differ.
+    Verify {
+        /// The local file.
+        local: PathBuf,
+        /// The file path on the device.
+        remote: String,
+        /// The checksum algorithm to use; the device's default if omitted
+        /// For more info, see `fs supported-checksums`
+        #[arg(long, verbatim_doc_comment)]
+        algo: Option<String>,
     },
     /// Shows status details about a file
     Status {
@@ -36,9 +111,14 @@ pub enum FsCommand {
     Checksum {
         /// The path of the file on the device
         name: String,
-        /// The checksum algorithm to use
-        /// For more info, see `fs supported-checksums`
-        #[arg(verbatim_doc_comment)]
+        /// The checksum algorithm to use, e.g. `sha256` or `crc32`; the device's default if omitted
+        /// For more info, see `fs checksum-types`
+        #[arg(
+            long = "type",
+            value_name = "TYPE",
+            visible_alias = "algo",
+            verbatim_doc_comment
+        )]
         algo: Option<String>,
         /// How many bytes in the file to skip
         #[arg(long, default_value_t = 0)]
@@ -48,11 +128,128 @@ pub enum FsCommand {
         length: Option<u64>,
     },
     /// Shows supported checksum algorithms
+    #[command(visible_alias = "checksum-types")]
     SupportedChecksums,
+    /// Lists the content of a directory
+    ///
+    /// Requires the device shell with file system commands
+    /// (`CONFIG_FILE_SYSTEM_SHELL` and `CONFIG_MCUMGR_GRP_SHELL`).
+    #[command(visible_alias = "ls")]
+    List {
+        /// The directory path on the device
+        #[arg(default_value = "/")]
+        path: String,
+    },
+    /// Prints all remote paths that start with the given partial path
+    ///
+    /// Intended to back shell completion scripts.
+    #[command(hide = true)]
+    Complete {
+        /// The partially typed path
+        #[arg(default_value = "")]
+        partial: String,
+    },
     /// Closes all files currently opened by MCUmgr
     Close,
 }
 
+/// The result of comparing a local file with a file on the device.
+struct FileComparison {
+    algorithm: String,
+    local_len: u64,
+    local_checksum: FileChecksumData,
+    remote_len: u64,
+    remote_checksum: FileChecksumData,
+}
+
+impl FileComparison {
+    fn matches(&self) -> bool {
+        self.local_len == self.remote_len && self.local_checksum == self.remote_checksum
+    }
+}
+
+/// Computes the checksum of a local file with the algorithm the device uses for the remote file.
+///
+/// Uses the device's default algorithm if `algorithm` is `None`.
+fn compare_with_remote(
+    client: &MCUmgrClient,
+    local: &Path,
+    remote: &str,
+    algorithm: Option<&str>,
+) -> Result<FileComparison, CliError> {
+    let device_checksum = client.fs_file_checksum(remote, algorithm, 0, None)?;
+
+    let hasher = checksum::hasher_for_algorithm(&device_checksum.r#type)
+        .ok_or_else(|| CliError::UnsupportedChecksumType(device_checksum.r#type.clone()))?;
+
+    let file = File::open(local).map_err(CliError::InputReadFailed)?;
+    let (local_checksum, local_len) =
+        checksum::compute_checksum(BufReader::new(file), hasher, 0, None)?;
+
+    Ok(FileComparison {
+        algorithm: device_checksum.r#type,
+        local_len,
+        local_checksum,
+        remote_len: device_checksum.len,
+        remote_checksum: device_checksum.output,
+    })
+}
+
+/// Compares a downloaded file with the checksum of the file on the device.
+///
+/// Fails if the device does not support checksums, as the download cannot be trusted then.
+fn verify_download(client: &MCUmgrClient, remote: &str, local: &Path) -> Result<(), CliError> {
+    let comparison = match compare_with_remote(client, local, remote, None) {
+        Ok(comparison) => comparison,
+        Err(CliError::CommandExecutionFailed(ExecuteError::ErrorResponse(e))) => {
+            return Err(CliError::DownloadVerificationUnavailable(format!(
+                "device does not provide a checksum ({e})"
+            )));
+        }
+        Err(CliError::UnsupportedChecksumType(algorithm)) => {
+            return Err(CliError::DownloadVerificationUnavailable(format!(
+                "unknown checksum type '{algorithm}'"
+            )));
+        }
+        Err(e) => return Err(e),
+    };
+
+    if !comparison.matches() {
+        return Err(CliError::DownloadVerificationFailed);
+    }
+
+    Ok(())
+}
+
+/// Recursively lists a local directory, in the same format as [`walk_remote_dir`].
+fn walk_local_dir(path: &Path) -> Result<DirTree, CliError> {
+    fn walk(root: &Path, relative: &str, tree: &mut DirTree) -> Result<(), CliError> {
+        let mut entries = std::fs::read_dir(root.join(relative))
+            .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+            .map_err(CliError::InputReadFailed)?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let entry_path = join_remote_path(relative, &entry.file_name().to_string_lossy());
+            if entry
+                .file_type()
+                .map_err(CliError::InputReadFailed)?
+                .is_dir()
+            {
+                tree.dirs.push(entry_path.clone());
+                walk(root, &entry_path, tree)?;
+            } else {
+                tree.files.push(entry_path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut tree = DirTree::default();
+    walk(path, "", &mut tree)?;
+    Ok(tree)
+}
+
 pub fn run(
     client: &Client,
     multiprogress: &MultiProgress,
@@ -61,18 +258,69 @@ pub fn run(
 ) -> Result<(), CliError> {
     let client = client.get()?;
     match command {
-        FsCommand::Download { remote, local } => {
-            let mut data = vec![];
-            with_progress_bar(multiprogress, !args.quiet, Some(&remote), |progress| {
-                client.fs_file_download(remote.as_str(), &mut data, progress)
+        FsCommand::Download {
+            remote,
+            local,
+            resume: true,
+            ..
+        } => {
+            let filename = remote.rsplit('/').next().filter(|s| !s.is_empty());
+            let ResumableOutputFile {
+                mut writer,
+                path,
+                existing_len,
+            } = open_output_file_for_resume(&local, filename)?;
+
+            with_progress_bar(multiprogress, &args, Some(&remote), |progress| {
+                client.fs_file_download_from(
+                    remote.as_str(),
+                    existing_len,
+                    &mut writer,
+                    progress,
+                    None,
+                )
             })?;
 
+            writer.flush().map_err(CliError::OutputWriteFailed)?;
+            drop(writer);
+
+            verify_download(client, &remote, &path)?;
+        }
+        FsCommand::Download {
+            remote,
+            local,
+            resume: false,
+            offset,
+            length,
+        } => {
             let filename = remote.rsplit('/').next().filter(|s| !s.is_empty());
+            let mut writer = open_output_file(&local, filename)?;
+
+            with_progress_bar(multiprogress, &args, Some(&remote), |progress| {
+                client.fs_file_download_range(
+                    remote.as_str(),
+                    offset,
+                    length,
+                    &mut writer,
+                    progress,
+                    None,
+                )
+            })?;
 
-            write_output_file(&local, filename, &data)?;
+            writer.finish()?;
         }
-        FsCommand::Upload { local, mut remote } => {
-            let (data, source_filename) = read_input_file(&local)?;
+        FsCommand::Upload {
+            local,
+            mut remote,
+            size,
+            resume,
+            verify,
+        } => {
+            let InputFile {
+                reader,
+                size: file_size,
+                basename: source_filename,
+            } = open_input_file(&local)?;
 
             if remote.ends_with("/") {
                 let filename =
@@ -80,10 +328,144 @@ pub fn run(
                 remote.push_str(&filename);
             }
 
-            with_progress_bar(multiprogress, !args.quiet, Some(&remote), |progress| {
-                client.fs_file_upload(remote.as_str(), &*data, data.len() as u64, progress)
+            let options = FsUploadOptions {
+                verify,
+                ..Default::default()
+            };
+
+            if resume {
+                // Resuming rewinds the input if the existing data does not match,
+                // so stdin gets spooled to a temporary file
+                let (reader, size) = match (reader, size.or(file_size)) {
+                    (InputReader::File(file), Some(size)) => (file, size),
+                    (reader, size) => spool_input(reader, size)?,
+                };
+                let reader = SizedReader::new(reader, size);
+
+                with_progress_bar(multiprogress, &args, Some(&remote), |progress| {
+                    client.fs_file_upload_resume(remote.as_str(), reader, size, &options, progress)
+                })?;
+            } else {
+                let (reader, size): (Box<dyn Read>, u64) = match size.or(file_size) {
+                    Some(size) => (Box::new(SizedReader::new(reader, size)), size),
+                    None => {
+                        // The device needs the file size in the first chunk, so spool
+                        // the input to a temporary file instead of into memory
+                        let (spool, size) = spool_input(reader, None)?;
+                        (Box::new(spool), size)
+                    }
+                };
+
+                with_progress_bar(multiprogress, &args, Some(&remote), |progress| {
+                    client.fs_file_upload(remote.as_str(), reader, size, &options, progress)
+                })?;
+            }
+        }
+        FsCommand::UploadDir { local, remote } => {
+            let tree = walk_local_dir(&local)?;
+
+            let sizes = tree
+                .files
+                .iter()
+                .map(|file| std::fs::metadata(local.join(file)).map(|metadata| metadata.len()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(CliError::InputReadFailed)?;
+            let total_size = sizes.iter().sum();
+
+            create_remote_dir_all(client, &remote)?;
+            for dir in &tree.dirs {
+                create_remote_dir(client, &join_remote_path(&remote, dir))?;
+            }
+
+            let message = local.to_string_lossy();
+            with_progress_bar(multiprogress, &args, Some(&message), |mut progress| {
+                let mut transferred = 0;
+                for (file, size) in tree.files.iter().zip(sizes) {
+                    let reader = BufReader::new(
+                        File::open(local.join(file)).map_err(CliError::InputReadFailed)?,
+                    );
+                    let mut file_progress = |current, _total| match &mut progress {
+                        Some(progress) => progress(transferred + current, total_size),
+                        None => true,
+                    };
+                    client.fs_file_upload(
+                        join_remote_path(&remote, file),
+                        reader,
+                        size,
+                        &FsUploadOptions::default(),
+                        Some(&mut file_progress),
+                    )?;
+                    transferred += size;
+                }
+                Ok::<_, CliError>(())
             })?;
         }
+        FsCommand::DownloadDir { remote, local } => {
+            let tree = walk_remote_dir(client, &remote)?;
+
+            let sizes = tree
+                .files
+                .iter()
+                .map(|file| {
+                    client
+                        .fs_file_status(join_remote_path(&remote, file))
+                        .map(|status| status.len)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let total_size = sizes.iter().sum();
+
+            std::fs::create_dir_all(&local).map_err(CliError::OutputWriteFailed)?;
+            for dir in &tree.dirs {
+                std::fs::create_dir_all(local.join(dir)).map_err(CliError::OutputWriteFailed)?;
+            }
+
+            with_progress_bar(multiprogress, &args, Some(&remote), |mut progress| {
+                let mut transferred = 0;
+                for (file, size) in tree.files.iter().zip(sizes) {
+                    let mut writer = BufWriter::new(
+                        File::create(local.join(file)).map_err(CliError::OutputWriteFailed)?,
+                    );
+                    let mut file_progress = |current, _total| match &mut progress {
+                        Some(progress) => progress(transferred + current, total_size),
+                        None => true,
+                    };
+                    client.fs_file_download(
+                        join_remote_path(&remote, file),
+                        &mut writer,
+                        Some(&mut file_progress),
+                        None,
+                    )?;
+                    writer.flush().map_err(CliError::OutputWriteFailed)?;
+                    transferred += size;
+                }
+                Ok::<_, CliError>(())
+            })?;
+        }
+        FsCommand::Verify {
+            local,
+            remote,
+            algo,
+        } => {
+            let comparison = compare_with_remote(client, &local, &remote, algo.as_deref())?;
+
+            if args.json || args.verbose {
+                structured_print(Some(remote.clone()), args.json, |s| {
+                    s.key_value("match", comparison.matches());
+                    s.key_value("type", comparison.algorithm.as_str());
+                    s.key_value("local checksum", comparison.local_checksum.hex());
+                    s.key_value("remote checksum", comparison.remote_checksum.hex());
+                    s.key_value("local length", comparison.local_len);
+                    s.key_value("remote length", comparison.remote_len);
+                })?;
+            } else {
+                let result = if comparison.matches() { "OK" } else { "FAILED" };
+                println!("{remote}: {result}");
+            }
+
+            if !comparison.matches() {
+                return Err(CliError::FileMismatch(remote));
+            }
+        }
         FsCommand::Status { name } => {
             let status = client.fs_file_status(&name)?;
             structured_print(Some(name), args.json, |s| {
@@ -106,7 +488,12 @@ pub fn run(
                     s.key_value("data length", checksum.len);
                 })?;
             } else {
-                println!("{}  {}", checksum.output.hex(), name);
+                println!(
+                    "{}  {}  ({} bytes)",
+                    checksum.output.hex(),
+                    name,
+                    checksum.len
+                );
             }
         }
         FsCommand::SupportedChecksums => {
@@ -132,6 +519,33 @@ pub fn run(
                 );
             }
         }
+        FsCommand::List { path } => {
+            let entries = list_remote_dir(client, &path)?;
+            if args.json || args.verbose {
+                structured_print(Some(path), args.json, |s| {
+                    for entry in entries {
+                        s.key_value(entry.name, if entry.is_dir { "directory" } else { "file" });
+                    }
+                })?;
+            } else {
+                for entry in entries {
+                    println!("{}{}", entry.name, if entry.is_dir { "/" } else { "" });
+                }
+            }
+        }
+        FsCommand::Complete { partial } => {
+            let candidates = RemotePathCompleter::default().complete(client, &partial);
+            if args.json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&candidates).map_err(CliError::JsonEncodeError)?
+                );
+            } else {
+                for candidate in candidates {
+                    println!("{candidate}");
+                }
+            }
+        }
         FsCommand::Close => client.fs_file_close()?,
     }
 
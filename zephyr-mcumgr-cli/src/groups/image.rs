@@ -2,14 +2,33 @@ use indicatif::MultiProgress;
 use zephyr_mcumgr::commands::image::ImageState;
 
 use crate::{
-    args::CommonArgs, client::Client, errors::CliError, file_read_write::read_input_file,
-    formatting::structured_print, groups::parse_sha256, progress::with_progress_bar,
+    args::CommonArgs,
+    client::Client,
+    errors::CliError,
+    file_read_write::read_image_file,
+    formatting::{print_table, structured_print},
+    groups::parse_sha256,
+    progress::with_progress_bar,
 };
 
-#[derive(Debug, clap::Subcommand)]
+#[derive(Debug, Clone, clap::Subcommand)]
 pub enum ImageCommand {
     /// Obtain a list of images with their current state
     GetState,
+    /// List all image slots as a table
+    List,
+    /// Boot to the given image once, reverting on the next reset unless confirmed
+    Test {
+        /// The hash ID of the image
+        #[arg(value_parser=parse_sha256)]
+        hash: [u8; 32],
+    },
+    /// Mark an image as confirmed, making it permanent
+    Confirm {
+        /// The hash ID of the image. Default: the currently running image
+        #[arg(value_parser=parse_sha256)]
+        hash: Option<[u8; 32]>,
+    },
     /// Changes the image state
     SetState {
         /// Boot to the image with the given hash ID
@@ -22,12 +41,22 @@ pub enum ImageCommand {
         confirm: bool,
     },
     /// Upload a firmware image to the device
+    ///
+    /// Only stages the image; activate it later through `image test` or `image confirm`.
     Upload {
-        /// The file to copy. '-' for stdin.
+        /// The image file to copy, binary or Intel HEX. '-' for stdin.
         image_file: String,
         /// Selects target image on the device. Default: 0
-        #[arg(long)]
+        #[arg(long = "image", visible_alias = "image-id")]
         image_id: Option<u32>,
+        /// Selects the target slot of the image, see `image slot-info`
+        ///
+        /// Requires `CONFIG_MCUMGR_GRP_IMG_DIRECT_UPLOAD` on the device.
+        #[arg(long)]
+        slot: Option<u32>,
+        /// Erase the target slot before uploading
+        #[arg(long)]
+        erase_first: bool,
         /// Prevent firmware downgrades
         #[arg(long)]
         upgrade_only: bool,
@@ -67,6 +96,51 @@ fn print_current_image_state(images: &[ImageState], args: CommonArgs) -> Result<
     Ok(())
 }
 
+fn print_new_image_state(images: &[ImageState], args: CommonArgs) -> Result<(), CliError> {
+    if !(args.quiet || args.json) {
+        println!();
+        println!("Success. New state:");
+    }
+
+    print_current_image_state(images, args)
+}
+
+fn print_image_table(images: &[ImageState], args: CommonArgs) -> Result<(), CliError> {
+    if args.json {
+        return print_current_image_state(images, args);
+    }
+
+    let header = ["IMAGE", "SLOT", "VERSION", "FLAGS", "HASH"];
+    let rows = images
+        .iter()
+        .map(|image| {
+            let flags = [
+                (image.active, "active"),
+                (image.confirmed, "confirmed"),
+                (image.pending, "pending"),
+                (image.permanent, "permanent"),
+                (image.bootable, "bootable"),
+            ]
+            .into_iter()
+            .filter_map(|(set, name)| set.then_some(name))
+            .collect::<Vec<_>>()
+            .join(",");
+
+            [
+                image.image.to_string(),
+                image.slot.to_string(),
+                image.version.clone(),
+                flags,
+                image.hash.map(hex::encode).unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    print_table(header, &rows);
+
+    Ok(())
+}
+
 pub fn run(
     client: &Client,
     multiprogress: &MultiProgress,
@@ -79,29 +153,66 @@ pub fn run(
             let images = client.image_get_state()?;
             print_current_image_state(&images, args)?;
         }
+        ImageCommand::List => {
+            let images = client.image_get_state()?;
+            print_image_table(&images, args)?;
+        }
         ImageCommand::SetState { hash, confirm } => {
             let images = client.image_set_state(hash, confirm)?;
-
-            if !(args.quiet || args.json) {
-                println!();
-                println!("Success. New state:");
-            }
-
-            print_current_image_state(&images, args)?;
+            print_new_image_state(&images, args)?;
+        }
+        ImageCommand::Test { hash } => {
+            let images = client.image_set_state(Some(hash), false)?;
+            print_new_image_state(&images, args)?;
+        }
+        ImageCommand::Confirm { hash } => {
+            let images = client.image_set_state(hash, true)?;
+            print_new_image_state(&images, args)?;
         }
         ImageCommand::Upload {
             image_file,
             image_id,
+            slot,
+            erase_first,
             upgrade_only,
             checksum,
         } => {
-            let (data, source_filename) = read_input_file(&image_file)?;
+            let (data, source_filename) = read_image_file(&image_file)?;
+
+            let image = image_id.unwrap_or(0);
+            let upload_image_id = match slot {
+                Some(slot) => Some(
+                    client
+                        .image_slot_info()?
+                        .into_iter()
+                        .filter(|info| info.image == image)
+                        .flat_map(|info| info.slots)
+                        .find(|info| info.slot == slot)
+                        .and_then(|info| info.upload_image_id)
+                        .ok_or(CliError::SlotNotUploadable { image, slot })?,
+                ),
+                None => image_id,
+            };
+
+            if erase_first {
+                // The erase command counts the slots of all images; the secondary slot is the default
+                client.image_erase(Some(image * 2 + slot.unwrap_or(1)))?;
+            }
 
             with_progress_bar(
                 multiprogress,
-                !args.quiet,
+                &args,
                 source_filename.as_deref(),
-                |progress| client.image_upload(&data, image_id, checksum, upgrade_only, progress),
+                |progress| {
+                    client.image_upload(
+                        &data,
+                        upload_image_id,
+                        checksum,
+                        upgrade_only,
+                        progress,
+                        None,
+                    )
+                },
             )?;
         }
         ImageCommand::Erase { slot } => client.image_erase(slot)?,
@@ -0,0 +1,75 @@
+use indicatif::MultiProgress;
+use zephyr_mcumgr::client::UsbSerialPorts;
+
+use crate::{args::CommonArgs, client::Client, errors::CliError, formatting::print_table};
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct ListPortsCommand {
+    /// Only show ports with the given USB vendor ID (hex)
+    #[arg(long, value_parser = parse_usb_id)]
+    vid: Option<u16>,
+    /// Only show ports with the given USB product ID (hex)
+    #[arg(long, value_parser = parse_usb_id)]
+    pid: Option<u16>,
+}
+
+fn parse_usb_id(s: &str) -> Result<u16, std::num::ParseIntError> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    u16::from_str_radix(s, 16)
+}
+
+pub fn run(
+    _client: &Client,
+    _multiprogress: &MultiProgress,
+    args: CommonArgs,
+    command: ListPortsCommand,
+) -> Result<(), CliError> {
+    let UsbSerialPorts(mut ports) =
+        UsbSerialPorts::available().map_err(CliError::ListPortsFailed)?;
+    ports.retain(|port| {
+        command.vid.is_none_or(|vid| port.port_info.vid == vid)
+            && command.pid.is_none_or(|pid| port.port_info.pid == pid)
+    });
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&ports).map_err(CliError::JsonEncodeError)?
+        );
+        return Ok(());
+    }
+
+    if ports.is_empty() {
+        println!("No USB serial ports available.");
+        return Ok(());
+    }
+
+    let header = [
+        "IDENTIFIER",
+        "PORT",
+        "VID",
+        "PID",
+        "MANUFACTURER",
+        "PRODUCT",
+        "SERIAL",
+    ];
+    let rows = ports
+        .into_iter()
+        .map(|port| {
+            let info = port.port_info;
+            [
+                port.identifier,
+                port.port_name,
+                format!("{:04x}", info.vid),
+                format!("{:04x}", info.pid),
+                info.manufacturer.unwrap_or_default(),
+                info.product.unwrap_or_default(),
+                info.serial_number.unwrap_or_default(),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    print_table(header, &rows);
+
+    Ok(())
+}
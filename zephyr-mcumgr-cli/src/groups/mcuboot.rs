@@ -1,15 +1,31 @@
 use indicatif::MultiProgress;
 
 use crate::{
-    args::CommonArgs, client::Client, errors::CliError, file_read_write::read_input_file,
+    args::CommonArgs,
+    client::Client,
+    errors::CliError,
+    file_read_write::{read_image_file, read_input_file},
     formatting::structured_print,
 };
 
-#[derive(Debug, clap::Subcommand)]
+#[derive(Debug, Clone, clap::Subcommand)]
 pub enum MCUbootCommand {
     /// Shows information about an MCUboot image file
     GetImageInfo {
-        /// The image file to analyze. '-' for stdin.
+        /// The image file to analyze, binary or Intel HEX. '-' for stdin.
+        file: String,
+    },
+    /// Verifies the signature of an MCUboot image file
+    VerifySignature {
+        /// The image file to verify, binary or Intel HEX. '-' for stdin.
+        file: String,
+        /// The public key, in DER or PEM format as exported by `imgtool getpub`
+        #[arg(short, long)]
+        key: String,
+    },
+    /// Decodes the MCUboot trailer of a raw slot dump
+    ParseTrailer {
+        /// The raw flash content of the slot. '-' for stdin.
         file: String,
     },
 }
@@ -22,13 +38,52 @@ pub fn run(
 ) -> Result<(), CliError> {
     match command {
         MCUbootCommand::GetImageInfo { file } => {
-            let (image_data, _source_filename) = read_input_file(&file)?;
+            let (image_data, _source_filename) = read_image_file(&file)?;
             let image_info =
                 zephyr_mcumgr::mcuboot::get_image_info(std::io::Cursor::new(image_data.as_ref()))?;
 
             structured_print(Some(file), args.json, |s| {
                 s.key_value("version", image_info.version.to_string());
                 s.key_value("hash", hex::encode(image_info.hash));
+                s.key_value_maybe("security_counter", image_info.security_counter);
+                s.key_value_maybe("boot_record", image_info.boot_record.map(hex::encode));
+                if !image_info.dependencies.is_empty() {
+                    s.sublist("dependencies", |s| {
+                        for dependency in &image_info.dependencies {
+                            s.key_value(
+                                format!("image {}", dependency.image_id),
+                                format!(">= {}", dependency.min_version),
+                            );
+                        }
+                    });
+                }
+            })?;
+        }
+        MCUbootCommand::VerifySignature { file, key } => {
+            let (image_data, _source_filename) = read_image_file(&file)?;
+            let (public_key, _key_filename) = read_input_file(&key)?;
+            let algorithm = zephyr_mcumgr::mcuboot::verify_signature(
+                std::io::Cursor::new(image_data.as_ref()),
+                &public_key,
+            )?;
+
+            structured_print(Some(file), args.json, |s| {
+                s.key_value("signature", format!("{algorithm:?}"));
+            })?;
+        }
+        MCUbootCommand::ParseTrailer { file } => {
+            let (slot_data, _source_filename) = read_input_file(&file)?;
+            let trailer = zephyr_mcumgr::mcuboot::parse_trailer(&slot_data)?;
+
+            structured_print(Some(file), args.json, |s| {
+                s.key_value("magic", format!("{:?}", trailer.magic));
+                if let Some(swap_info) = trailer.swap_info {
+                    s.key_value("swap_type", format!("{:?}", swap_info.swap_type));
+                    s.key_value("swap_image", swap_info.image);
+                }
+                s.key_value("copy_done", format!("{:?}", trailer.copy_done));
+                s.key_value("image_ok", format!("{:?}", trailer.image_ok));
+                s.key_value_maybe("swap_size", trailer.swap_size);
             })?;
         }
     }
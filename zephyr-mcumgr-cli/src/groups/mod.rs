@@ -2,16 +2,27 @@ use indicatif::MultiProgress;
 
 use crate::{args::CommonArgs, client::Client, errors::CliError};
 
+mod benchmark;
+mod datetime;
+mod echo;
+mod enumerate;
 mod fs;
 mod image;
+mod list_ports;
 mod mcuboot;
 mod os;
 mod raw;
+mod reset;
+mod settings;
 mod shell;
+mod taskstats;
+mod update;
 mod zephyr;
 
-#[derive(Debug, clap::Subcommand)]
+#[derive(Debug, Clone, clap::Subcommand)]
 pub enum Group {
+    /// Send echoes to test the connection and measure the round trip time
+    Echo(#[command(flatten)] echo::EchoCommand),
     /// Default/OS Management
     Os {
         #[command(subcommand)]
@@ -32,19 +43,57 @@ pub enum Group {
         #[command(subcommand)]
         command: fs::FsCommand,
     },
+    /// Device RTC datetime
+    Datetime {
+        #[command(subcommand)]
+        command: datetime::DatetimeCommand,
+    },
+    /// Settings Management
+    Settings {
+        #[command(subcommand)]
+        command: settings::SettingsCommand,
+    },
     /// Shell command execution
     Shell {
+        /// Open an interactive shell session on the device
+        #[arg(short, long, conflicts_with = "argv")]
+        interactive: bool,
+        /// Execute the commands of a script file, one per line. '-' for stdin.
+        ///
+        /// Empty lines and lines starting with '#' are ignored.
+        #[arg(long, conflicts_with_all = ["argv", "interactive"])]
+        script: Option<String>,
+        /// Continue the script after a command returned a non-zero exit code
+        #[arg(long, requires = "script")]
+        keep_going: bool,
         /// The shell command to execute
-        #[arg(required = true, trailing_var_arg = true)]
+        #[arg(
+            required_unless_present_any = ["interactive", "script"],
+            trailing_var_arg = true
+        )]
         argv: Vec<String>,
     },
+    /// Show live task statistics as a table, similar to `top`
+    Taskstats(#[command(flatten)] taskstats::TaskstatsCommand),
+    /// List the management groups the device supports
+    ///
+    /// Requires `CONFIG_MCUMGR_GRP_ENUM` on the device.
+    Enumerate,
     /// Zephyr Management
     Zephyr {
         #[command(subcommand)]
         command: zephyr::ZephyrCommand,
     },
+    /// List the available USB serial ports
+    ListPorts(#[command(flatten)] list_ports::ListPortsCommand),
+    /// Measure the echo round trip time and the file transfer throughput
+    Benchmark(#[command(flatten)] benchmark::BenchmarkCommand),
+    /// Reset the device, optionally into the bootloader
+    Reset(#[command(flatten)] reset::ResetCommand),
     /// Execute a raw SMP command
     Raw(#[command(flatten)] raw::RawCommand),
+    /// Perform a firmware update: upload, test boot and optionally confirm an image
+    Update(#[command(flatten)] update::UpdateCommand),
 }
 
 pub fn run(
@@ -54,13 +103,41 @@ pub fn run(
     group: Group,
 ) -> Result<(), CliError> {
     match group {
+        Group::Echo(echo_command) => echo::run(client, multiprogress, args, echo_command),
         Group::Os { command } => os::run(client, multiprogress, args, command),
         Group::Image { command } => image::run(client, multiprogress, args, command),
         Group::Mcuboot { command } => mcuboot::run(client, multiprogress, args, command),
         Group::Fs { command } => fs::run(client, multiprogress, args, command),
-        Group::Shell { argv } => shell::run(client, multiprogress, args, argv),
+        Group::Datetime { command } => datetime::run(client, multiprogress, args, command),
+        Group::Settings { command } => settings::run(client, multiprogress, args, command),
+        Group::Shell {
+            interactive,
+            script,
+            keep_going,
+            argv,
+        } => {
+            if interactive {
+                shell::run_interactive(client, multiprogress, args)
+            } else if let Some(script) = script {
+                shell::run_script(client, multiprogress, args, &script, keep_going)
+            } else {
+                shell::run(client, multiprogress, args, argv)
+            }
+        }
+        Group::Taskstats(taskstats_command) => {
+            taskstats::run(client, multiprogress, args, taskstats_command)
+        }
+        Group::Enumerate => enumerate::run(client, multiprogress, args),
         Group::Zephyr { command } => zephyr::run(client, multiprogress, args, command),
+        Group::ListPorts(list_ports_command) => {
+            list_ports::run(client, multiprogress, args, list_ports_command)
+        }
+        Group::Benchmark(benchmark_command) => {
+            benchmark::run(client, multiprogress, args, benchmark_command)
+        }
+        Group::Reset(reset_command) => reset::run(client, multiprogress, args, reset_command),
         Group::Raw(raw_command) => raw::run(client, multiprogress, args, raw_command),
+        Group::Update(update_command) => update::run(client, multiprogress, args, update_command),
     }
 }
 
@@ -3,13 +3,17 @@ use std::collections::HashSet;
 use indicatif::MultiProgress;
 use zephyr_mcumgr::{
     bootloader::{BootloaderInfo, MCUbootMode},
-    commands::os::ThreadStateFlags,
+    commands::{
+        fs::file_upload_max_data_chunk_size, image::image_upload_max_data_chunk_size,
+        os::ThreadStateFlags,
+    },
     connection::ExecuteError,
+    transport::SmpVersion,
 };
 
 use crate::{args::CommonArgs, client::Client, errors::CliError, formatting::structured_print};
 
-#[derive(Debug, clap::Subcommand)]
+#[derive(Debug, Clone, clap::Subcommand)]
 pub enum OsCommand {
     /// Executes an echo command on the device
     Echo {
@@ -44,6 +48,10 @@ pub enum OsCommand {
         bootmode: Option<u8>,
     },
     /// Fetch parameters from the MCUmgr library
+    ///
+    /// Also shows the resulting maximum upload chunk sizes, and the number of
+    /// upload chunks that can be in flight at the same time. The file chunk size
+    /// assumes a 64 character path; longer paths leave less room for data.
     McumgrParameters,
     /// Fetch information on the running image, similar to `uname`
     ///
@@ -57,7 +65,7 @@ pub enum OsCommand {
     BootloaderInfo,
 }
 
-#[derive(Debug, clap::Args)]
+#[derive(Debug, Clone, clap::Args)]
 pub struct ApplicationInfoFlags {
     /// Kernel name
     #[arg(short = 's', long)]
@@ -138,7 +146,14 @@ pub fn run(
 ) -> Result<(), CliError> {
     let client = client.get()?;
     match command {
-        OsCommand::Echo { msg } => println!("{}", client.os_echo(msg)?),
+        OsCommand::Echo { msg } => {
+            let response = client.os_echo(msg)?;
+            if args.json {
+                structured_print(None, true, |s| s.key_value("response", response))?;
+            } else {
+                println!("{response}");
+            }
+        }
         OsCommand::TaskStatistics => {
             let tasks_map = client.os_task_statistics()?;
 
@@ -168,8 +183,7 @@ pub fn run(
                             if let (Some(stkuse), Some(stksiz)) = (stats.stkuse, stats.stksiz) {
                                 s.key_value(
                                     "Stack Usage",
-                                    if stksiz != 0 {
-                                        let pct = stkuse * 100 / stksiz;
+                                    if let Some(pct) = (stkuse * 100).checked_div(stksiz) {
                                         format!("{stkuse} / {stksiz} bytes ({pct} %)")
                                     } else {
                                         format!("{stkuse} / {stksiz} bytes")
@@ -188,51 +202,30 @@ pub fn run(
             }
         }
         OsCommand::SetDatetime { value, utc } => {
-            use chrono::{DateTime, FixedOffset, NaiveDateTime};
-
-            let datetime_value = if let Some(value) = value {
-                value
-                    .parse::<DateTime<FixedOffset>>()
-                    .map(|datetime| {
-                        if utc {
-                            datetime.naive_utc()
-                        } else {
-                            datetime.naive_local()
-                        }
-                    })
-                    .or_else(|_| value.parse::<NaiveDateTime>())?
-            } else {
-                let now = chrono::Local::now();
-                if utc {
-                    now.naive_utc()
-                } else {
-                    now.naive_local()
-                }
-            };
-
-            client.os_set_datetime(datetime_value)?;
-
-            if args.verbose {
-                println!("Set device time to: {}", datetime_value.format("%F %T"));
-            }
-        }
-        OsCommand::GetDatetime => {
-            let datetime = client.os_get_datetime()?;
-            if args.verbose {
-                println!("Device time: {}", datetime);
-            } else {
-                println!("{:?}", datetime);
-            }
+            super::datetime::set_datetime(client, &args, value, utc)?
         }
+        OsCommand::GetDatetime => super::datetime::get_datetime(client, &args)?,
         OsCommand::SystemReset { force, bootmode } => {
             client.os_system_reset(force, bootmode)?;
         }
         OsCommand::McumgrParameters => {
             let params = client.os_mcumgr_parameters()?;
 
+            const EXAMPLE_FILE_PATH_LENGTH: usize = 64;
+            let frame_size = params.buf_size as usize;
+            let image_chunk_size = image_upload_max_data_chunk_size(frame_size).ok();
+            let file_chunk_size =
+                file_upload_max_data_chunk_size(frame_size, &"a".repeat(EXAMPLE_FILE_PATH_LENGTH))
+                    .ok();
+            // One buffer is needed for the response
+            let upload_window_size = params.buf_count.saturating_sub(1).max(1);
+
             structured_print(Some("MCUmgr Parameters".to_string()), args.json, |s| {
                 s.key_value("buf_size", params.buf_size);
                 s.key_value("buf_count", params.buf_count);
+                s.key_value_maybe("max_image_chunk_size", image_chunk_size);
+                s.key_value_maybe("max_file_chunk_size", file_chunk_size);
+                s.key_value("upload_window_size", upload_window_size);
             })?;
         }
         OsCommand::ApplicationInfo(flags) => {
@@ -270,6 +263,13 @@ pub fn run(
                     s.key_value("Processor", processor);
                     s.key_value("Hardware platform", hardware_platform);
                     s.key_value("Operating system", operating_system);
+                    match client.smp_version() {
+                        Some(SmpVersion::V1) => {
+                            s.key_value("SMP version", "1 (no group error codes)")
+                        }
+                        Some(SmpVersion::V2) => s.key_value("SMP version", "2"),
+                        None => {}
+                    }
                 })?;
             } else {
                 let output = client.os_application_info(Some(&flags.iter().collect::<String>()))?;
@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use indicatif::MultiProgress;
 
 use crate::{args::CommonArgs, client::Client, errors::CliError};
@@ -10,11 +12,30 @@ pub enum RawCommandOp {
     Write,
 }
 
-fn parse_raw_command_data(s: &str) -> Result<ciborium::Value, serde_json::Error> {
-    serde_json::from_str(s)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+pub enum RawOutputFormat {
+    /// CBOR diagnostic notation
+    Diag,
+    /// JSON
+    Json,
+    /// Annotated hexdump of the CBOR data
+    Hex,
+    /// Write the CBOR data to the file given by `--output-file`
+    CborFile,
+}
+
+/// Parses JSON or CBOR diagnostic notation.
+fn parse_raw_command_data(s: &str) -> Result<ciborium::Value, String> {
+    if let Ok(value) = serde_json::from_str(s) {
+        return Ok(value);
+    }
+
+    let item = cbor_diag::parse_diag(s)
+        .map_err(|_| "invalid JSON or CBOR diagnostic notation".to_string())?;
+    ciborium::from_reader(item.to_bytes().as_slice()).map_err(|e| e.to_string())
 }
 
-#[derive(Debug, clap::Args)]
+#[derive(Debug, Clone, clap::Args)]
 pub struct RawCommand {
     /// Whether this is a read or write command
     #[arg(value_enum)]
@@ -23,9 +44,17 @@ pub struct RawCommand {
     pub group_id: u16,
     /// The command ID
     pub command_id: u8,
-    /// The payload of the command, as JSON
+    /// The payload of the command, as JSON or CBOR diagnostic notation
+    ///
+    /// Example: `{"name": "foo", "data": h'0102ff'}`
     #[arg(value_parser=parse_raw_command_data, default_value = "{}")]
     pub data: ciborium::Value,
+    /// How to print the response
+    #[arg(long, value_enum, default_value_t = RawOutputFormat::Json)]
+    pub output: RawOutputFormat,
+    /// The file to write the response to, for `--output cbor-file`
+    #[arg(long, required_if_eq("output", "cbor-file"))]
+    pub output_file: Option<PathBuf>,
 }
 
 impl zephyr_mcumgr::commands::McuMgrCommand for RawCommand {
@@ -59,12 +88,33 @@ pub fn run(
     command: RawCommand,
 ) -> Result<(), CliError> {
     let client = client.get()?;
-    let response = client.raw_command(&command)?;
+    let response = client.execute(&command)?;
 
-    let json_response =
-        serde_json::to_string_pretty(&response).map_err(CliError::JsonEncodeError)?;
+    if command.output == RawOutputFormat::Json {
+        let json_response =
+            serde_json::to_string_pretty(&response).map_err(CliError::JsonEncodeError)?;
+        println!("{json_response}");
+        return Ok(());
+    }
+
+    let mut cbor = vec![];
+    ciborium::into_writer(&response, &mut cbor)
+        .map_err(|e| CliError::CborConversionFailed(e.to_string()))?;
 
-    println!("{json_response}");
+    match (command.output, command.output_file) {
+        (RawOutputFormat::CborFile, Some(path)) => {
+            std::fs::write(path, cbor).map_err(CliError::OutputWriteFailed)?;
+        }
+        (output, _) => {
+            let item = cbor_diag::parse_bytes(&cbor)
+                .map_err(|e| CliError::CborConversionFailed(e.to_string()))?;
+            if output == RawOutputFormat::Hex {
+                println!("{}", item.to_hex());
+            } else {
+                println!("{}", item.to_diag_pretty());
+            }
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,39 @@
+use indicatif::MultiProgress;
+
+use crate::{args::CommonArgs, client::Client, errors::CliError};
+
+/// The boot mode that makes the bootloader enter its recovery mode,
+/// for example the MCUboot serial recovery.
+const BOOT_MODE_BOOTLOADER: u8 = 1;
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct ResetCommand {
+    /// Reset even if the device vetoes the reset
+    #[arg(short, long)]
+    force: bool,
+    /// Overwrite the boot mode
+    ///
+    /// - 0: Normal system boot
+    /// - 1: Bootloader recovery mode
+    ///
+    /// Requires `CONFIG_MCUMGR_GRP_OS_RESET_BOOT_MODE`
+    #[arg(verbatim_doc_comment)]
+    #[arg(long)]
+    boot_mode: Option<u8>,
+    /// Boot into the bootloader recovery mode, same as `--boot-mode 1`
+    #[arg(long, conflicts_with = "boot_mode")]
+    bootloader: bool,
+}
+
+pub fn run(
+    client: &Client,
+    _multiprogress: &MultiProgress,
+    _args: CommonArgs,
+    command: ResetCommand,
+) -> Result<(), CliError> {
+    let boot_mode = command
+        .boot_mode
+        .or(command.bootloader.then_some(BOOT_MODE_BOOTLOADER));
+    client.get()?.os_system_reset(command.force, boot_mode)?;
+    Ok(())
+}
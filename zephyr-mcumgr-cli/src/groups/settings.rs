@@ -0,0 +1,112 @@
+use indicatif::MultiProgress;
+
+use crate::{args::CommonArgs, client::Client, errors::CliError};
+
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum SettingsCommand {
+    /// Read the value of a setting
+    Read {
+        /// The name of the setting, e.g. `app/serial`
+        key: String,
+        #[command(flatten)]
+        format: ValueFormat,
+    },
+    /// Write the value of a setting
+    ///
+    /// The new value only takes effect after `settings commit`
+    /// and only persists across reboots after `settings save`.
+    #[command(verbatim_doc_comment)]
+    Write {
+        /// The name of the setting, e.g. `app/serial`
+        key: String,
+        /// The new value, in the format given by the format flags
+        value: String,
+        #[command(flatten)]
+        format: ValueFormat,
+    },
+    /// Delete a setting
+    Delete {
+        /// The name of the setting, e.g. `app/serial`
+        key: String,
+    },
+    /// Apply all pending setting changes to the running application
+    Commit,
+    /// Load all settings from persistent storage
+    Load,
+    /// Save all settings to persistent storage
+    Save,
+}
+
+/// The representation of a setting value on the command line.
+#[derive(Debug, Clone, Copy, clap::Args)]
+#[group(multiple = false)]
+pub struct ValueFormat {
+    /// The value is raw data, given as hex string (default)
+    #[arg(long)]
+    hex: bool,
+    /// The value is a UTF-8 string
+    #[arg(long)]
+    string: bool,
+    /// The value is a little-endian 32-bit unsigned integer
+    #[arg(long)]
+    u32: bool,
+}
+
+impl ValueFormat {
+    fn encode(&self, value: &str) -> Result<Vec<u8>, CliError> {
+        if self.string {
+            Ok(value.as_bytes().to_vec())
+        } else if self.u32 {
+            let value = match value.strip_prefix("0x") {
+                Some(hex) => u32::from_str_radix(hex, 16),
+                None => value.parse(),
+            }
+            .map_err(|e| CliError::SettingValueInvalid(e.to_string()))?;
+            Ok(value.to_le_bytes().to_vec())
+        } else {
+            hex::decode(value).map_err(|e| CliError::SettingValueInvalid(e.to_string()))
+        }
+    }
+
+    fn decode(&self, data: &[u8]) -> Result<serde_json::Value, CliError> {
+        if self.string {
+            String::from_utf8(data.to_vec())
+                .map(Into::into)
+                .map_err(|_| CliError::SettingFormatMismatch("not a valid UTF-8 string"))
+        } else if self.u32 {
+            data.try_into()
+                .map(|bytes| u32::from_le_bytes(bytes).into())
+                .map_err(|_| CliError::SettingFormatMismatch("not 4 bytes long"))
+        } else {
+            Ok(hex::encode(data).into())
+        }
+    }
+}
+
+pub fn run(
+    client: &Client,
+    _multiprogress: &MultiProgress,
+    args: CommonArgs,
+    command: SettingsCommand,
+) -> Result<(), CliError> {
+    let client = client.get()?;
+
+    match command {
+        SettingsCommand::Read { key, format } => {
+            let value = format.decode(&client.settings_read(key, None)?)?;
+            match value {
+                serde_json::Value::String(value) if !args.json => println!("{value}"),
+                value => println!("{value}"),
+            }
+        }
+        SettingsCommand::Write { key, value, format } => {
+            client.settings_write(key, &format.encode(&value)?)?
+        }
+        SettingsCommand::Delete { key } => client.settings_delete(key)?,
+        SettingsCommand::Commit => client.settings_commit()?,
+        SettingsCommand::Load => client.settings_load()?,
+        SettingsCommand::Save => client.settings_save()?,
+    }
+
+    Ok(())
+}
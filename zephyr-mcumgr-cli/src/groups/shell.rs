@@ -1,21 +1,236 @@
+use std::cell::RefCell;
+
 use indicatif::MultiProgress;
+use rustyline::{
+    Context, Editor, Helper, completion::Completer, error::ReadlineError, highlight::Highlighter,
+    hint::Hinter, history::DefaultHistory, validate::Validator,
+};
+use zephyr_mcumgr::{Errno, MCUmgrClient};
+
+use crate::{
+    args::CommonArgs,
+    client::Client,
+    errors::CliError,
+    file_read_write::read_input_file,
+    formatting::{print_json_error, structured_print},
+    remote_paths::RemotePathCompleter,
+};
+
+/// Splits a command line into arguments like a POSIX shell.
+///
+/// Supports single quotes, double quotes and backslash escapes.
+/// Returns `None` if a quote is not terminated.
+fn split_command_line(line: &str) -> Option<Vec<String>> {
+    let mut argv = vec![];
+    let mut current: Option<String> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => argv.extend(current.take()),
+            '\\' => current
+                .get_or_insert_default()
+                .push(chars.next().unwrap_or('\\')),
+            '\'' => {
+                let arg = current.get_or_insert_default();
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => arg.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_default();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            c @ ('"' | '\\' | '$' | '`') => arg.push(c),
+                            c => arg.extend(['\\', c]),
+                        },
+                        c => arg.push(c),
+                    }
+                }
+            }
+            c => current.get_or_insert_default().push(c),
+        }
+    }
+    argv.extend(current);
+
+    Some(argv)
+}
+
+/// Completes remote paths in the interactive shell.
+///
+/// Lives as long as the session, so directory listings get reused across completions.
+struct ShellHelper<'a> {
+    client: &'a MCUmgrClient,
+    completer: RefCell<RemotePathCompleter>,
+}
+
+impl Completer for ShellHelper<'_> {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos]
+            .rfind(char::is_whitespace)
+            .map_or(0, |index| index + 1);
+        let word = &line[start..pos];
+
+        // Only absolute paths can be completed without knowing the shell's working directory
+        if !word.starts_with('/') {
+            return Ok((start, vec![]));
+        }
 
-use crate::{args::CommonArgs, client::Client, errors::CliError};
+        let candidates = self.completer.borrow_mut().complete(self.client, word);
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ShellHelper<'_> {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper<'_> {}
+
+impl Validator for ShellHelper<'_> {}
+
+impl Helper for ShellHelper<'_> {}
+
+fn print_json_result(returncode: i32, output: &str) -> Result<(), CliError> {
+    structured_print(None, true, |s| {
+        s.key_value("output", output);
+        s.key_value("exit_code", returncode);
+        if returncode < 0 {
+            s.key_value("error", Errno::errno_to_string(returncode));
+        }
+    })
+}
+
+/// Prints the result of a command that is part of a session or script.
+fn print_session_result(json: bool, returncode: i32, output: &str) -> Result<(), CliError> {
+    if json {
+        return print_json_result(returncode, output);
+    }
+
+    if !output.is_empty() {
+        println!("{}", output.trim_end());
+    }
+    if returncode < 0 {
+        println!("Exit code: {}", Errno::errno_to_string(returncode));
+    } else if returncode > 0 {
+        println!("Exit code: {returncode}");
+    }
+    Ok(())
+}
 
 pub fn run(
     client: &Client,
     _multiprogress: &MultiProgress,
-    _args: CommonArgs,
+    args: CommonArgs,
     argv: Vec<String>,
 ) -> Result<(), CliError> {
     let client = client.get()?;
     let (returncode, output) = client.shell_execute(&argv)?;
-    println!("{output}");
+    if args.json {
+        print_json_result(returncode, &output)?;
+    } else {
+        println!("{output}");
+    }
     if returncode < 0 {
         return Err(CliError::ShellExitCode(returncode));
-    } else if returncode > 0 {
+    } else if returncode > 0 && !args.json {
         println!();
         println!("Exit code: {returncode}")
     }
     Ok(())
 }
+
+pub fn run_interactive(
+    client: &Client,
+    _multiprogress: &MultiProgress,
+    args: CommonArgs,
+) -> Result<(), CliError> {
+    let client = client.get()?;
+    let mut editor = Editor::<ShellHelper, DefaultHistory>::new()?;
+    editor.set_helper(Some(ShellHelper {
+        client,
+        completer: RefCell::default(),
+    }));
+
+    if !args.json {
+        println!("Connected to device shell. Press Ctrl+D to exit.");
+    }
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let Some(argv) = split_command_line(&line) else {
+            eprintln!("Unterminated quote");
+            continue;
+        };
+        if argv.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line.as_str())?;
+
+        // Errors of a single command should not end the session
+        match client.shell_execute(&argv) {
+            Ok((returncode, output)) => print_session_result(args.json, returncode, &output)?,
+            Err(e) if args.json => print_json_error(&e),
+            Err(e) => eprintln!("{:?}", miette::Report::new(e)),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn run_script(
+    client: &Client,
+    _multiprogress: &MultiProgress,
+    args: CommonArgs,
+    script: &str,
+    keep_going: bool,
+) -> Result<(), CliError> {
+    let client = client.get()?;
+
+    let (content, _) = read_input_file(script)?;
+    let lines = String::from_utf8_lossy(&content)
+        .lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| {
+            split_command_line(line).ok_or_else(|| CliError::ShellScriptSyntax {
+                line: index + 1,
+                reason: "unterminated quote",
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let results = client.shell_run_script(&lines, !keep_going)?;
+
+    for (argv, (returncode, output)) in lines.iter().zip(&results) {
+        if !args.json {
+            println!("> {}", argv.join(" "));
+        }
+        print_session_result(args.json, *returncode, output)?;
+    }
+
+    if let Some(&(returncode, _)) = results.iter().find(|(returncode, _)| *returncode != 0) {
+        return Err(CliError::ShellExitCode(returncode));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+use std::{collections::HashMap, time::Duration};
+
+use console::Term;
+use indicatif::MultiProgress;
+use zephyr_mcumgr::{
+    client::{TaskStatsMonitor, TaskUsage},
+    commands::os::ThreadStateFlags,
+};
+
+use crate::{args::CommonArgs, client::Client, errors::CliError, formatting::print_table};
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct TaskstatsCommand {
+    /// Refresh the table in place every given number of seconds, until interrupted
+    ///
+    /// From the second refresh on, the CPU usage of every task is shown.
+    #[arg(short, long, value_name = "SECONDS")]
+    watch: Option<f64>,
+}
+
+const USAGE_BAR_WIDTH: u64 = 10;
+
+fn usage_bar(used: u64, size: u64) -> String {
+    let Some(pct) = (used * 100).checked_div(size) else {
+        return String::new();
+    };
+    let filled = (pct * USAGE_BAR_WIDTH / 100).min(USAGE_BAR_WIDTH) as usize;
+    format!(
+        "[{}{}] {pct:>3}%",
+        "#".repeat(filled),
+        " ".repeat(USAGE_BAR_WIDTH as usize - filled)
+    )
+}
+
+const HEADER: [&str; 7] = ["NAME", "PRIO", "STATE", "STACK", "USAGE", "RUNTIME", "CPU"];
+
+fn table_rows(tasks_map: &HashMap<String, TaskUsage>) -> Vec<[String; 7]> {
+    let mut tasks = tasks_map.iter().collect::<Vec<_>>();
+    tasks.sort_by_key(|(name, task)| (task.stats.prio, (*name).clone()));
+
+    tasks
+        .into_iter()
+        .map(|(name, task)| {
+            let stats = &task.stats;
+            let state = ThreadStateFlags::pretty_print(stats.state as u8).replace(" | ", ",");
+            let (stack, usage) = match (stats.stkuse, stats.stksiz) {
+                (Some(stkuse), Some(stksiz)) => {
+                    (format!("{stkuse}/{stksiz}"), usage_bar(stkuse, stksiz))
+                }
+                _ => Default::default(),
+            };
+
+            [
+                name.clone(),
+                stats.prio.to_string(),
+                if state.is_empty() { "-".into() } else { state },
+                stack,
+                usage,
+                stats.runtime.map(|r| r.to_string()).unwrap_or_default(),
+                task.cpu_percent
+                    .map(|cpu| format!("{cpu:.1}%"))
+                    .unwrap_or_default(),
+            ]
+        })
+        .collect()
+}
+
+pub fn run(
+    client: &Client,
+    _multiprogress: &MultiProgress,
+    args: CommonArgs,
+    command: TaskstatsCommand,
+) -> Result<(), CliError> {
+    let client = client.get()?;
+
+    let interval = command
+        .watch
+        .map(Duration::try_from_secs_f64)
+        .transpose()
+        .map_err(|e| CliError::InvalidInterval(e.to_string()))?;
+
+    let term = Term::stdout();
+    let mut printed_lines = 0;
+    let mut monitor = TaskStatsMonitor::new(client);
+
+    loop {
+        let tasks = monitor.sample()?;
+
+        if args.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&tasks).map_err(CliError::JsonEncodeError)?
+            );
+        } else {
+            let rows = table_rows(&tasks);
+            // Only clear after the new data arrived, to avoid flickering
+            if term.is_term() {
+                term.clear_last_lines(printed_lines)
+                    .map_err(CliError::OutputWriteFailed)?;
+            }
+            print_table(HEADER, &rows);
+            printed_lines = rows.len() + 1;
+        }
+
+        let Some(interval) = interval else {
+            return Ok(());
+        };
+        std::thread::sleep(interval);
+    }
+}
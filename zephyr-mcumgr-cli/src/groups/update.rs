@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use indicatif::MultiProgress;
+use zephyr_mcumgr::client::FirmwareUpdateParams;
+
+use crate::{
+    args::CommonArgs, client::Client, errors::CliError, file_read_write::read_image_file,
+    progress::with_progress_bar,
+};
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct UpdateCommand {
+    /// The firmware image, binary or Intel HEX. '-' for stdin.
+    pub file: String,
+    /// Selects target image on the device. Default: 0
+    #[arg(long)]
+    pub image_index: Option<u32>,
+    /// Prevent firmware downgrades
+    #[arg(long)]
+    pub upgrade_only: bool,
+    /// Do not reboot the device after the upload
+    #[arg(long)]
+    pub no_reboot: bool,
+    /// Wait for the device to boot the new image and confirm it
+    ///
+    /// Optionally takes the timeout in seconds, e.g. `--confirm=120`. Default: 60
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "60",
+        conflicts_with = "no_reboot"
+    )]
+    pub confirm: Option<u64>,
+    /// Continue a previously interrupted upload instead of restarting it
+    #[arg(long)]
+    pub resume: bool,
+}
+
+pub fn run(
+    client: &Client,
+    multiprogress: &MultiProgress,
+    args: CommonArgs,
+    command: UpdateCommand,
+) -> Result<(), CliError> {
+    let client = client.get()?;
+    let (data, source_filename) = read_image_file(&command.file)?;
+
+    let params = FirmwareUpdateParams {
+        image_index: command.image_index,
+        upgrade_only: command.upgrade_only,
+        skip_reboot: command.no_reboot,
+        confirm_timeout: command.confirm.map(Duration::from_secs),
+        restart_upload: !command.resume,
+        ..Default::default()
+    };
+
+    with_progress_bar(
+        multiprogress,
+        &args,
+        source_filename.as_deref(),
+        |progress| client.firmware_update(&data, &params, progress),
+    )?;
+
+    Ok(())
+}
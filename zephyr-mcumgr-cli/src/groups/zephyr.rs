@@ -2,7 +2,7 @@ use indicatif::MultiProgress;
 
 use crate::{args::CommonArgs, client::Client, errors::CliError};
 
-#[derive(Debug, clap::Subcommand)]
+#[derive(Debug, Clone, clap::Subcommand)]
 pub enum ZephyrCommand {
     /// Erase the `storage_partition` flash partition
     EraseStorage,
@@ -2,11 +2,15 @@
 
 mod args;
 mod client;
+mod config;
+mod devices;
 mod errors;
 mod file_read_write;
 mod formatting;
 mod groups;
 mod progress;
+mod remote_paths;
+mod smp_trace;
 
 use client::Client;
 use indicatif::MultiProgress;
@@ -14,16 +18,20 @@ use indicatif_log_bridge::LogWrapper;
 
 use std::time::Duration;
 
-use clap::Parser;
-use zephyr_mcumgr::{MCUmgrClient, client::UsbSerialError};
+use zephyr_mcumgr::{
+    client::UsbSerialPorts, connection::RetryPolicy, transport::serial::SerialPacing,
+};
 
 use crate::errors::CliError;
 
-fn cli_main() -> Result<(), CliError> {
+fn cli_main(args: args::App) -> Result<(), CliError> {
     let multiprogress = {
-        let logger =
-            env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-                .build();
+        let mut builder =
+            env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+        if args.trace_smp == Some(smp_trace::TraceLevel::Serial) {
+            builder.filter_module("zephyr_mcumgr::transport::serial", log::LevelFilter::Trace);
+        }
+        let logger = builder.build();
         let level = logger.filter();
         let multiprogress = MultiProgress::new();
         LogWrapper::new(multiprogress.clone(), logger)
@@ -34,63 +42,129 @@ fn cli_main() -> Result<(), CliError> {
         multiprogress
     };
 
-    let args = args::App::parse();
-
-    let client = if let Some(serial_name) = args.serial {
-        let serial = serialport::new(serial_name, args.baud)
-            .timeout(Duration::from_millis(args.timeout))
-            .open()
-            .map_err(CliError::OpenSerialFailed)?;
-        Client::new(MCUmgrClient::new_from_serial(serial))
-    } else if let Some(identifier) = args.usb_serial {
-        let result = MCUmgrClient::new_from_usb_serial(
-            identifier,
-            args.baud,
-            Duration::from_millis(args.timeout),
-        );
-
-        if let Err(UsbSerialError::IdentifierEmpty { ports }) = &result {
-            if args.common.json {
-                println!(
-                    "{}",
-                    serde_json::to_string_pretty(ports).map_err(CliError::JsonEncodeError)?
-                );
+    let profile = config::load_profile(args.profile.as_deref())?;
+    let (serial, usb_serial) = if !args.serial.is_empty() || !args.usb_serial.is_empty() {
+        (args.serial.clone(), args.usb_serial.clone())
+    } else {
+        (
+            profile.serial.into_iter().collect(),
+            profile.usb_serial.into_iter().collect::<Vec<_>>(),
+        )
+    };
+    let baud = args.baud.or(profile.baud).unwrap_or(115200);
+    let timeout = Duration::from_millis(args.timeout.or(profile.timeout).unwrap_or(2000));
+    let retries = args.retries.or(profile.retries).unwrap_or(0);
+    let pacing = SerialPacing {
+        inter_frame_delay: Duration::from_millis(
+            args.frame_delay.or(profile.frame_delay).unwrap_or(0),
+        ),
+        max_write_burst: args.write_burst.or(profile.write_burst),
+    };
+
+    if usb_serial.iter().any(String::is_empty) {
+        let ports = UsbSerialPorts::available().map_err(CliError::ListPortsFailed)?;
+        if args.common.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&ports).map_err(CliError::JsonEncodeError)?
+            );
+        } else {
+            println!();
+            if ports.0.is_empty() {
+                println!("No USB serial ports available.");
             } else {
-                println!();
-                if ports.0.is_empty() {
-                    println!("No USB serial ports available.");
-                } else {
-                    println!("Available USB serial ports:");
-                    println!("{}", ports);
-                }
-                println!();
+                println!("Available USB serial ports:");
+                println!("{}", ports);
             }
-            std::process::exit(0);
+            println!();
         }
+        return Ok(());
+    }
 
-        Client::new(result?)
-    } else {
-        Client::default()
+    let Some(group) = &args.group else {
+        return Err(CliError::MissingCommand);
     };
 
-    if let Ok(client) = client.get() {
-        if let Err(e) = client.use_auto_frame_size() {
-            log::warn!("Failed to read SMP frame size from device, using slow default");
-            log::warn!("Reason: {e}");
-            log::warn!("Hint: Make sure that `CONFIG_MCUMGR_GRP_OS_MCUMGR_PARAMS` is enabled.");
-        }
+    let devices = devices::resolve(serial, usb_serial, args.all_matching)?;
+    let multiple_devices = devices.len() > 1;
+    if multiple_devices && (args.capture.is_some() || args.frame_log.is_some()) {
+        return Err(CliError::CaptureMultipleDevices);
     }
 
-    if let Some(group) = args.group {
-        groups::run(&client, &multiprogress, args.common, group)?;
-    } else {
-        client.get()?.check_connection()?;
-        println!("Device alive and responsive.");
+    let run = |device: Option<&devices::Device>| -> Result<(), CliError> {
+        let label = device
+            .filter(|_| multiple_devices)
+            .map(|device| device.label().to_string());
+
+        let client = match device {
+            Some(device) => Client::new(device.connect(baud, timeout, pacing)?),
+            None => Client::default(),
+        };
+
+        if let (Ok(client), Some(capture_path)) = (client.get(), &args.capture) {
+            let observer = zephyr_mcumgr::capture::pcapng_file_observer(capture_path)
+                .map_err(CliError::CaptureFileCreateFailed)?;
+            client.add_frame_observer(observer);
+        }
+
+        if let (Ok(client), Some(frame_log_path)) = (client.get(), &args.frame_log) {
+            let observer = zephyr_mcumgr::capture::frame_log_file_observer(frame_log_path)
+                .map_err(CliError::FrameLogOpenFailed)?;
+            client.add_frame_observer(observer);
+        }
+
+        if let (Ok(client), Some(_)) = (client.get(), args.trace_smp) {
+            client.add_frame_observer(smp_trace::stderr_observer(
+                multiprogress.clone(),
+                label.clone(),
+            ));
+        }
+
+        if let Ok(client) = client.get() {
+            client.set_retry_policy(RetryPolicy {
+                attempts: retries.saturating_add(1),
+                backoff: Duration::ZERO,
+            });
+        }
+
+        if let (Ok(client), Some(mtu)) = (client.get(), profile.mtu) {
+            client.set_frame_size(mtu);
+        } else if let Ok(client) = client.get() {
+            if let Err(e) = client.use_auto_frame_size() {
+                log::warn!("Failed to read SMP frame size from device, using slow default");
+                log::warn!("Reason: {e}");
+                log::warn!("Hint: Make sure that `CONFIG_MCUMGR_GRP_OS_MCUMGR_PARAMS` is enabled.");
+            }
+        }
+
+        let common = args::CommonArgs {
+            device: label,
+            ..args.common.clone()
+        };
+
+        groups::run(&client, &multiprogress, common, group.clone())?;
+
+        Ok(())
+    };
+
+    match devices.as_slice() {
+        [] => run(None)?,
+        [device] => run(Some(device))?,
+        devices => devices::run_parallel(devices, args.common.json, |device| run(Some(device)))?,
     }
 
     Ok(())
 }
 
 fn main() -> miette::Result<()> {
-    cli_main().map_err(Into::into)
+    let args = args::App::parse_with_env();
+    let json = args.common.json;
+
+    match cli_main(args) {
+        Err(e) if json => {
+            formatting::print_json_error(&e);
+            std::process::exit(1);
+        }
+        result => result.map_err(Into::into),
+    }
 }
@@ -1,25 +1,79 @@
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::{io::IsTerminal, time::Instant};
 
-pub fn with_progress_bar<T>(
+use indicatif::{DecimalBytes, HumanDuration, MultiProgress, ProgressBar, ProgressStyle};
+use zephyr_mcumgr::client::{ProgressTracker, TransferProgress};
+
+use crate::{args::CommonArgs, errors::CliError, formatting::structured_print};
+
+/// Runs a data transfer with a progress bar.
+///
+/// If stderr is not a terminal, prints a plain line every 10 percent instead.
+/// In JSON mode, prints a summary of the transfer instead.
+pub fn with_progress_bar<T, E>(
     multiprogress: &MultiProgress,
-    show: bool,
+    args: &CommonArgs,
     message: Option<&str>,
-    action: impl FnOnce(Option<&mut dyn FnMut(u64, u64) -> bool>) -> T,
-) -> T {
-    if show {
-        let mut progress = None;
+    action: impl FnOnce(Option<&mut dyn FnMut(u64, u64) -> bool>) -> Result<T, E>,
+) -> Result<T, CliError>
+where
+    CliError: From<E>,
+{
+    let show = !(args.quiet || args.json);
+    if !(show || args.json) {
+        return Ok(action(None)?);
+    }
+
+    let label = match (&args.device, message) {
+        (Some(device), Some(message)) => Some(format!("{device}: {message}")),
+        (Some(device), None) => Some(device.clone()),
+        (None, message) => message.map(str::to_string),
+    };
+
+    let start = Instant::now();
+    let mut transferred = 0;
+    let mut progress = None;
+
+    let interactive = std::io::stderr().is_terminal();
+    let mut last_step = None;
+    let mut plain_output = ProgressTracker::new(|progress: &TransferProgress| {
+        let step = (progress.fraction() * 10.0) as u64;
+        if last_step != Some(step) {
+            last_step = Some(step);
+            eprintln!(
+                "{}{:.0}% ({} / {}, {}/s, ETA {})",
+                label
+                    .as_ref()
+                    .map(|label| format!("{label}: "))
+                    .unwrap_or_default(),
+                progress.fraction() * 100.0,
+                DecimalBytes(progress.transferred),
+                DecimalBytes(progress.total),
+                DecimalBytes(progress.bytes_per_second as u64),
+                progress
+                    .eta
+                    .map(|eta| HumanDuration(eta).to_string())
+                    .unwrap_or_else(|| "unknown".to_string()),
+            );
+        }
+        true
+    });
+
+    let mut callback = |current, total| {
+        transferred = current;
 
-        let mut callback = |current, total| {
+        if show && !interactive {
+            plain_output.update(current, total);
+        } else if show {
             let progress = progress.get_or_insert_with(|| {
                 let progress = multiprogress.add(ProgressBar::new(total));
 
-                if let Some(message) = &message {
-                    progress.set_message(message.to_string());
+                if let Some(label) = &label {
+                    progress.set_message(label.clone());
                 }
 
                 progress.set_style(
                 ProgressStyle::with_template(
-                    "{msg} {wide_bar} {decimal_bytes:>9} / {decimal_total_bytes:9} ({decimal_bytes_per_sec:9})",
+                    "{msg} {wide_bar} {percent:>3}% {decimal_bytes:>9} / {decimal_total_bytes:9} ({decimal_bytes_per_sec:9}, ETA {eta})",
                 )
                 .unwrap());
 
@@ -28,18 +82,31 @@ pub fn with_progress_bar<T>(
 
             progress.set_length(total);
             progress.set_position(current);
-            true
-        };
+        }
+        true
+    };
 
-        let result = action(Some(&mut callback));
+    let result = action(Some(&mut callback));
 
-        if let Some(progress) = progress {
-            progress.finish();
-            multiprogress.remove(&progress);
-        }
+    if let Some(progress) = progress {
+        progress.finish();
+        multiprogress.remove(&progress);
+    }
+
+    let result = result?;
 
-        result
-    } else {
-        action(None)
+    if args.json {
+        let seconds = start.elapsed().as_secs_f64();
+        structured_print(None, true, |s| {
+            s.key_value_maybe("device", args.device.clone());
+            s.key_value_maybe("name", message);
+            s.key_value("bytes", transferred);
+            s.key_value("seconds", seconds);
+            if seconds > 0.0 {
+                s.key_value("bytes_per_second", (transferred as f64 / seconds) as u64);
+            }
+        })?;
     }
+
+    Ok(result)
 }
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use zephyr_mcumgr::MCUmgrClient;
+
+use crate::errors::CliError;
+
+/// A single entry of a directory on the device.
+#[derive(Debug, Clone)]
+pub struct RemoteDirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// Lists a directory on the device.
+///
+/// MCUmgr's file management group has no directory listing command,
+/// so this uses the `fs ls` command of the device shell instead.
+/// Requires `CONFIG_FILE_SYSTEM_SHELL` and `CONFIG_MCUMGR_GRP_SHELL`.
+pub fn list_remote_dir(client: &MCUmgrClient, path: &str) -> Result<Vec<RemoteDirEntry>, CliError> {
+    let argv = ["fs", "ls", path].map(String::from);
+    let (returncode, output) = client.shell_execute(&argv)?;
+    if returncode != 0 {
+        return Err(CliError::ShellExitCode(returncode));
+    }
+
+    Ok(output
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_suffix('/') {
+            Some(name) => RemoteDirEntry {
+                name: name.to_string(),
+                is_dir: true,
+            },
+            None => RemoteDirEntry {
+                name: line.to_string(),
+                is_dir: false,
+            },
+        })
+        .collect())
+}
+
+/// The content of a directory tree, as paths relative to its root.
+///
+/// Directories are listed before their content.
+#[derive(Debug, Default)]
+pub struct DirTree {
+    pub dirs: Vec<String>,
+    pub files: Vec<String>,
+}
+
+/// Recursively lists a directory on the device, see [`list_remote_dir`].
+pub fn walk_remote_dir(client: &MCUmgrClient, path: &str) -> Result<DirTree, CliError> {
+    fn walk(
+        client: &MCUmgrClient,
+        root: &str,
+        relative: &str,
+        tree: &mut DirTree,
+    ) -> Result<(), CliError> {
+        for entry in list_remote_dir(client, &join_remote_path(root, relative))? {
+            let entry_path = join_remote_path(relative, &entry.name);
+            if entry.is_dir {
+                tree.dirs.push(entry_path.clone());
+                walk(client, root, &entry_path, tree)?;
+            } else {
+                tree.files.push(entry_path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut tree = DirTree::default();
+    walk(client, path, "", &mut tree)?;
+    Ok(tree)
+}
+
+/// Creates a directory on the device, if it does not exist yet.
+///
+/// Uses the `fs mkdir` command of the device shell, see [`list_remote_dir`].
+pub fn create_remote_dir(client: &MCUmgrClient, path: &str) -> Result<(), CliError> {
+    if list_remote_dir(client, path).is_ok() {
+        return Ok(());
+    }
+
+    let argv = ["fs", "mkdir", path].map(String::from);
+    let (returncode, _output) = client.shell_execute(&argv)?;
+    if returncode != 0 {
+        return Err(CliError::ShellExitCode(returncode));
+    }
+
+    Ok(())
+}
+
+/// Creates a directory on the device, including all missing parents.
+pub fn create_remote_dir_all(client: &MCUmgrClient, path: &str) -> Result<(), CliError> {
+    let mut current = String::new();
+    for component in path.split('/').filter(|component| !component.is_empty()) {
+        current.push('/');
+        current.push_str(component);
+        create_remote_dir(client, &current)?;
+    }
+    Ok(())
+}
+
+/// Joins two remote path fragments with a single `/`.
+pub fn join_remote_path(base: &str, path: &str) -> String {
+    match (base.trim_end_matches('/'), path.trim_start_matches('/')) {
+        (base, "") => base.to_string(),
+        ("", path) if base.is_empty() => path.to_string(),
+        (base, path) => format!("{base}/{path}"),
+    }
+}
+
+/// Completes partially typed remote file paths.
+///
+/// Directory listings are cached, so repeated completions
+/// within the same directory don't cause additional traffic.
+#[derive(Default)]
+pub struct RemotePathCompleter {
+    cache: HashMap<String, Vec<RemoteDirEntry>>,
+}
+
+impl RemotePathCompleter {
+    /// Returns all remote paths that start with `partial`.
+    ///
+    /// Directories are returned with a trailing `/`.
+    /// Errors while listing are treated as 'no candidates'.
+    pub fn complete(&mut self, client: &MCUmgrClient, partial: &str) -> Vec<String> {
+        let (dir, prefix) = match partial.rsplit_once('/') {
+            Some((dir, prefix)) => (dir, prefix),
+            None => ("", partial),
+        };
+        let dir = dir.trim_start_matches('/');
+
+        if !self.cache.contains_key(dir) {
+            match list_remote_dir(client, &format!("/{dir}")) {
+                Ok(entries) => {
+                    self.cache.insert(dir.to_string(), entries);
+                }
+                Err(e) => {
+                    log::debug!("Unable to list remote directory '/{dir}': {e}");
+                    return vec![];
+                }
+            }
+        }
+
+        self.cache[dir]
+            .iter()
+            .filter(|entry| entry.name.starts_with(prefix))
+            .map(|entry| {
+                let mut path = if dir.is_empty() {
+                    format!("/{}", entry.name)
+                } else {
+                    format!("/{dir}/{}", entry.name)
+                };
+                if entry.is_dir {
+                    path.push('/');
+                }
+                path
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,67 @@
+use indicatif::MultiProgress;
+use zephyr_mcumgr::{
+    MCUmgrGroup,
+    connection::{FrameDirection, FrameObserver},
+    transport::SmpHeader,
+};
+
+/// The amount of detail printed by `--trace-smp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TraceLevel {
+    /// Decoded SMP headers and hex payloads
+    Frames,
+    /// Additionally the raw serial chunks, before base64 encoding
+    Serial,
+}
+
+fn op_to_string(op: u8) -> String {
+    match op {
+        0 => "read".to_string(),
+        1 => "read rsp".to_string(),
+        2 => "write".to_string(),
+        3 => "write rsp".to_string(),
+        op => format!("op {op}"),
+    }
+}
+
+fn format_frame(direction: FrameDirection, header: &SmpHeader, payload: &[u8]) -> String {
+    let arrow = match direction {
+        FrameDirection::Sent => "-->",
+        FrameDirection::Received => "<--",
+    };
+
+    let mut text = format!(
+        "{arrow} SMP v{} {}, group {} ({}), cmd {}, seq {}, len {}, flags 0x{:02x}",
+        header.ver,
+        op_to_string(header.op),
+        MCUmgrGroup::group_id_to_string(header.group_id),
+        header.group_id,
+        header.command_id,
+        header.sequence_num,
+        header.data_length,
+        header.flags,
+    );
+    for (row, chunk) in payload.chunks(16).enumerate() {
+        let bytes = chunk
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        text += &format!("\n    {:04x}: {bytes}", row * 16);
+    }
+
+    text
+}
+
+/// Creates a frame observer that prints every SMP frame to stderr.
+///
+/// If given, every frame is prefixed with the device it belongs to.
+pub fn stderr_observer(multiprogress: MultiProgress, device: Option<String>) -> FrameObserver {
+    Box::new(move |direction, header, payload| {
+        let mut text = format_frame(direction, header, payload);
+        if let Some(device) = &device {
+            text.insert_str(0, &format!("[{device}] "));
+        }
+        multiprogress.suspend(|| eprintln!("{text}"));
+    })
+}
@@ -0,0 +1,180 @@
+use alloc::{string::String, vec::Vec};
+
+use serde::{Deserialize, Serialize};
+
+use super::macros::impl_serialize_as_empty_map;
+
+/// [Count of supported groups](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_10.html#count-of-supported-groups) command
+#[derive(Debug, Eq, PartialEq)]
+pub struct GroupCount;
+impl_serialize_as_empty_map!(GroupCount);
+
+/// Response for [`GroupCount`] command
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct GroupCountResponse {
+    /// number of supported groups
+    pub count: u16,
+}
+
+/// [List supported groups](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_10.html#list-supported-groups) command
+#[derive(Debug, Eq, PartialEq)]
+pub struct ListGroups;
+impl_serialize_as_empty_map!(ListGroups);
+
+/// Response for [`ListGroups`] command
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct ListGroupsResponse {
+    /// IDs of the supported groups
+    pub groups: Vec<u16>,
+}
+
+/// [Fetch single group ID](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_10.html#fetch-single-group-id) command
+#[derive(Debug, Serialize, Eq, PartialEq)]
+pub struct GroupId {
+    /// index of the group to fetch
+    pub index: u32,
+}
+
+/// Response for [`GroupId`] command
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct GroupIdResponse {
+    /// ID of the group at the requested index
+    pub group: u16,
+    /// whether this is the last group
+    #[serde(default)]
+    pub end: bool,
+}
+
+/// [Details on supported groups](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_10.html#details-on-supported-groups) command
+#[derive(Debug, Serialize, Eq, PartialEq)]
+pub struct GroupDetails<'a> {
+    /// IDs of the groups to fetch the details of; all groups if `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub groups: Option<&'a [u16]>,
+}
+
+/// Details of a single group, see [`GroupDetailsResponse`]
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct GroupDetailsEntry {
+    /// ID of the group
+    pub group: u16,
+    /// name of the group
+    ///
+    /// Requires `CONFIG_MCUMGR_GRP_ENUM_DETAILS_NAME` on the device.
+    pub name: Option<String>,
+    /// number of command handlers of the group
+    ///
+    /// Requires `CONFIG_MCUMGR_GRP_ENUM_DETAILS_HANDLERS` on the device.
+    pub handlers: Option<u16>,
+}
+
+/// Response for [`GroupDetails`] command
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct GroupDetailsResponse {
+    /// details of the requested groups
+    pub groups: Vec<GroupDetailsEntry>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::macros::command_encode_decode_test;
+    use super::*;
+    use ciborium::cbor;
+
+    command_encode_decode_test! {
+        group_count,
+        (0, 10, 0),
+        GroupCount,
+        cbor!({}),
+        cbor!({
+            "count" => 3,
+        }),
+        GroupCountResponse{
+            count: 3,
+        },
+    }
+
+    command_encode_decode_test! {
+        list_groups,
+        (0, 10, 1),
+        ListGroups,
+        cbor!({}),
+        cbor!({
+            "groups" => [0, 1, 10],
+        }),
+        ListGroupsResponse{
+            groups: vec![0, 1, 10],
+        },
+    }
+
+    command_encode_decode_test! {
+        group_id,
+        (0, 10, 2),
+        GroupId{
+            index: 2,
+        },
+        cbor!({
+            "index" => 2,
+        }),
+        cbor!({
+            "group" => 10,
+            "end" => true,
+        }),
+        GroupIdResponse{
+            group: 10,
+            end: true,
+        },
+    }
+
+    command_encode_decode_test! {
+        group_details,
+        (0, 10, 3),
+        GroupDetails{
+            groups: Some(&[0, 64]),
+        },
+        cbor!({
+            "groups" => [0, 64],
+        }),
+        cbor!({
+            "groups" => [
+                {
+                    "group" => 0,
+                    "name" => "os",
+                    "handlers" => 6,
+                },
+                {
+                    "group" => 64,
+                },
+            ],
+        }),
+        GroupDetailsResponse{
+            groups: vec![
+                GroupDetailsEntry{
+                    group: 0,
+                    name: Some("os".into()),
+                    handlers: Some(6),
+                },
+                GroupDetailsEntry{
+                    group: 64,
+                    name: None,
+                    handlers: None,
+                },
+            ],
+        },
+    }
+
+    command_encode_decode_test! {
+        all_group_details,
+        (0, 10, 3),
+        GroupDetails{
+            groups: None,
+        },
+        cbor!({}),
+        cbor!({
+            "groups" => [],
+        }),
+        GroupDetailsResponse{
+            groups: vec![],
+        },
+    }
+}
@@ -1,12 +1,15 @@
-use std::collections::HashMap;
+use alloc::{boxed::Box, collections::BTreeMap, format, string::String, vec::Vec};
 
 use serde::{Deserialize, Serialize};
 use serde_repr::Deserialize_repr;
 use strum::Display;
 
-use crate::commands::{
-    CountingWriter, data_too_large_error,
-    macros::{impl_deserialize_from_empty_map_and_into_unit, impl_serialize_as_empty_map},
+use crate::{
+    DataTooLargeError,
+    commands::{
+        CountingWriter,
+        macros::{impl_deserialize_from_empty_map_and_into_unit, impl_serialize_as_empty_map},
+    },
 };
 
 use super::is_default;
@@ -40,7 +43,7 @@ pub struct FileDownloadResponse {
 pub fn file_upload_max_data_chunk_size(
     smp_frame_size: usize,
     filename: &str,
-) -> std::io::Result<usize> {
+) -> Result<usize, DataTooLargeError> {
     const MGMT_HDR_SIZE: usize = 8; // Size of SMP header
 
     let mut size_counter = CountingWriter::new();
@@ -53,19 +56,19 @@ pub fn file_upload_max_data_chunk_size(
         },
         &mut size_counter,
     )
-    .map_err(|_| data_too_large_error())?;
+    .map_err(|_| DataTooLargeError)?;
 
     let size_with_one_byte = size_counter.bytes_written;
     let size_without_data = size_with_one_byte - 1;
 
     let estimated_data_size = smp_frame_size
         .checked_sub(MGMT_HDR_SIZE)
-        .ok_or_else(data_too_large_error)?
+        .ok_or(DataTooLargeError)?
         .checked_sub(size_without_data)
-        .ok_or_else(data_too_large_error)?;
+        .ok_or(DataTooLargeError)?;
 
     let data_length_bytes = if estimated_data_size == 0 {
-        return Err(data_too_large_error());
+        return Err(DataTooLargeError);
     } else if estimated_data_size <= u8::MAX as usize {
         1
     } else if estimated_data_size <= u16::MAX as usize {
@@ -79,10 +82,10 @@ pub fn file_upload_max_data_chunk_size(
     // Remove data length entry from estimated data size
     let actual_data_size = estimated_data_size
         .checked_sub(data_length_bytes as usize)
-        .ok_or_else(data_too_large_error)?;
+        .ok_or(DataTooLargeError)?;
 
     if actual_data_size == 0 {
-        return Err(data_too_large_error());
+        return Err(DataTooLargeError);
     }
 
     Ok(actual_data_size)
@@ -184,7 +187,7 @@ impl_serialize_as_empty_map!(SupportedFileChecksumTypes);
 #[derive(Debug, Deserialize, Eq, PartialEq)]
 pub struct SupportedFileChecksumTypesResponse {
     /// names and properties of the hash/checksum types
-    pub r#types: HashMap<String, FileChecksumProperties>,
+    pub r#types: BTreeMap<String, FileChecksumProperties>,
 }
 
 /// Data format of the hash/checksum type
@@ -429,7 +432,7 @@ mod tests {
             },
         }),
         SupportedFileChecksumTypesResponse{
-            types: HashMap::from([
+            types: BTreeMap::from([
                 (
                     "crc32".to_string(),
                     FileChecksumProperties{
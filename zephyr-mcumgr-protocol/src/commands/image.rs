@@ -1,8 +1,13 @@
+use alloc::{string::String, vec::Vec};
+
 use serde::{Deserialize, Serialize};
 
-use crate::commands::{
-    CountingWriter, data_too_large_error,
-    macros::{impl_deserialize_from_empty_map_and_into_unit, impl_serialize_as_empty_map},
+use crate::{
+    DataTooLargeError,
+    commands::{
+        CountingWriter,
+        macros::{impl_deserialize_from_empty_map_and_into_unit, impl_serialize_as_empty_map},
+    },
 };
 
 fn serialize_option_hex<S, T>(data: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
@@ -133,7 +138,7 @@ pub struct ImageUploadResponse {
 ///
 /// * `smp_frame_size`  - The max allowed size of an SMP frame.
 ///
-pub fn image_upload_max_data_chunk_size(smp_frame_size: usize) -> std::io::Result<usize> {
+pub fn image_upload_max_data_chunk_size(smp_frame_size: usize) -> Result<usize, DataTooLargeError> {
     const MGMT_HDR_SIZE: usize = 8; // Size of SMP header
 
     let mut size_counter = CountingWriter::new();
@@ -148,19 +153,19 @@ pub fn image_upload_max_data_chunk_size(smp_frame_size: usize) -> std::io::Resul
         },
         &mut size_counter,
     )
-    .map_err(|_| data_too_large_error())?;
+    .map_err(|_| DataTooLargeError)?;
 
     let size_with_one_byte = size_counter.bytes_written;
     let size_without_data = size_with_one_byte - 1;
 
     let estimated_data_size = smp_frame_size
         .checked_sub(MGMT_HDR_SIZE)
-        .ok_or_else(data_too_large_error)?
+        .ok_or(DataTooLargeError)?
         .checked_sub(size_without_data)
-        .ok_or_else(data_too_large_error)?;
+        .ok_or(DataTooLargeError)?;
 
     let data_length_bytes = if estimated_data_size == 0 {
-        return Err(data_too_large_error());
+        return Err(DataTooLargeError);
     } else if estimated_data_size <= u8::MAX as usize {
         1
     } else if estimated_data_size <= u16::MAX as usize {
@@ -174,10 +179,10 @@ pub fn image_upload_max_data_chunk_size(smp_frame_size: usize) -> std::io::Resul
     // Remove data length entry from estimated data size
     let actual_data_size = estimated_data_size
         .checked_sub(data_length_bytes as usize)
-        .ok_or_else(data_too_large_error)?;
+        .ok_or(DataTooLargeError)?;
 
     if actual_data_size == 0 {
-        return Err(data_too_large_error());
+        return Err(DataTooLargeError);
     }
 
     Ok(actual_data_size)
@@ -117,7 +117,7 @@ macro_rules! impl_deserialize_from_empty_map_and_into_unit {
                 impl<'de> ::serde::de::Visitor<'de> for InternalVisitor {
                     type Value = $type;
 
-                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    fn expecting(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
                         write!(f, "an empty map/object (`{{}}`)")
                     }
 
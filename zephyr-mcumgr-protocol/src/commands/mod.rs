@@ -1,17 +1,28 @@
+/// [Enumeration management](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_10.html) group commands
+pub mod enumeration;
 /// [File management](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_8.html) group commands
 pub mod fs;
 /// [Application/software image management](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_1.html) group commands
 pub mod image;
 /// [Default/OS management](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_0.html) group commands
 pub mod os;
+/// [Settings management](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_3.html) group commands
+pub mod settings;
 /// [Shell management](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_9.html) group commands
 pub mod shell;
+/// [Statistics management](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_2.html) group commands
+pub mod stat;
+/// Commands of application specific management groups
+pub mod user;
 /// [Zephyr management](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_63.html) group commands
 pub mod zephyr;
 
 mod macros;
 use macros::impl_mcumgr_command;
 
+use alloc::string::String;
+
+use crate::DataTooLargeError;
 use serde::{Deserialize, Serialize};
 
 /// SMP version 2 group based error message
@@ -34,7 +45,10 @@ pub struct ErrResponse {
     pub err: Option<ErrResponseV2>,
 }
 
-/// An MCUmgr command that can be executed through [`Connection::execute_command`](crate::connection::Connection::execute_command).
+/// An MCUmgr command that can be sent to a device.
+///
+/// Commands of application specific management groups can implement this trait
+/// for their own request types, or use [`user::UserCommand`].
 pub trait McuMgrCommand {
     /// the data payload type
     type Payload: Serialize;
@@ -55,12 +69,6 @@ fn is_default<T: Default + PartialEq>(val: &T) -> bool {
     val == &T::default()
 }
 
-fn data_too_large_error() -> std::io::Error {
-    std::io::Error::other(Box::<dyn std::error::Error + Send + Sync>::from(
-        "Serialized data too large".to_string(),
-    ))
-}
-
 // A writer that simply counts the number of bytes written to it
 struct CountingWriter {
     bytes_written: usize,
@@ -70,17 +78,18 @@ impl CountingWriter {
         Self { bytes_written: 0 }
     }
 }
-impl std::io::Write for CountingWriter {
-    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
-        let len = buf.len();
+impl ciborium_io::Write for &mut CountingWriter {
+    type Error = DataTooLargeError;
+
+    fn write_all(&mut self, data: &[u8]) -> Result<(), Self::Error> {
         self.bytes_written = self
             .bytes_written
-            .checked_add(len)
-            .ok_or_else(data_too_large_error)?;
-        Ok(len)
+            .checked_add(data.len())
+            .ok_or(DataTooLargeError)?;
+        Ok(())
     }
 
-    fn flush(&mut self) -> std::io::Result<()> {
+    fn flush(&mut self) -> Result<(), Self::Error> {
         Ok(())
     }
 }
@@ -101,6 +110,16 @@ impl_mcumgr_command!((write,  MGMT_GROUP_ID_IMAGE, 1): image::ImageUpload<'_, '_
 impl_mcumgr_command!((write,  MGMT_GROUP_ID_IMAGE, 5): image::ImageErase => image::ImageEraseResponse);
 impl_mcumgr_command!((read,  MGMT_GROUP_ID_IMAGE, 6): image::SlotInfo => image::SlotInfoResponse);
 
+impl_mcumgr_command!((read,  MGMT_GROUP_ID_STAT, 0): stat::GroupData<'_> => stat::GroupDataResponse);
+impl_mcumgr_command!((read,  MGMT_GROUP_ID_STAT, 1): stat::ListGroups => stat::ListGroupsResponse);
+
+impl_mcumgr_command!((read,  MGMT_GROUP_ID_SETTINGS, 0): settings::SettingRead<'_> => settings::SettingReadResponse);
+impl_mcumgr_command!((write, MGMT_GROUP_ID_SETTINGS, 0): settings::SettingWrite<'_, '_> => settings::SettingWriteResponse);
+impl_mcumgr_command!((write, MGMT_GROUP_ID_SETTINGS, 1): settings::SettingDelete<'_> => settings::SettingDeleteResponse);
+impl_mcumgr_command!((write, MGMT_GROUP_ID_SETTINGS, 2): settings::SettingsCommit => settings::SettingsCommitResponse);
+impl_mcumgr_command!((read,  MGMT_GROUP_ID_SETTINGS, 3): settings::SettingsLoad => settings::SettingsLoadResponse);
+impl_mcumgr_command!((write, MGMT_GROUP_ID_SETTINGS, 3): settings::SettingsSave => settings::SettingsSaveResponse);
+
 impl_mcumgr_command!((write, MGMT_GROUP_ID_FS, 0): fs::FileUpload<'_, '_> => fs::FileUploadResponse);
 impl_mcumgr_command!((read,  MGMT_GROUP_ID_FS, 0): fs::FileDownload<'_> => fs::FileDownloadResponse);
 impl_mcumgr_command!((read,  MGMT_GROUP_ID_FS, 1): fs::FileStatus<'_> => fs::FileStatusResponse);
@@ -110,6 +129,11 @@ impl_mcumgr_command!((write, MGMT_GROUP_ID_FS, 4): fs::FileClose => fs::FileClos
 
 impl_mcumgr_command!((write, MGMT_GROUP_ID_SHELL, 0): shell::ShellCommandLineExecute<'_> => shell::ShellCommandLineExecuteResponse);
 
+impl_mcumgr_command!((read,  MGMT_GROUP_ID_ENUM, 0): enumeration::GroupCount => enumeration::GroupCountResponse);
+impl_mcumgr_command!((read,  MGMT_GROUP_ID_ENUM, 1): enumeration::ListGroups => enumeration::ListGroupsResponse);
+impl_mcumgr_command!((read,  MGMT_GROUP_ID_ENUM, 2): enumeration::GroupId => enumeration::GroupIdResponse);
+impl_mcumgr_command!((read,  MGMT_GROUP_ID_ENUM, 3): enumeration::GroupDetails<'_> => enumeration::GroupDetailsResponse);
+
 impl_mcumgr_command!((write, ZEPHYR_MGMT_GRP_BASIC, 0): zephyr::EraseStorage => zephyr::EraseStorageResponse);
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use alloc::{collections::BTreeMap, format, string::String, vec};
 
 use chrono::Timelike;
 use serde::{Deserialize, Serialize};
@@ -96,7 +96,7 @@ impl ThreadStateFlags {
 #[derive(Debug, Deserialize, Eq, PartialEq)]
 pub struct TaskStatisticsResponse {
     /// Dictionary of task names with their respective statistics
-    pub tasks: HashMap<String, TaskStatisticsEntry>,
+    pub tasks: BTreeMap<String, TaskStatisticsEntry>,
 }
 
 /// Parses a [`chrono::NaiveDateTime`] object with optional timezone specifiers
@@ -280,7 +280,7 @@ mod tests {
         TaskStatistics,
         cbor!({}),
         cbor!({"tasks" => {}}),
-        TaskStatisticsResponse{ tasks: HashMap::new() },
+        TaskStatisticsResponse{ tasks: BTreeMap::new() },
     }
 
     command_encode_decode_test! {
@@ -306,7 +306,7 @@ mod tests {
                 "next_checkin" => 0,
             },
         }}),
-        TaskStatisticsResponse{ tasks: HashMap::from([
+        TaskStatisticsResponse{ tasks: BTreeMap::from([
             (
                 "task_a".to_string(),
                 TaskStatisticsEntry{
@@ -0,0 +1,184 @@
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use super::macros::{impl_deserialize_from_empty_map_and_into_unit, impl_serialize_as_empty_map};
+
+/// [Read setting](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_3.html#read-setting-request) command
+#[derive(Debug, Serialize, Eq, PartialEq)]
+pub struct SettingRead<'a> {
+    /// string of the setting to retrieve
+    pub name: &'a str,
+    /// maximum size of data to return
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<u32>,
+}
+
+/// Response for [`SettingRead`] command
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct SettingReadResponse {
+    /// binary string of the returned data
+    #[serde(with = "serde_bytes")]
+    pub val: Vec<u8>,
+    /// maximum size of the setting, only present if the returned data was truncated
+    pub max_size: Option<u32>,
+}
+
+/// [Write setting](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_3.html#write-setting-request) command
+#[derive(Debug, Serialize, Eq, PartialEq)]
+pub struct SettingWrite<'a, 'b> {
+    /// string of the setting to update/set
+    pub name: &'a str,
+    /// value to set the setting to
+    #[serde(with = "serde_bytes")]
+    pub val: &'b [u8],
+}
+
+/// Response for [`SettingWrite`] command
+#[derive(Default, Debug, Eq, PartialEq)]
+pub struct SettingWriteResponse;
+impl_deserialize_from_empty_map_and_into_unit!(SettingWriteResponse);
+
+/// [Delete setting](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_3.html#delete-setting-command) command
+#[derive(Debug, Serialize, Eq, PartialEq)]
+pub struct SettingDelete<'a> {
+    /// string of the setting to delete
+    pub name: &'a str,
+}
+
+/// Response for [`SettingDelete`] command
+#[derive(Default, Debug, Eq, PartialEq)]
+pub struct SettingDeleteResponse;
+impl_deserialize_from_empty_map_and_into_unit!(SettingDeleteResponse);
+
+/// [Commit settings](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_3.html#commit-settings-command) command
+#[derive(Debug, Eq, PartialEq)]
+pub struct SettingsCommit;
+impl_serialize_as_empty_map!(SettingsCommit);
+
+/// Response for [`SettingsCommit`] command
+#[derive(Default, Debug, Eq, PartialEq)]
+pub struct SettingsCommitResponse;
+impl_deserialize_from_empty_map_and_into_unit!(SettingsCommitResponse);
+
+/// [Load settings](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_3.html#load-save-settings-command) command
+#[derive(Debug, Eq, PartialEq)]
+pub struct SettingsLoad;
+impl_serialize_as_empty_map!(SettingsLoad);
+
+/// Response for [`SettingsLoad`] command
+#[derive(Default, Debug, Eq, PartialEq)]
+pub struct SettingsLoadResponse;
+impl_deserialize_from_empty_map_and_into_unit!(SettingsLoadResponse);
+
+/// [Save settings](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_3.html#load-save-settings-command) command
+#[derive(Debug, Eq, PartialEq)]
+pub struct SettingsSave;
+impl_serialize_as_empty_map!(SettingsSave);
+
+/// Response for [`SettingsSave`] command
+#[derive(Default, Debug, Eq, PartialEq)]
+pub struct SettingsSaveResponse;
+impl_deserialize_from_empty_map_and_into_unit!(SettingsSaveResponse);
+
+#[cfg(test)]
+mod tests {
+    use super::super::macros::command_encode_decode_test;
+    use super::*;
+    use ciborium::cbor;
+
+    command_encode_decode_test! {
+        setting_read,
+        (0, 3, 0),
+        SettingRead{
+            name: "app/serial",
+            max_size: None,
+        },
+        cbor!({
+            "name" => "app/serial",
+        }),
+        cbor!({
+            "val" => ciborium::Value::Bytes(vec![1,2,3]),
+        }),
+        SettingReadResponse{
+            val: vec![1,2,3],
+            max_size: None,
+        },
+    }
+
+    command_encode_decode_test! {
+        setting_read_truncated,
+        (0, 3, 0),
+        SettingRead{
+            name: "app/serial",
+            max_size: Some(2),
+        },
+        cbor!({
+            "name" => "app/serial",
+            "max_size" => 2,
+        }),
+        cbor!({
+            "val" => ciborium::Value::Bytes(vec![1,2]),
+            "max_size" => 3,
+        }),
+        SettingReadResponse{
+            val: vec![1,2],
+            max_size: Some(3),
+        },
+    }
+
+    command_encode_decode_test! {
+        setting_write,
+        (2, 3, 0),
+        SettingWrite{
+            name: "app/serial",
+            val: &[4,5,6],
+        },
+        cbor!({
+            "name" => "app/serial",
+            "val" => ciborium::Value::Bytes(vec![4,5,6]),
+        }),
+        cbor!({}),
+        SettingWriteResponse,
+    }
+
+    command_encode_decode_test! {
+        setting_delete,
+        (2, 3, 1),
+        SettingDelete{
+            name: "app/serial",
+        },
+        cbor!({
+            "name" => "app/serial",
+        }),
+        cbor!({}),
+        SettingDeleteResponse,
+    }
+
+    command_encode_decode_test! {
+        settings_commit,
+        (2, 3, 2),
+        SettingsCommit,
+        cbor!({}),
+        cbor!({}),
+        SettingsCommitResponse,
+    }
+
+    command_encode_decode_test! {
+        settings_load,
+        (0, 3, 3),
+        SettingsLoad,
+        cbor!({}),
+        cbor!({}),
+        SettingsLoadResponse,
+    }
+
+    command_encode_decode_test! {
+        settings_save,
+        (2, 3, 3),
+        SettingsSave,
+        cbor!({}),
+        cbor!({}),
+        SettingsSaveResponse,
+    }
+}
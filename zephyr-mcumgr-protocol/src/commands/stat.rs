@@ -0,0 +1,74 @@
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+
+use serde::{Deserialize, Serialize};
+
+use super::macros::impl_serialize_as_empty_map;
+
+/// [Statistic: group data](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_2.html#statistic-group-data) command
+#[derive(Debug, Serialize, Eq, PartialEq)]
+pub struct GroupData<'a> {
+    /// name of the statistics group
+    pub name: &'a str,
+}
+
+/// Response for [`GroupData`] command
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct GroupDataResponse {
+    /// name of the statistics group
+    pub name: String,
+    /// names and values of the counters in the group
+    pub fields: BTreeMap<String, u64>,
+}
+
+/// [Statistic: list of groups](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_2.html#statistic-list-of-groups) command
+#[derive(Debug, Eq, PartialEq)]
+pub struct ListGroups;
+impl_serialize_as_empty_map!(ListGroups);
+
+/// Response for [`ListGroups`] command
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+pub struct ListGroupsResponse {
+    /// names of the statistics groups
+    pub stat_list: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::macros::command_encode_decode_test;
+    use super::*;
+    use ciborium::cbor;
+
+    command_encode_decode_test! {
+        group_data,
+        (0, 2, 0),
+        GroupData{
+            name: "smp_svr_stats",
+        },
+        cbor!({
+            "name" => "smp_svr_stats",
+        }),
+        cbor!({
+            "name" => "smp_svr_stats",
+            "fields" => {
+                "ticks" => 1234,
+            },
+        }),
+        GroupDataResponse{
+            name: "smp_svr_stats".into(),
+            fields: BTreeMap::from([("ticks".into(), 1234)]),
+        },
+    }
+
+    command_encode_decode_test! {
+        list_groups,
+        (0, 2, 1),
+        ListGroups,
+        cbor!({}),
+        cbor!({
+            "stat_list" => ["smp_svr_stats", "ble_att"],
+        }),
+        ListGroupsResponse{
+            stat_list: vec!["smp_svr_stats".into(), "ble_att".into()],
+        },
+    }
+}
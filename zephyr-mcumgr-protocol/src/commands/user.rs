@@ -0,0 +1,155 @@
+use core::marker::PhantomData;
+
+use serde::{Deserialize, Serialize};
+
+use super::McuMgrCommand;
+use crate::MCUmgrGroup;
+
+/// The ID of an application specific management group.
+///
+/// Zephyr reserves the group IDs below
+/// [`MGMT_GROUP_ID_PERUSER`](MCUmgrGroup::MGMT_GROUP_ID_PERUSER) for its own groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UserGroupId(u16);
+
+impl UserGroupId {
+    /// The first group ID that is available to applications.
+    pub const FIRST: Self = Self(MCUmgrGroup::MGMT_GROUP_ID_PERUSER as u16);
+
+    /// Returns `None` if the group ID is reserved by Zephyr.
+    pub const fn new(group_id: u16) -> Option<Self> {
+        if group_id >= Self::FIRST.0 {
+            Some(Self(group_id))
+        } else {
+            None
+        }
+    }
+
+    /// The raw group ID.
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+}
+
+/// A command of an application specific management group.
+///
+/// `P` is the payload of the request and `R` the type the response gets decoded into.
+///
+/// Groups with many commands can also implement [`McuMgrCommand`] for their
+/// own request types instead.
+///
+/// ```
+/// # use zephyr_mcumgr_protocol::commands::user::{UserCommand, UserGroupId};
+/// #[derive(serde::Serialize)]
+/// struct SetLed {
+///     on: bool,
+/// }
+///
+/// #[derive(serde::Deserialize)]
+/// struct SetLedResponse {
+///     brightness: u8,
+/// }
+///
+/// const LED_GROUP: UserGroupId = UserGroupId::new(64).unwrap();
+///
+/// let request = SetLed { on: true };
+/// let command = UserCommand::<_, SetLedResponse>::write(LED_GROUP, 0, &request);
+/// ```
+#[derive(Debug)]
+pub struct UserCommand<'a, P, R> {
+    group_id: UserGroupId,
+    command_id: u8,
+    write_operation: bool,
+    payload: &'a P,
+    _response: PhantomData<fn() -> R>,
+}
+
+impl<'a, P, R> UserCommand<'a, P, R> {
+    /// Creates a read command.
+    pub const fn read(group_id: UserGroupId, command_id: u8, payload: &'a P) -> Self {
+        Self {
+            group_id,
+            command_id,
+            write_operation: false,
+            payload,
+            _response: PhantomData,
+        }
+    }
+
+    /// Creates a write command.
+    pub const fn write(group_id: UserGroupId, command_id: u8, payload: &'a P) -> Self {
+        Self {
+            group_id,
+            command_id,
+            write_operation: true,
+            payload,
+            _response: PhantomData,
+        }
+    }
+}
+
+impl<P, R> McuMgrCommand for UserCommand<'_, P, R>
+where
+    P: Serialize,
+    R: for<'de> Deserialize<'de>,
+{
+    type Payload = P;
+    type Response = R;
+
+    fn is_write_operation(&self) -> bool {
+        self.write_operation
+    }
+
+    fn group_id(&self) -> u16 {
+        self.group_id.get()
+    }
+
+    fn command_id(&self) -> u8 {
+        self.command_id
+    }
+
+    fn data(&self) -> &P {
+        self.payload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::macros::command_encode_decode_test;
+    use super::*;
+    use ciborium::cbor;
+
+    #[derive(Serialize)]
+    struct Counter<'a> {
+        name: &'a str,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct CounterResponse {
+        value: u32,
+    }
+
+    #[test]
+    fn reject_reserved_group_ids() {
+        assert_eq!(UserGroupId::new(63), None);
+        assert_eq!(UserGroupId::new(64), Some(UserGroupId::FIRST));
+        assert_eq!(UserGroupId::new(300).map(UserGroupId::get), Some(300));
+    }
+
+    command_encode_decode_test! {
+        user_command,
+        (0, 65, 3),
+        UserCommand::<_, CounterResponse>::read(
+            UserGroupId::new(65).unwrap(),
+            3,
+            &Counter { name: "boots" },
+        ),
+        cbor!({
+            "name" => "boots",
+        }),
+        cbor!({
+            "value" => 42,
+        }),
+        CounterResponse { value: 42 },
+    }
+}
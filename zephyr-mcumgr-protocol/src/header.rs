@@ -0,0 +1,98 @@
+/// The header of an SMP frame.
+///
+/// See Zephyr's [SMP Protocol Specification](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_protocol.html#frame-header)
+/// for the meaning of the fields.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SmpHeader {
+    /// SMP protocol version
+    pub ver: u8,
+    /// Operation code (0: read, 1: read response, 2: write, 3: write response)
+    pub op: u8,
+    /// Flags, currently unused by Zephyr
+    pub flags: u8,
+    /// Length of the payload data
+    pub data_length: u16,
+    /// Group ID of the command
+    pub group_id: u16,
+    /// Sequence number of the frame
+    pub sequence_num: u8,
+    /// Command ID within the group
+    pub command_id: u8,
+}
+
+impl SmpHeader {
+    /// Decodes a header from its wire format.
+    pub fn from_bytes(data: [u8; SMP_HEADER_SIZE]) -> Self {
+        Self {
+            ver: (data[0] >> 3) & 0b11,
+            op: data[0] & 0b111,
+            flags: data[1],
+            data_length: u16::from_be_bytes([data[2], data[3]]),
+            group_id: u16::from_be_bytes([data[4], data[5]]),
+            sequence_num: data[6],
+            command_id: data[7],
+        }
+    }
+
+    /// Encodes the header into its wire format.
+    pub fn to_bytes(self) -> [u8; SMP_HEADER_SIZE] {
+        let [length_0, length_1] = self.data_length.to_be_bytes();
+        let [group_id_0, group_id_1] = self.group_id.to_be_bytes();
+        [
+            ((self.ver & 0b11) << 3) | (self.op & 0b111),
+            self.flags,
+            length_0,
+            length_1,
+            group_id_0,
+            group_id_1,
+            self.sequence_num,
+            self.command_id,
+        ]
+    }
+}
+
+/// The version of the SMP protocol.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum SmpVersion {
+    /// The original protocol, for devices running Zephyr 3.4 or older
+    V1,
+    /// The protocol with group based error responses, introduced in Zephyr 3.5
+    #[default]
+    V2,
+}
+
+impl SmpVersion {
+    /// The value of the `ver` field of the [`SmpHeader`].
+    pub fn header_value(self) -> u8 {
+        match self {
+            SmpVersion::V1 => 0b00,
+            SmpVersion::V2 => 0b01,
+        }
+    }
+
+    /// Interprets the `ver` field of a received [`SmpHeader`].
+    ///
+    /// Returns `None` for versions this crate does not know.
+    pub fn from_header_value(ver: u8) -> Option<Self> {
+        match ver {
+            0b00 => Some(SmpVersion::V1),
+            0b01 => Some(SmpVersion::V2),
+            _ => None,
+        }
+    }
+}
+
+/// The size of an encoded [`SmpHeader`].
+pub const SMP_HEADER_SIZE: usize = 8;
+
+/// The values of the `op` field of the [`SmpHeader`].
+pub mod smp_op {
+    /// Read request
+    pub const READ: u8 = 0;
+    /// Read response
+    pub const READ_RSP: u8 = 1;
+    /// Write request
+    pub const WRITE: u8 = 2;
+    /// Write response
+    pub const WRITE_RSP: u8 = 3;
+}
@@ -0,0 +1,93 @@
+#![cfg_attr(not(test), no_std)]
+#![deny(missing_docs)]
+#![deny(unreachable_pub)]
+#![forbid(unsafe_code)]
+#![doc(issue_tracker_base_url = "https://github.com/Finomnis/zephyr-mcumgr-client/issues")]
+
+//! Transport independent definitions of Zephyr's [SMP protocol](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_protocol.html).
+//!
+//! Only requires `alloc`, so the command and response types can be shared between
+//! host tools, embedded hosts and test firmware.
+//!
+//! For a ready-to-use client, see the [`zephyr-mcumgr`](https://docs.rs/zephyr-mcumgr) crate.
+
+extern crate alloc;
+
+use alloc::{format, string::String};
+
+/// [MCUmgr command group](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_protocol.html#specifications-of-management-groups-supported-by-zephyr) definitions
+pub mod commands;
+
+/// [SMP frame header](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_protocol.html#frame-header) encoding
+pub mod header;
+
+/// [SMP over serial](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_transport.html#uart-serial-and-console) framing
+pub mod serial;
+
+/// The data does not fit into an SMP frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataTooLargeError;
+
+impl core::fmt::Display for DataTooLargeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Serialized data too large")
+    }
+}
+
+impl core::error::Error for DataTooLargeError {}
+
+/// See [`enum mcumgr_group_t`](https://docs.zephyrproject.org/latest/doxygen/html/mgmt__defines_8h.html).
+#[derive(strum::FromRepr, strum::Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u16)]
+#[allow(non_camel_case_types)]
+#[allow(missing_docs)]
+pub enum MCUmgrGroup {
+    MGMT_GROUP_ID_OS = 0,
+    MGMT_GROUP_ID_IMAGE,
+    MGMT_GROUP_ID_STAT,
+    MGMT_GROUP_ID_SETTINGS,
+    MGMT_GROUP_ID_LOG,
+    MGMT_GROUP_ID_CRASH,
+    MGMT_GROUP_ID_SPLIT,
+    MGMT_GROUP_ID_RUN,
+    MGMT_GROUP_ID_FS,
+    MGMT_GROUP_ID_SHELL,
+    MGMT_GROUP_ID_ENUM,
+    ZEPHYR_MGMT_GRP_BASIC = 63,
+    MGMT_GROUP_ID_PERUSER = 64,
+}
+
+impl MCUmgrGroup {
+    /// Converts a raw group id to a string
+    pub fn group_id_to_string(group_id: u16) -> String {
+        const PERUSER: MCUmgrGroup = MCUmgrGroup::MGMT_GROUP_ID_PERUSER;
+        if group_id < PERUSER as u16 {
+            if let Some(group_enum) = Self::from_repr(group_id) {
+                format!("{group_enum}")
+            } else {
+                format!("MGMT_GROUP_ID_UNKNOWN({group_id})")
+            }
+        } else {
+            format!("{PERUSER}({group_id})")
+        }
+    }
+
+    /// A human readable name of the group, e.g. `image management`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MGMT_GROUP_ID_OS => "OS management",
+            Self::MGMT_GROUP_ID_IMAGE => "image management",
+            Self::MGMT_GROUP_ID_STAT => "statistics management",
+            Self::MGMT_GROUP_ID_SETTINGS => "settings management",
+            Self::MGMT_GROUP_ID_LOG => "log management",
+            Self::MGMT_GROUP_ID_CRASH => "crash management",
+            Self::MGMT_GROUP_ID_SPLIT => "split image management",
+            Self::MGMT_GROUP_ID_RUN => "run test management",
+            Self::MGMT_GROUP_ID_FS => "file management",
+            Self::MGMT_GROUP_ID_SHELL => "shell management",
+            Self::MGMT_GROUP_ID_ENUM => "enumeration management",
+            Self::ZEPHYR_MGMT_GRP_BASIC => "Zephyr basic management",
+            Self::MGMT_GROUP_ID_PERUSER => "user defined",
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use alloc::vec::Vec;
+
+use base64::prelude::*;
+
+use crate::{DataTooLargeError, header::SMP_HEADER_SIZE};
+
+pub use base64::DecodeSliceError;
+
+/// The marker at the start of the first packet of a frame.
+pub const FRAME_START: [u8; 2] = [6, 9];
+
+/// The marker at the start of all following packets of a frame.
+pub const FRAME_CONTINUATION: [u8; 2] = [4, 20];
+
+/// The marker at the end of every packet.
+pub const PACKET_END: u8 = b'\n';
+
+/// The maximum size of a packet, including its markers.
+///
+/// See Zephyr's [`MCUMGR_SERIAL_MAX_FRAME`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/include/zephyr/mgmt/mcumgr/transport/serial.h#L18).
+pub const MAX_PACKET_SIZE: usize = 127;
+
+/// The maximum number of raw bytes in a packet,
+/// so that their base64 encoding fits into [`MAX_PACKET_SIZE`].
+pub const MAX_PACKET_BODY_SIZE: usize = ((MAX_PACKET_SIZE - 3) / 4) * 3;
+
+const CRC: crc::Crc<u16> = crc::Crc::<u16>::new(&crc::CRC_16_XMODEM);
+
+/// Splits an SMP frame into the raw bodies of its serial packets.
+///
+/// The bodies contain the length of the frame, the frame itself
+/// and its CRC16 checksum, and still have to be encoded with [`encode_packet`].
+pub fn packet_bodies(
+    header: &[u8; SMP_HEADER_SIZE],
+    data: &[u8],
+) -> Result<Vec<Vec<u8>>, DataTooLargeError> {
+    let checksum = {
+        let mut digest = CRC.digest();
+        digest.update(header);
+        digest.update(data);
+        digest.finalize().to_be_bytes()
+    };
+
+    let size = u16::try_from(header.len() + data.len() + checksum.len())
+        .map_err(|_| DataTooLargeError)?
+        .to_be_bytes();
+
+    let raw = size
+        .iter()
+        .chain(header)
+        .chain(data)
+        .chain(&checksum)
+        .copied()
+        .collect::<Vec<_>>();
+
+    Ok(raw
+        .chunks(MAX_PACKET_BODY_SIZE)
+        .map(<[u8]>::to_vec)
+        .collect())
+}
+
+/// Encodes a packet body into a packet, including its markers.
+///
+/// # Arguments
+///
+/// * `body` - The raw body; at most [`MAX_PACKET_BODY_SIZE`] bytes long.
+/// * `first` - Whether this is the first packet of the frame.
+///
+pub fn encode_packet(body: &[u8], first: bool) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(MAX_PACKET_SIZE);
+    packet.extend_from_slice(if first {
+        &FRAME_START
+    } else {
+        &FRAME_CONTINUATION
+    });
+    packet.extend_from_slice(BASE64_STANDARD.encode(body).as_bytes());
+    packet.push(PACKET_END);
+    packet
+}
+
+/// Encodes an SMP frame into the data that has to be written to the serial port.
+pub fn encode_frame(
+    header: &[u8; SMP_HEADER_SIZE],
+    data: &[u8],
+) -> Result<Vec<u8>, DataTooLargeError> {
+    Ok(packet_bodies(header, data)?
+        .iter()
+        .enumerate()
+        .flat_map(|(index, body)| encode_packet(body, index == 0))
+        .collect())
+}
+
+/// Decodes the base64 encoded part of a packet, without its markers.
+///
+/// Returns the number of bytes written to `output`.
+pub fn decode_packet(encoded: &[u8], output: &mut [u8]) -> Result<usize, DecodeSliceError> {
+    BASE64_STANDARD.decode_slice(encoded, output)
+}
+
+/// Verifies the CRC16 checksum at the end of a reassembled frame.
+///
+/// The frame must not contain the length prefix of the first packet.
+///
+/// Returns the SMP frame without its checksum, or `None` if the checksum does not match.
+pub fn verify_checksum(frame: &[u8]) -> Option<&[u8]> {
+    let (data, checksum) = frame.split_last_chunk::<2>()?;
+    (CRC.checksum(data) == u16::from_be_bytes(*checksum)).then_some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_small_frame() {
+        let encoded = encode_frame(&[0x0a, 0, 0, 3, 0, 0, 42, 0], &[1, 2, 3]).unwrap();
+
+        assert_eq!(&encoded[..2], &FRAME_START);
+        assert_eq!(encoded.last(), Some(&PACKET_END));
+
+        let mut raw = [0; MAX_PACKET_BODY_SIZE];
+        let len = decode_packet(&encoded[2..encoded.len() - 1], &mut raw).unwrap();
+        assert_eq!(&raw[..2], &[0, 13]);
+        assert_eq!(
+            verify_checksum(&raw[2..len]),
+            Some([0x0a, 0, 0, 3, 0, 0, 42, 0, 1, 2, 3].as_slice())
+        );
+    }
+
+    #[test]
+    fn split_large_frame() {
+        let data = [0x55; 200];
+        let bodies = packet_bodies(&[0; SMP_HEADER_SIZE], &data).unwrap();
+
+        assert_eq!(bodies.len(), 3);
+        assert!(bodies.iter().all(|body| body.len() <= MAX_PACKET_BODY_SIZE));
+        assert_eq!(
+            bodies.iter().map(Vec::len).sum::<usize>(),
+            2 + SMP_HEADER_SIZE + data.len() + 2
+        );
+
+        for (index, body) in bodies.iter().enumerate() {
+            let packet = encode_packet(body, index == 0);
+            assert!(packet.len() <= MAX_PACKET_SIZE);
+        }
+    }
+
+    #[test]
+    fn reject_corrupted_checksum() {
+        let bodies = packet_bodies(&[0; SMP_HEADER_SIZE], &[1, 2, 3]).unwrap();
+        let mut frame = bodies[0][2..].to_vec();
+        frame[SMP_HEADER_SIZE] ^= 0xff;
+
+        assert_eq!(verify_checksum(&frame), None);
+    }
+}
@@ -0,0 +1,121 @@
+use pyo3::{exceptions::PyRuntimeError, prelude::*};
+use pyo3_stub_gen::create_exception;
+
+use ::zephyr_mcumgr::{
+    client::UsbSerialError,
+    connection::ExecuteError,
+    smp_errors::DeviceError as RustDeviceError,
+    transport::{ReceiveError, SendError},
+};
+
+create_exception!(
+    zephyr_mcumgr,
+    MCUmgrError,
+    PyRuntimeError,
+    "Base class of all errors raised by the MCUmgr client"
+);
+create_exception!(
+    zephyr_mcumgr,
+    DeviceError,
+    MCUmgrError,
+    "The device responded with an error code
+
+Carries the `group` ID (`None` for SMP v1 errors) and the `rc` error code as attributes."
+);
+create_exception!(
+    zephyr_mcumgr,
+    TransportError,
+    MCUmgrError,
+    "Communicating with the device failed"
+);
+create_exception!(
+    zephyr_mcumgr,
+    MCUmgrTimeoutError,
+    TransportError,
+    "The device did not respond in time"
+);
+create_exception!(
+    zephyr_mcumgr,
+    DecodeError,
+    MCUmgrError,
+    "The response of the device could not be decoded"
+);
+create_exception!(
+    zephyr_mcumgr,
+    ShellCommandError,
+    MCUmgrError,
+    "A shell command returned a non-zero exit code
+
+Carries the `returncode` and the `stdout` of the command as attributes."
+);
+
+fn device_error_to_pyerr(error: &RustDeviceError, message: String) -> PyErr {
+    let (group, rc) = match error {
+        RustDeviceError::V1 { rc, .. } => (None, *rc),
+        RustDeviceError::V2 { group, rc, .. } => (Some(*group), *rc),
+    };
+
+    let err = DeviceError::new_err(message);
+    Python::attach(|py| {
+        let value = err.value(py);
+        value.setattr("group", group)?;
+        value.setattr("rc", rc)
+    })
+    .err()
+    .unwrap_or(err)
+}
+
+pub(crate) fn shell_command_error(returncode: i32, stdout: &str) -> PyErr {
+    let err = ShellCommandError::new_err(format!(
+        "Shell command returned exit code {returncode}\n{stdout}"
+    ));
+    Python::attach(|py| {
+        let value = err.value(py);
+        value.setattr("returncode", returncode)?;
+        value.setattr("stdout", stdout)
+    })
+    .err()
+    .unwrap_or(err)
+}
+
+/// Converts an error to the Python exception of its category.
+pub(crate) fn err_to_pyerr<E: Into<miette::Report>>(err: E) -> PyErr {
+    let report: miette::Report = err.into();
+    let message = format!("{report:?}");
+
+    let mut transport_error = false;
+    for error in report.chain() {
+        if let Some(error) = error.downcast_ref::<ExecuteError>() {
+            match error {
+                ExecuteError::ErrorResponse(device_error) => {
+                    return device_error_to_pyerr(device_error, message);
+                }
+                ExecuteError::DecodeFailed(_) => return DecodeError::new_err(message),
+                _ => {}
+            }
+        }
+        if let Some(device_error) = error.downcast_ref::<RustDeviceError>() {
+            return device_error_to_pyerr(device_error, message);
+        }
+        if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+            if io_error.kind() == std::io::ErrorKind::TimedOut {
+                return MCUmgrTimeoutError::new_err(message);
+            }
+        }
+        if error.is::<SendError>() || error.is::<ReceiveError>() || error.is::<UsbSerialError>() {
+            transport_error = true;
+        }
+    }
+
+    if transport_error {
+        TransportError::new_err(message)
+    } else {
+        MCUmgrError::new_err(message)
+    }
+}
+
+/// Converts an error that happened while opening a connection.
+pub(crate) fn transport_err_to_pyerr<E: Into<miette::Report>>(err: E) -> PyErr {
+    let report: miette::Report = err.into();
+    TransportError::new_err(format!("{report:?}"))
+}
@@ -0,0 +1,77 @@
+use pyo3::{prelude::*, types::PyBytes};
+use pyo3_stub_gen::derive::gen_stub_pyclass;
+
+use ::zephyr_mcumgr::{
+    connection::{FrameDirection, FrameObserver},
+    transport::SmpHeader,
+};
+use serde::Serialize;
+
+use crate::repr_macro::generate_repr_from_serialize;
+
+/// A single SMP frame, as passed to the `MCUmgrClient.set_frame_tracer` callback.
+#[gen_stub_pyclass]
+#[pyclass(frozen)]
+#[derive(Serialize)]
+pub struct SmpFrame {
+    /// `"tx"` for frames sent to the device, `"rx"` for frames received from the device
+    #[pyo3(get)]
+    pub direction: String,
+    /// SMP protocol version
+    #[pyo3(get)]
+    pub version: u8,
+    /// operation code (0: read, 1: read response, 2: write, 3: write response)
+    #[pyo3(get)]
+    pub op: u8,
+    /// header flags
+    #[pyo3(get)]
+    pub flags: u8,
+    /// group ID of the command
+    #[pyo3(get)]
+    pub group_id: u16,
+    /// command ID within the group
+    #[pyo3(get)]
+    pub command_id: u8,
+    /// sequence number of the frame
+    #[pyo3(get)]
+    pub sequence_num: u8,
+    /// raw payload of the frame, most likely CBOR encoded
+    #[pyo3(get)]
+    #[serde(serialize_with = "crate::repr_macro::serialize_pybytes_as_hex")]
+    pub payload: Py<PyBytes>,
+}
+generate_repr_from_serialize!(SmpFrame);
+
+impl SmpFrame {
+    fn new(py: Python<'_>, direction: FrameDirection, header: &SmpHeader, payload: &[u8]) -> Self {
+        Self {
+            direction: match direction {
+                FrameDirection::Sent => "tx",
+                FrameDirection::Received => "rx",
+            }
+            .to_string(),
+            version: header.ver,
+            op: header.op,
+            flags: header.flags,
+            group_id: header.group_id,
+            command_id: header.command_id,
+            sequence_num: header.sequence_num,
+            payload: PyBytes::new(py, payload).unbind(),
+        }
+    }
+}
+
+/// Creates a frame observer that forwards every frame to the given Python callable.
+///
+/// Exceptions raised by the callback cannot abort the running operation,
+/// so they are reported through `sys.unraisablehook` instead.
+pub(crate) fn frame_observer_from_callback(callback: Py<PyAny>) -> FrameObserver {
+    Box::new(move |direction, header, payload| {
+        Python::attach(|py| {
+            let frame = SmpFrame::new(py, direction, header, payload);
+            if let Err(e) = callback.call1(py, (frame,)) {
+                e.write_unraisable(py, Some(callback.bind(py)));
+            }
+        })
+    })
+}
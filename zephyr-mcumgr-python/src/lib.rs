@@ -13,16 +13,22 @@ use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use crate::errors::{MCUmgrError, err_to_pyerr, shell_command_error, transport_err_to_pyerr};
 use crate::raw_py_any_command::RawPyAnyCommand;
 use crate::sha256_type::Sha256;
 
 mod return_types;
 pub use return_types::*;
 
+mod errors;
+mod frame_tracing;
+mod logging_bridge;
 mod mcuboot;
+mod py_stream;
 mod raw_py_any_command;
 mod repr_macro;
 mod sha256_type;
+mod usb_serial_ports;
 
 /// A high level client for Zephyr's MCUmgr SMP functionality
 #[gen_stub_pyclass]
@@ -32,11 +38,6 @@ struct MCUmgrClient {
     client: Mutex<Option<Arc<::zephyr_mcumgr::MCUmgrClient>>>,
 }
 
-fn err_to_pyerr<E: Into<miette::Report>>(err: E) -> PyErr {
-    let e: miette::Report = err.into();
-    PyRuntimeError::new_err(format!("{e:?}"))
-}
-
 impl MCUmgrClient {
     fn get_client(&self) -> PyResult<Arc<::zephyr_mcumgr::MCUmgrClient>> {
         let locked_client = self.client.lock().unwrap();
@@ -65,7 +66,7 @@ impl MCUmgrClient {
             .timeout(Duration::from_millis(timeout_ms))
             .open()
             .into_diagnostic()
-            .map_err(err_to_pyerr)?;
+            .map_err(transport_err_to_pyerr)?;
         let client = ::zephyr_mcumgr::MCUmgrClient::new_from_serial(serial);
         Ok(MCUmgrClient {
             client: Mutex::new(Some(Arc::new(client))),
@@ -97,7 +98,53 @@ impl MCUmgrClient {
             baud_rate,
             Duration::from_millis(timeout_ms),
         )
-        .map_err(err_to_pyerr)?;
+        .map_err(transport_err_to_pyerr)?;
+        Ok(MCUmgrClient {
+            client: Mutex::new(Some(Arc::new(client))),
+        })
+    }
+
+    /// Creates a Zephyr MCUmgr SMP client based on a UDP connection.
+    ///
+    /// Useful for devices connected via Ethernet or Wi-Fi, with
+    /// `CONFIG_MCUMGR_TRANSPORT_UDP` enabled.
+    ///
+    /// ### Arguments
+    ///
+    /// * `host` - The hostname or IP address of the device.
+    /// * `port` - The UDP port of the device's SMP server.
+    /// * `timeout_ms` - The communication timeout, in ms.
+    ///
+    #[staticmethod]
+    #[pyo3(signature = (host, port=1337, timeout_ms=500))]
+    fn udp(host: &str, port: u16, timeout_ms: u64) -> PyResult<Self> {
+        let client = ::zephyr_mcumgr::MCUmgrClient::new_from_udp(
+            (host, port),
+            Duration::from_millis(timeout_ms),
+        )
+        .into_diagnostic()
+        .map_err(transport_err_to_pyerr)?;
+        Ok(MCUmgrClient {
+            client: Mutex::new(Some(Arc::new(client))),
+        })
+    }
+
+    /// Creates a Zephyr MCUmgr SMP client based on a Python stream object.
+    ///
+    /// Useful for connections without native support, like a `pyserial` port,
+    /// the `makefile()` of a socket or a custom bridge.
+    ///
+    /// ### Arguments
+    ///
+    /// * `stream` - An object with `read(size)` and `write(data)` methods that speaks the SMP serial protocol.
+    ///              `read` may return fewer bytes than requested and must return empty bytes on timeout.
+    ///
+    /// If the object has a `settimeout` method or a `timeout` attribute, `set_timeout_ms` uses it.
+    ///
+    #[staticmethod]
+    fn stream(stream: Py<PyAny>) -> PyResult<Self> {
+        let client =
+            ::zephyr_mcumgr::MCUmgrClient::new_from_serial(py_stream::PyStream::new(stream));
         Ok(MCUmgrClient {
             client: Mutex::new(Some(Arc::new(client))),
         })
@@ -131,6 +178,29 @@ impl MCUmgrClient {
             .map_err(err_to_pyerr)
     }
 
+    /// Registers a callback that receives every SMP frame sent to or received from the device.
+    ///
+    /// Useful for logging or asserting on the exact traffic the client generates.
+    ///
+    /// ### Arguments
+    ///
+    /// * `callback` - A callable object that takes an `SmpFrame` as parameter, or `None` to disable tracing.
+    ///                Any return value is ignored. Exceptions raised by the callback do not abort the
+    ///                running operation, they are reported through `sys.unraisablehook` instead.
+    ///
+    /// The callback must not use the client itself.
+    ///
+    #[pyo3(signature = (callback=None))]
+    pub fn set_frame_tracer(
+        &self,
+        #[gen_stub(override_type(type_repr="typing.Optional[collections.abc.Callable[[SmpFrame], None]]", imports=("collections.abc", "typing")))]
+        callback: Option<Py<PyAny>>,
+    ) -> PyResult<()> {
+        self.get_client()?
+            .set_frame_observer(callback.map(frame_tracing::frame_observer_from_callback));
+        Ok(())
+    }
+
     /// Checks if the device is alive and responding.
     ///
     /// Runs a simple echo with random data and checks if the response matches.
@@ -172,10 +242,16 @@ impl MCUmgrClient {
     /// Sets the RTC of the device to the given datetime.
     ///
     /// Uses the contained local time and discards timezone information.
+    /// If `datetime` is omitted, uses the current local time of the host.
     ///
-    pub fn os_set_datetime<'py>(&self, datetime: Bound<'py, PyDateTime>) -> PyResult<()> {
+    #[pyo3(signature = (datetime=None))]
+    pub fn os_set_datetime<'py>(&self, datetime: Option<Bound<'py, PyDateTime>>) -> PyResult<()> {
+        let datetime = match datetime {
+            Some(datetime) => datetime.extract()?,
+            None => chrono::Local::now().naive_local(),
+        };
         self.get_client()?
-            .os_set_datetime(datetime.extract()?)
+            .os_set_datetime(datetime)
             .map_err(err_to_pyerr)
     }
 
@@ -331,11 +407,17 @@ impl MCUmgrClient {
                     false
                 }
             };
-            self.get_client()?
-                .image_upload(bytes, image, checksum, upgrade_only, Some(&mut cb))
+            self.get_client()?.image_upload(
+                bytes,
+                image,
+                checksum,
+                upgrade_only,
+                Some(&mut cb),
+                None,
+            )
         } else {
             self.get_client()?
-                .image_upload(bytes, image, checksum, upgrade_only, None)
+                .image_upload(bytes, image, checksum, upgrade_only, None, None)
         };
 
         if let Some(cb_error) = cb_error {
@@ -373,23 +455,27 @@ impl MCUmgrClient {
     /// * `name` - The full path of the file on the device.
     /// * `progress` - A callable object that takes (transmitted, total) values as parameters.
     ///                Any return value is ignored. Raising an exception aborts the operation.
+    /// * `offset` - The position in the file to start loading from.
+    /// * `length` - The maximum number of bytes to load. Until the end of the file if omitted.
     ///
     /// ### Return
     ///
-    /// The file content
+    /// The requested part of the file content
     ///
     /// ### Performance
     ///
     /// Downloading files with Zephyr's default parameters is slow.
     /// You want to increase [`MCUMGR_TRANSPORT_NETBUF_SIZE`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/subsys/mgmt/mcumgr/transport/Kconfig#L40)
     /// to maybe `4096` or larger.
-    #[pyo3(signature = (name, progress=None))]
+    #[pyo3(signature = (name, progress=None, offset=0, length=None))]
     pub fn fs_file_download<'py>(
         &self,
         py: Python<'py>,
         name: &str,
         #[gen_stub(override_type(type_repr="typing.Optional[collections.abc.Callable[[builtins.int, builtins.int], None]]", imports=("builtins", "collections.abc", "typing")))]
         progress: Option<Bound<'py, PyAny>>,
+        offset: u64,
+        length: Option<u64>,
     ) -> PyResult<Bound<'py, PyBytes>> {
         let mut data = vec![];
 
@@ -403,10 +489,17 @@ impl MCUmgrClient {
                     false
                 }
             };
-            self.get_client()?
-                .fs_file_download(name, &mut data, Some(&mut cb))
+            self.get_client()?.fs_file_download_range(
+                name,
+                offset,
+                length,
+                &mut data,
+                Some(&mut cb),
+                None,
+            )
         } else {
-            self.get_client()?.fs_file_download(name, &mut data, None)
+            self.get_client()?
+                .fs_file_download_range(name, offset, length, &mut data, None, None)
         };
 
         if let Some(cb_error) = cb_error {
@@ -425,6 +518,9 @@ impl MCUmgrClient {
     /// * `data` - The file content.
     /// * `progress` - A callable object that takes (transmitted, total) values as parameters.
     ///                Any return value is ignored. Raising an exception aborts the operation.
+    /// * `resume` - Continue a previously interrupted upload if the data already on the device matches.
+    /// * `verify` - Compare the SHA256 hash of the uploaded file to `data`.
+    ///              Requires `CONFIG_MCUMGR_GRP_FS_HASH_SHA256` on the device.
     ///
     /// ### Performance
     ///
@@ -432,31 +528,51 @@ impl MCUmgrClient {
     /// You want to increase [`MCUMGR_TRANSPORT_NETBUF_SIZE`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/subsys/mgmt/mcumgr/transport/Kconfig#L40)
     /// to maybe `4096` and then enable larger chunking through either `set_frame_size`
     /// or `use_auto_frame_size`.
-    #[pyo3(signature = (name, data, progress=None))]
+    #[pyo3(signature = (name, data, progress=None, resume=false, verify=false))]
     pub fn fs_file_upload<'py>(
         &self,
         name: &str,
         data: &Bound<'py, PyBytes>,
         #[gen_stub(override_type(type_repr="typing.Optional[collections.abc.Callable[[builtins.int, builtins.int], None]]", imports=("builtins", "collections.abc", "typing")))]
         progress: Option<Bound<'py, PyAny>>,
+        resume: bool,
+        verify: bool,
     ) -> PyResult<()> {
         let bytes: &[u8] = data.extract()?;
 
         let mut cb_error = None;
 
-        let res = if let Some(progress) = progress {
-            let mut cb = |current, total| match progress.call((current, total), None) {
+        let options = ::zephyr_mcumgr::client::FsUploadOptions {
+            verify,
+            ..Default::default()
+        };
+
+        let has_progress = progress.is_some();
+        let mut cb = |current: u64, total: u64| {
+            let Some(progress) = &progress else {
+                return true;
+            };
+            match progress.call((current, total), None) {
                 Ok(_) => true,
                 Err(e) => {
                     cb_error = Some(e);
                     false
                 }
-            };
-            self.get_client()?
-                .fs_file_upload(name, bytes, bytes.len() as u64, Some(&mut cb))
+            }
+        };
+        let cb = has_progress.then_some(&mut cb as &mut dyn FnMut(u64, u64) -> bool);
+
+        let client = self.get_client()?;
+        let res = if resume {
+            client.fs_file_upload_resume(
+                name,
+                std::io::Cursor::new(bytes),
+                bytes.len() as u64,
+                &options,
+                cb,
+            )
         } else {
-            self.get_client()?
-                .fs_file_upload(name, bytes, bytes.len() as u64, None)
+            client.fs_file_upload(name, bytes, bytes.len() as u64, &options, cb)
         };
 
         if let Some(cb_error) = cb_error {
@@ -518,6 +634,60 @@ impl MCUmgrClient {
         self.get_client()?.fs_file_close().map_err(err_to_pyerr)
     }
 
+    /// Read the value of a setting.
+    ///
+    /// ### Arguments
+    ///
+    /// * `name` - The name of the setting, e.g. `app/serial`.
+    /// * `max_size` - The maximum number of bytes to return; longer values get truncated.
+    ///
+    #[pyo3(signature = (name, max_size=None))]
+    pub fn settings_read<'py>(
+        &self,
+        py: Python<'py>,
+        name: &str,
+        max_size: Option<u32>,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        let data = self
+            .get_client()?
+            .settings_read(name, max_size)
+            .map_err(err_to_pyerr)?;
+        Ok(PyBytes::new(py, &data))
+    }
+
+    /// Write the value of a setting.
+    ///
+    /// The new value only takes effect after `settings_commit`
+    /// and only persists after `settings_save`.
+    pub fn settings_write<'py>(&self, name: &str, value: &Bound<'py, PyBytes>) -> PyResult<()> {
+        let bytes: &[u8] = value.extract()?;
+        self.get_client()?
+            .settings_write(name, bytes)
+            .map_err(err_to_pyerr)
+    }
+
+    /// Delete a setting.
+    pub fn settings_delete(&self, name: &str) -> PyResult<()> {
+        self.get_client()?
+            .settings_delete(name)
+            .map_err(err_to_pyerr)
+    }
+
+    /// Apply all pending setting changes to the running application.
+    pub fn settings_commit(&self) -> PyResult<()> {
+        self.get_client()?.settings_commit().map_err(err_to_pyerr)
+    }
+
+    /// Load all settings from persistent storage.
+    pub fn settings_load(&self) -> PyResult<()> {
+        self.get_client()?.settings_load().map_err(err_to_pyerr)
+    }
+
+    /// Save all settings to persistent storage.
+    pub fn settings_save(&self) -> PyResult<()> {
+        self.get_client()?.settings_save().map_err(err_to_pyerr)
+    }
+
     /// Run a shell command.
     ///
     /// ### Arguments
@@ -535,7 +705,7 @@ impl MCUmgrClient {
             .map_err(err_to_pyerr)?;
 
         if exitcode < 0 {
-            return Err(PyRuntimeError::new_err(format!(
+            return Err(MCUmgrError::new_err(format!(
                 "Shell command returned error exit code: {}\n{}",
                 ::zephyr_mcumgr::Errno::errno_to_string(exitcode),
                 data
@@ -545,6 +715,29 @@ impl MCUmgrClient {
         Ok(data)
     }
 
+    /// Run a shell command and return its output and exit code.
+    ///
+    /// Similar to Python's `subprocess.run`.
+    ///
+    /// ### Arguments
+    ///
+    /// * `argv` - The shell command to be executed.
+    /// * `check` - Raise a `ShellCommandError` if the exit code is not zero.
+    ///
+    #[pyo3(signature = (argv, check=true))]
+    pub fn shell_run(&self, argv: Vec<String>, check: bool) -> PyResult<ShellResult> {
+        let (returncode, stdout) = self
+            .get_client()?
+            .shell_execute(&argv)
+            .map_err(err_to_pyerr)?;
+
+        if check && returncode != 0 {
+            return Err(shell_command_error(returncode, &stdout));
+        }
+
+        Ok(ShellResult::new(returncode, stdout))
+    }
+
     /// Erase the `storage_partition` flash partition.
     pub fn zephyr_erase_storage(&self) -> PyResult<()> {
         self.get_client()?
@@ -584,10 +777,7 @@ impl MCUmgrClient {
         data: &Bound<'py, PyAny>,
     ) -> PyResult<Bound<'py, PyAny>> {
         let command = RawPyAnyCommand::new(write_operation, group_id, command_id, data)?;
-        let result = self
-            .get_client()?
-            .raw_command(&command)
-            .map_err(err_to_pyerr)?;
+        let result = self.get_client()?.execute(&command).map_err(err_to_pyerr)?;
         RawPyAnyCommand::convert_result(py, result)
     }
 
@@ -638,21 +828,52 @@ mod zephyr_mcumgr {
     #[pymodule_export]
     use super::return_types::MCUmgrParameters;
     #[pymodule_export]
+    use super::return_types::ShellResult;
+    #[pymodule_export]
     use super::return_types::SlotInfoImage;
     #[pymodule_export]
     use super::return_types::SlotInfoImageSlot;
     #[pymodule_export]
     use super::return_types::TaskStatistics;
 
+    #[pymodule_export]
+    use super::frame_tracing::SmpFrame;
+
+    #[pymodule_export]
+    use super::errors::DecodeError;
+    #[pymodule_export]
+    use super::errors::DeviceError;
+    #[pymodule_export]
+    use super::errors::MCUmgrError;
+    #[pymodule_export]
+    use super::errors::MCUmgrTimeoutError;
+    #[pymodule_export]
+    use super::errors::ShellCommandError;
+    #[pymodule_export]
+    use super::errors::TransportError;
+
     #[pymodule_export]
     use super::mcuboot::McubootImageInfo;
     #[pymodule_export]
+    use super::mcuboot::McubootImageTlv;
+    #[pymodule_export]
     use super::mcuboot::mcuboot_get_image_info;
+    #[pymodule_export]
+    use super::mcuboot::mcuboot_get_image_tlvs;
+    #[pymodule_export]
+    use super::mcuboot::mcuboot_verify_signature;
+
+    #[pymodule_export]
+    use super::logging_bridge::enable_logging;
+
+    #[pymodule_export]
+    use super::usb_serial_ports::UsbSerialPort;
+    #[pymodule_export]
+    use super::usb_serial_ports::list_usb_serial_ports;
 
     #[pymodule_init]
-    fn init(_m: &Bound<'_, PyModule>) -> PyResult<()> {
-        pyo3_log::init();
-        Ok(())
+    fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
+        super::logging_bridge::init(m.py())
     }
 }
 
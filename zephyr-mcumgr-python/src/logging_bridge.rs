@@ -0,0 +1,62 @@
+use std::sync::OnceLock;
+
+use log::LevelFilter;
+use pyo3::prelude::*;
+use pyo3_log::{Caching, Logger, ResetHandle};
+use pyo3_stub_gen::derive::gen_stub_pyfunction;
+
+static RESET_HANDLE: OnceLock<ResetHandle> = OnceLock::new();
+
+/// Installs the bridge from Rust's `log` crate to Python's `logging` module.
+///
+/// Only forwards debug messages and above until `enable_logging` asks for more.
+pub(crate) fn init(py: Python<'_>) -> PyResult<()> {
+    let handle = Logger::new(py, Caching::LoggersAndLevels)?
+        .filter(LevelFilter::Trace)
+        .install()
+        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))?;
+    log::set_max_level(LevelFilter::Debug);
+
+    let _ = RESET_HANDLE.set(handle);
+    Ok(())
+}
+
+/// Converts a Python logging level to the matching Rust level filter.
+///
+/// Python has no trace level; `pyo3-log` reports trace messages with level `5`.
+fn level_filter(level: u32) -> LevelFilter {
+    match level {
+        0..=5 => LevelFilter::Trace,
+        6..=10 => LevelFilter::Debug,
+        11..=20 => LevelFilter::Info,
+        21..=30 => LevelFilter::Warn,
+        31..=40 => LevelFilter::Error,
+        _ => LevelFilter::Off,
+    }
+}
+
+/// Shows the log output of the library through Python's `logging` module
+///
+/// Sets the level of the `zephyr_mcumgr` logger and configures a default handler
+/// via `logging.basicConfig()` if logging is not configured yet.
+///
+/// The messages are emitted through child loggers like `zephyr_mcumgr.transport.serial`.
+/// Level `5` additionally includes the raw serial data of every SMP frame.
+#[pyfunction]
+#[gen_stub_pyfunction]
+#[pyo3(signature = (level=10))]
+pub fn enable_logging(py: Python<'_>, level: u32) -> PyResult<()> {
+    let logging = py.import("logging")?;
+    logging.call_method0("basicConfig")?;
+    logging
+        .call_method1("getLogger", ("zephyr_mcumgr",))?
+        .call_method1("setLevel", (level,))?;
+
+    log::set_max_level(level_filter(level));
+    // The bridge caches the Python logger levels
+    if let Some(handle) = RESET_HANDLE.get() {
+        handle.reset();
+    }
+
+    Ok(())
+}
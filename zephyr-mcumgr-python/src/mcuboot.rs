@@ -1,3 +1,5 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
 use pyo3::{prelude::*, types::PyBytes};
 use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyfunction};
 use serde::Serialize;
@@ -12,6 +14,12 @@ pub struct McubootImageInfo {
     /// Firmware version
     #[pyo3(get)]
     pub version: String,
+    /// The address the image gets loaded to, if the `IMAGE_F_RAM_LOAD` flag is set
+    #[pyo3(get)]
+    pub load_address: u32,
+    /// The header flags, like `IMAGE_F_ENCRYPTED_AES128` or `IMAGE_F_ROM_FIXED`
+    #[pyo3(get)]
+    pub flags: u32,
     /// The identifying hash for the firmware
     ///
     /// Note that this will not be the same as the SHA256 of the whole file, it is the field in the
@@ -20,22 +28,121 @@ pub struct McubootImageInfo {
     #[serde(serialize_with = "crate::repr_macro::serialize_pybytes_as_hex")]
     #[pyo3(get)]
     pub hash: Py<PyBytes>,
+    /// The anti-rollback security counter
+    ///
+    /// Devices with downgrade protection reject images with a counter lower than
+    /// the one of the currently installed image.
+    #[pyo3(get)]
+    pub security_counter: Option<u32>,
+    /// The CBOR encoded measured boot record
+    #[serde(serialize_with = "crate::repr_macro::serialize_option_pybytes_as_hex")]
+    #[pyo3(get)]
+    pub boot_record: Option<Py<PyBytes>>,
+    /// The minimum versions of other images this image requires, by image index
+    #[pyo3(get)]
+    pub dependencies: BTreeMap<u8, String>,
 }
 generate_repr_from_serialize!(McubootImageInfo);
 
+/// A TLV (type-length-value) entry of an MCUboot image
+#[gen_stub_pyclass]
+#[pyclass(frozen)]
+#[derive(Serialize, Debug)]
+pub struct McubootImageTlv {
+    /// The type of the entry, as defined in MCUboot's `image.h`
+    #[pyo3(name = "type", get)]
+    pub r#type: u16,
+    /// Whether the entry is part of the protected TLV area, which is covered by the signature
+    #[pyo3(get)]
+    pub protected: bool,
+    /// The raw data of the entry
+    #[serde(serialize_with = "crate::repr_macro::serialize_pybytes_as_hex")]
+    #[pyo3(get)]
+    pub data: Py<PyBytes>,
+}
+generate_repr_from_serialize!(McubootImageTlv);
+
+/// Converts Intel HEX files to binary, passes binary images through.
+fn image_data_to_binary(data: &[u8]) -> PyResult<Cow<'_, [u8]>> {
+    if zephyr_mcumgr::mcuboot::is_ihex(data) {
+        let data = zephyr_mcumgr::mcuboot::from_ihex(data).map_err(super::err_to_pyerr)?;
+        Ok(Cow::Owned(data))
+    } else {
+        Ok(Cow::Borrowed(data))
+    }
+}
+
 /// Extract information from an MCUboot image file
+///
+/// The image can be binary or Intel HEX.
 #[pyfunction]
 #[gen_stub_pyfunction]
 pub fn mcuboot_get_image_info<'py>(
     py: Python<'py>,
     image_data: Bound<'py, PyBytes>,
 ) -> PyResult<McubootImageInfo> {
-    let data = image_data.as_bytes();
-    let image_info = zephyr_mcumgr::mcuboot::get_image_info(std::io::Cursor::new(data))
+    let data = image_data_to_binary(image_data.as_bytes())?;
+    let image_info = zephyr_mcumgr::mcuboot::get_image_info(std::io::Cursor::new(data.as_ref()))
         .map_err(super::err_to_pyerr)?;
 
     Ok(McubootImageInfo {
         version: image_info.version.to_string(),
+        load_address: image_info.load_address,
+        flags: image_info.flags,
         hash: PyBytes::new(py, &image_info.hash).unbind(),
+        security_counter: image_info.security_counter,
+        boot_record: image_info
+            .boot_record
+            .map(|boot_record| PyBytes::new(py, &boot_record).unbind()),
+        dependencies: image_info
+            .dependencies
+            .iter()
+            .map(|dependency| (dependency.image_id, dependency.min_version.to_string()))
+            .collect(),
     })
 }
+
+/// Read all TLV entries of an MCUboot image file, protected and unprotected
+///
+/// The image can be binary or Intel HEX.
+#[pyfunction]
+#[gen_stub_pyfunction]
+pub fn mcuboot_get_image_tlvs<'py>(
+    py: Python<'py>,
+    image_data: Bound<'py, PyBytes>,
+) -> PyResult<Vec<McubootImageTlv>> {
+    let data = image_data_to_binary(image_data.as_bytes())?;
+    let tlvs = zephyr_mcumgr::mcuboot::get_image_tlvs(std::io::Cursor::new(data.as_ref()))
+        .map_err(super::err_to_pyerr)?;
+
+    Ok(tlvs
+        .into_iter()
+        .map(|tlv| McubootImageTlv {
+            r#type: tlv.tlv_type,
+            protected: tlv.protected,
+            data: PyBytes::new(py, &tlv.data).unbind(),
+        })
+        .collect())
+}
+
+/// Verify the signature of an MCUboot image file against a public key
+///
+/// The image can be binary or Intel HEX.
+/// The public key has to be in DER or PEM format, as exported by `imgtool getpub`.
+///
+/// Returns the name of the signature algorithm.
+#[pyfunction]
+#[gen_stub_pyfunction]
+pub fn mcuboot_verify_signature<'py>(
+    image_data: Bound<'py, PyBytes>,
+    public_key: Bound<'py, PyBytes>,
+) -> PyResult<String> {
+    let data = image_data_to_binary(image_data.as_bytes())?;
+    let algorithm = zephyr_mcumgr::mcuboot::verify_signature(
+        std::io::Cursor::new(data.as_ref()),
+        public_key.as_bytes(),
+    )
+    .map_err(super::err_to_pyerr)?;
+
+    Ok(format!("{algorithm:?}"))
+}
@@ -0,0 +1,112 @@
+use std::{
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use pyo3::{exceptions::PyTimeoutError, prelude::*, types::PyBytes};
+use zephyr_mcumgr::transport::serial::ConfigurableTimeout;
+
+/// Adapts a Python object with `read` and `write` methods to Rust's IO traits.
+pub struct PyStream {
+    stream: Py<PyAny>,
+}
+
+impl PyStream {
+    pub fn new(stream: Py<PyAny>) -> Self {
+        Self { stream }
+    }
+}
+
+fn pyerr_to_io(py: Python<'_>, err: PyErr) -> io::Error {
+    let kind = if err.is_instance_of::<PyTimeoutError>(py) {
+        io::ErrorKind::TimedOut
+    } else {
+        io::ErrorKind::Other
+    };
+    io::Error::new(kind, err.to_string())
+}
+
+impl Read for PyStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        Python::attach(|py| {
+            let stream = self.stream.bind(py);
+
+            // pyserial's `read` and buffered streams' `read` block until all requested bytes
+            // arrived, so only request the available bytes or use `read1` instead
+            let mut size = buf.len();
+            let mut method = "read";
+            if let Ok(in_waiting) = stream
+                .getattr("in_waiting")
+                .and_then(|val| val.extract::<usize>())
+            {
+                size = size.min(in_waiting.max(1));
+            } else if stream.hasattr("read1").map_err(|e| pyerr_to_io(py, e))? {
+                method = "read1";
+            }
+
+            let result = stream
+                .call_method1(method, (size,))
+                .map_err(|e| pyerr_to_io(py, e))?;
+            let data: &[u8] = if result.is_none() {
+                &[]
+            } else {
+                result.extract().map_err(|e| pyerr_to_io(py, e))?
+            };
+
+            if data.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "Python stream returned no data",
+                ));
+            }
+
+            let len = data.len().min(buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            Ok(len)
+        })
+    }
+}
+
+impl Write for PyStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Python::attach(|py| {
+            let written = self
+                .stream
+                .bind(py)
+                .call_method1("write", (PyBytes::new(py, buf),))
+                .and_then(|val| val.extract::<Option<usize>>())
+                .map_err(|e| pyerr_to_io(py, e))?;
+            Ok(written.unwrap_or(buf.len()))
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Python::attach(|py| {
+            let stream = self.stream.bind(py);
+            if stream.hasattr("flush").map_err(|e| pyerr_to_io(py, e))? {
+                stream
+                    .call_method0("flush")
+                    .map_err(|e| pyerr_to_io(py, e))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl ConfigurableTimeout for PyStream {
+    fn set_timeout(&mut self, duration: Duration) -> Result<(), miette::Report> {
+        Python::attach(|py| {
+            let stream = self.stream.bind(py);
+            let seconds = duration.as_secs_f64();
+
+            // pyserial ports have a `timeout` property, sockets a `settimeout` method
+            if stream.hasattr("settimeout")? {
+                stream.call_method1("settimeout", (seconds,))?;
+            } else if stream.hasattr("timeout")? {
+                stream.setattr("timeout", seconds)?;
+            }
+            Ok(())
+        })
+        .map_err(|e: PyErr| miette::miette!("Failed to set the timeout of the Python stream: {e}"))
+    }
+}
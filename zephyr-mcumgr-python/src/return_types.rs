@@ -169,6 +169,34 @@ impl From<commands::os::TaskStatisticsEntry> for TaskStatistics {
     }
 }
 
+/// Return value of `MCUmgrClient.shell_run`.
+#[gen_stub_pyclass]
+#[pyclass(frozen)]
+#[derive(Serialize)]
+pub struct ShellResult {
+    /// output of the command
+    #[pyo3(get)]
+    pub stdout: String,
+    /// exit code of the command; negative values are errno codes
+    #[pyo3(get)]
+    pub returncode: i32,
+    /// name of the errno code, if `returncode` is negative
+    #[pyo3(get)]
+    pub errno_name: Option<String>,
+}
+generate_repr_from_serialize!(ShellResult);
+
+impl ShellResult {
+    pub(crate) fn new(returncode: i32, stdout: String) -> Self {
+        Self {
+            stdout,
+            returncode,
+            errno_name: (returncode < 0)
+                .then(|| ::zephyr_mcumgr::Errno::errno_to_string(returncode)),
+        }
+    }
+}
+
 /// The state of an image slot
 #[gen_stub_pyclass]
 #[pyclass(frozen)]
@@ -0,0 +1,70 @@
+use miette::IntoDiagnostic;
+use pyo3::prelude::*;
+use pyo3_stub_gen::derive::{gen_stub_pyclass, gen_stub_pyfunction};
+use serde::Serialize;
+use zephyr_mcumgr::client::{UsbSerialPortInfo, UsbSerialPorts};
+
+use crate::errors::transport_err_to_pyerr;
+use crate::repr_macro::generate_repr_from_serialize;
+
+/// A USB serial port that is available on the system
+#[gen_stub_pyclass]
+#[pyclass(frozen)]
+#[derive(Serialize)]
+pub struct UsbSerialPort {
+    /// The identifier that `MCUmgrClient.usb_serial` matches against
+    #[pyo3(get)]
+    pub identifier: String,
+    /// The name of the port, like `/dev/ttyACM0` or `COM3`
+    #[pyo3(get)]
+    pub port_name: String,
+    /// The USB vendor ID
+    #[pyo3(get)]
+    pub vid: u16,
+    /// The USB product ID
+    #[pyo3(get)]
+    pub pid: u16,
+    /// The USB interface number, if the device has multiple interfaces
+    #[pyo3(get)]
+    pub interface: Option<u8>,
+    /// The serial number of the device
+    #[pyo3(get)]
+    pub serial_number: Option<String>,
+    /// The manufacturer of the device
+    #[pyo3(get)]
+    pub manufacturer: Option<String>,
+    /// The product name of the device
+    #[pyo3(get)]
+    pub product: Option<String>,
+}
+generate_repr_from_serialize!(UsbSerialPort);
+
+impl From<UsbSerialPortInfo> for UsbSerialPort {
+    fn from(port: UsbSerialPortInfo) -> Self {
+        let info = port.port_info;
+        Self {
+            identifier: port.identifier,
+            port_name: port.port_name,
+            vid: info.vid,
+            pid: info.pid,
+            interface: info.interface,
+            serial_number: info.serial_number,
+            manufacturer: info.manufacturer,
+            product: info.product,
+        }
+    }
+}
+
+/// List all USB serial ports currently available on the system
+///
+/// The `identifier` of a port can be passed to `MCUmgrClient.usb_serial`
+/// to connect to it.
+#[pyfunction]
+#[gen_stub_pyfunction]
+pub fn list_usb_serial_ports() -> PyResult<Vec<UsbSerialPort>> {
+    let UsbSerialPorts(ports) = UsbSerialPorts::available()
+        .into_diagnostic()
+        .map_err(transport_err_to_pyerr)?;
+
+    Ok(ports.into_iter().map(UsbSerialPort::from).collect())
+}
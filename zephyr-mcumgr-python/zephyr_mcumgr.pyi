@@ -7,6 +7,20 @@ import datetime
 import enum
 import typing
 
+class DecodeError(MCUmgrError):
+    r"""
+    The response of the device could not be decoded
+    """
+    ...
+
+class DeviceError(MCUmgrError):
+    r"""
+    The device responded with an error code
+    
+    Carries the `group` ID (`None` for SMP v1 errors) and the `rc` error code as attributes.
+    """
+    ...
+
 @typing.final
 class FileChecksum:
     r"""
@@ -151,6 +165,35 @@ class MCUmgrClient:
         - `1234:89AB:12` - Vendor ID 1234, Product ID 89AB, Interface 12.
         - `1234:.*:[2-3]` - Vendor ID 1234, any Product Id, Interface 2 or 3.
         """
+    @staticmethod
+    def udp(host: builtins.str, port: builtins.int = 1337, timeout_ms: builtins.int = 500) -> 'MCUmgrClient':
+        r"""
+        Creates a Zephyr MCUmgr SMP client based on a UDP connection.
+        
+        Useful for devices connected via Ethernet or Wi-Fi, with
+        `CONFIG_MCUMGR_TRANSPORT_UDP` enabled.
+        
+        ### Arguments
+        
+        * `host` - The hostname or IP address of the device.
+        * `port` - The UDP port of the device's SMP server.
+        * `timeout_ms` - The communication timeout, in ms.
+        """
+    @staticmethod
+    def stream(stream: typing.Any) -> 'MCUmgrClient':
+        r"""
+        Creates a Zephyr MCUmgr SMP client based on a Python stream object.
+        
+        Useful for connections without native support, like a `pyserial` port,
+        the `makefile()` of a socket or a custom bridge.
+        
+        ### Arguments
+        
+        * `stream` - An object with `read(size)` and `write(data)` methods that speaks the SMP serial protocol.
+                     `read` may return fewer bytes than requested and must return empty bytes on timeout.
+        
+        If the object has a `settimeout` method or a `timeout` attribute, `set_timeout_ms` uses it.
+        """
     def set_frame_size(self, smp_frame_size: builtins.int) -> None:
         r"""
         Configures the maximum SMP frame size that we can send to the device.
@@ -171,6 +214,20 @@ class MCUmgrClient:
         When the device does not respond to packets within the set
         duration, an error will be raised.
         """
+    def set_frame_tracer(self, callback: typing.Optional[collections.abc.Callable[[SmpFrame], None]] = None) -> None:
+        r"""
+        Registers a callback that receives every SMP frame sent to or received from the device.
+        
+        Useful for logging or asserting on the exact traffic the client generates.
+        
+        ### Arguments
+        
+        * `callback` - A callable object that takes an `SmpFrame` as parameter, or `None` to disable tracing.
+                       Any return value is ignored. Exceptions raised by the callback do not abort the
+                       running operation, they are reported through `sys.unraisablehook` instead.
+        
+        The callback must not use the client itself.
+        """
     def check_connection(self) -> None:
         r"""
         Checks if the device is alive and responding.
@@ -198,11 +255,12 @@ class MCUmgrClient:
         
         A map of task names with their respective statistics
         """
-    def os_set_datetime(self, datetime: datetime.datetime) -> None:
+    def os_set_datetime(self, datetime: typing.Optional[datetime.datetime] = None) -> None:
         r"""
         Sets the RTC of the device to the given datetime.
         
         Uses the contained local time and discards timezone information.
+        If `datetime` is omitted, uses the current local time of the host.
         """
     def os_get_datetime(self) -> datetime.datetime:
         r"""
@@ -300,7 +358,7 @@ class MCUmgrClient:
         r"""
         Obtain a list of available image slots.
         """
-    def fs_file_download(self, name: builtins.str, progress: typing.Optional[collections.abc.Callable[[builtins.int, builtins.int], None]] = None) -> bytes:
+    def fs_file_download(self, name: builtins.str, progress: typing.Optional[collections.abc.Callable[[builtins.int, builtins.int], None]] = None, offset: builtins.int = 0, length: typing.Optional[builtins.int] = None) -> bytes:
         r"""
         Load a file from the device.
         
@@ -309,10 +367,12 @@ class MCUmgrClient:
         * `name` - The full path of the file on the device.
         * `progress` - A callable object that takes (transmitted, total) values as parameters.
                        Any return value is ignored. Raising an exception aborts the operation.
+        * `offset` - The position in the file to start loading from.
+        * `length` - The maximum number of bytes to load. Until the end of the file if omitted.
         
         ### Return
         
-        The file content
+        The requested part of the file content
         
         ### Performance
         
@@ -320,7 +380,7 @@ class MCUmgrClient:
         You want to increase [`MCUMGR_TRANSPORT_NETBUF_SIZE`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/subsys/mgmt/mcumgr/transport/Kconfig#L40)
         to maybe `4096` or larger.
         """
-    def fs_file_upload(self, name: builtins.str, data: bytes, progress: typing.Optional[collections.abc.Callable[[builtins.int, builtins.int], None]] = None) -> None:
+    def fs_file_upload(self, name: builtins.str, data: bytes, progress: typing.Optional[collections.abc.Callable[[builtins.int, builtins.int], None]] = None, resume: builtins.bool = False, verify: builtins.bool = False) -> None:
         r"""
         Write a file to the device.
         
@@ -330,6 +390,9 @@ class MCUmgrClient:
         * `data` - The file content.
         * `progress` - A callable object that takes (transmitted, total) values as parameters.
                        Any return value is ignored. Raising an exception aborts the operation.
+        * `resume` - Continue a previously interrupted upload if the data already on the device matches.
+        * `verify` - Compare the SHA256 hash of the uploaded file to `data`.
+                     Requires `CONFIG_MCUMGR_GRP_FS_HASH_SHA256` on the device.
         
         ### Performance
         
@@ -363,6 +426,38 @@ class MCUmgrClient:
         r"""
         Close all device files MCUmgr has currently open
         """
+    def settings_read(self, name: builtins.str, max_size: typing.Optional[builtins.int] = None) -> bytes:
+        r"""
+        Read the value of a setting.
+        
+        ### Arguments
+        
+        * `name` - The name of the setting, e.g. `app/serial`.
+        * `max_size` - The maximum number of bytes to return; longer values get truncated.
+        """
+    def settings_write(self, name: builtins.str, value: bytes) -> None:
+        r"""
+        Write the value of a setting.
+        
+        The new value only takes effect after `settings_commit`
+        and only persists after `settings_save`.
+        """
+    def settings_delete(self, name: builtins.str) -> None:
+        r"""
+        Delete a setting.
+        """
+    def settings_commit(self) -> None:
+        r"""
+        Apply all pending setting changes to the running application.
+        """
+    def settings_load(self) -> None:
+        r"""
+        Load all settings from persistent storage.
+        """
+    def settings_save(self) -> None:
+        r"""
+        Save all settings to persistent storage.
+        """
     def shell_execute(self, argv: typing.Sequence[builtins.str]) -> builtins.str:
         r"""
         Run a shell command.
@@ -375,6 +470,17 @@ class MCUmgrClient:
         
         The command output
         """
+    def shell_run(self, argv: typing.Sequence[builtins.str], check: builtins.bool = True) -> ShellResult:
+        r"""
+        Run a shell command and return its output and exit code.
+        
+        Similar to Python's `subprocess.run`.
+        
+        ### Arguments
+        
+        * `argv` - The shell command to be executed.
+        * `check` - Raise a `ShellCommandError` if the exit code is not zero.
+        """
     def zephyr_erase_storage(self) -> None:
         r"""
         Erase the `storage_partition` flash partition.
@@ -410,6 +516,12 @@ class MCUmgrClient:
         Closes the connection
         """
 
+class MCUmgrError(builtins.RuntimeError):
+    r"""
+    Base class of all errors raised by the MCUmgr client
+    """
+    ...
+
 @typing.final
 class MCUmgrParameters:
     r"""
@@ -427,6 +539,12 @@ class MCUmgrParameters:
         """
 
 @typing.final
+class MCUmgrTimeoutError(TransportError):
+    r"""
+    The device did not respond in time
+    """
+    ...
+
 class McubootImageInfo:
     r"""
     Information about an MCUboot firmware image
@@ -437,6 +555,16 @@ class McubootImageInfo:
         Firmware version
         """
     @property
+    def load_address(self) -> builtins.int:
+        r"""
+        The address the image gets loaded to, if the `IMAGE_F_RAM_LOAD` flag is set
+        """
+    @property
+    def flags(self) -> builtins.int:
+        r"""
+        The header flags, like `IMAGE_F_ENCRYPTED_AES128` or `IMAGE_F_ROM_FIXED`
+        """
+    @property
     def hash(self) -> bytes:
         r"""
         The identifying hash for the firmware
@@ -445,6 +573,74 @@ class McubootImageInfo:
         MCUboot TLV section that contains a hash of the data which is used for signature
         verification purposes.
         """
+    @property
+    def security_counter(self) -> typing.Optional[builtins.int]:
+        r"""
+        The anti-rollback security counter
+        
+        Devices with downgrade protection reject images with a counter lower than
+        the one of the currently installed image.
+        """
+    @property
+    def boot_record(self) -> typing.Optional[bytes]:
+        r"""
+        The CBOR encoded measured boot record
+        """
+    @property
+    def dependencies(self) -> builtins.dict[builtins.int, builtins.str]:
+        r"""
+        The minimum versions of other images this image requires, by image index
+        """
+
+@typing.final
+class McubootImageTlv:
+    r"""
+    A TLV (type-length-value) entry of an MCUboot image
+    """
+    @property
+    def type(self) -> builtins.int:
+        r"""
+        The type of the entry, as defined in MCUboot's `image.h`
+        """
+    @property
+    def protected(self) -> builtins.bool:
+        r"""
+        Whether the entry is part of the protected TLV area, which is covered by the signature
+        """
+    @property
+    def data(self) -> bytes:
+        r"""
+        The raw data of the entry
+        """
+
+class ShellCommandError(MCUmgrError):
+    r"""
+    A shell command returned a non-zero exit code
+    
+    Carries the `returncode` and the `stdout` of the command as attributes.
+    """
+    ...
+
+@typing.final
+class ShellResult:
+    r"""
+    Return value of `MCUmgrClient.shell_run`.
+    """
+    @property
+    def stdout(self) -> builtins.str:
+        r"""
+        output of the command
+        """
+    @property
+    def returncode(self) -> builtins.int:
+        r"""
+        exit code of the command; negative values are errno codes
+        """
+    @property
+    def errno_name(self) -> typing.Optional[builtins.str]:
+        r"""
+        name of the errno code, if `returncode` is negative
+        """
 
 @typing.final
 class SlotInfoImage:
@@ -488,6 +684,52 @@ class SlotInfoImageSlot:
         specifies the image ID that can be used by external tools to upload an image to that slot
         """
 
+@typing.final
+class SmpFrame:
+    r"""
+    A single SMP frame, as passed to the `MCUmgrClient.set_frame_tracer` callback.
+    """
+    @property
+    def direction(self) -> builtins.str:
+        r"""
+        `"tx"` for frames sent to the device, `"rx"` for frames received from the device
+        """
+    @property
+    def version(self) -> builtins.int:
+        r"""
+        SMP protocol version
+        """
+    @property
+    def op(self) -> builtins.int:
+        r"""
+        operation code (0: read, 1: read response, 2: write, 3: write response)
+        """
+    @property
+    def flags(self) -> builtins.int:
+        r"""
+        header flags
+        """
+    @property
+    def group_id(self) -> builtins.int:
+        r"""
+        group ID of the command
+        """
+    @property
+    def command_id(self) -> builtins.int:
+        r"""
+        command ID within the group
+        """
+    @property
+    def sequence_num(self) -> builtins.int:
+        r"""
+        sequence number of the frame
+        """
+    @property
+    def payload(self) -> bytes:
+        r"""
+        raw payload of the frame, most likely CBOR encoded
+        """
+
 @typing.final
 class TaskStatistics:
     r"""
@@ -529,6 +771,58 @@ class TaskStatistics:
         task’s/thread’s runtime in “ticks”
         """
 
+class TransportError(MCUmgrError):
+    r"""
+    Communicating with the device failed
+    """
+    ...
+
+@typing.final
+class UsbSerialPort:
+    r"""
+    A USB serial port that is available on the system
+    """
+    @property
+    def identifier(self) -> builtins.str:
+        r"""
+        The identifier that `MCUmgrClient.usb_serial` matches against
+        """
+    @property
+    def port_name(self) -> builtins.str:
+        r"""
+        The name of the port, like `/dev/ttyACM0` or `COM3`
+        """
+    @property
+    def vid(self) -> builtins.int:
+        r"""
+        The USB vendor ID
+        """
+    @property
+    def pid(self) -> builtins.int:
+        r"""
+        The USB product ID
+        """
+    @property
+    def interface(self) -> typing.Optional[builtins.int]:
+        r"""
+        The USB interface number, if the device has multiple interfaces
+        """
+    @property
+    def serial_number(self) -> typing.Optional[builtins.str]:
+        r"""
+        The serial number of the device
+        """
+    @property
+    def manufacturer(self) -> typing.Optional[builtins.str]:
+        r"""
+        The manufacturer of the device
+        """
+    @property
+    def product(self) -> typing.Optional[builtins.str]:
+        r"""
+        The product name of the device
+        """
+
 @typing.final
 class FileChecksumDataFormat(enum.Enum):
     r"""
@@ -543,8 +837,46 @@ class FileChecksumDataFormat(enum.Enum):
     Data is a bytes array
     """
 
+def enable_logging(level: builtins.int = 10) -> None:
+    r"""
+    Shows the log output of the library through Python's `logging` module
+    
+    Sets the level of the `zephyr_mcumgr` logger and configures a default handler
+    via `logging.basicConfig()` if logging is not configured yet.
+    
+    The messages are emitted through child loggers like `zephyr_mcumgr.transport.serial`.
+    Level `5` additionally includes the raw serial data of every SMP frame.
+    """
+
+def list_usb_serial_ports() -> 'builtins.list[UsbSerialPort]':
+    r"""
+    List all USB serial ports currently available on the system
+    
+    The `identifier` of a port can be passed to `MCUmgrClient.usb_serial`
+    to connect to it.
+    """
+
 def mcuboot_get_image_info(image_data: bytes) -> 'McubootImageInfo':
     r"""
     Extract information from an MCUboot image file
+    
+    The image can be binary or Intel HEX.
+    """
+
+def mcuboot_get_image_tlvs(image_data: bytes) -> 'builtins.list[McubootImageTlv]':
+    r"""
+    Read all TLV entries of an MCUboot image file, protected and unprotected
+    
+    The image can be binary or Intel HEX.
+    """
+
+def mcuboot_verify_signature(image_data: bytes, public_key: bytes) -> builtins.str:
+    r"""
+    Verify the signature of an MCUboot image file against a public key
+    
+    The image can be binary or Intel HEX.
+    The public key has to be in DER or PEM format, as exported by `imgtool getpub`.
+    
+    Returns the name of the signature algorithm.
     """
 
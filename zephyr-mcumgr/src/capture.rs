@@ -0,0 +1,448 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::{
+    connection::{FrameDirection, FrameObserver},
+    transport::SmpHeader,
+};
+
+/// The link type of the captured packets.
+///
+/// SMP has no registered link type, so the first user defined one (`LINKTYPE_USER0`) is used.
+/// In Wireshark, it can be mapped to a dissector through the `DLT_USER` protocol preferences.
+pub const SMP_LINKTYPE: u16 = 147;
+
+const BLOCK_TYPE_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const BLOCK_TYPE_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const BLOCK_TYPE_ENHANCED_PACKET: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+const OPTION_END_OF_OPT: u16 = 0;
+const OPTION_EPB_FLAGS: u16 = 2;
+const EPB_FLAGS_INBOUND: u32 = 0b01;
+const EPB_FLAGS_OUTBOUND: u32 = 0b10;
+
+/// Writes SMP frames to a [pcapng](https://www.ietf.org/archive/id/draft-ietf-opsawg-pcapng-03.html) stream.
+///
+/// Every frame is stored as one packet, consisting of the 8 byte SMP header followed by the payload.
+/// The packet direction is stored in the `epb_flags` option, timestamps have microsecond resolution.
+pub struct PcapngWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PcapngWriter<W> {
+    /// Creates a new pcapng writer and writes the file header.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        write_block(&mut writer, BLOCK_TYPE_SECTION_HEADER, |body| {
+            body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+            // Version 1.0
+            body.extend_from_slice(&1u16.to_le_bytes());
+            body.extend_from_slice(&0u16.to_le_bytes());
+            // Section length unknown
+            body.extend_from_slice(&(-1i64).to_le_bytes());
+        })?;
+
+        write_block(&mut writer, BLOCK_TYPE_INTERFACE_DESCRIPTION, |body| {
+            body.extend_from_slice(&SMP_LINKTYPE.to_le_bytes());
+            // Reserved
+            body.extend_from_slice(&0u16.to_le_bytes());
+            // No snapshot length limit
+            body.extend_from_slice(&0u32.to_le_bytes());
+        })?;
+
+        writer.flush()?;
+
+        Ok(Self { writer })
+    }
+
+    /// Writes a single SMP frame, timestamped with the current time.
+    pub fn write_frame(
+        &mut self,
+        direction: FrameDirection,
+        header: &SmpHeader,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+
+        let packet_len = (header.to_bytes().len() + payload.len()) as u32;
+
+        write_block(&mut self.writer, BLOCK_TYPE_ENHANCED_PACKET, |body| {
+            // Interface ID
+            body.extend_from_slice(&0u32.to_le_bytes());
+            body.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+            body.extend_from_slice(&(timestamp as u32).to_le_bytes());
+            // Captured and original length
+            body.extend_from_slice(&packet_len.to_le_bytes());
+            body.extend_from_slice(&packet_len.to_le_bytes());
+            body.extend_from_slice(&header.to_bytes());
+            body.extend_from_slice(payload);
+            pad_to_u32(body);
+
+            let flags = match direction {
+                FrameDirection::Sent => EPB_FLAGS_OUTBOUND,
+                FrameDirection::Received => EPB_FLAGS_INBOUND,
+            };
+            body.extend_from_slice(&OPTION_EPB_FLAGS.to_le_bytes());
+            body.extend_from_slice(&4u16.to_le_bytes());
+            body.extend_from_slice(&flags.to_le_bytes());
+            body.extend_from_slice(&OPTION_END_OF_OPT.to_le_bytes());
+            body.extend_from_slice(&0u16.to_le_bytes());
+        })?;
+
+        // Flush every frame, so the capture is usable even if the program crashes
+        self.writer.flush()
+    }
+
+    /// Consumes the writer and returns the underlying stream.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send + 'static> PcapngWriter<W> {
+    /// Converts the writer into a [`FrameObserver`] that captures every observed frame.
+    ///
+    /// Write errors cannot be propagated to the running command, so they get logged
+    /// and stop the capture.
+    pub fn into_frame_observer(self) -> FrameObserver {
+        let mut capture = Some(self);
+        Box::new(move |direction, header, payload| {
+            if let Some(writer) = &mut capture {
+                if let Err(e) = writer.write_frame(direction, header, payload) {
+                    log::error!("Failed to write SMP capture, stopping capture: {e}");
+                    capture = None;
+                }
+            }
+        })
+    }
+}
+
+/// Creates a pcapng file and returns a [`FrameObserver`] that captures every frame into it.
+///
+/// See [`PcapngWriter`] for the format of the captured packets.
+pub fn pcapng_file_observer(path: impl AsRef<Path>) -> io::Result<FrameObserver> {
+    let file = BufWriter::new(File::create(path)?);
+    Ok(PcapngWriter::new(file)?.into_frame_observer())
+}
+
+/// Writes SMP frames to a text based frame log, one frame per line.
+///
+/// Every line consists of an RFC 3339 timestamp, the direction (`TX` or `RX`),
+/// the hex encoded 8 byte SMP header and the hex encoded payload, separated by spaces:
+///
+/// ```text
+/// 2026-01-24T12:00:00.000000Z TX 0800000100000000 a0
+/// 2026-01-24T12:00:00.012345Z RX 0900000100000000 a0
+/// ```
+///
+/// Empty lines and lines starting with `#` are ignored by [`read_frame_log`],
+/// so logs can be annotated by hand before attaching them to a bug report.
+pub struct FrameLogWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> FrameLogWriter<W> {
+    /// Creates a new frame log writer.
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes a single SMP frame, timestamped with the current time.
+    pub fn write_frame(
+        &mut self,
+        direction: FrameDirection,
+        header: &SmpHeader,
+        payload: &[u8],
+    ) -> io::Result<()> {
+        let frame = LoggedFrame {
+            timestamp: Utc::now(),
+            direction,
+            header: *header,
+            payload: payload.to_vec(),
+        };
+        writeln!(self.writer, "{frame}")?;
+
+        // Flush every frame, so the log is usable even if the program crashes
+        self.writer.flush()
+    }
+
+    /// Consumes the writer and returns the underlying stream.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write + Send + 'static> FrameLogWriter<W> {
+    /// Converts the writer into a [`FrameObserver`] that logs every observed frame.
+    ///
+    /// Write errors cannot be propagated to the running command, so they get logged
+    /// and stop the frame log.
+    pub fn into_frame_observer(self) -> FrameObserver {
+        let mut frame_log = Some(self);
+        Box::new(move |direction, header, payload| {
+            if let Some(writer) = &mut frame_log {
+                if let Err(e) = writer.write_frame(direction, header, payload) {
+                    log::error!("Failed to write SMP frame log, stopping frame log: {e}");
+                    frame_log = None;
+                }
+            }
+        })
+    }
+}
+
+/// Opens a frame log file for appending and returns a [`FrameObserver`] that logs every frame into it.
+///
+/// The file gets created if it does not exist. See [`FrameLogWriter`] for the format.
+pub fn frame_log_file_observer(path: impl AsRef<Path>) -> io::Result<FrameObserver> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(FrameLogWriter::new(BufWriter::new(file)).into_frame_observer())
+}
+
+/// A single frame of a frame log, see [`FrameLogWriter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedFrame {
+    /// When the frame passed through the connection
+    pub timestamp: DateTime<Utc>,
+    /// Whether the frame was sent or received
+    pub direction: FrameDirection,
+    /// The SMP header of the frame
+    pub header: SmpHeader,
+    /// The raw payload of the frame, most likely CBOR encoded
+    pub payload: Vec<u8>,
+}
+
+impl std::fmt::Display for LoggedFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = match self.direction {
+            FrameDirection::Sent => "TX",
+            FrameDirection::Received => "RX",
+        };
+        write!(
+            f,
+            "{} {direction} {} {}",
+            self.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+            hex::encode(self.header.to_bytes()),
+            hex::encode(&self.payload)
+        )
+    }
+}
+
+/// Error while reading a frame log
+#[derive(Error, Debug, Diagnostic)]
+pub enum FrameLogReadError {
+    /// Reading from the underlying stream failed
+    #[error("Failed to read frame log")]
+    #[diagnostic(code(zephyr_mcumgr::capture::frame_log::io))]
+    Io(#[from] io::Error),
+    /// A line does not contain a valid frame
+    #[error("Invalid frame in line {line} of frame log")]
+    #[diagnostic(
+        code(zephyr_mcumgr::capture::frame_log::invalid_line),
+        help(
+            "Every line must consist of a timestamp, `TX` or `RX`, the hex encoded header and the hex encoded payload."
+        )
+    )]
+    InvalidLine {
+        /// The line number, starting at 1
+        line: usize,
+    },
+}
+
+/// Reads the frames of a frame log written by [`FrameLogWriter`].
+///
+/// Empty lines and lines starting with `#` are skipped.
+pub fn read_frame_log(
+    reader: impl BufRead,
+) -> impl Iterator<Item = Result<LoggedFrame, FrameLogReadError>> {
+    reader.lines().enumerate().filter_map(|(index, line)| {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let line_content = line.trim();
+        if line_content.is_empty() || line_content.starts_with('#') {
+            return None;
+        }
+
+        Some(
+            parse_logged_frame(line_content)
+                .ok_or(FrameLogReadError::InvalidLine { line: index + 1 }),
+        )
+    })
+}
+
+/// Parses a single line of a frame log.
+fn parse_logged_frame(line: &str) -> Option<LoggedFrame> {
+    let mut fields = line.split_whitespace();
+
+    let timestamp = DateTime::parse_from_rfc3339(fields.next()?)
+        .ok()?
+        .with_timezone(&Utc);
+    let direction = match fields.next()? {
+        "TX" => FrameDirection::Sent,
+        "RX" => FrameDirection::Received,
+        _ => return None,
+    };
+    let header = SmpHeader::from_bytes(hex::decode(fields.next()?).ok()?.try_into().ok()?);
+    // Frames without payload have no payload field
+    let payload = hex::decode(fields.next().unwrap_or_default()).ok()?;
+
+    if fields.next().is_some() {
+        return None;
+    }
+
+    Some(LoggedFrame {
+        timestamp,
+        direction,
+        header,
+        payload,
+    })
+}
+
+/// Pads the given buffer with zeros to a multiple of four bytes.
+fn pad_to_u32(data: &mut Vec<u8>) {
+    data.resize(data.len().next_multiple_of(4), 0);
+}
+
+/// Writes a generic pcapng block with the given type and body.
+fn write_block(
+    writer: &mut impl Write,
+    block_type: u32,
+    body_fn: impl FnOnce(&mut Vec<u8>),
+) -> io::Result<()> {
+    let mut body = vec![];
+    body_fn(&mut body);
+    pad_to_u32(&mut body);
+
+    // Block type, two length fields and the body
+    let total_len = (body.len() + 12) as u32;
+
+    writer.write_all(&block_type.to_le_bytes())?;
+    writer.write_all(&total_len.to_le_bytes())?;
+    writer.write_all(&body)?;
+    writer.write_all(&total_len.to_le_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// Splits a pcapng stream into (block_type, body) pairs.
+    fn parse_blocks(mut data: &[u8]) -> Vec<(u32, Vec<u8>)> {
+        let mut blocks = vec![];
+        while !data.is_empty() {
+            let block_type = read_u32(data, 0);
+            let len = read_u32(data, 4) as usize;
+            assert_eq!(len % 4, 0);
+            assert_eq!(read_u32(data, len - 4) as usize, len);
+            blocks.push((block_type, data[8..len - 4].to_vec()));
+            data = &data[len..];
+        }
+        blocks
+    }
+
+    #[test]
+    fn capture() {
+        let header = SmpHeader {
+            ver: 1,
+            op: 2,
+            flags: 0,
+            data_length: 3,
+            group_id: 8,
+            sequence_num: 42,
+            command_id: 1,
+        };
+
+        let mut writer = PcapngWriter::new(vec![]).unwrap();
+        writer
+            .write_frame(FrameDirection::Sent, &header, &[1, 2, 3])
+            .unwrap();
+        writer
+            .write_frame(FrameDirection::Received, &header, &[])
+            .unwrap();
+
+        let blocks = parse_blocks(&writer.into_inner());
+        assert_eq!(blocks.len(), 4);
+
+        let (block_type, body) = &blocks[0];
+        assert_eq!(*block_type, BLOCK_TYPE_SECTION_HEADER);
+        assert_eq!(read_u32(body, 0), BYTE_ORDER_MAGIC);
+
+        let (block_type, body) = &blocks[1];
+        assert_eq!(*block_type, BLOCK_TYPE_INTERFACE_DESCRIPTION);
+        assert_eq!(u16::from_le_bytes([body[0], body[1]]), SMP_LINKTYPE);
+
+        let (block_type, body) = &blocks[2];
+        assert_eq!(*block_type, BLOCK_TYPE_ENHANCED_PACKET);
+        assert_eq!(read_u32(body, 12), 11);
+        assert_eq!(&body[20..28], &header.to_bytes());
+        assert_eq!(&body[28..31], &[1, 2, 3]);
+        assert_eq!(read_u32(body, 36), EPB_FLAGS_OUTBOUND);
+
+        let (block_type, body) = &blocks[3];
+        assert_eq!(*block_type, BLOCK_TYPE_ENHANCED_PACKET);
+        assert_eq!(read_u32(body, 12), 8);
+        assert_eq!(read_u32(body, 32), EPB_FLAGS_INBOUND);
+    }
+
+    #[test]
+    fn frame_log_round_trip() {
+        let header = SmpHeader {
+            ver: 1,
+            op: 2,
+            flags: 0,
+            data_length: 3,
+            group_id: 8,
+            sequence_num: 42,
+            command_id: 1,
+        };
+
+        let mut writer = FrameLogWriter::new(b"# Device firmware v1.2.3\n".to_vec());
+        writer
+            .write_frame(FrameDirection::Sent, &header, &[1, 2, 3])
+            .unwrap();
+        writer
+            .write_frame(FrameDirection::Received, &header, &[])
+            .unwrap();
+        let log = writer.into_inner();
+
+        let frames = read_frame_log(log.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].direction, FrameDirection::Sent);
+        assert_eq!(frames[0].header, header);
+        assert_eq!(frames[0].payload, [1, 2, 3]);
+        assert_eq!(frames[1].direction, FrameDirection::Received);
+        assert!(frames[1].payload.is_empty());
+        assert!(frames[0].timestamp <= frames[1].timestamp);
+    }
+
+    #[test]
+    fn frame_log_invalid_line() {
+        let log = "2026-01-24T12:00:00Z TX 0800000100000000 a0\n\nnot a frame\n";
+        let frames = read_frame_log(log.as_bytes()).collect::<Vec<_>>();
+        assert!(frames[0].is_ok());
+        assert!(matches!(
+            frames[1],
+            Err(FrameLogReadError::InvalidLine { line: 3 })
+        ));
+    }
+}
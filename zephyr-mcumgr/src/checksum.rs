@@ -0,0 +1,267 @@
+use std::io::{self, Read, Seek};
+
+use miette::Diagnostic;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::commands::fs::FileChecksumData;
+
+/// The size of the buffer used to stream data into a [`ChecksumHasher`].
+const HASH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// The CRC32 variant used by Zephyr's `crc32` file checksum.
+const CRC32_IEEE: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+/// A streaming hash/checksum algorithm.
+///
+/// Implement this trait to plug in custom hash backends.
+pub trait ChecksumHasher: Send {
+    /// The name of the algorithm, as used by the device (e.g. `sha256`).
+    fn name(&self) -> &str;
+    /// Feeds data into the hasher.
+    fn update(&mut self, data: &[u8]);
+    /// Consumes the hasher and returns the result.
+    fn finalize(self: Box<Self>) -> FileChecksumData;
+}
+
+/// SHA256 hasher, matching the device's `sha256` algorithm.
+#[derive(Default)]
+pub struct Sha256Hasher(Sha256);
+
+impl ChecksumHasher for Sha256Hasher {
+    fn name(&self) -> &str {
+        "sha256"
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finalize(self: Box<Self>) -> FileChecksumData {
+        FileChecksumData::Hash(self.0.finalize().to_vec().into_boxed_slice())
+    }
+}
+
+/// CRC32 (IEEE) checksum, matching the device's `crc32` algorithm.
+pub struct Crc32Hasher(crc::Digest<'static, u32>);
+
+impl Default for Crc32Hasher {
+    fn default() -> Self {
+        Self(CRC32_IEEE.digest())
+    }
+}
+
+impl ChecksumHasher for Crc32Hasher {
+    fn name(&self) -> &str {
+        "crc32"
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finalize(self: Box<Self>) -> FileChecksumData {
+        FileChecksumData::Checksum(self.0.finalize())
+    }
+}
+
+/// Creates a hasher for one of the algorithms known to Zephyr.
+///
+/// # Arguments
+///
+/// * `algorithm` - The name of the algorithm, as reported by [`MCUmgrClient::fs_supported_checksum_types`](crate::MCUmgrClient::fs_supported_checksum_types).
+///
+/// # Return
+///
+/// The hasher, or `None` if the algorithm is unknown.
+pub fn hasher_for_algorithm(algorithm: &str) -> Option<Box<dyn ChecksumHasher>> {
+    match algorithm {
+        "sha256" => Some(Box::new(Sha256Hasher::default())),
+        "crc32" => Some(Box::new(Crc32Hasher::default())),
+        _ => None,
+    }
+}
+
+/// Computes the SHA256 hash of all data that gets read through it.
+pub(crate) struct HashingReader<R> {
+    reader: R,
+    hasher: Option<Sha256Hasher>,
+}
+
+impl<R: Read> HashingReader<R> {
+    /// Wraps `reader`; only computes the hash if `enabled` is set.
+    pub(crate) fn new(reader: R, enabled: bool) -> Self {
+        Self {
+            reader,
+            hasher: enabled.then(Sha256Hasher::default),
+        }
+    }
+
+    /// The hash of the data read so far, or `None` if hashing is disabled.
+    pub(crate) fn finalize(self) -> Option<FileChecksumData> {
+        self.hasher.map(|hasher| Box::new(hasher).finalize())
+    }
+}
+
+impl<R: Read + Seek> HashingReader<R> {
+    /// Seeks the wrapped reader back to `position` and discards the data hashed so far.
+    pub(crate) fn rewind_to(&mut self, position: u64) -> io::Result<()> {
+        self.reader.seek(io::SeekFrom::Start(position))?;
+        if let Some(hasher) = &mut self.hasher {
+            *hasher = Sha256Hasher::default();
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let num_read = self.reader.read(buf)?;
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(&buf[..num_read]);
+        }
+        Ok(num_read)
+    }
+}
+
+/// Possible error values of [`compute_checksum`].
+#[derive(Error, Debug, Diagnostic)]
+pub enum ChecksumError {
+    /// The reader returned an error.
+    #[error("Reader returned an error")]
+    #[diagnostic(code(zephyr_mcumgr::checksum::reader))]
+    ReaderError(#[from] io::Error),
+    /// The progress callback returned an error.
+    #[error("Progress callback returned an error")]
+    #[diagnostic(code(zephyr_mcumgr::checksum::progress_cb_error))]
+    ProgressCallbackError,
+}
+
+/// Computes the hash/checksum of a data stream without loading it into memory.
+///
+/// # Arguments
+///
+/// * `reader` - A [`Read`] object that contains the data.
+/// * `hasher` - The hash/checksum algorithm to use.
+/// * `size` - The total size of the data, only used for progress reporting.
+/// * `progress` - A callback that receives a pair of (hashed, total) bytes and returns false on error.
+///
+/// # Return
+///
+/// A tuple of (result, hashed_bytes).
+///
+pub fn compute_checksum<T: Read>(
+    mut reader: T,
+    mut hasher: Box<dyn ChecksumHasher>,
+    size: u64,
+    mut progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+) -> Result<(FileChecksumData, u64), ChecksumError> {
+    let mut buffer = vec![0u8; HASH_BUFFER_SIZE].into_boxed_slice();
+    let mut hashed = 0;
+
+    loop {
+        let num_read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(num_read) => num_read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        hasher.update(&buffer[..num_read]);
+        hashed += num_read as u64;
+
+        if let Some(progress) = &mut progress {
+            if !progress(hashed, size.max(hashed)) {
+                return Err(ChecksumError::ProgressCallbackError);
+            }
+        }
+    }
+
+    Ok((hasher.finalize(), hashed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256() {
+        let (result, len) = compute_checksum(
+            &b"123456789"[..],
+            Box::new(Sha256Hasher::default()),
+            9,
+            None,
+        )
+        .unwrap();
+        assert_eq!(len, 9);
+        assert_eq!(
+            result.hex(),
+            "15e2b0d3c33891ebb0f1ef609ec419420c20e320ce94c65fbc8c3312448eb225"
+        );
+    }
+
+    #[test]
+    fn crc32() {
+        let (result, len) =
+            compute_checksum(&b"123456789"[..], Box::new(Crc32Hasher::default()), 9, None).unwrap();
+        assert_eq!(len, 9);
+        assert_eq!(result, FileChecksumData::Checksum(0xcbf43926));
+    }
+
+    #[test]
+    fn hashing_reader() {
+        let mut reader = HashingReader::new(&b"123456789"[..], true);
+        let mut first = [0; 4];
+        reader.read_exact(&mut first).unwrap();
+        io::copy(&mut reader, &mut io::sink()).unwrap();
+        assert_eq!(
+            reader.finalize().unwrap().hex(),
+            "15e2b0d3c33891ebb0f1ef609ec419420c20e320ce94c65fbc8c3312448eb225"
+        );
+
+        let mut reader = HashingReader::new(&b"123456789"[..], false);
+        io::copy(&mut reader, &mut io::sink()).unwrap();
+        assert_eq!(reader.finalize(), None);
+    }
+
+    #[test]
+    fn by_name() {
+        assert_eq!(hasher_for_algorithm("sha256").unwrap().name(), "sha256");
+        assert_eq!(hasher_for_algorithm("crc32").unwrap().name(), "crc32");
+        assert!(hasher_for_algorithm("md5").is_none());
+    }
+
+    #[test]
+    fn progress() {
+        let data = vec![42u8; HASH_BUFFER_SIZE * 2 + 10];
+        let mut reports = vec![];
+        let mut cb = |current, total| {
+            reports.push((current, total));
+            true
+        };
+
+        compute_checksum(
+            data.as_slice(),
+            Box::new(Sha256Hasher::default()),
+            data.len() as u64,
+            Some(&mut cb),
+        )
+        .unwrap();
+
+        assert_eq!(
+            reports.last(),
+            Some(&(data.len() as u64, data.len() as u64))
+        );
+
+        let mut abort = |_, _| false;
+        assert!(matches!(
+            compute_checksum(
+                data.as_slice(),
+                Box::new(Crc32Hasher::default()),
+                data.len() as u64,
+                Some(&mut abort),
+            ),
+            Err(ChecksumError::ProgressCallbackError)
+        ));
+    }
+}
@@ -0,0 +1,357 @@
+use std::{
+    io::{self, Read, Write},
+    net::ToSocketAddrs,
+    time::Duration,
+};
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::{
+    MCUmgrClient,
+    connection::{ExecuteError, FrameObserver, RetryPolicy},
+    transport::{
+        SmpVersion, Transport,
+        serial::{ConfigurableTimeout, SerialPacing, SerialTransport},
+    },
+};
+
+#[cfg(feature = "serialport")]
+use super::{UsbPortSelector, UsbSerialError, find_usb_serial_port};
+#[cfg(feature = "serialport")]
+use crate::transport::{
+    reconnect::{ReconnectPolicy, ReconnectingSerialPort},
+    serial::SerialOptions,
+};
+
+/// The communication timeout of transports that get opened by the builder,
+/// if none is configured.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How the SMP frame size gets determined.
+enum FrameSize {
+    Fixed(usize),
+    Auto,
+}
+
+/// Possible error values of the `build_from_*` functions of [`MCUmgrClientBuilder`].
+#[derive(Error, Debug, Diagnostic)]
+pub enum ClientBuildError {
+    /// Opening the USB serial port failed
+    #[cfg(feature = "serialport")]
+    #[error("Failed to open USB serial port")]
+    #[diagnostic(code(zephyr_mcumgr::client::build::usb_serial), forward(0))]
+    UsbSerial(#[from] UsbSerialError),
+    /// Opening the UDP socket failed
+    #[error("Failed to open UDP socket")]
+    #[diagnostic(code(zephyr_mcumgr::client::build::udp))]
+    Udp(#[source] io::Error),
+    /// The transport rejected the timeout
+    #[error("Failed to set the communication timeout")]
+    #[diagnostic(code(zephyr_mcumgr::client::build::timeout))]
+    Timeout(#[source] Box<dyn miette::Diagnostic + Send + Sync>),
+    /// Reading the frame size from the device failed
+    #[error("Failed to read the SMP frame size from the device")]
+    #[diagnostic(code(zephyr_mcumgr::client::build::auto_frame_size), forward(0))]
+    AutoFrameSize(#[from] ExecuteError),
+}
+
+/// Configures an [`MCUmgrClient`] before its first command gets sent.
+///
+/// Created through [`MCUmgrClient::builder`].
+///
+/// ```no_run
+/// # use zephyr_mcumgr::{MCUmgrClient, connection::RetryPolicy};
+/// # use std::time::Duration;
+/// # fn main() {
+/// let client = MCUmgrClient::builder()
+///     .timeout(Duration::from_millis(500))
+///     .auto_frame_size()
+///     .retry_policy(RetryPolicy {
+///         attempts: 3,
+///         backoff: Duration::from_millis(100),
+///     })
+///     .build_from_usb_serial("2fe3:0004", 115200)
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Default)]
+pub struct MCUmgrClientBuilder {
+    timeout: Option<Duration>,
+    frame_size: Option<FrameSize>,
+    window_size: Option<usize>,
+    max_upload_rate: Option<u64>,
+    chunk_alignment: Option<usize>,
+    retry_policy: RetryPolicy,
+    smp_version: SmpVersion,
+    frame_observers: Vec<FrameObserver>,
+    serial_pacing: SerialPacing,
+    #[cfg(feature = "serialport")]
+    serial_options: SerialOptions,
+    #[cfg(feature = "serialport")]
+    reconnect: Option<ReconnectPolicy>,
+}
+
+impl MCUmgrClientBuilder {
+    /// The communication timeout.
+    ///
+    /// Transports opened by the builder use 2 seconds if none is given;
+    /// the timeout of user provided transports stays untouched.
+    ///
+    /// See [`MCUmgrClient::set_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// A fixed maximum SMP frame size.
+    ///
+    /// See [`MCUmgrClient::set_frame_size`].
+    pub fn frame_size(mut self, smp_frame_size: usize) -> Self {
+        self.frame_size = Some(FrameSize::Fixed(smp_frame_size));
+        self
+    }
+
+    /// Reads the maximum SMP frame size from the device while building the client.
+    ///
+    /// See [`MCUmgrClient::use_auto_frame_size`].
+    pub fn auto_frame_size(mut self) -> Self {
+        self.frame_size = Some(FrameSize::Auto);
+        self
+    }
+
+    /// The number of upload requests that may be in flight at the same time.
+    ///
+    /// See [`MCUmgrClient::set_window_size`].
+    pub fn window_size(mut self, window_size: usize) -> Self {
+        self.window_size = Some(window_size);
+        self
+    }
+
+    /// Caps the data rate of uploads, in bytes per second.
+    ///
+    /// See [`MCUmgrClient::set_max_upload_rate`].
+    pub fn max_upload_rate(mut self, max_bytes_per_second: u64) -> Self {
+        self.max_upload_rate = Some(max_bytes_per_second);
+        self
+    }
+
+    /// Makes the upload chunks a multiple of `alignment` bytes.
+    ///
+    /// See [`MCUmgrClient::set_chunk_alignment`].
+    pub fn chunk_alignment(mut self, alignment: usize) -> Self {
+        self.chunk_alignment = Some(alignment);
+        self
+    }
+
+    /// The retransmission of requests whose responses time out.
+    ///
+    /// See [`MCUmgrClient::set_retry_policy`].
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// The SMP protocol version of the sent requests.
+    ///
+    /// Devices running Zephyr 3.4 or older require [`SmpVersion::V1`].
+    pub fn smp_version(mut self, version: SmpVersion) -> Self {
+        self.smp_version = version;
+        self
+    }
+
+    /// Registers a callback that gets invoked for every SMP frame sent or received,
+    /// including the ones of [`MCUmgrClientBuilder::auto_frame_size`].
+    ///
+    /// Can be called multiple times to register multiple observers.
+    ///
+    /// See [`MCUmgrClient::add_frame_observer`].
+    pub fn frame_observer(mut self, observer: FrameObserver) -> Self {
+        self.frame_observers.push(observer);
+        self
+    }
+
+    /// Paces the data written to the serial port, for UART bridges that lose bytes
+    /// when data is written back to back.
+    ///
+    /// Only affects [`MCUmgrClientBuilder::build_from_serial`] and
+    /// [`MCUmgrClientBuilder::build_from_usb_serial`]. See [`SerialTransport::with_pacing`].
+    pub fn serial_pacing(mut self, pacing: SerialPacing) -> Self {
+        self.serial_pacing = pacing;
+        self
+    }
+
+    /// The line settings of the USB serial port of [`MCUmgrClientBuilder::build_from_usb_serial`],
+    /// like parity, hardware flow control or the DTR level.
+    ///
+    /// Ports passed to [`MCUmgrClientBuilder::build_from_serial`] are already configured.
+    #[cfg(feature = "serialport")]
+    pub fn serial_options(mut self, options: SerialOptions) -> Self {
+        self.serial_options = options;
+        self
+    }
+
+    /// Re-opens the USB serial port of [`MCUmgrClientBuilder::build_from_usb_serial`]
+    /// if it disconnects, for example because the device rebooted.
+    ///
+    /// See [`ReconnectingSerialPort`].
+    #[cfg(feature = "serialport")]
+    pub fn reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = Some(policy);
+        self
+    }
+
+    /// Creates the client based on a configured and opened serial port.
+    ///
+    /// See [`MCUmgrClient::new_from_serial`].
+    pub fn build_from_serial<T: Send + Read + Write + ConfigurableTimeout + 'static>(
+        self,
+        serial: T,
+    ) -> Result<MCUmgrClient, ClientBuildError> {
+        let client = self.new_from_serial(serial);
+        self.configure(client)
+    }
+
+    /// Creates the client based on an arbitrary transport.
+    ///
+    /// See [`MCUmgrClient::new_from_transport`].
+    pub fn build_from_transport<T: Transport + Send + 'static>(
+        self,
+        transport: T,
+    ) -> Result<MCUmgrClient, ClientBuildError> {
+        self.configure(MCUmgrClient::new_from_transport(transport))
+    }
+
+    /// Creates the client based on a USB serial port identified by VID:PID.
+    ///
+    /// See [`MCUmgrClient::new_from_usb_serial`].
+    #[cfg(feature = "serialport")]
+    pub fn build_from_usb_serial(
+        self,
+        identifier: impl Into<UsbPortSelector>,
+        baud_rate: u32,
+    ) -> Result<MCUmgrClient, ClientBuildError> {
+        let timeout = self.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let port = find_usb_serial_port(&identifier.into())?;
+        let client = match self.reconnect {
+            Some(policy) => {
+                let serial = ReconnectingSerialPort::open_usb_with_options(
+                    &port,
+                    baud_rate,
+                    timeout,
+                    self.serial_options,
+                    policy,
+                )
+                .map_err(UsbSerialError::from)?;
+                self.new_from_serial(serial)
+            }
+            None => {
+                let serial = self
+                    .serial_options
+                    .open(&port.port_name, baud_rate, timeout)
+                    .map_err(UsbSerialError::from)?;
+                self.new_from_serial(serial)
+            }
+        };
+        client.connection.assume_timeout(timeout);
+        self.configure(client)
+    }
+
+    /// Creates the client based on a UDP connection.
+    ///
+    /// See [`MCUmgrClient::new_from_udp`].
+    pub fn build_from_udp(
+        self,
+        addr: impl ToSocketAddrs,
+    ) -> Result<MCUmgrClient, ClientBuildError> {
+        let timeout = self.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let client = MCUmgrClient::new_from_udp(addr, timeout).map_err(ClientBuildError::Udp)?;
+        self.configure(client)
+    }
+
+    /// Creates a client based on a serial port, with the configured [`SerialPacing`].
+    fn new_from_serial<T: Send + Read + Write + ConfigurableTimeout + 'static>(
+        &self,
+        serial: T,
+    ) -> MCUmgrClient {
+        MCUmgrClient::new_from_serial_transport(
+            SerialTransport::new(serial).with_pacing(self.serial_pacing),
+        )
+    }
+
+    /// Applies the configuration to a freshly created client.
+    fn configure(self, client: MCUmgrClient) -> Result<MCUmgrClient, ClientBuildError> {
+        if let Some(timeout) = self.timeout {
+            client
+                .set_timeout(timeout)
+                .map_err(|e| ClientBuildError::Timeout(e.into()))?;
+        }
+
+        client.set_retry_policy(self.retry_policy);
+        client.set_smp_version(self.smp_version);
+        for observer in self.frame_observers {
+            client.add_frame_observer(observer);
+        }
+
+        client.set_window_size(self.window_size);
+        client.set_max_upload_rate(self.max_upload_rate);
+        client.set_chunk_alignment(self.chunk_alignment);
+
+        match self.frame_size {
+            Some(FrameSize::Fixed(smp_frame_size)) => client.set_frame_size(smp_frame_size),
+            Some(FrameSize::Auto) => client.use_auto_frame_size()?,
+            None => {}
+        }
+
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+
+    use super::*;
+
+    #[test]
+    fn sends_configured_smp_version() {
+        let device = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let client = MCUmgrClient::builder()
+            .timeout(Duration::from_secs(1))
+            .smp_version(SmpVersion::V1)
+            .build_from_udp(device.local_addr().unwrap())
+            .unwrap();
+
+        let responder = std::thread::spawn(move || {
+            let mut request = [0; 64];
+            let (_, host) = device.recv_from(&mut request).unwrap();
+            let sequence_num = request[6];
+            device
+                .send_to(
+                    &[
+                        0x01,
+                        0x00,
+                        0x00,
+                        0x06,
+                        0x00,
+                        0x00,
+                        sequence_num,
+                        0x00,
+                        0xa1,
+                        0x61,
+                        b'r',
+                        0x62,
+                        b'h',
+                        b'i',
+                    ],
+                    host,
+                )
+                .unwrap();
+            request[0]
+        });
+
+        assert_eq!(client.os_echo("hi").unwrap(), "hi");
+        // Version 1, read operation
+        assert_eq!(responder.join().unwrap(), 0x00);
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// Aborts a long-running transfer from another thread.
+///
+/// Cheap to clone; all clones share the same state. Cancelling only sets an
+/// atomic flag, so it can also be done from a signal handler.
+///
+/// The transfer stops before its next chunk and returns a `Cancelled` error.
+///
+/// ```no_run
+/// # use zephyr_mcumgr::{MCUmgrClient, client::CancellationToken};
+/// # fn main() {
+/// # let serial = serialport::new("COM42", 115200).open().unwrap();
+/// # let client = MCUmgrClient::new_from_serial(serial);
+/// let cancel = CancellationToken::new();
+///
+/// std::thread::spawn({
+///     let cancel = cancel.clone();
+///     move || {
+///         std::thread::sleep(std::time::Duration::from_secs(10));
+///         cancel.cancel();
+///     }
+/// });
+///
+/// let result = client.fs_file_download("/lfs/log.txt", std::io::sink(), None, Some(&cancel));
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of all transfers that use this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether [`CancellationToken::cancel`] was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Two tokens are equal if they are clones of each other.
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_share_cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+
+        clone.cancel();
+        assert!(token.is_cancelled());
+        assert_eq!(token, clone);
+        assert_ne!(token, CancellationToken::new());
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    sync::{Weak, atomic::Ordering},
+    time::{Duration, Instant},
+};
+
+use super::{MCUmgrClient, periodic::PeriodicWorker};
+
+/// Configuration of [`MCUmgrClient::start_keepalive`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    /// How long to wait between two checks.
+    pub interval: Duration,
+    /// The number of consecutive failed checks after which the client is considered unhealthy.
+    ///
+    /// `0` behaves like `1`.
+    pub max_failures: u32,
+}
+
+impl Default for KeepaliveConfig {
+    /// Checks every 5 seconds, unhealthy after 3 failures.
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(5),
+            max_failures: 3,
+        }
+    }
+}
+
+/// A running background keepalive, created through [`MCUmgrClient::start_keepalive`].
+///
+/// The keepalive stops when this handle gets dropped, which marks the client healthy again.
+pub struct Keepalive {
+    worker: Option<PeriodicWorker>,
+    client: Weak<MCUmgrClient>,
+}
+
+impl Keepalive {
+    pub(super) fn start(client: Weak<MCUmgrClient>, config: KeepaliveConfig) -> Self {
+        let max_failures = config.max_failures.max(1);
+        let mut failures = 0;
+
+        let first_check = Instant::now() + config.interval;
+        let worker = PeriodicWorker::start(
+            client.clone(),
+            first_check,
+            config.interval,
+            move |client| {
+                if client.is_alive() {
+                    failures = 0;
+                    if !client.healthy.swap(true, Ordering::SeqCst) {
+                        log::info!("Device is responding again");
+                    }
+                } else {
+                    failures += 1;
+                    log::debug!("Keepalive check failed ({failures}/{max_failures})");
+                    if failures >= max_failures && client.healthy.swap(false, Ordering::SeqCst) {
+                        log::warn!("Device stopped responding");
+                    }
+                }
+            },
+        );
+
+        Self {
+            worker: Some(worker),
+            client,
+        }
+    }
+}
+
+impl Drop for Keepalive {
+    fn drop(&mut self) {
+        // Stop the checks first, so they cannot overwrite the reset
+        drop(self.worker.take());
+        if let Some(client) = self.client.upgrade() {
+            client.healthy.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::UdpSocket, sync::Arc, time::Instant};
+
+    use super::*;
+
+    #[test]
+    fn marks_unresponsive_device_unhealthy() {
+        let device = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let client = Arc::new(
+            MCUmgrClient::new_from_udp(device.local_addr().unwrap(), Duration::from_millis(20))
+                .unwrap(),
+        );
+        assert!(client.is_healthy());
+
+        let keepalive = client.start_keepalive(KeepaliveConfig {
+            interval: Duration::from_millis(10),
+            max_failures: 2,
+        });
+
+        let start = Instant::now();
+        while client.is_healthy() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        drop(keepalive);
+        assert!(client.is_healthy());
+    }
+
+    #[test]
+    fn alive_check_uses_short_timeout() {
+        let device = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let client =
+            MCUmgrClient::new_from_udp(device.local_addr().unwrap(), Duration::from_secs(30))
+                .unwrap();
+
+        let start = Instant::now();
+        assert!(!client.is_alive());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+}
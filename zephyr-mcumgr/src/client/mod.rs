@@ -0,0 +1,2516 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    io::{self, Read, Seek, Write},
+    net::ToSocketAddrs,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize},
+        mpsc,
+    },
+    time::{Duration, Instant},
+};
+
+use miette::Diagnostic;
+use rand::distr::SampleString;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use zephyr_mcumgr_protocol::DataTooLargeError;
+
+use crate::{
+    bootloader::BootloaderInfo,
+    checksum,
+    commands::{
+        self, McuMgrCommand, fs::file_upload_max_data_chunk_size,
+        image::image_upload_max_data_chunk_size,
+    },
+    connection::{
+        Connection, ConnectionStats, ExecuteError, FrameObserver, FrameObserverId, PendingResponse,
+        Pipeline, RetryPolicy, UnsolicitedFrame,
+    },
+    mcuboot::{self, IhexError, ImageDependency, ImageParseError, ImageVersion},
+    transport::{
+        SmpVersion, Transport,
+        remote_serial::{RemoteSerialError, RemoteSerialPort},
+        serial::{ConfigurableTimeout, SerialTransport},
+        udp::UdpTransport,
+    },
+};
+
+mod builder;
+pub use builder::{ClientBuildError, MCUmgrClientBuilder};
+
+mod cancellation;
+pub use cancellation::CancellationToken;
+
+mod keepalive;
+pub use keepalive::{Keepalive, KeepaliveConfig};
+
+mod periodic;
+
+mod progress;
+pub use progress::{ProgressTracker, TransferProgress};
+
+mod throttle;
+use throttle::Throttle;
+
+mod task_stats;
+pub use task_stats::{TaskStatsMonitor, TaskUsage};
+
+mod sampler;
+pub use sampler::{SampleData, SampleSource, Sampler, SamplerConfig, Snapshot};
+
+/// The default SMP frame size of Zephyr.
+///
+/// Matches Zephyr default value of [MCUMGR_TRANSPORT_NETBUF_SIZE](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/subsys/mgmt/mcumgr/transport/Kconfig#L40).
+const ZEPHYR_DEFAULT_SMP_FRAME_SIZE: usize = 384;
+
+/// The size of the chunks in which the local data gets hashed when resuming an upload.
+const RESUME_HASH_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A high level client for Zephyr's MCUmgr SMP protocol.
+///
+/// This struct is the central entry point of this crate.
+pub struct MCUmgrClient {
+    connection: Connection,
+    smp_frame_size: AtomicUsize,
+    smp_buf_count: AtomicUsize,
+    window_size: AtomicUsize,
+    max_upload_rate: AtomicU64,
+    chunk_alignment: AtomicUsize,
+    console_subscribers: ConsoleSubscribers,
+    /// Updated by [`MCUmgrClient::start_keepalive`].
+    healthy: AtomicBool,
+}
+
+/// The receivers of [`MCUmgrClient::console_lines`].
+type ConsoleSubscribers = Arc<Mutex<Vec<mpsc::Sender<String>>>>;
+
+/// Possible error values of [`MCUmgrClient::fs_file_download`].
+#[derive(Error, Debug, Diagnostic)]
+pub enum FileDownloadError {
+    /// The command failed in the SMP protocol layer.
+    #[error("Command execution failed")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_download::execute), forward(0))]
+    ExecuteError(#[from] ExecuteError),
+    /// A device response contained an unexpected offset value.
+    #[error("Received offset does not match requested offset")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_download::offset_mismatch))]
+    UnexpectedOffset,
+    /// The writer returned an error.
+    #[error("Writer returned an error")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_download::writer))]
+    WriterError(#[from] io::Error),
+    /// The received data does not match the reported file size.
+    #[error("Received data does not match reported size")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_download::size_mismatch))]
+    SizeMismatch,
+    /// The received data unexpectedly did not report the file size.
+    #[error("Received data is missing file size information")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_download::missing_size))]
+    MissingSize,
+    /// The requested start offset is larger than the file.
+    #[error("Requested offset is beyond the end of the file")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_download::offset_beyond_end))]
+    OffsetBeyondEnd,
+    /// The progress callback returned an error.
+    #[error("Progress callback returned an error")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_download::progress_cb_error))]
+    ProgressCallbackError,
+    /// The transfer was aborted through its [`CancellationToken`].
+    #[error("Transfer was cancelled")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_download::cancelled))]
+    Cancelled,
+}
+
+/// Possible error values of [`MCUmgrClient::fs_file_upload`].
+#[derive(Error, Debug, Diagnostic)]
+pub enum FileUploadError {
+    /// The command failed in the SMP protocol layer.
+    #[error("Command execution failed")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_upload::execute), forward(0))]
+    ExecuteError(#[from] ExecuteError),
+    /// The reader returned an error.
+    #[error("Reader returned an error")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_upload::reader))]
+    ReaderError(#[from] io::Error),
+    /// The progress callback returned an error.
+    #[error("Progress callback returned an error")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_upload::progress_cb_error))]
+    ProgressCallbackError,
+    /// The transfer was aborted through its [`CancellationToken`].
+    #[error("Transfer was cancelled")]
+    #[diagnostic(code(zephyr_mcumgr::client::file_upload::cancelled))]
+    Cancelled,
+    /// The SHA256 hash of the uploaded file does not match the local data.
+    #[error("Uploaded file does not match the local data")]
+    #[diagnostic(
+        code(zephyr_mcumgr::client::file_upload::verification_failed),
+        help(
+            "The file got corrupted during the transfer or modified concurrently. Upload it again."
+        )
+    )]
+    VerificationFailed,
+    /// The current SMP frame size is too small for this command.
+    #[error("SMP frame size too small for this command")]
+    #[diagnostic(
+        code(zephyr_mcumgr::client::file_upload::framesize_too_small),
+        help(
+            "Increase the frame size through `set_frame_size` or `use_auto_frame_size`, which requires `CONFIG_MCUMGR_GRP_OS_MCUMGR_PARAMS` on the device."
+        )
+    )]
+    FrameSizeTooSmall(#[source] DataTooLargeError),
+}
+
+/// Possible error values of [`MCUmgrClient::image_upload`].
+#[derive(Error, Debug, Diagnostic)]
+pub enum ImageUploadError {
+    /// The command failed in the SMP protocol layer.
+    #[error("Command execution failed")]
+    #[diagnostic(code(zephyr_mcumgr::client::image_upload::execute), forward(0))]
+    ExecuteError(#[from] ExecuteError),
+    /// The progress callback returned an error.
+    #[error("Progress callback returned an error")]
+    #[diagnostic(code(zephyr_mcumgr::client::image_upload::progress_cb_error))]
+    ProgressCallbackError,
+    /// The transfer was aborted through its [`CancellationToken`].
+    #[error("Transfer was cancelled")]
+    #[diagnostic(code(zephyr_mcumgr::client::image_upload::cancelled))]
+    Cancelled,
+    /// The current SMP frame size is too small for this command.
+    #[error("SMP frame size too small for this command")]
+    #[diagnostic(
+        code(zephyr_mcumgr::client::image_upload::framesize_too_small),
+        help(
+            "Increase the frame size through `set_frame_size` or `use_auto_frame_size`, which requires `CONFIG_MCUMGR_GRP_OS_MCUMGR_PARAMS` on the device."
+        )
+    )]
+    FrameSizeTooSmall(#[source] DataTooLargeError),
+    /// A device response contained an unexpected offset value.
+    #[error("Received offset out of expected range")]
+    #[diagnostic(code(zephyr_mcumgr::client::image_upload::invalid_offset))]
+    UnexpectedOffset,
+    /// The device reported a checksum mismatch
+    #[error("Device reported checksum mismatch")]
+    #[diagnostic(
+        code(zephyr_mcumgr::client::image_upload::checksum_mismatch_on_device),
+        help("The image got corrupted during the transfer. Retry the upload.")
+    )]
+    ChecksumMismatchOnDevice,
+    /// The firmware image does not match the given checksum
+    #[error("Firmware image does not match given checksum")]
+    #[diagnostic(code(zephyr_mcumgr::client::image_upload::checksum_mismatch))]
+    ChecksumMismatch,
+}
+
+/// Options of [`MCUmgrClient::fs_file_upload`] and [`MCUmgrClient::fs_file_upload_resume`].
+#[derive(Debug, Clone, Default)]
+pub struct FsUploadOptions {
+    /// If true, compare the SHA256 hash of the uploaded file to the local data.
+    ///
+    /// See [`MCUmgrClient::fs_file_upload`] for more information.
+    pub verify: bool,
+    /// If set, the upload can be aborted through this token before its next chunk.
+    pub cancel: Option<CancellationToken>,
+}
+
+/// Parameters of [`MCUmgrClient::firmware_update`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct FirmwareUpdateParams {
+    /// The image index to update, `0` if omitted.
+    ///
+    /// Multi-image devices use this to address additional cores,
+    /// like the network core of the nRF5340.
+    pub image_index: Option<u32>,
+    /// If true, allow firmware upgrades only and reject downgrades.
+    pub upgrade_only: bool,
+    /// If true, do not reboot the device after the new image was marked for test boot.
+    pub skip_reboot: bool,
+    /// If set, wait up to this long for the device to come back after the reboot,
+    /// verify that the new image is running and confirm it.
+    ///
+    /// Without confirmation, MCUboot reverts to the previous image on the next reset.
+    /// Has no effect if `skip_reboot` is set.
+    pub confirm_timeout: Option<Duration>,
+    /// If true, skip the update if the active image is not older than the new one.
+    ///
+    /// Useful for idempotent deployment scripts.
+    pub skip_if_version_not_newer: bool,
+    /// Caps the upload data rate, overriding [`MCUmgrClient::set_max_upload_rate`].
+    pub max_bytes_per_second: Option<u64>,
+    /// If true, discard a previously interrupted upload of the image and start over.
+    ///
+    /// The upload slot gets erased before the upload. By default, the device continues
+    /// interrupted uploads where they stopped, see [`MCUmgrClient::image_upload`].
+    pub restart_upload: bool,
+    /// If set, refuse to upload images that are not signed with this public key.
+    ///
+    /// See `mcuboot::verify_signature` for the supported key formats.
+    /// Requires the `verify` feature, the update fails otherwise.
+    pub public_key: Option<Vec<u8>>,
+    /// If set, the update can be aborted through this token.
+    ///
+    /// Cancelling is possible until the new image gets marked for test boot.
+    pub cancel: Option<CancellationToken>,
+}
+
+/// The result of [`MCUmgrClient::verify_active_image`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ActiveImageVerification {
+    /// The expected image is running.
+    Active,
+    /// The expected image is present on the device, but not running.
+    ///
+    /// MCUboot most likely failed to boot it and reverted to the previous image.
+    Reverted,
+    /// The expected image is not present on the device.
+    Mismatch,
+    /// The device did not respond within the timeout.
+    Timeout,
+}
+
+/// The result of a successful [`MCUmgrClient::firmware_update`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FirmwareUpdateOutcome {
+    /// The new image was uploaded.
+    Updated,
+    /// The update was skipped, because the active image is not older than the new one.
+    ///
+    /// See [`FirmwareUpdateParams::skip_if_version_not_newer`].
+    Skipped {
+        /// The version of the image that is currently running on the device
+        active_version: ImageVersion,
+    },
+}
+
+/// Possible error values of [`MCUmgrClient::firmware_update`].
+#[derive(Error, Debug, Diagnostic)]
+pub enum FirmwareUpdateError {
+    /// The firmware file is not a valid MCUboot image.
+    #[error("Failed to parse firmware image")]
+    #[diagnostic(code(zephyr_mcumgr::client::firmware_update::image_parse), forward(0))]
+    ImageParseFailed(#[from] ImageParseError),
+    /// The firmware file is not a valid Intel HEX file.
+    #[error("Failed to convert Intel HEX firmware file")]
+    #[diagnostic(code(zephyr_mcumgr::client::firmware_update::ihex), forward(0))]
+    IhexConversionFailed(#[from] IhexError),
+    /// The firmware image is not signed with the expected key.
+    #[error("Firmware image signature verification failed")]
+    #[diagnostic(code(zephyr_mcumgr::client::firmware_update::signature), forward(0))]
+    SignatureVerificationFailed(#[from] mcuboot::ImageVerifyError),
+    /// A public key was given, but signature verification is not compiled in.
+    #[error("Firmware image signature verification is not available")]
+    #[diagnostic(
+        code(zephyr_mcumgr::client::firmware_update::signature_unavailable),
+        help("Enable the `verify` feature of the `zephyr-mcumgr` crate.")
+    )]
+    SignatureVerificationUnavailable,
+    /// Uploading the firmware image failed.
+    #[error("Image upload failed")]
+    #[diagnostic(code(zephyr_mcumgr::client::firmware_update::upload), forward(0))]
+    UploadFailed(#[from] ImageUploadError),
+    /// The command failed in the SMP protocol layer.
+    #[error("Command execution failed")]
+    #[diagnostic(code(zephyr_mcumgr::client::firmware_update::execute), forward(0))]
+    ExecuteError(#[from] ExecuteError),
+    /// The device did not respond within the confirmation timeout after the reboot.
+    #[error("Device did not come back after reboot")]
+    #[diagnostic(
+        code(zephyr_mcumgr::client::firmware_update::reboot_timeout),
+        help(
+            "The new image was not confirmed. Increase the timeout if the device takes long to boot."
+        )
+    )]
+    RebootTimeout,
+    /// The device is running, but not with the new image.
+    #[error("Device did not boot into the new image")]
+    #[diagnostic(
+        code(zephyr_mcumgr::client::firmware_update::not_active),
+        help(
+            "MCUboot most likely rejected the image or reverted it. Check the image signature and the boot log."
+        )
+    )]
+    ImageNotActive,
+    /// The image requires a newer version of another image on the device.
+    #[error("Image requires image {} with version {} or newer", .0.image_id, .0.min_version)]
+    #[diagnostic(
+        code(zephyr_mcumgr::client::firmware_update::dependency),
+        help("Update the required image first.")
+    )]
+    DependencyNotSatisfied(ImageDependency),
+    /// The update was aborted through [`FirmwareUpdateParams::cancel`].
+    #[error("Firmware update was cancelled")]
+    #[diagnostic(code(zephyr_mcumgr::client::firmware_update::cancelled))]
+    Cancelled,
+}
+
+/// Information about a serial port
+#[cfg(feature = "serialport")]
+#[derive(Debug, serde::Serialize, Clone, Eq, PartialEq)]
+pub struct UsbSerialPortInfo {
+    /// The identifier that the regex will match against
+    pub identifier: String,
+    /// The name of the port
+    pub port_name: String,
+    /// Information about the port
+    pub port_info: serialport::UsbPortInfo,
+}
+
+/// A list of available serial ports
+///
+/// Used for pretty error messages.
+#[cfg(feature = "serialport")]
+#[derive(serde::Serialize, Clone, Eq, PartialEq)]
+#[serde(transparent)]
+pub struct UsbSerialPorts(pub Vec<UsbSerialPortInfo>);
+#[cfg(feature = "serialport")]
+impl std::fmt::Display for UsbSerialPorts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for UsbSerialPortInfo {
+            identifier,
+            port_name,
+            port_info,
+        } in &self.0
+        {
+            writeln!(f)?;
+            write!(f, " - {identifier}")?;
+
+            let mut print_port_string = true;
+            let port_string = format!("({port_name})");
+
+            if port_info.manufacturer.is_some() || port_info.product.is_some() {
+                write!(f, " -")?;
+                if let Some(manufacturer) = &port_info.manufacturer {
+                    let mut print_manufacturer = true;
+
+                    if let Some(product) = &port_info.product {
+                        if product.starts_with(manufacturer) {
+                            print_manufacturer = false;
+                        }
+                    }
+
+                    if print_manufacturer {
+                        write!(f, " {manufacturer}")?;
+                    }
+                }
+                if let Some(product) = &port_info.product {
+                    write!(f, " {product}")?;
+
+                    if product.ends_with(&port_string) {
+                        print_port_string = false;
+                    }
+                }
+            }
+
+            if print_port_string {
+                write!(f, " {port_string}")?;
+            }
+        }
+        Ok(())
+    }
+}
+#[cfg(feature = "serialport")]
+impl UsbSerialPorts {
+    /// Lists all USB serial ports currently available on the system.
+    ///
+    /// The identifiers of the ports are the ones [`MCUmgrClient::new_from_usb_serial`] matches against.
+    pub fn available() -> Result<Self, serialport::Error> {
+        let ports = serialport::available_ports()?
+            .into_iter()
+            .filter_map(|port| {
+                if let serialport::SerialPortType::UsbPort(port_info) = port.port_type {
+                    if let Some(interface) = port_info.interface {
+                        Some(UsbSerialPortInfo {
+                            identifier: format!(
+                                "{:04x}:{:04x}:{}",
+                                port_info.vid, port_info.pid, interface
+                            ),
+                            port_name: port.port_name,
+                            port_info,
+                        })
+                    } else {
+                        Some(UsbSerialPortInfo {
+                            identifier: format!("{:04x}:{:04x}", port_info.vid, port_info.pid),
+                            port_name: port.port_name,
+                            port_info,
+                        })
+                    }
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        Ok(Self(ports))
+    }
+
+    /// Returns the ports whose identifier matches the given regex.
+    ///
+    /// The regex is case insensitive and has to match at the beginning of the identifier,
+    /// see [`MCUmgrClient::new_from_usb_serial`].
+    pub fn matching(&self, identifier: &str) -> Result<Self, regex::Error> {
+        let port_regex = regex::RegexBuilder::new(identifier)
+            .case_insensitive(true)
+            .unicode(true)
+            .build()?;
+
+        let matches = self
+            .0
+            .iter()
+            .filter(|port| {
+                if let Some(m) = port_regex.find(&port.identifier) {
+                    // Only accept if the regex matches at the beginning of the string
+                    m.start() == 0
+                } else {
+                    false
+                }
+            })
+            .cloned()
+            .collect();
+
+        Ok(Self(matches))
+    }
+}
+#[cfg(feature = "serialport")]
+impl UsbSerialPorts {
+    /// Returns the ports that match the given [`UsbMatcher`].
+    pub fn filter(&self, matcher: &UsbMatcher) -> Self {
+        Self(
+            self.0
+                .iter()
+                .filter(|port| matcher.matches(port))
+                .cloned()
+                .collect(),
+        )
+    }
+}
+#[cfg(feature = "serialport")]
+impl std::fmt::Debug for UsbSerialPorts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+/// Selects USB serial ports through their USB descriptors.
+///
+/// Every criterion that is `Some` has to match; the default matches every port.
+#[cfg(feature = "serialport")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UsbMatcher {
+    /// The USB vendor ID.
+    pub vid: Option<u16>,
+    /// The USB product ID.
+    pub pid: Option<u16>,
+    /// The number of the USB interface, for devices with multiple serial ports.
+    pub interface: Option<u8>,
+    /// The USB serial number, compared exactly.
+    pub serial_number: Option<String>,
+    /// A case insensitive substring of the USB product string.
+    pub product: Option<String>,
+}
+#[cfg(feature = "serialport")]
+impl UsbMatcher {
+    /// Checks whether the given port fulfills all criteria.
+    pub fn matches(&self, port: &UsbSerialPortInfo) -> bool {
+        let info = &port.port_info;
+        self.vid.is_none_or(|vid| info.vid == vid)
+            && self.pid.is_none_or(|pid| info.pid == pid)
+            && self
+                .interface
+                .is_none_or(|interface| info.interface == Some(interface))
+            && self
+                .serial_number
+                .as_ref()
+                .is_none_or(|serial_number| info.serial_number.as_ref() == Some(serial_number))
+            && self.product.as_ref().is_none_or(|product| {
+                info.product
+                    .as_ref()
+                    .is_some_and(|p| p.to_lowercase().contains(&product.to_lowercase()))
+            })
+    }
+}
+#[cfg(feature = "serialport")]
+impl std::fmt::Display for UsbMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut criteria = vec![];
+        if let Some(vid) = self.vid {
+            criteria.push(format!("vid={vid:04x}"));
+        }
+        if let Some(pid) = self.pid {
+            criteria.push(format!("pid={pid:04x}"));
+        }
+        if let Some(interface) = self.interface {
+            criteria.push(format!("interface={interface}"));
+        }
+        if let Some(serial_number) = &self.serial_number {
+            criteria.push(format!("serial_number={serial_number}"));
+        }
+        if let Some(product) = &self.product {
+            criteria.push(format!("product={product}"));
+        }
+        write!(f, "{}", criteria.join(","))
+    }
+}
+
+/// Selects the port of [`MCUmgrClient::new_from_usb_serial`].
+///
+/// Created from a regex identifier string or from a [`UsbMatcher`].
+#[cfg(feature = "serialport")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsbPortSelector {
+    /// A regex that has to match the beginning of [`UsbSerialPortInfo::identifier`].
+    Identifier(String),
+    /// Structured criteria, see [`UsbMatcher`].
+    Matcher(UsbMatcher),
+}
+#[cfg(feature = "serialport")]
+impl From<&str> for UsbPortSelector {
+    fn from(identifier: &str) -> Self {
+        Self::Identifier(identifier.to_string())
+    }
+}
+#[cfg(feature = "serialport")]
+impl From<&String> for UsbPortSelector {
+    fn from(identifier: &String) -> Self {
+        Self::Identifier(identifier.clone())
+    }
+}
+#[cfg(feature = "serialport")]
+impl From<String> for UsbPortSelector {
+    fn from(identifier: String) -> Self {
+        Self::Identifier(identifier)
+    }
+}
+#[cfg(feature = "serialport")]
+impl From<UsbMatcher> for UsbPortSelector {
+    fn from(matcher: UsbMatcher) -> Self {
+        Self::Matcher(matcher)
+    }
+}
+#[cfg(feature = "serialport")]
+impl std::fmt::Display for UsbPortSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Identifier(identifier) => f.write_str(identifier),
+            Self::Matcher(matcher) => std::fmt::Display::fmt(matcher, f),
+        }
+    }
+}
+
+/// Possible error values of [`MCUmgrClient::new_from_usb_serial`].
+#[cfg(feature = "serialport")]
+#[derive(Error, Debug, Diagnostic)]
+pub enum UsbSerialError {
+    /// Serialport error
+    #[error("Serialport returned an error")]
+    #[diagnostic(code(zephyr_mcumgr::usb_serial::serialport_error))]
+    SerialPortError(#[from] serialport::Error),
+    /// No port matched the given identifier
+    #[error("No serial port matched the identifier '{identifier}'\nAvailable ports:\n{available}")]
+    #[diagnostic(
+        code(zephyr_mcumgr::usb_serial::no_matches),
+        help(
+            "Make sure the device is connected. The identifier is a regex matching `VID:PID:INTERFACE`."
+        )
+    )]
+    NoMatchingPort {
+        /// The original identifier provided by the user
+        identifier: String,
+        /// A list of available ports
+        available: UsbSerialPorts,
+    },
+    /// More than one port matched the given identifier
+    #[error("Multiple serial ports matched the identifier '{identifier}'\n{ports}")]
+    #[diagnostic(
+        code(zephyr_mcumgr::usb_serial::multiple_matches),
+        help(
+            "Append the interface number to the identifier, e.g. `1234:89AB:12`, or select the device by its serial number."
+        )
+    )]
+    MultipleMatchingPorts {
+        /// The original identifier provided by the user
+        identifier: String,
+        /// The matching ports; their [`UsbSerialPortInfo::port_info`] can be used
+        /// to build a more specific [`UsbMatcher`]
+        ports: UsbSerialPorts,
+    },
+    /// Returned when the identifier was empty;
+    /// can be used to query all available ports
+    #[error("An empty identifier was provided")]
+    #[diagnostic(code(zephyr_mcumgr::usb_serial::empty_identifier))]
+    IdentifierEmpty {
+        /// A list of available ports
+        ports: UsbSerialPorts,
+    },
+    /// The given identifier was not a valid RegEx
+    #[error("The given identifier was not a valid RegEx")]
+    #[diagnostic(code(zephyr_mcumgr::usb_serial::regex_error))]
+    RegexError(#[from] regex::Error),
+}
+
+/// Finds the single USB serial port that matches the given selector,
+/// see [`MCUmgrClient::new_from_usb_serial`].
+#[cfg(feature = "serialport")]
+fn find_usb_serial_port(selector: &UsbPortSelector) -> Result<UsbSerialPortInfo, UsbSerialError> {
+    let ports = UsbSerialPorts::available()?;
+
+    let matches = match selector {
+        UsbPortSelector::Identifier(identifier) => {
+            if identifier.is_empty() {
+                return Err(UsbSerialError::IdentifierEmpty { ports });
+            }
+            ports.matching(identifier)?.0
+        }
+        UsbPortSelector::Matcher(matcher) => ports.filter(matcher).0,
+    };
+
+    if matches.len() > 1 {
+        return Err(UsbSerialError::MultipleMatchingPorts {
+            identifier: selector.to_string(),
+            ports: UsbSerialPorts(matches),
+        });
+    }
+
+    matches
+        .into_iter()
+        .next()
+        .ok_or_else(|| UsbSerialError::NoMatchingPort {
+            identifier: selector.to_string(),
+            available: ports,
+        })
+}
+
+/// An upload chunk that was sent to the device.
+///
+/// Without pipelining, chunks get executed right away instead,
+/// so that they are retransmitted according to the [`RetryPolicy`].
+enum SentChunk<T> {
+    Pending(PendingResponse<T>),
+    Done(T),
+}
+
+impl<T: for<'a> Deserialize<'a>> SentChunk<T> {
+    /// Sends `request` through `pipeline`, or executes it if `window_size` is `1`.
+    fn send<R: McuMgrCommand<Response = T>>(
+        connection: &Connection,
+        pipeline: &mut Pipeline<'_>,
+        request: &R,
+        window_size: usize,
+    ) -> Result<Self, ExecuteError> {
+        if window_size > 1 {
+            pipeline.send(request).map(SentChunk::Pending)
+        } else {
+            connection.execute_command(request).map(SentChunk::Done)
+        }
+    }
+
+    /// Returns the response of the chunk.
+    fn receive(self, pipeline: &mut Pipeline<'_>) -> Result<T, ExecuteError> {
+        match self {
+            SentChunk::Pending(pending) => pipeline.receive(pending),
+            SentChunk::Done(response) => Ok(response),
+        }
+    }
+}
+
+impl MCUmgrClient {
+    /// Configures a Zephyr MCUmgr SMP client before connecting it.
+    ///
+    /// See [`MCUmgrClientBuilder`].
+    pub fn builder() -> MCUmgrClientBuilder {
+        MCUmgrClientBuilder::default()
+    }
+
+    /// Creates a Zephyr MCUmgr SMP client based on a configured and opened serial port.
+    ///
+    /// ```no_run
+    /// # use zephyr_mcumgr::MCUmgrClient;
+    /// # fn main() {
+    /// let serial = serialport::new("COM42", 115200)
+    ///     .timeout(std::time::Duration::from_millis(2000))
+    ///     .open()
+    ///     .unwrap();
+    ///
+    /// let mut client = MCUmgrClient::new_from_serial(serial);
+    /// # }
+    /// ```
+    pub fn new_from_serial<T: Send + Read + Write + ConfigurableTimeout + 'static>(
+        serial: T,
+    ) -> Self {
+        Self::new_from_serial_transport(SerialTransport::new(serial))
+    }
+
+    /// Creates a client based on a [`SerialTransport`], forwarding its console output
+    /// to [`MCUmgrClient::console_lines`].
+    fn new_from_serial_transport<T: Send + Read + Write + ConfigurableTimeout + 'static>(
+        transport: SerialTransport<T>,
+    ) -> Self {
+        let console_subscribers = ConsoleSubscribers::default();
+
+        let transport = transport.with_console_handler(Box::new({
+            let console_subscribers = Arc::clone(&console_subscribers);
+            move |line| {
+                let mut subscribers = console_subscribers.lock().unwrap();
+                if subscribers.is_empty() {
+                    log::debug!("Console: {line}");
+                }
+                subscribers.retain(|subscriber| subscriber.send(line.to_string()).is_ok());
+            }
+        }));
+
+        let mut client = Self::new_from_transport(transport);
+        client.console_subscribers = console_subscribers;
+        client
+    }
+
+    /// Creates a Zephyr MCUmgr SMP client based on an arbitrary transport.
+    ///
+    /// Useful for customized transports, for example a [`SerialTransport`] that forwards
+    /// the console output of a shared UART:
+    ///
+    /// ```no_run
+    /// # use zephyr_mcumgr::{MCUmgrClient, transport::serial::SerialTransport};
+    /// # fn main() {
+    /// let serial = serialport::new("COM42", 115200)
+    ///     .timeout(std::time::Duration::from_millis(2000))
+    ///     .open()
+    ///     .unwrap();
+    ///
+    /// let transport = SerialTransport::new(serial)
+    ///     .with_console_handler(Box::new(|line| println!("Device: {line}")));
+    ///
+    /// let mut client = MCUmgrClient::new_from_transport(transport);
+    /// # }
+    /// ```
+    pub fn new_from_transport<T: Transport + Send + 'static>(transport: T) -> Self {
+        Self {
+            connection: Connection::new(transport),
+            smp_frame_size: ZEPHYR_DEFAULT_SMP_FRAME_SIZE.into(),
+            smp_buf_count: 0.into(),
+            window_size: 0.into(),
+            max_upload_rate: 0.into(),
+            chunk_alignment: 0.into(),
+            console_subscribers: ConsoleSubscribers::default(),
+            healthy: true.into(),
+        }
+    }
+
+    /// Creates a Zephyr MCUmgr SMP client based on a USB serial port identified by VID:PID.
+    ///
+    /// Useful for programming many devices in rapid succession, as Windows usually
+    /// gives each one a different COMxx identifier.
+    ///
+    /// # Arguments
+    ///
+    /// * `identifier` - A regex that identifies the device, or a [`UsbMatcher`].
+    /// * `baud_rate` - The baud rate the port should operate at.
+    /// * `timeout` - The communication timeout.
+    ///
+    /// # Identifier examples
+    ///
+    /// - `1234:89AB` - Vendor ID 1234, Product ID 89AB. Will fail if product has multiple serial ports.
+    /// - `1234:89AB:12` - Vendor ID 1234, Product ID 89AB, Interface 12.
+    /// - `1234:.*:[2-3]` - Vendor ID 1234, any Product Id, Interface 2 or 3.
+    ///
+    /// # Disambiguation
+    ///
+    /// If several boards of the same type are connected, the error lists all of them:
+    ///
+    /// ```no_run
+    /// # use zephyr_mcumgr::{MCUmgrClient, client::{UsbMatcher, UsbSerialError}};
+    /// # use std::time::Duration;
+    /// # fn main() {
+    /// let timeout = Duration::from_secs(2);
+    /// let client = match MCUmgrClient::new_from_usb_serial("2fe3:0004", 115200, timeout) {
+    ///     Err(UsbSerialError::MultipleMatchingPorts { ports, .. }) => {
+    ///         let matcher = UsbMatcher {
+    ///             serial_number: ports.0[0].port_info.serial_number.clone(),
+    ///             ..Default::default()
+    ///         };
+    ///         MCUmgrClient::new_from_usb_serial(matcher, 115200, timeout)
+    ///     }
+    ///     result => result,
+    /// }
+    /// .unwrap();
+    /// # }
+    /// ```
+    #[cfg(feature = "serialport")]
+    pub fn new_from_usb_serial(
+        identifier: impl Into<UsbPortSelector>,
+        baud_rate: u32,
+        timeout: Duration,
+    ) -> Result<Self, UsbSerialError> {
+        let port_name = find_usb_serial_port(&identifier.into())?.port_name;
+
+        let serial = serialport::new(port_name, baud_rate)
+            .timeout(timeout)
+            .open()?;
+
+        let client = Self::new_from_serial(serial);
+        client.connection.assume_timeout(timeout);
+        Ok(client)
+    }
+
+    /// Creates a Zephyr MCUmgr SMP client based on a UDP connection.
+    ///
+    /// Useful for devices connected via Ethernet or Wi-Fi, with
+    /// `CONFIG_MCUMGR_TRANSPORT_UDP` enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the device, e.g. `("192.168.1.42", UdpTransport::DEFAULT_PORT)`.
+    /// * `timeout` - The communication timeout.
+    ///
+    pub fn new_from_udp(addr: impl ToSocketAddrs, timeout: Duration) -> io::Result<Self> {
+        let client = Self::new_from_transport(UdpTransport::new(addr, timeout)?);
+        client.connection.assume_timeout(timeout);
+        Ok(client)
+    }
+
+    /// Creates a Zephyr MCUmgr SMP client based on a serial port that is bridged over TCP.
+    ///
+    /// Useful for devices that are attached to a `ser2net` server in a lab.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The address of the bridge, see [`RemoteSerialPort::open`].
+    /// * `timeout` - The communication timeout.
+    ///
+    pub fn new_from_remote_serial(
+        url: impl AsRef<str>,
+        timeout: Duration,
+    ) -> Result<Self, RemoteSerialError> {
+        let serial = RemoteSerialPort::open(url.as_ref(), timeout)?;
+        let client = Self::new_from_serial(serial);
+        client.connection.assume_timeout(timeout);
+        Ok(client)
+    }
+
+    /// Subscribes to the console output of the device.
+    ///
+    /// Returns a channel that receives every text line the device prints between SMP frames,
+    /// for devices that share one UART between the Zephyr console and SMP.
+    ///
+    /// The serial port is only read while waiting for SMP responses, so console lines
+    /// arrive in bursts whenever a command gets executed. Use [`mpsc::Receiver::try_iter`]
+    /// to collect the lines received so far.
+    ///
+    /// Only available for clients created through [`MCUmgrClient::new_from_serial`],
+    /// [`MCUmgrClient::new_from_usb_serial`] or [`MCUmgrClient::new_from_remote_serial`]; for other clients, the channel never receives anything.
+    pub fn console_lines(&self) -> mpsc::Receiver<String> {
+        let (sender, receiver) = mpsc::channel();
+        self.console_subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Subscribes to SMP frames that match no pending request, like notifications
+    /// of custom management groups.
+    ///
+    /// See [`Connection::unsolicited_frames`] for more information.
+    pub fn unsolicited_frames(&self) -> mpsc::Receiver<UnsolicitedFrame> {
+        self.connection.unsolicited_frames()
+    }
+
+    /// Configures the maximum SMP frame size that we can send to the device.
+    ///
+    /// Must not exceed [`MCUMGR_TRANSPORT_NETBUF_SIZE`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/subsys/mgmt/mcumgr/transport/Kconfig#L40),
+    /// otherwise we might crash the device.
+    pub fn set_frame_size(&self, smp_frame_size: usize) {
+        self.smp_frame_size
+            .store(smp_frame_size, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Configures the maximum SMP frame size that we can send to the device automatically
+    /// by reading the value of [`MCUMGR_TRANSPORT_NETBUF_SIZE`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/subsys/mgmt/mcumgr/transport/Kconfig#L40)
+    /// from the device.
+    pub fn use_auto_frame_size(&self) -> Result<(), ExecuteError> {
+        let mcumgr_params = self
+            .connection
+            .execute_command(&commands::os::MCUmgrParameters)?;
+
+        log::debug!("Using frame size {}.", mcumgr_params.buf_size);
+
+        self.smp_frame_size.store(
+            mcumgr_params.buf_size as usize,
+            std::sync::atomic::Ordering::SeqCst,
+        );
+        self.smp_buf_count.store(
+            mcumgr_params.buf_count as usize,
+            std::sync::atomic::Ordering::SeqCst,
+        );
+
+        Ok(())
+    }
+
+    /// Configures how many upload requests may be in flight at the same time.
+    ///
+    /// Keeping multiple requests in flight hides the round trip latency of the
+    /// transport, which speeds up uploads considerably, especially over USB CDC.
+    ///
+    /// Also applies to [`MCUmgrClient::execute_batch`].
+    ///
+    /// If `None`, the window size is derived from the number of SMP buffers
+    /// the device reported in [`MCUmgrClient::use_auto_frame_size`], leaving
+    /// one buffer for the response. Without that information, every request
+    /// waits for its response before the next one is sent.
+    ///
+    /// With a window size of `1`, upload chunks are retransmitted according to
+    /// the retry policy, see [`MCUmgrClient::set_retry_policy`].
+    ///
+    /// Must not exceed [`MCUMGR_TRANSPORT_NETBUF_COUNT`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/subsys/mgmt/mcumgr/transport/Kconfig),
+    /// otherwise the device will drop requests.
+    pub fn set_window_size(&self, window_size: Option<usize>) {
+        self.window_size.store(
+            window_size.map_or(0, |size| size.max(1)),
+            std::sync::atomic::Ordering::SeqCst,
+        );
+    }
+
+    /// Caps the data rate of [`MCUmgrClient::image_upload`] and [`MCUmgrClient::fs_file_upload`].
+    ///
+    /// Some transports, for example ones bridged through a coprocessor, lock up
+    /// if they get flooded with data. Chunks are delayed as required to stay
+    /// below the given number of payload bytes per second.
+    ///
+    /// If `None`, uploads are not throttled.
+    pub fn set_max_upload_rate(&self, max_bytes_per_second: Option<u64>) {
+        self.max_upload_rate.store(
+            max_bytes_per_second.map_or(0, |rate| rate.max(1)),
+            std::sync::atomic::Ordering::SeqCst,
+        );
+    }
+
+    /// The configured upload rate limit in bytes per second, if any.
+    fn max_upload_rate(&self) -> Option<u64> {
+        match self
+            .max_upload_rate
+            .load(std::sync::atomic::Ordering::SeqCst)
+        {
+            0 => None,
+            rate => Some(rate),
+        }
+    }
+
+    /// Makes the length of every chunk of [`MCUmgrClient::image_upload`] and
+    /// [`MCUmgrClient::fs_file_upload`] except the last one a multiple of `alignment`.
+    ///
+    /// Devices with strict flash write block sizes, for example `4` or `512` bytes,
+    /// write aligned chunks more efficiently. The chunk size gets rounded down,
+    /// so the SMP frame size has to fit at least one aligned chunk.
+    ///
+    /// If `None`, chunks are as large as the SMP frame size allows.
+    pub fn set_chunk_alignment(&self, alignment: Option<usize>) {
+        self.chunk_alignment.store(
+            alignment.map_or(0, |alignment| alignment.max(1)),
+            std::sync::atomic::Ordering::SeqCst,
+        );
+    }
+
+    /// Rounds the maximum chunk size down to the configured chunk alignment.
+    fn align_chunk_size(&self, chunk_size_max: usize) -> Result<usize, DataTooLargeError> {
+        let alignment = self
+            .chunk_alignment
+            .load(std::sync::atomic::Ordering::SeqCst)
+            .max(1);
+        match chunk_size_max - chunk_size_max % alignment {
+            0 => Err(DataTooLargeError),
+            chunk_size => Ok(chunk_size),
+        }
+    }
+
+    /// The number of upload requests that may be in flight at the same time.
+    fn effective_window_size(&self) -> usize {
+        match self.window_size.load(std::sync::atomic::Ordering::SeqCst) {
+            // Every request occupies one of the device's SMP buffers until it is processed,
+            // and the response needs a buffer of its own. Filling all buffers with requests
+            // would leave none for the response, so the device would drop requests.
+            0 => self
+                .smp_buf_count
+                .load(std::sync::atomic::Ordering::SeqCst)
+                .saturating_sub(1)
+                .max(1),
+            window_size => window_size,
+        }
+    }
+
+    /// Changes the communication timeout.
+    ///
+    /// When the device does not respond to packets within the set
+    /// duration, an error will be raised.
+    pub fn set_timeout(&self, timeout: Duration) -> Result<(), miette::Report> {
+        self.connection.set_timeout(timeout)
+    }
+
+    /// Replaces all registered frame observers with the given one.
+    ///
+    /// See [`Connection::set_frame_observer`] for more information.
+    pub fn set_frame_observer(&self, observer: Option<FrameObserver>) {
+        self.connection.set_frame_observer(observer)
+    }
+
+    /// Registers a callback that gets invoked for every SMP frame sent or received.
+    ///
+    /// See [`Connection::add_frame_observer`] for more information.
+    pub fn add_frame_observer(&self, observer: FrameObserver) -> FrameObserverId {
+        self.connection.add_frame_observer(observer)
+    }
+
+    /// Removes an observer registered through [`MCUmgrClient::add_frame_observer`].
+    ///
+    /// Returns `false` if the observer was already removed.
+    pub fn remove_frame_observer(&self, id: FrameObserverId) -> bool {
+        self.connection.remove_frame_observer(id)
+    }
+
+    /// Configures the retransmission of requests whose responses time out.
+    ///
+    /// By default, requests are not retransmitted.
+    /// Upload chunks are only retransmitted if the window size is `1`,
+    /// because pipelined requests are never retransmitted,
+    /// see [`MCUmgrClient::set_window_size`].
+    ///
+    /// See [`Connection::set_retry_policy`] for more information.
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        self.connection.set_retry_policy(policy)
+    }
+
+    /// Configures the SMP protocol version of the sent requests.
+    ///
+    /// See [`Connection::set_smp_version`] for more information.
+    pub fn set_smp_version(&self, version: SmpVersion) {
+        self.connection.set_smp_version(version)
+    }
+
+    /// The SMP protocol version the device responded with, or `None` before the first response.
+    ///
+    /// See [`Connection::device_smp_version`] for more information.
+    pub fn smp_version(&self) -> Option<SmpVersion> {
+        self.connection.device_smp_version()
+    }
+
+    /// Returns a snapshot of the traffic statistics of the connection.
+    ///
+    /// Useful for computing the effective throughput of transfers:
+    /// reset the statistics before the transfer with [`MCUmgrClient::reset_stats`]
+    /// and divide the transferred bytes by the elapsed time afterwards.
+    pub fn stats(&self) -> ConnectionStats {
+        self.connection.stats()
+    }
+
+    /// Resets the traffic statistics of the connection.
+    pub fn reset_stats(&self) {
+        self.connection.reset_stats()
+    }
+
+    /// Checks if the device is alive and responding.
+    ///
+    /// Runs a simple echo with random data and checks if the response matches.
+    ///
+    /// # Return
+    ///
+    /// An error if the device is not alive and responding.
+    pub fn check_connection(&self) -> Result<(), ExecuteError> {
+        let random_message = rand::distr::Alphanumeric.sample_string(&mut rand::rng(), 16);
+        let response = self.os_echo(&random_message)?;
+        if random_message == response {
+            Ok(())
+        } else {
+            Err(ExecuteError::ReceiveFailed(
+                crate::transport::ReceiveError::UnexpectedResponse,
+            ))
+        }
+    }
+
+    /// Checks if the device is alive and responding.
+    ///
+    /// Same as [`MCUmgrClient::check_connection`], but without retransmissions
+    /// and with a timeout of at most one second, so dead links are detected quickly.
+    ///
+    /// The timeout only gets shortened if the client knows its communication timeout,
+    /// i.e. if it got created with one or [`MCUmgrClient::set_timeout`] was called.
+    pub fn is_alive(&self) -> bool {
+        const ALIVE_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+
+        let random_message = rand::distr::Alphanumeric.sample_string(&mut rand::rng(), 16);
+
+        let mut pipeline = self.connection.pipeline();
+        let result = pipeline
+            .send(&commands::os::Echo { d: &random_message })
+            .and_then(|pending| pipeline.receive_within(pending, Some(ALIVE_CHECK_TIMEOUT)));
+
+        match result {
+            Ok(response) => response.r == random_message,
+            Err(e) => {
+                log::debug!("Device is not alive: {e}");
+                false
+            }
+        }
+    }
+
+    /// Starts checking periodically in a background thread whether the device is alive.
+    ///
+    /// After [`KeepaliveConfig::max_failures`] consecutive failed checks, the client
+    /// is marked unhealthy, see [`MCUmgrClient::is_healthy`]. It gets marked healthy
+    /// again as soon as a check succeeds.
+    ///
+    /// The checks stop when the returned [`Keepalive`] or the client gets dropped.
+    ///
+    /// ```no_run
+    /// # use zephyr_mcumgr::{MCUmgrClient, client::KeepaliveConfig};
+    /// # use std::sync::Arc;
+    /// # fn main() {
+    /// # let serial = serialport::new("COM42", 115200).open().unwrap();
+    /// let client = Arc::new(MCUmgrClient::new_from_serial(serial));
+    /// let _keepalive = client.start_keepalive(KeepaliveConfig::default());
+    ///
+    /// loop {
+    ///     if !client.is_healthy() {
+    ///         eprintln!("Device is gone");
+    ///     }
+    ///     std::thread::sleep(std::time::Duration::from_secs(1));
+    /// }
+    /// # }
+    /// ```
+    pub fn start_keepalive(self: &Arc<Self>, config: KeepaliveConfig) -> Keepalive {
+        Keepalive::start(Arc::downgrade(self), config)
+    }
+
+    /// Starts polling the configured sources on a background thread.
+    ///
+    /// Useful for collecting data during long-running soak tests.
+    /// The snapshots are delivered through the returned channel.
+    ///
+    /// ```no_run
+    /// # use zephyr_mcumgr::{MCUmgrClient, client::{SampleSource, SamplerConfig}};
+    /// # use std::{sync::Arc, time::Duration};
+    /// # fn main() {
+    /// # let serial = serialport::new("COM42", 115200).open().unwrap();
+    /// let client = Arc::new(MCUmgrClient::new_from_serial(serial));
+    ///
+    /// let (_sampler, snapshots) = client.start_sampler(SamplerConfig {
+    ///     interval: Duration::from_secs(10),
+    ///     sources: vec![
+    ///         SampleSource::TaskStatistics,
+    ///         SampleSource::StatGroup("smp_svr_stats".into()),
+    ///     ],
+    /// });
+    ///
+    /// for snapshot in snapshots {
+    ///     println!("{snapshot:?}");
+    /// }
+    /// # }
+    /// ```
+    pub fn start_sampler(
+        self: &Arc<Self>,
+        config: SamplerConfig,
+    ) -> (Sampler, mpsc::Receiver<Snapshot>) {
+        let (sender, receiver) = mpsc::channel();
+        let sampler = self.start_sampler_with_callback(config, move |snapshot| {
+            // The receiver may already be gone; the sampler stops once its handle is dropped
+            let _ = sender.send(snapshot);
+        });
+        (sampler, receiver)
+    }
+
+    /// Same as [`MCUmgrClient::start_sampler`], but delivers the snapshots to a callback.
+    ///
+    /// The callback runs on the background thread.
+    pub fn start_sampler_with_callback(
+        self: &Arc<Self>,
+        config: SamplerConfig,
+        callback: impl FnMut(Snapshot) + Send + 'static,
+    ) -> Sampler {
+        Sampler::start(Arc::downgrade(self), config, callback)
+    }
+
+    /// Whether the device responded to the recent checks of [`MCUmgrClient::start_keepalive`].
+    ///
+    /// Always `true` if no keepalive is running.
+    pub fn is_healthy(&self) -> bool {
+        self.healthy.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Sends a message to the device and expects the same message back as response.
+    ///
+    /// This can be used as a sanity check for whether the device is connected and responsive.
+    pub fn os_echo(&self, msg: impl AsRef<str>) -> Result<String, ExecuteError> {
+        self.connection
+            .execute_command(&commands::os::Echo { d: msg.as_ref() })
+            .map(|resp| resp.r)
+    }
+
+    /// Queries live task statistics
+    ///
+    /// # Note
+    ///
+    /// Converts `stkuse` and `stksiz` to bytes.
+    /// Zephyr originally reports them as number of 4 byte words.
+    ///
+    /// # Return
+    ///
+    /// A map of task names with their respective statistics
+    pub fn os_task_statistics(
+        &self,
+    ) -> Result<HashMap<String, commands::os::TaskStatisticsEntry>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::os::TaskStatistics)
+            .map(|resp| {
+                let mut tasks = resp.tasks;
+                for (_, stats) in tasks.iter_mut() {
+                    stats.stkuse = stats.stkuse.map(|val| val * 4);
+                    stats.stksiz = stats.stksiz.map(|val| val * 4);
+                }
+                tasks.into_iter().collect()
+            })
+    }
+
+    /// Sets the RTC of the device to the given datetime.
+    pub fn os_set_datetime(&self, datetime: chrono::NaiveDateTime) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::os::DateTimeSet { datetime })
+            .map(Into::into)
+    }
+
+    /// Retrieves the device RTC's datetime.
+    pub fn os_get_datetime(&self) -> Result<chrono::NaiveDateTime, ExecuteError> {
+        self.connection
+            .execute_command(&commands::os::DateTimeGet)
+            .map(|val| val.datetime)
+    }
+
+    /// Issues a system reset.
+    ///
+    /// # Arguments
+    ///
+    /// * `force` - Issues a force reset.
+    /// * `boot_mode` - Overwrites the boot mode.
+    ///
+    /// Known `boot_mode` values:
+    /// * `0` - Normal system boot
+    /// * `1` - Bootloader recovery mode
+    ///
+    /// Note that `boot_mode` only works if [`MCUMGR_GRP_OS_RESET_BOOT_MODE`](https://docs.zephyrproject.org/latest/kconfig.html#CONFIG_MCUMGR_GRP_OS_RESET_BOOT_MODE) is enabled.
+    ///
+    pub fn os_system_reset(&self, force: bool, boot_mode: Option<u8>) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::os::SystemReset { force, boot_mode })
+            .map(Into::into)
+    }
+
+    /// Fetch parameters from the MCUmgr library
+    pub fn os_mcumgr_parameters(
+        &self,
+    ) -> Result<commands::os::MCUmgrParametersResponse, ExecuteError> {
+        self.connection
+            .execute_command(&commands::os::MCUmgrParameters)
+    }
+
+    /// Fetch information on the running image
+    ///
+    /// Similar to Linux's `uname` command.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - Format specifier for the returned response
+    ///
+    /// For more information about the format specifier fields, see
+    /// the [SMP documentation](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_groups/smp_group_0.html#os-application-info-request).
+    ///
+    pub fn os_application_info(&self, format: Option<&str>) -> Result<String, ExecuteError> {
+        self.connection
+            .execute_command(&commands::os::ApplicationInfo { format })
+            .map(|resp| resp.output)
+    }
+
+    /// Fetch information on the device's bootloader
+    pub fn os_bootloader_info(&self) -> Result<BootloaderInfo, ExecuteError> {
+        Ok(
+            match self
+                .connection
+                .execute_command(&commands::os::BootloaderInfo)?
+                .bootloader
+                .as_str()
+            {
+                "MCUboot" => {
+                    let mode_data = self
+                        .connection
+                        .execute_command(&commands::os::BootloaderInfoMcubootMode {})?;
+                    BootloaderInfo::MCUboot {
+                        mode: mode_data.mode,
+                        no_downgrade: mode_data.no_downgrade,
+                    }
+                }
+                name => BootloaderInfo::Unknown {
+                    name: name.to_string(),
+                },
+            },
+        )
+    }
+
+    /// Obtain a list of images with their current state.
+    pub fn image_get_state(&self) -> Result<Vec<commands::image::ImageState>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::image::GetImageState)
+            .map(|val| val.images)
+    }
+
+    /// Modify the current image state
+    ///
+    /// # Arguments
+    ///
+    /// * `hash` - the SHA256 id of the image.
+    /// * `confirm` - mark the given image as 'confirmed'
+    ///
+    /// If `confirm` is `false`, perform a test boot with the given image and revert upon hard reset.
+    ///
+    /// If `confirm` is `true`, boot to the given image and mark it as `confirmed`. If `hash` is omitted,
+    /// confirm the currently running image.
+    ///
+    /// Note that `hash` will not be the same as the SHA256 of the whole firmware image,
+    /// it is the field in the MCUboot TLV section that contains a hash of the data
+    /// which is used for signature verification purposes.
+    pub fn image_set_state(
+        &self,
+        hash: Option<[u8; 32]>,
+        confirm: bool,
+    ) -> Result<Vec<commands::image::ImageState>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::image::SetImageState {
+                hash: hash.as_ref(),
+                confirm,
+            })
+            .map(|val| val.images)
+    }
+
+    /// Upload a firmware image to an image slot.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The firmware image data
+    /// * `image` - Selects target image on the device, e.g. `1` for the nRF5340 network core. Defaults to `0`.
+    /// * `checksum` - The SHA256 checksum of the image. If missing, will be computed from the image data.
+    /// * `upgrade_only` - If true, allow firmware upgrades only and reject downgrades.
+    /// * `progress` - A callback that receives a pair of (transferred, total) bytes and returns false on error.
+    /// * `cancel` - A token that aborts the upload before its next chunk.
+    ///
+    /// Multiple chunks are kept in flight at the same time, see [`MCUmgrClient::set_window_size`].
+    /// The data rate can be capped through [`MCUmgrClient::set_max_upload_rate`].
+    ///
+    /// # Resuming
+    ///
+    /// If a previous upload of the same image got interrupted, for example through a dropped
+    /// connection, the device recognizes the image by its SHA256 checksum and reports the
+    /// offset it stopped at. The upload then continues from there instead of starting over.
+    ///
+    pub fn image_upload(
+        &self,
+        data: impl AsRef<[u8]>,
+        image: Option<u32>,
+        checksum: Option<[u8; 32]>,
+        upgrade_only: bool,
+        progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(), ImageUploadError> {
+        self.image_upload_throttled(
+            data.as_ref(),
+            image,
+            checksum,
+            upgrade_only,
+            progress,
+            cancel,
+            self.max_upload_rate(),
+        )
+    }
+
+    /// Same as [`MCUmgrClient::image_upload`], but with an explicit upload rate limit.
+    #[allow(clippy::too_many_arguments)]
+    fn image_upload_throttled(
+        &self,
+        data: &[u8],
+        image: Option<u32>,
+        checksum: Option<[u8; 32]>,
+        upgrade_only: bool,
+        mut progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+        cancel: Option<&CancellationToken>,
+        max_bytes_per_second: Option<u64>,
+    ) -> Result<(), ImageUploadError> {
+        let chunk_size_max = image_upload_max_data_chunk_size(
+            self.smp_frame_size
+                .load(std::sync::atomic::Ordering::SeqCst),
+        )
+        .and_then(|chunk_size| self.align_chunk_size(chunk_size))
+        .map_err(ImageUploadError::FrameSizeTooSmall)?;
+
+        let actual_checksum: [u8; 32] = Sha256::digest(data).into();
+        if let Some(checksum) = checksum {
+            if actual_checksum != checksum {
+                return Err(ImageUploadError::ChecksumMismatch);
+            }
+        }
+
+        let size = data.len();
+
+        let window_size = self.effective_window_size();
+        let mut throttle = Throttle::new(max_bytes_per_second);
+        let mut pipeline = self.connection.pipeline();
+        let mut in_flight = VecDeque::with_capacity(window_size);
+
+        let mut sent = 0;
+        let mut offset = 0;
+
+        let mut checksum_matched = None;
+
+        while offset < size {
+            // The response to the first chunk tells whether the device resumes
+            // a previous upload session, so wait for it before filling the window.
+            let current_window_size = if offset == 0 { 1 } else { window_size };
+
+            while sent < size && in_flight.len() < current_window_size {
+                let current_chunk_size = (size - sent).min(chunk_size_max);
+                let chunk_data = &data[sent..sent + current_chunk_size];
+
+                if cancel.is_some_and(CancellationToken::is_cancelled) {
+                    return Err(ImageUploadError::Cancelled);
+                }
+
+                throttle.wait(current_chunk_size as u64);
+
+                let request = if sent == 0 {
+                    commands::image::ImageUpload {
+                        image,
+                        len: Some(size as u64),
+                        off: sent as u64,
+                        sha: Some(&actual_checksum),
+                        data: chunk_data,
+                        upgrade: Some(upgrade_only),
+                    }
+                } else {
+                    commands::image::ImageUpload {
+                        image: None,
+                        len: None,
+                        off: sent as u64,
+                        sha: None,
+                        data: chunk_data,
+                        upgrade: None,
+                    }
+                };
+                let chunk =
+                    SentChunk::send(&self.connection, &mut pipeline, &request, window_size)?;
+
+                sent += current_chunk_size;
+                in_flight.push_back((chunk, sent));
+            }
+
+            let Some((chunk, chunk_end)) = in_flight.pop_front() else {
+                break;
+            };
+            let mut upload_response = chunk.receive(&mut pipeline)?;
+
+            // The device continues at a different offset than we expected,
+            // for example because it resumed a previous upload session.
+            // The chunks that are still in flight are void, so wait for them
+            // to finish and continue at the offset the device reported last.
+            if upload_response.off != chunk_end as u64 {
+                while let Some((chunk, _)) = in_flight.pop_front() {
+                    upload_response = chunk.receive(&mut pipeline)?;
+                }
+                log::debug!("Device continues upload at offset {}", upload_response.off);
+            }
+
+            offset = upload_response
+                .off
+                .try_into()
+                .map_err(|_| ImageUploadError::UnexpectedOffset)?;
+
+            if offset > size {
+                return Err(ImageUploadError::UnexpectedOffset);
+            }
+
+            if in_flight.is_empty() {
+                sent = offset;
+            }
+
+            if let Some(progress) = &mut progress {
+                if !progress(offset as u64, size as u64) {
+                    return Err(ImageUploadError::ProgressCallbackError);
+                };
+            }
+
+            if let Some(is_match) = upload_response.r#match {
+                checksum_matched = Some(is_match);
+            }
+        }
+
+        if let Some(checksum_matched) = checksum_matched {
+            if !checksum_matched {
+                return Err(ImageUploadError::ChecksumMismatchOnDevice);
+            }
+        } else {
+            log::warn!("Device did not perform image checksum verification");
+        }
+
+        Ok(())
+    }
+
+    /// Performs a firmware update.
+    ///
+    /// Uploads the image, marks it for a test boot and reboots the device.
+    ///
+    /// If [`FirmwareUpdateParams::confirm_timeout`] is set, the new image gets confirmed
+    /// once the device is back up and running it. If the new image fails to boot,
+    /// MCUboot reverts to the previous image.
+    ///
+    /// # Arguments
+    ///
+    /// * `firmware` - The MCUboot firmware image data, either binary or Intel HEX
+    /// * `params` - Parameters that customize the update process
+    /// * `progress` - A callback that receives a pair of (transferred, total) bytes and returns false on error.
+    ///
+    pub fn firmware_update(
+        &self,
+        firmware: impl AsRef<[u8]>,
+        params: &FirmwareUpdateParams,
+        progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+    ) -> Result<FirmwareUpdateOutcome, FirmwareUpdateError> {
+        let converted_firmware;
+        let firmware = if mcuboot::is_ihex(firmware.as_ref()) {
+            converted_firmware = mcuboot::from_ihex(firmware.as_ref())?;
+            &converted_firmware
+        } else {
+            firmware.as_ref()
+        };
+
+        let image_info = mcuboot::get_image_info(io::Cursor::new(firmware))?;
+        let image_index = u64::from(params.image_index.unwrap_or(0));
+
+        if let Some(public_key) = &params.public_key {
+            #[cfg(feature = "verify")]
+            {
+                let algorithm = mcuboot::verify_signature(io::Cursor::new(firmware), public_key)?;
+                log::debug!("Image signature verified ({algorithm:?})");
+            }
+            #[cfg(not(feature = "verify"))]
+            {
+                let _ = public_key;
+                return Err(FirmwareUpdateError::SignatureVerificationUnavailable);
+            }
+        }
+
+        if params.skip_if_version_not_newer {
+            if let Some(active_version) = self.active_image_version(image_index)? {
+                if active_version >= image_info.version {
+                    log::debug!(
+                        "Skipping update of image {image_index}, active version {active_version} is not older than {}",
+                        image_info.version
+                    );
+                    return Ok(FirmwareUpdateOutcome::Skipped { active_version });
+                }
+            }
+        }
+
+        if !image_info.dependencies.is_empty() {
+            let image_states = self.image_get_state()?;
+            if let Some(dependency) = image_info
+                .dependencies
+                .iter()
+                .find(|dependency| !dependency.is_satisfied_by(&image_states))
+            {
+                return Err(FirmwareUpdateError::DependencyNotSatisfied(*dependency));
+            }
+        }
+
+        log::debug!(
+            "Updating image {image_index} to version {}",
+            image_info.version
+        );
+
+        if params.restart_upload {
+            // Every image has two slots, uploads go to the second one
+            let upload_slot = params.image_index.unwrap_or(0) * 2 + 1;
+            log::debug!("Erasing slot {upload_slot} to restart the upload");
+            self.image_erase(Some(upload_slot))?;
+        }
+
+        self.image_upload_throttled(
+            firmware,
+            params.image_index,
+            None,
+            params.upgrade_only,
+            progress,
+            params.cancel.as_ref(),
+            params.max_bytes_per_second.or(self.max_upload_rate()),
+        )
+        .map_err(|e| match e {
+            ImageUploadError::Cancelled => FirmwareUpdateError::Cancelled,
+            e => e.into(),
+        })?;
+
+        if params
+            .cancel
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(FirmwareUpdateError::Cancelled);
+        }
+
+        self.image_set_state(Some(image_info.hash), false)?;
+
+        if params.skip_reboot {
+            return Ok(FirmwareUpdateOutcome::Updated);
+        }
+
+        self.os_system_reset(false, None)?;
+
+        if let Some(timeout) = params.confirm_timeout {
+            // Zephyr only resets after sending the response, with a delay of
+            // `CONFIG_MCUMGR_GRP_OS_RESET_MS`; don't mistake the old firmware for the new one.
+            const RESET_DELAY: Duration = Duration::from_secs(1);
+            std::thread::sleep(RESET_DELAY);
+
+            match self.verify_active_image(image_info.hash, timeout.saturating_sub(RESET_DELAY))? {
+                ActiveImageVerification::Active => (),
+                ActiveImageVerification::Timeout => {
+                    return Err(FirmwareUpdateError::RebootTimeout);
+                }
+                ActiveImageVerification::Reverted | ActiveImageVerification::Mismatch => {
+                    return Err(FirmwareUpdateError::ImageNotActive);
+                }
+            }
+
+            self.image_set_state(Some(image_info.hash), true)?;
+            log::debug!("Confirmed image {}", hex::encode(image_info.hash));
+        }
+
+        Ok(FirmwareUpdateOutcome::Updated)
+    }
+
+    /// Reads the version of the active image with the given index from the device.
+    ///
+    /// Returns `None` if there is no active image or its version cannot be parsed.
+    fn active_image_version(&self, image_index: u64) -> Result<Option<ImageVersion>, ExecuteError> {
+        let Some(active_image) = self
+            .image_get_state()?
+            .into_iter()
+            .find(|state| state.image == image_index && state.active)
+        else {
+            log::debug!("Image {image_index} has no active slot");
+            return Ok(None);
+        };
+
+        match active_image.version.parse() {
+            Ok(version) => Ok(Some(version)),
+            Err(e) => {
+                log::warn!("Unable to compare firmware versions: {e}");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Checks whether the expected image is running on the device.
+    ///
+    /// Intended to be used after rebooting into a new image: polls the device
+    /// until it responds or `timeout` elapses, then inspects the image state.
+    /// Errors that are not [retryable](ExecuteError::is_retryable) end the polling early.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_hash` - The identifying hash of the image, see [`ImageInfo::hash`](crate::mcuboot::ImageInfo::hash).
+    /// * `timeout` - How long to wait for the device to respond.
+    ///
+    pub fn verify_active_image(
+        &self,
+        expected_hash: [u8; 32],
+        timeout: Duration,
+    ) -> Result<ActiveImageVerification, ExecuteError> {
+        let start = Instant::now();
+
+        loop {
+            match self.check_connection() {
+                Ok(()) => break,
+                Err(e) if e.is_retryable() => log::debug!("Device not responding yet: {e}"),
+                Err(e) => return Err(e),
+            }
+
+            if start.elapsed() >= timeout {
+                return Ok(ActiveImageVerification::Timeout);
+            }
+
+            // Some errors return immediately, don't hammer the device
+            std::thread::sleep(Duration::from_millis(100));
+        }
+
+        let images = self.image_get_state()?;
+        let expected_image = images
+            .iter()
+            .find(|state| state.hash == Some(expected_hash));
+
+        Ok(match expected_image {
+            Some(state) if state.active => ActiveImageVerification::Active,
+            Some(_) => ActiveImageVerification::Reverted,
+            None => ActiveImageVerification::Mismatch,
+        })
+    }
+
+    /// Erase image slot on target device.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot` - The slot ID of the image to erase. Slot `1` if omitted.
+    ///
+    pub fn image_erase(&self, slot: Option<u32>) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::image::ImageErase { slot })
+            .map(Into::into)
+    }
+
+    /// Obtain a list of available image slots.
+    pub fn image_slot_info(&self) -> Result<Vec<commands::image::SlotInfoImage>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::image::SlotInfo)
+            .map(|val| val.images)
+    }
+
+    /// Load a file from the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The full path of the file on the device.
+    /// * `writer` - A [`Write`] object that the file content will be written to.
+    /// * `progress` - A callback that receives a pair of (transferred, total) bytes.
+    /// * `cancel` - A token that aborts the download before its next chunk.
+    ///
+    /// Chunks are written to `writer` as they arrive, so the file
+    /// does not need to fit into memory.
+    ///
+    /// # Performance
+    ///
+    /// Downloading files with Zephyr's default parameters is slow.
+    /// You want to increase [`MCUMGR_TRANSPORT_NETBUF_SIZE`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/subsys/mgmt/mcumgr/transport/Kconfig#L40)
+    /// to maybe `4096` or larger.
+    pub fn fs_file_download<T: Write>(
+        &self,
+        name: impl AsRef<str>,
+        writer: T,
+        progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(), FileDownloadError> {
+        self.fs_file_download_from(name, 0, writer, progress, cancel)
+    }
+
+    /// Load the remainder of a file from the device, starting at the given offset.
+    ///
+    /// Allows continuing an interrupted [`fs_file_download`](MCUmgrClient::fs_file_download).
+    /// Only the data after `offset` is written to `writer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The full path of the file on the device.
+    /// * `offset` - The number of bytes to skip.
+    /// * `writer` - A [`Write`] object that the file content will be written to.
+    /// * `progress` - A callback that receives a pair of (transferred, total) bytes,
+    ///   where `transferred` includes the skipped bytes.
+    /// * `cancel` - A token that aborts the download before its next chunk.
+    ///
+    pub fn fs_file_download_from<T: Write>(
+        &self,
+        name: impl AsRef<str>,
+        offset: u64,
+        writer: T,
+        progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(), FileDownloadError> {
+        self.fs_file_download_range(name, offset, None, writer, progress, cancel)
+    }
+
+    /// Load a byte range of a file from the device.
+    ///
+    /// Useful for tailing large log files or for inspecting parts of a file
+    /// without transferring all of it. Only the data in the range is written to `writer`;
+    /// the range ends early if the file is shorter.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The full path of the file on the device.
+    /// * `offset` - The position of the first byte to load.
+    /// * `length` - The maximum number of bytes to load. Until the end of the file if omitted.
+    /// * `writer` - A [`Write`] object that the file content will be written to.
+    /// * `progress` - A callback that receives a pair of (transferred, total) bytes,
+    ///   where both include the skipped bytes.
+    /// * `cancel` - A token that aborts the download before its next chunk.
+    ///
+    pub fn fs_file_download_range<T: Write>(
+        &self,
+        name: impl AsRef<str>,
+        offset: u64,
+        length: Option<u64>,
+        mut writer: T,
+        mut progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<(), FileDownloadError> {
+        let name = name.as_ref();
+
+        let mut report_progress = |offset, end| {
+            if let Some(progress) = &mut progress {
+                if !progress(offset, end) {
+                    return Err(FileDownloadError::ProgressCallbackError);
+                }
+            }
+            Ok(())
+        };
+
+        let range_end = |file_len: u64| match length {
+            Some(length) => file_len.min(offset.saturating_add(length)),
+            None => file_len,
+        };
+
+        // The device only reports the file size in the response at offset 0
+        let mut end = None;
+        if offset > 0 {
+            let len = self.fs_file_status(name)?.len;
+            if offset > len {
+                return Err(FileDownloadError::OffsetBeyondEnd);
+            }
+            let len = range_end(len);
+            report_progress(offset, len)?;
+            end = Some(len);
+        }
+
+        let mut offset = offset;
+
+        while end.is_none_or(|end| offset < end) {
+            if cancel.is_some_and(CancellationToken::is_cancelled) {
+                return Err(FileDownloadError::Cancelled);
+            }
+
+            let response = self
+                .connection
+                .execute_command(&commands::fs::FileDownload { name, off: offset })?;
+
+            if response.off != offset {
+                return Err(FileDownloadError::UnexpectedOffset);
+            }
+
+            let end_offset = match end {
+                Some(end) => end,
+                None => {
+                    let len = range_end(response.len.ok_or(FileDownloadError::MissingSize)?);
+                    report_progress(offset, len)?;
+                    *end.insert(len)
+                }
+            };
+
+            // The last chunk may reach past the end of the range
+            let remaining = usize::try_from(end_offset - offset).unwrap_or(usize::MAX);
+            let data = &response.data[..response.data.len().min(remaining)];
+            if data.is_empty() {
+                break;
+            }
+
+            writer.write_all(data)?;
+            offset += data.len() as u64;
+
+            report_progress(offset, end_offset)?;
+        }
+
+        if end != Some(offset) {
+            return Err(FileDownloadError::SizeMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Write a file to the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The full path of the file on the device.
+    /// * `reader` - A [`Read`] object that contains the file content, starting at its current position.
+    /// * `size` - The file size.
+    /// * `options` - Verification and cancellation of the upload, see [`FsUploadOptions`].
+    /// * `progress` - A callback that receives a pair of (transferred, total) bytes and returns false on error.
+    ///
+    /// The content is read from `reader` incrementally, one chunk at a time,
+    /// so the file does not need to fit into memory.
+    ///
+    /// # Performance
+    ///
+    /// Uploading files with Zephyr's default parameters is slow.
+    /// You want to increase [`MCUMGR_TRANSPORT_NETBUF_SIZE`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/subsys/mgmt/mcumgr/transport/Kconfig#L40)
+    /// to maybe `4096` and then enable larger chunking through either [`MCUmgrClient::set_frame_size`]
+    /// or [`MCUmgrClient::use_auto_frame_size`].
+    ///
+    /// Multiple chunks are kept in flight at the same time, see [`MCUmgrClient::set_window_size`].
+    /// The data rate can be capped through [`MCUmgrClient::set_max_upload_rate`].
+    ///
+    /// Interrupted uploads can be continued through [`MCUmgrClient::fs_file_upload_resume`].
+    ///
+    /// # Verification
+    ///
+    /// If [`FsUploadOptions::verify`] is set, the device computes the SHA256 hash of the file after the
+    /// last chunk, which requires `CONFIG_MCUMGR_GRP_FS_HASH_SHA256`. A mismatch with
+    /// the hash of the data read from `reader` returns [`FileUploadError::VerificationFailed`].
+    pub fn fs_file_upload<T: Read>(
+        &self,
+        name: impl AsRef<str>,
+        reader: T,
+        size: u64,
+        options: &FsUploadOptions,
+        progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+    ) -> Result<(), FileUploadError> {
+        let reader = checksum::HashingReader::new(reader, options.verify);
+        self.fs_file_upload_from(name.as_ref(), reader, 0, size, options, progress)
+    }
+
+    /// Continue a previously interrupted write of a file to the device.
+    ///
+    /// Takes the same arguments as [`MCUmgrClient::fs_file_upload`].
+    ///
+    /// If the file already exists on the device, its content gets compared to
+    /// the start of `reader` through the device's default checksum algorithm.
+    /// If they match, only the remaining data is uploaded; otherwise `reader` gets
+    /// rewound to its initial position and the file is uploaded from the start.
+    pub fn fs_file_upload_resume<T: Read + Seek>(
+        &self,
+        name: impl AsRef<str>,
+        mut reader: T,
+        size: u64,
+        options: &FsUploadOptions,
+        progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+    ) -> Result<(), FileUploadError> {
+        let name = name.as_ref();
+        let start = reader.stream_position()?;
+
+        // Hashes every byte exactly once, including the data compared while resuming
+        let mut reader = checksum::HashingReader::new(reader, options.verify);
+
+        let offset = self.fs_file_resume_offset(name, &mut reader, start, size)?;
+        self.fs_file_upload_from(name, reader, offset, size, options, progress)
+    }
+
+    /// Uploads the data of `reader` to the file, starting at `offset`.
+    fn fs_file_upload_from<T: Read>(
+        &self,
+        name: &str,
+        mut reader: checksum::HashingReader<T>,
+        offset: u64,
+        size: u64,
+        options: &FsUploadOptions,
+        mut progress: Option<&mut dyn FnMut(u64, u64) -> bool>,
+    ) -> Result<(), FileUploadError> {
+        let chunk_size_max = file_upload_max_data_chunk_size(
+            self.smp_frame_size
+                .load(std::sync::atomic::Ordering::SeqCst),
+            name,
+        )
+        .and_then(|chunk_size| self.align_chunk_size(chunk_size))
+        .map_err(FileUploadError::FrameSizeTooSmall)?;
+        let mut data_buffer = vec![0u8; chunk_size_max].into_boxed_slice();
+
+        let mut sent = offset;
+        let session_start = offset;
+
+        let window_size = self.effective_window_size();
+        let mut throttle = Throttle::new(self.max_upload_rate());
+        let mut pipeline = self.connection.pipeline();
+        let mut in_flight = VecDeque::with_capacity(window_size);
+
+        let mut acknowledged = sent;
+
+        if acknowledged > 0 {
+            if let Some(progress) = &mut progress {
+                if !progress(acknowledged, size) {
+                    return Err(FileUploadError::ProgressCallbackError);
+                };
+            }
+        }
+
+        while acknowledged < size {
+            while sent < size && in_flight.len() < window_size {
+                let current_chunk_size = (size - sent).min(data_buffer.len() as u64) as usize;
+
+                if options
+                    .cancel
+                    .as_ref()
+                    .is_some_and(CancellationToken::is_cancelled)
+                {
+                    return Err(FileUploadError::Cancelled);
+                }
+
+                let chunk_buffer = &mut data_buffer[..current_chunk_size];
+                reader.read_exact(chunk_buffer)?;
+
+                throttle.wait(current_chunk_size as u64);
+
+                let request = commands::fs::FileUpload {
+                    off: sent,
+                    data: chunk_buffer,
+                    name,
+                    // The first chunk of every upload session announces the file size,
+                    // including sessions that resume an interrupted upload
+                    len: (sent == session_start).then_some(size),
+                };
+                let chunk =
+                    SentChunk::send(&self.connection, &mut pipeline, &request, window_size)?;
+
+                sent += chunk_buffer.len() as u64;
+                in_flight.push_back((chunk, sent));
+            }
+
+            let Some((chunk, chunk_end)) = in_flight.pop_front() else {
+                break;
+            };
+            chunk.receive(&mut pipeline)?;
+            acknowledged = chunk_end;
+
+            if let Some(progress) = &mut progress {
+                if !progress(acknowledged, size) {
+                    return Err(FileUploadError::ProgressCallbackError);
+                };
+            }
+        }
+
+        if let Some(local_hash) = reader.finalize() {
+            let device_hash = self.fs_file_checksum(name, Some("sha256"), 0, None)?;
+            if device_hash.len != size || device_hash.output != local_hash {
+                return Err(FileUploadError::VerificationFailed);
+            }
+            log::debug!("Verified upload of '{name}'");
+        }
+
+        Ok(())
+    }
+
+    /// Determines how much of a previously interrupted upload can be skipped.
+    ///
+    /// Consumes the compared data from `reader`. If the data on the device does not match,
+    /// `reader` gets rewound to `start` and the returned offset is zero.
+    fn fs_file_resume_offset(
+        &self,
+        name: &str,
+        reader: &mut checksum::HashingReader<impl Read + Seek>,
+        start: u64,
+        size: u64,
+    ) -> Result<u64, FileUploadError> {
+        let existing_len = match self.fs_file_status(name) {
+            Ok(status) => status.len,
+            Err(ExecuteError::ErrorResponse(e)) => {
+                log::debug!("Unable to resume upload of '{name}': {e}");
+                return Ok(0);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if existing_len == 0 || existing_len > size {
+            return Ok(0);
+        }
+
+        let device_checksum = match self.fs_file_checksum(name, None::<&str>, 0, Some(existing_len))
+        {
+            Ok(checksum) => checksum,
+            Err(ExecuteError::ErrorResponse(e)) => {
+                log::debug!("Unable to verify existing data of '{name}': {e}");
+                return Ok(0);
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let Some(mut hasher) = checksum::hasher_for_algorithm(&device_checksum.r#type) else {
+            log::debug!(
+                "Unable to verify existing data of '{name}': unknown checksum type '{}'",
+                device_checksum.r#type
+            );
+            return Ok(0);
+        };
+
+        let mut buffer = vec![0u8; RESUME_HASH_CHUNK_SIZE].into_boxed_slice();
+        let mut remaining = existing_len;
+        while remaining > 0 {
+            let chunk = &mut buffer[..remaining.min(RESUME_HASH_CHUNK_SIZE as u64) as usize];
+            reader.read_exact(chunk)?;
+            hasher.update(chunk);
+            remaining -= chunk.len() as u64;
+        }
+
+        if device_checksum.len == existing_len && hasher.finalize() == device_checksum.output {
+            log::debug!("Resuming upload of '{name}' at offset {existing_len}");
+            Ok(existing_len)
+        } else {
+            log::debug!("Existing data of '{name}' does not match, restarting upload");
+            reader.rewind_to(start)?;
+            Ok(0)
+        }
+    }
+
+    /// Queries the file status
+    pub fn fs_file_status(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Result<commands::fs::FileStatusResponse, ExecuteError> {
+        self.connection.execute_command(&commands::fs::FileStatus {
+            name: name.as_ref(),
+        })
+    }
+
+    /// Computes the hash/checksum of a file
+    ///
+    /// For available algorithms, see [`fs_supported_checksum_types()`](MCUmgrClient::fs_supported_checksum_types).
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The absolute path of the file on the device
+    /// * `algorithm` - The hash/checksum algorithm to use, or default if None
+    /// * `offset` - How many bytes of the file to skip
+    /// * `length` - How many bytes to read after `offset`. None for the entire file.
+    ///
+    pub fn fs_file_checksum(
+        &self,
+        name: impl AsRef<str>,
+        algorithm: Option<impl AsRef<str>>,
+        offset: u64,
+        length: Option<u64>,
+    ) -> Result<commands::fs::FileChecksumResponse, ExecuteError> {
+        self.connection
+            .execute_command(&commands::fs::FileChecksum {
+                name: name.as_ref(),
+                r#type: algorithm.as_ref().map(AsRef::as_ref),
+                off: offset,
+                len: length,
+            })
+    }
+
+    /// Queries which hash/checksum algorithms are available on the target
+    pub fn fs_supported_checksum_types(
+        &self,
+    ) -> Result<HashMap<String, commands::fs::FileChecksumProperties>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::fs::SupportedFileChecksumTypes)
+            .map(|val| val.types.into_iter().collect())
+    }
+
+    /// Close all device files MCUmgr has currently open
+    pub fn fs_file_close(&self) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::fs::FileClose)
+            .map(Into::into)
+    }
+
+    /// Read the counters of a statistics group.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the group, see [`MCUmgrClient::stat_list_groups`].
+    ///
+    /// # Return
+    ///
+    /// A map of counter names with their respective values
+    pub fn stat_group_data(
+        &self,
+        name: impl AsRef<str>,
+    ) -> Result<BTreeMap<String, u64>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::stat::GroupData {
+                name: name.as_ref(),
+            })
+            .map(|resp| resp.fields)
+    }
+
+    /// Obtain the names of all statistics groups on the device.
+    pub fn stat_list_groups(&self) -> Result<Vec<String>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::stat::ListGroups)
+            .map(|resp| resp.stat_list)
+    }
+
+    /// Read the value of a setting.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the setting, e.g. `app/serial`.
+    /// * `max_size` - The maximum number of bytes to return; longer values get truncated.
+    ///
+    pub fn settings_read(
+        &self,
+        name: impl AsRef<str>,
+        max_size: Option<u32>,
+    ) -> Result<Vec<u8>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::settings::SettingRead {
+                name: name.as_ref(),
+                max_size,
+            })
+            .map(|resp| resp.val)
+    }
+
+    /// Write the value of a setting.
+    ///
+    /// The new value only takes effect after [`settings_commit`](Self::settings_commit)
+    /// and only persists after [`settings_save`](Self::settings_save).
+    pub fn settings_write(&self, name: impl AsRef<str>, value: &[u8]) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::settings::SettingWrite {
+                name: name.as_ref(),
+                val: value,
+            })
+            .map(Into::into)
+    }
+
+    /// Delete a setting.
+    pub fn settings_delete(&self, name: impl AsRef<str>) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::settings::SettingDelete {
+                name: name.as_ref(),
+            })
+            .map(Into::into)
+    }
+
+    /// Apply all pending setting changes to the running application.
+    pub fn settings_commit(&self) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::settings::SettingsCommit)
+            .map(Into::into)
+    }
+
+    /// Load all settings from persistent storage.
+    pub fn settings_load(&self) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::settings::SettingsLoad)
+            .map(Into::into)
+    }
+
+    /// Save all settings to persistent storage.
+    pub fn settings_save(&self) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::settings::SettingsSave)
+            .map(Into::into)
+    }
+
+    /// Run a shell command.
+    ///
+    /// # Arguments
+    ///
+    /// * `argv` - The shell command to be executed.
+    ///
+    /// # Return
+    ///
+    /// A tuple of (returncode, stdout) produced by the command execution.
+    pub fn shell_execute(&self, argv: &[String]) -> Result<(i32, String), ExecuteError> {
+        self.connection
+            .execute_command(&commands::shell::ShellCommandLineExecute { argv })
+            .map(|ret| (ret.ret, ret.o))
+    }
+
+    /// Run a sequence of shell commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The shell commands to be executed, in order.
+    /// * `stop_on_error` - If true, skip the remaining commands after one returned a non-zero returncode.
+    ///
+    /// # Return
+    ///
+    /// A (returncode, stdout) tuple for every executed command.
+    /// Communication errors abort the whole script.
+    pub fn shell_run_script(
+        &self,
+        lines: &[Vec<String>],
+        stop_on_error: bool,
+    ) -> Result<Vec<(i32, String)>, ExecuteError> {
+        let mut results = Vec::with_capacity(lines.len());
+
+        for argv in lines {
+            let (returncode, output) = self.shell_execute(argv)?;
+            results.push((returncode, output));
+
+            if stop_on_error && returncode != 0 {
+                log::debug!("Shell command {argv:?} failed with returncode {returncode}");
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// List the IDs of all management groups the device supports.
+    ///
+    /// Requires `CONFIG_MCUMGR_GRP_ENUM` on the device.
+    pub fn enum_list_groups(&self) -> Result<Vec<u16>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::enumeration::ListGroups)
+            .map(|resp| resp.groups)
+    }
+
+    /// Fetch the details of the management groups the device supports.
+    ///
+    /// # Arguments
+    ///
+    /// * `groups` - The IDs of the groups to fetch the details of; all groups if `None`.
+    ///
+    /// Requires `CONFIG_MCUMGR_GRP_ENUM_DETAILS` on the device.
+    pub fn enum_group_details(
+        &self,
+        groups: Option<&[u16]>,
+    ) -> Result<Vec<commands::enumeration::GroupDetailsEntry>, ExecuteError> {
+        self.connection
+            .execute_command(&commands::enumeration::GroupDetails { groups })
+            .map(|resp| resp.groups)
+    }
+
+    /// Erase the `storage_partition` flash partition.
+    pub fn zephyr_erase_storage(&self) -> Result<(), ExecuteError> {
+        self.connection
+            .execute_command(&commands::zephyr::EraseStorage)
+            .map(Into::into)
+    }
+
+    /// Execute an arbitrary [`commands::McuMgrCommand`].
+    ///
+    /// This is the extension point for commands this client has no dedicated
+    /// function for, like the ones of application specific management groups.
+    ///
+    /// Only returns if no error happened, so the
+    /// user does not need to check for an `rc` or `err`
+    /// field in the response.
+    ///
+    /// ```no_run
+    /// # use zephyr_mcumgr::{MCUmgrClient, commands::user::{UserCommand, UserGroupId}};
+    /// # fn main() {
+    /// # let serial = serialport::new("COM42", 115200).open().unwrap();
+    /// # let client = MCUmgrClient::new_from_serial(serial);
+    /// #[derive(serde::Serialize)]
+    /// struct ReadSensor {
+    ///     channel: u8,
+    /// }
+    ///
+    /// #[derive(serde::Deserialize)]
+    /// struct ReadSensorResponse {
+    ///     value: f32,
+    /// }
+    ///
+    /// const SENSOR_GROUP: UserGroupId = UserGroupId::new(64).unwrap();
+    ///
+    /// let response: ReadSensorResponse = client
+    ///     .execute(&UserCommand::read(SENSOR_GROUP, 0, &ReadSensor { channel: 2 }))
+    ///     .unwrap();
+    /// println!("{}", response.value);
+    /// # }
+    /// ```
+    pub fn execute<T: commands::McuMgrCommand>(
+        &self,
+        command: &T,
+    ) -> Result<T::Response, ExecuteError> {
+        self.connection.execute_command(command)
+    }
+
+    /// Executes multiple [`commands::McuMgrCommand`]s with pipelining.
+    ///
+    /// Up to [`MCUmgrClient::set_window_size`] requests are in flight at the same time,
+    /// which is a large speedup over high latency transports.
+    ///
+    /// Returns one result per command, in the order of `commands`,
+    /// see [`Connection::execute_batch`].
+    ///
+    /// ```no_run
+    /// # use zephyr_mcumgr::{MCUmgrClient, commands::os::Echo};
+    /// # fn main() {
+    /// # let serial = serialport::new("COM42", 115200).open().unwrap();
+    /// # let client = MCUmgrClient::new_from_serial(serial);
+    /// let requests = ["a", "b", "c"].map(|d| Echo { d });
+    /// for result in client.execute_batch(&requests) {
+    ///     println!("{}", result.unwrap().r);
+    /// }
+    /// # }
+    /// ```
+    pub fn execute_batch<T: commands::McuMgrCommand>(
+        &self,
+        commands: &[T],
+    ) -> Vec<Result<T::Response, ExecuteError>> {
+        self.connection
+            .execute_batch(commands, self.effective_window_size())
+    }
+
+    /// Execute a raw [`commands::McuMgrCommand`].
+    #[deprecated(note = "use `MCUmgrClient::execute` instead")]
+    pub fn raw_command<T: commands::McuMgrCommand>(
+        &self,
+        command: &T,
+    ) -> Result<T::Response, ExecuteError> {
+        self.execute(command)
+    }
+}
+
+#[cfg(all(test, feature = "serialport"))]
+mod tests {
+    use super::*;
+
+    fn port(pid: u16, interface: u8, serial_number: &str) -> UsbSerialPortInfo {
+        UsbSerialPortInfo {
+            identifier: format!("2fe3:{pid:04x}:{interface}"),
+            port_name: format!("/dev/ttyACM{interface}"),
+            port_info: serialport::UsbPortInfo {
+                vid: 0x2fe3,
+                pid,
+                serial_number: Some(serial_number.to_string()),
+                manufacturer: Some("ZEPHYR".to_string()),
+                product: Some("USB-DEV".to_string()),
+                interface: Some(interface),
+            },
+        }
+    }
+
+    #[test]
+    fn usb_matcher_criteria() {
+        let ports = UsbSerialPorts(vec![
+            port(0x0004, 0, "A1B2"),
+            port(0x0004, 2, "A1B2"),
+            port(0x0100, 0, "C3D4"),
+        ]);
+
+        let matching = |matcher: UsbMatcher| {
+            ports
+                .filter(&matcher)
+                .0
+                .into_iter()
+                .map(|port| port.identifier)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matching(UsbMatcher::default()).len(), 3);
+        assert_eq!(
+            matching(UsbMatcher {
+                serial_number: Some("A1B2".into()),
+                interface: Some(2),
+                ..Default::default()
+            }),
+            ["2fe3:0004:2"]
+        );
+        assert_eq!(
+            matching(UsbMatcher {
+                vid: Some(0x2fe3),
+                pid: Some(0x0100),
+                product: Some("usb-dev".into()),
+                ..Default::default()
+            }),
+            ["2fe3:0100:0"]
+        );
+        assert!(
+            matching(UsbMatcher {
+                product: Some("other".into()),
+                ..Default::default()
+            })
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn usb_matcher_display() {
+        let matcher = UsbMatcher {
+            vid: Some(0x2fe3),
+            serial_number: Some("A1B2".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            UsbPortSelector::from(matcher).to_string(),
+            "vid=2fe3,serial_number=A1B2"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    sync::{Weak, mpsc},
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use super::MCUmgrClient;
+
+/// A background thread that runs a task with a client at a fixed rate.
+///
+/// Stops when this handle gets dropped or when the client gets dropped.
+pub(super) struct PeriodicWorker {
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PeriodicWorker {
+    /// Runs `task` every `interval`, starting at `first_run`.
+    ///
+    /// If the task takes longer than the interval, the next run starts right away.
+    pub(super) fn start(
+        client: Weak<MCUmgrClient>,
+        first_run: Instant,
+        interval: Duration,
+        mut task: impl FnMut(&MCUmgrClient) + Send + 'static,
+    ) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let thread = std::thread::spawn(move || {
+            let mut next_run = first_run;
+
+            loop {
+                // Dropping the sender disconnects the channel and ends the loop
+                let now = Instant::now();
+                if !matches!(
+                    stopped.recv_timeout(next_run.saturating_duration_since(now)),
+                    Err(mpsc::RecvTimeoutError::Timeout)
+                ) {
+                    break;
+                }
+
+                let Some(client) = client.upgrade() else {
+                    break;
+                };
+                task(&client);
+                drop(client);
+
+                // Keep a fixed rate instead of accumulating the duration of the task
+                next_run = (next_run + interval).max(Instant::now());
+            }
+        });
+
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for PeriodicWorker {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            // A panic in the worker thread already got reported
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::time::{Duration, Instant};
+
+/// A snapshot of a running transfer, see [`ProgressTracker`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransferProgress {
+    /// The number of bytes transferred so far, including skipped bytes of resumed transfers.
+    pub transferred: u64,
+    /// The total number of bytes.
+    pub total: u64,
+    /// The average data rate since the first progress report.
+    pub bytes_per_second: f64,
+    /// The estimated remaining time, or `None` while the data rate is unknown.
+    pub eta: Option<Duration>,
+    /// The time since the first progress report.
+    pub elapsed: Duration,
+}
+
+impl TransferProgress {
+    /// The completed fraction of the transfer, between `0.0` and `1.0`.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            1.0
+        } else {
+            (self.transferred as f64 / self.total as f64).min(1.0)
+        }
+    }
+
+    fn compute(transferred: u64, total: u64, initial: u64, elapsed: Duration) -> Self {
+        let elapsed_secs = elapsed.as_secs_f64();
+        let bytes_per_second = if elapsed_secs > 0.0 {
+            transferred.saturating_sub(initial) as f64 / elapsed_secs
+        } else {
+            0.0
+        };
+
+        let eta = (bytes_per_second > 0.0).then(|| {
+            Duration::from_secs_f64(total.saturating_sub(transferred) as f64 / bytes_per_second)
+        });
+
+        Self {
+            transferred,
+            total,
+            bytes_per_second,
+            eta,
+            elapsed,
+        }
+    }
+}
+
+/// Turns the `(transferred, total)` reports of the transfer functions
+/// of [`MCUmgrClient`](crate::MCUmgrClient) into [`TransferProgress`] snapshots.
+///
+/// The data rate only counts bytes that were transferred after the first report,
+/// so resumed transfers do not report inflated rates.
+///
+/// ```no_run
+/// # use zephyr_mcumgr::{MCUmgrClient, client::ProgressTracker};
+/// # fn main() {
+/// # let serial = serialport::new("COM42", 115200).open().unwrap();
+/// # let client = MCUmgrClient::new_from_serial(serial);
+/// let mut tracker = ProgressTracker::new(|progress| {
+///     println!(
+///         "{:.0}% at {:.0} B/s, {:?} remaining",
+///         progress.fraction() * 100.0,
+///         progress.bytes_per_second,
+///         progress.eta
+///     );
+///     true
+/// });
+///
+/// client
+///     .fs_file_download(
+///         "/lfs/log.txt",
+///         std::io::sink(),
+///         Some(&mut |transferred, total| tracker.update(transferred, total)),
+///         None,
+///     )
+///     .unwrap();
+/// # }
+/// ```
+pub struct ProgressTracker<F> {
+    callback: F,
+    start: Option<(Instant, u64)>,
+}
+
+impl<F: FnMut(&TransferProgress) -> bool> ProgressTracker<F> {
+    /// Creates a tracker that forwards every report to `callback`.
+    ///
+    /// Returning `false` from `callback` aborts the transfer.
+    pub fn new(callback: F) -> Self {
+        Self {
+            callback,
+            start: None,
+        }
+    }
+
+    /// Processes a `(transferred, total)` progress report.
+    ///
+    /// Returns the result of the callback.
+    pub fn update(&mut self, transferred: u64, total: u64) -> bool {
+        let (start, initial) = *self.start.get_or_insert((Instant::now(), transferred));
+        (self.callback)(&TransferProgress::compute(
+            transferred,
+            total,
+            initial,
+            start.elapsed(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_and_eta() {
+        let progress = TransferProgress::compute(600, 1000, 100, Duration::from_secs(2));
+        assert_eq!(progress.bytes_per_second, 250.0);
+        assert_eq!(progress.eta, Some(Duration::from_millis(1600)));
+        assert_eq!(progress.fraction(), 0.6);
+
+        let start = TransferProgress::compute(100, 1000, 100, Duration::ZERO);
+        assert_eq!(start.bytes_per_second, 0.0);
+        assert_eq!(start.eta, None);
+    }
+
+    #[test]
+    fn tracker_forwards_reports() {
+        let mut reports = vec![];
+        let mut tracker = ProgressTracker::new(|progress: &TransferProgress| {
+            reports.push((progress.transferred, progress.total));
+            progress.transferred < 20
+        });
+
+        assert!(tracker.update(10, 30));
+        assert!(!tracker.update(20, 30));
+
+        assert_eq!(reports, [(10, 30), (20, 30)]);
+    }
+}
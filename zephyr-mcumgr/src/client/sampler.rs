@@ -0,0 +1,161 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Weak,
+    time::{Duration, Instant, SystemTime},
+};
+
+use super::{MCUmgrClient, periodic::PeriodicWorker};
+use crate::{
+    commands::os::{MCUmgrParametersResponse, TaskStatisticsEntry},
+    connection::ExecuteError,
+};
+
+/// A data source that gets polled by [`MCUmgrClient::start_sampler`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SampleSource {
+    /// The result of [`MCUmgrClient::os_task_statistics`].
+    TaskStatistics,
+    /// The counters of the given statistics group, see [`MCUmgrClient::stat_group_data`].
+    StatGroup(String),
+    /// The result of [`MCUmgrClient::os_mcumgr_parameters`].
+    McumgrParameters,
+}
+
+/// The data of a single [`SampleSource`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum SampleData {
+    /// See [`SampleSource::TaskStatistics`].
+    TaskStatistics(HashMap<String, TaskStatisticsEntry>),
+    /// See [`SampleSource::StatGroup`].
+    StatGroup(BTreeMap<String, u64>),
+    /// See [`SampleSource::McumgrParameters`].
+    McumgrParameters(MCUmgrParametersResponse),
+}
+
+/// The data of all sources at one point in time.
+#[derive(Debug)]
+pub struct Snapshot {
+    /// When the sampling of the snapshot started.
+    pub timestamp: SystemTime,
+    /// The results of all sources, in the order of [`SamplerConfig::sources`].
+    ///
+    /// A failing source does not affect the other sources or the following snapshots.
+    pub data: Vec<(SampleSource, Result<SampleData, ExecuteError>)>,
+}
+
+/// Configuration of [`MCUmgrClient::start_sampler`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SamplerConfig {
+    /// The time between the start of two snapshots.
+    ///
+    /// If sampling takes longer, the next snapshot starts right away.
+    pub interval: Duration,
+    /// The sources that get polled for every snapshot.
+    pub sources: Vec<SampleSource>,
+}
+
+impl Default for SamplerConfig {
+    /// Polls the task statistics every second.
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            sources: vec![SampleSource::TaskStatistics],
+        }
+    }
+}
+
+/// A running background sampler, created through [`MCUmgrClient::start_sampler`].
+///
+/// The sampler stops when this handle gets dropped or when the client gets dropped.
+pub struct Sampler {
+    _worker: PeriodicWorker,
+}
+
+impl Sampler {
+    pub(super) fn start(
+        client: Weak<MCUmgrClient>,
+        config: SamplerConfig,
+        mut callback: impl FnMut(Snapshot) + Send + 'static,
+    ) -> Self {
+        let worker =
+            PeriodicWorker::start(client, Instant::now(), config.interval, move |client| {
+                callback(take_snapshot(client, &config.sources))
+            });
+
+        Self { _worker: worker }
+    }
+}
+
+/// Polls all sources once.
+fn take_snapshot(client: &MCUmgrClient, sources: &[SampleSource]) -> Snapshot {
+    let timestamp = SystemTime::now();
+    let data = sources
+        .iter()
+        .map(|source| {
+            let data = match source {
+                SampleSource::TaskStatistics => {
+                    client.os_task_statistics().map(SampleData::TaskStatistics)
+                }
+                SampleSource::StatGroup(name) => {
+                    client.stat_group_data(name).map(SampleData::StatGroup)
+                }
+                SampleSource::McumgrParameters => client
+                    .os_mcumgr_parameters()
+                    .map(SampleData::McumgrParameters),
+            };
+            (source.clone(), data)
+        })
+        .collect();
+
+    Snapshot { timestamp, data }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, mpsc};
+
+    use super::*;
+    use crate::testing::FakeDevice;
+
+    #[test]
+    fn delivers_snapshots_until_dropped() {
+        let client = Arc::new(MCUmgrClient::new_from_transport(FakeDevice::new()));
+
+        let (sampler, snapshots) = client.start_sampler(SamplerConfig {
+            interval: Duration::from_millis(10),
+            sources: vec![
+                SampleSource::McumgrParameters,
+                SampleSource::StatGroup("smp_svr_stats".into()),
+            ],
+        });
+
+        let snapshot = snapshots.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(snapshot.data.len(), 2);
+        assert!(matches!(
+            &snapshot.data[0],
+            (
+                SampleSource::McumgrParameters,
+                Ok(SampleData::McumgrParameters(MCUmgrParametersResponse {
+                    buf_count: 4,
+                    ..
+                }))
+            )
+        ));
+        // A failing source does not affect the others
+        assert!(matches!(
+            &snapshot.data[1],
+            (
+                SampleSource::StatGroup(_),
+                Err(ExecuteError::ErrorResponse(_))
+            )
+        ));
+        assert!(snapshots.recv_timeout(Duration::from_secs(5)).is_ok());
+
+        drop(sampler);
+        while snapshots.try_recv().is_ok() {}
+        assert_eq!(
+            snapshots.recv_timeout(Duration::from_millis(100)).err(),
+            Some(mpsc::RecvTimeoutError::Disconnected)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use std::{collections::HashMap, time::Instant};
+
+use super::MCUmgrClient;
+use crate::{commands::os::TaskStatisticsEntry, connection::ExecuteError};
+
+/// The statistics of a task, together with their changes since the previous sample.
+///
+/// The changes are `None` in the first sample, for new tasks, and if the device
+/// does not report the underlying value.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub struct TaskUsage {
+    /// The raw statistics, see [`MCUmgrClient::os_task_statistics`].
+    #[serde(flatten)]
+    pub stats: TaskStatisticsEntry,
+    /// The share of the task in the runtime of all tasks, in percent.
+    ///
+    /// Requires `CONFIG_SCHED_THREAD_USAGE` on the device.
+    pub cpu_percent: Option<f64>,
+    /// The change of the stack usage high-water mark, in bytes.
+    pub stack_usage_change: Option<i64>,
+    /// The number of context switches per second.
+    pub context_switches_per_second: Option<f64>,
+}
+
+/// The values of the previous sample that the changes are computed from.
+struct PreviousSample {
+    stkuse: Option<u64>,
+    cswcnt: Option<u64>,
+    runtime: Option<u64>,
+}
+
+/// Samples [`MCUmgrClient::os_task_statistics`] repeatedly and computes
+/// the changes between two samples.
+///
+/// Raw runtime ticks and context switch counters are only meaningful as deltas.
+///
+/// ```no_run
+/// # use zephyr_mcumgr::{MCUmgrClient, client::TaskStatsMonitor};
+/// # fn main() {
+/// # let serial = serialport::new("COM42", 115200).open().unwrap();
+/// # let client = MCUmgrClient::new_from_serial(serial);
+/// let mut monitor = TaskStatsMonitor::new(&client);
+/// loop {
+///     for (name, usage) in monitor.sample().unwrap() {
+///         println!("{name}: {:?}%", usage.cpu_percent);
+///     }
+///     std::thread::sleep(std::time::Duration::from_secs(1));
+/// }
+/// # }
+/// ```
+pub struct TaskStatsMonitor<'a> {
+    client: &'a MCUmgrClient,
+    changes: TaskUsageChanges,
+}
+
+impl<'a> TaskStatsMonitor<'a> {
+    /// Creates a monitor; the first sample has no changes.
+    pub fn new(client: &'a MCUmgrClient) -> Self {
+        Self {
+            client,
+            changes: TaskUsageChanges::default(),
+        }
+    }
+
+    /// Queries the task statistics and computes the changes since the previous sample.
+    pub fn sample(&mut self) -> Result<HashMap<String, TaskUsage>, ExecuteError> {
+        let tasks = self.client.os_task_statistics()?;
+        Ok(self.changes.update(tasks, Instant::now()))
+    }
+}
+
+/// Computes the changes of the task statistics between two samples.
+#[derive(Default)]
+struct TaskUsageChanges {
+    previous: Option<(Instant, HashMap<String, PreviousSample>)>,
+}
+
+impl TaskUsageChanges {
+    fn update(
+        &mut self,
+        tasks: HashMap<String, TaskStatisticsEntry>,
+        now: Instant,
+    ) -> HashMap<String, TaskUsage> {
+        let previous = self.previous.take();
+        let elapsed = previous
+            .as_ref()
+            .map(|(timestamp, _)| now.duration_since(*timestamp).as_secs_f64());
+        let previous_tasks = previous.map(|(_, tasks)| tasks).unwrap_or_default();
+
+        let delta = |name: &str, value: fn(&PreviousSample) -> Option<u64>, current| {
+            let previous = value(previous_tasks.get(name)?)?;
+            Some((current?, previous))
+        };
+
+        let runtime_deltas = tasks
+            .iter()
+            .filter_map(|(name, stats)| {
+                let (current, previous) = delta(name, |p| p.runtime, stats.runtime)?;
+                Some((name.clone(), current.saturating_sub(previous)))
+            })
+            .collect::<HashMap<_, _>>();
+        let total_runtime = runtime_deltas.values().sum::<u64>();
+
+        let samples = tasks
+            .iter()
+            .map(|(name, stats)| {
+                let sample = PreviousSample {
+                    stkuse: stats.stkuse,
+                    cswcnt: stats.cswcnt,
+                    runtime: stats.runtime,
+                };
+                (name.clone(), sample)
+            })
+            .collect();
+
+        let usage = tasks
+            .into_iter()
+            .map(|(name, stats)| {
+                let cpu_percent = runtime_deltas
+                    .get(&name)
+                    .filter(|_| total_runtime > 0)
+                    .map(|runtime| *runtime as f64 * 100.0 / total_runtime as f64);
+                let stack_usage_change = delta(&name, |p| p.stkuse, stats.stkuse)
+                    .map(|(current, previous)| current as i64 - previous as i64);
+                let context_switches_per_second = delta(&name, |p| p.cswcnt, stats.cswcnt)
+                    .zip(elapsed.filter(|elapsed| *elapsed > 0.0))
+                    .map(|((current, previous), elapsed)| {
+                        current.saturating_sub(previous) as f64 / elapsed
+                    });
+
+                let usage = TaskUsage {
+                    stats,
+                    cpu_percent,
+                    stack_usage_change,
+                    context_switches_per_second,
+                };
+                (name, usage)
+            })
+            .collect();
+
+        self.previous = Some((now, samples));
+        usage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn entry(stkuse: u64, cswcnt: u64, runtime: u64) -> TaskStatisticsEntry {
+        TaskStatisticsEntry {
+            prio: 0,
+            tid: 0,
+            state: 0,
+            stkuse: Some(stkuse),
+            stksiz: Some(1024),
+            cswcnt: Some(cswcnt),
+            runtime: Some(runtime),
+        }
+    }
+
+    #[test]
+    fn computes_deltas_between_samples() {
+        let mut changes = TaskUsageChanges::default();
+        let start = Instant::now();
+
+        let first = changes.update(
+            HashMap::from([
+                ("main".to_string(), entry(100, 10, 1000)),
+                ("idle".to_string(), entry(50, 20, 5000)),
+            ]),
+            start,
+        );
+        assert_eq!(first["main"].cpu_percent, None);
+        assert_eq!(first["main"].stack_usage_change, None);
+
+        let second = changes.update(
+            HashMap::from([
+                ("main".to_string(), entry(132, 30, 1250)),
+                ("idle".to_string(), entry(50, 40, 5750)),
+                ("worker".to_string(), entry(10, 1, 100)),
+            ]),
+            start + Duration::from_secs(2),
+        );
+        assert_eq!(second["main"].cpu_percent, Some(25.0));
+        assert_eq!(second["idle"].cpu_percent, Some(75.0));
+        assert_eq!(second["main"].stack_usage_change, Some(32));
+        assert_eq!(second["main"].context_switches_per_second, Some(10.0));
+        assert_eq!(second["worker"].cpu_percent, None);
+        assert_eq!(second["worker"].stats.runtime, Some(100));
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+/// Delays upload chunks to stay below a maximum data rate.
+pub(super) struct Throttle {
+    max_bytes_per_second: Option<u64>,
+    start: Instant,
+    transferred: u64,
+}
+
+impl Throttle {
+    pub(super) fn new(max_bytes_per_second: Option<u64>) -> Self {
+        Self {
+            max_bytes_per_second,
+            start: Instant::now(),
+            transferred: 0,
+        }
+    }
+
+    /// Waits until the given number of bytes may be sent.
+    pub(super) fn wait(&mut self, bytes: u64) {
+        if let Some(delay) = self.delay(self.start.elapsed()) {
+            std::thread::sleep(delay);
+        }
+
+        self.transferred += bytes;
+    }
+
+    /// The time left until the next chunk may be sent, `elapsed` after the start.
+    fn delay(&self, elapsed: Duration) -> Option<Duration> {
+        let max_bytes_per_second = self.max_bytes_per_second?;
+
+        let earliest =
+            Duration::from_secs_f64(self.transferred as f64 / max_bytes_per_second as f64);
+        earliest
+            .checked_sub(elapsed)
+            .filter(|delay| !delay.is_zero())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_chunks_beyond_the_rate() {
+        let mut throttle = Throttle::new(Some(1000));
+        assert_eq!(throttle.delay(Duration::ZERO), None);
+
+        throttle.transferred = 500;
+        assert_eq!(
+            throttle.delay(Duration::from_millis(100)),
+            Some(Duration::from_millis(400))
+        );
+        assert_eq!(throttle.delay(Duration::from_millis(500)), None);
+        assert_eq!(throttle.delay(Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn unlimited_rate_never_delays() {
+        let mut throttle = Throttle::new(None);
+        throttle.transferred = u64::MAX;
+        assert_eq!(throttle.delay(Duration::ZERO), None);
+    }
+
+    #[test]
+    fn wait_keeps_the_rate() {
+        let mut throttle = Throttle::new(Some(10_000));
+        let start = Instant::now();
+
+        // The first chunk goes out right away, the others are spread over 200ms
+        for _ in 0..5 {
+            throttle.wait(500);
+        }
+
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(200), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(1), "{elapsed:?}");
+    }
+}
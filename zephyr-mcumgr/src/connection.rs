@@ -1,26 +1,825 @@
-use std::{io::Cursor, sync::Mutex, time::Duration};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, Cursor},
+    marker::PhantomData,
+    sync::{Condvar, Mutex, mpsc},
+    time::{Duration, Instant},
+};
 
 use crate::{
     commands::{ErrResponse, ErrResponseV2, McuMgrCommand},
-    smp_errors::DeviceError,
-    transport::{ReceiveError, SendError, Transport},
+    smp_errors::{DeviceError, EnumMgmtErrCode, GroupError, ImgMgmtErrCode, MCUmgrErr},
+    transport::{
+        ReceiveError, SMP_HEADER_SIZE, SMP_TRANSFER_BUFFER_SIZE, SendError, SmpHeader, SmpVersion,
+        Transport, response_payload,
+    },
 };
 
 use miette::{Diagnostic, IntoDiagnostic};
+use serde::Deserialize;
 use thiserror::Error;
 
-struct Inner {
+/// The direction of an observed SMP frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameDirection {
+    /// The frame was sent to the device
+    Sent,
+    /// The frame was received from the device
+    Received,
+}
+
+/// A callback that gets invoked for every SMP frame that passes through a [`Connection`].
+///
+/// Receives the direction, the decoded header and the raw payload of the frame.
+pub type FrameObserver = Box<dyn FnMut(FrameDirection, &SmpHeader, &[u8]) + Send>;
+
+/// Identifies a [`FrameObserver`] registered through [`Connection::add_frame_observer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameObserverId(u64);
+
+/// The transport that responses are read from, together with its receive buffer.
+struct TransportState {
     transport: Box<dyn Transport + Send>,
+    transport_buffer: Box<[u8; SMP_TRANSFER_BUFFER_SIZE]>,
+}
+
+#[derive(Default)]
+struct FrameObservers {
+    observers: Vec<(FrameObserverId, FrameObserver)>,
+    next_id: u64,
+}
+
+impl FrameObservers {
+    fn add(&mut self, observer: FrameObserver) -> FrameObserverId {
+        let id = FrameObserverId(self.next_id);
+        self.next_id += 1;
+        self.observers.push((id, observer));
+        id
+    }
+}
+
+/// The state shared by all requests of a [`Connection`].
+struct State {
     next_seqnum: u8,
-    transport_buffer: Box<[u8; u16::MAX as usize]>,
+    /// The requests that await their response, by sequence number.
+    ///
+    /// Holds the received response frame until its request picks it up.
+    in_flight: HashMap<u8, Option<Box<[u8]>>>,
+    /// Whether one of the waiting requests currently reads from the transport.
+    receiving: bool,
+    /// The sequence numbers of recently finished requests, to recognize late responses.
+    finished: VecDeque<u8>,
+    /// See [`Connection::unsolicited_frames`].
+    unsolicited_subscribers: Vec<mpsc::Sender<UnsolicitedFrame>>,
+    retry_policy: RetryPolicy,
+    smp_version: SmpVersion,
+    /// The SMP version of the last response of the device.
+    device_smp_version: Option<SmpVersion>,
+    /// The communication timeout of the transport, if known.
+    timeout: Option<Duration>,
+    stats: ConnectionStats,
+}
+
+/// Statistics about the traffic of a [`Connection`].
+///
+/// Byte counts include the SMP headers, but not the transport specific framing.
+/// Round-trip times are measured from the first transmission of a request
+/// until its response is received.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// Number of executed commands, including failed ones
+    pub commands: u64,
+    /// Number of commands that failed, for any reason
+    pub errors: u64,
+    /// Number of commands whose response did not arrive in time
+    pub timeouts: u64,
+    /// Number of retransmitted requests, see [`RetryPolicy`]
+    pub retransmits: u64,
+    /// Number of received frames that were discarded because they were corrupted
+    pub corrupted_frames: u64,
+    /// Number of responses that arrived after their request already finished,
+    /// for example after a timeout or as the answer to a retransmission
+    pub late_responses: u64,
+    /// Number of additional responses to a request whose first response was not processed yet
+    pub duplicate_responses: u64,
+    /// Number of received frames that were discarded because they were too short for an SMP header
+    pub discarded_frames: u64,
+    /// Number of received frames that matched no request, see [`Connection::unsolicited_frames`]
+    pub unsolicited_frames: u64,
+    /// Number of bytes sent to the device
+    pub bytes_sent: u64,
+    /// Number of bytes received from the device
+    pub bytes_received: u64,
+    /// Number of received responses
+    pub responses: u64,
+    /// Sum of the round-trip times of all received responses
+    pub total_round_trip_time: Duration,
+    /// Round-trip time of the most recently received response
+    pub last_round_trip_time: Option<Duration>,
+    /// Longest round-trip time of all received responses
+    pub max_round_trip_time: Duration,
+}
+
+impl ConnectionStats {
+    /// The average round-trip time of all received responses.
+    pub fn average_round_trip_time(&self) -> Option<Duration> {
+        let responses = u32::try_from(self.responses).ok().filter(|&r| r > 0)?;
+        Some(self.total_round_trip_time / responses)
+    }
+
+    fn record_sent(&mut self, data_size: usize) {
+        self.bytes_sent += (SMP_HEADER_SIZE + data_size) as u64;
+    }
+
+    fn record_response(&mut self, header: RequestHeader, data_size: usize) {
+        let round_trip_time = header.sent_at.elapsed();
+
+        self.bytes_received += (SMP_HEADER_SIZE + data_size) as u64;
+        self.responses += 1;
+        self.total_round_trip_time += round_trip_time;
+        self.last_round_trip_time = Some(round_trip_time);
+        self.max_round_trip_time = self.max_round_trip_time.max(round_trip_time);
+    }
+
+    fn record_outcome<T>(&mut self, result: &Result<T, ExecuteError>) {
+        self.commands += 1;
+        if let Err(e) = result {
+            self.errors += 1;
+            if is_timeout(e) {
+                self.timeouts += 1;
+            }
+        }
+    }
+}
+
+/// Whether the error was caused by a response that did not arrive in time.
+fn is_timeout(err: &ExecuteError) -> bool {
+    matches!(
+        err,
+        ExecuteError::ReceiveFailed(ReceiveError::TransportError(e))
+            if e.kind() == io::ErrorKind::TimedOut
+    )
+}
+
+/// Whether the device rejected the request because of a temporary condition,
+/// like a lack of memory.
+fn is_device_busy(err: &ExecuteError) -> bool {
+    matches!(err, ExecuteError::ErrorResponse(_)) && err.is_retryable()
+}
+
+/// Whether the transport re-established a lost connection, see
+/// [`ReconnectingSerialPort`](crate::transport::reconnect::ReconnectingSerialPort).
+fn is_reconnect(err: &ExecuteError) -> bool {
+    matches!(
+        err,
+        ExecuteError::SendFailed(SendError::TransportError(e))
+            | ExecuteError::ReceiveFailed(ReceiveError::TransportError(e))
+            if e.kind() == io::ErrorKind::ConnectionReset
+    )
+}
+
+/// Whether the device does not support the SMP version of the request.
+fn is_version_rejected(err: &ExecuteError) -> bool {
+    matches!(
+        err,
+        ExecuteError::ErrorResponse(DeviceError::V1 { rc, .. })
+            if *rc == MCUmgrErr::MGMT_ERR_UNSUPPORTED_TOO_NEW as i32
+    )
+}
+
+/// Determines how often a request gets retransmitted if its response times out
+/// or the device is temporarily unable to process it.
+///
+/// The latter are device errors that [`ExecuteError::is_retryable`] considers transient,
+/// like [`MCUmgrErr::MGMT_ERR_EBUSY`].
+///
+/// Retransmissions reuse the sequence number of the original request, so a late
+/// response to the original request is accepted as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How often a request gets sent before giving up, including the first attempt.
+    ///
+    /// `0` and `1` both disable retransmissions.
+    pub attempts: u32,
+    /// How long to wait before each retransmission.
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// No retransmissions.
+    fn default() -> Self {
+        Self {
+            attempts: 1,
+            backoff: Duration::ZERO,
+        }
+    }
+}
+
+/// The header information of a sent request, required to match its response.
+#[derive(Debug, Clone, Copy)]
+struct RequestHeader {
+    write_operation: bool,
+    sequence_num: u8,
+    group_id: u16,
+    command_id: u8,
+    sent_at: Instant,
+    /// Waits at most this long for the response, if shorter than the communication timeout.
+    timeout: Option<Duration>,
+}
+
+/// How many finished requests are remembered to recognize their late responses.
+const RECENTLY_FINISHED_REQUESTS: usize = 32;
+
+/// CBOR encodes the payload of a request.
+fn encode_request<R: McuMgrCommand>(request: &R) -> Result<Vec<u8>, ExecuteError> {
+    let mut data = vec![];
+    ciborium::into_writer(request.data(), &mut data)
+        .into_diagnostic()
+        .map_err(Into::into)
+        .map_err(ExecuteError::EncodeFailed)?;
+
+    log::debug!("TX data: {}", hex::encode(&data));
+
+    Ok(data)
+}
+
+impl Connection {
+    /// Wraps the given transport in the registered frame observers.
+    fn observed<'a>(&'a self, transport: &'a mut dyn Transport) -> ObservedTransport<'a> {
+        ObservedTransport {
+            transport,
+            observers: &self.frame_observers,
+        }
+    }
+
+    /// Allocates a sequence number for a new request and registers it as in flight.
+    fn start_request(&self, write_operation: bool, group_id: u16, command_id: u8) -> RequestHeader {
+        let mut state = self.state.lock().unwrap();
+
+        // Skip sequence numbers whose requests still await their response
+        let mut sequence_num = state.next_seqnum;
+        for _ in 0..u8::MAX {
+            if !state.in_flight.contains_key(&sequence_num) {
+                break;
+            }
+            sequence_num = sequence_num.wrapping_add(1);
+        }
+
+        state.next_seqnum = sequence_num.wrapping_add(1);
+        state.in_flight.insert(sequence_num, None);
+        state.finished.retain(|finished| *finished != sequence_num);
+
+        RequestHeader {
+            write_operation,
+            sequence_num,
+            group_id,
+            command_id,
+            sent_at: Instant::now(),
+            timeout: None,
+        }
+    }
+
+    /// Stops waiting for the response of the given request.
+    fn finish_request(&self, header: RequestHeader) {
+        let mut state = self.state.lock().unwrap();
+        if state.in_flight.remove(&header.sequence_num).is_some() {
+            if state.finished.len() >= RECENTLY_FINISHED_REQUESTS {
+                state.finished.pop_front();
+            }
+            state.finished.push_back(header.sequence_num);
+        }
+    }
+
+    fn record_outcome<T>(&self, result: &Result<T, ExecuteError>) {
+        self.state.lock().unwrap().stats.record_outcome(result);
+    }
+
+    /// Sends the given data as a request.
+    ///
+    /// Uses the dedicated sender of the transport if it has one,
+    /// so that sending does not have to wait for a receiving thread.
+    fn send_data(&self, header: RequestHeader, data: &[u8]) -> Result<(), SendError> {
+        let smp_version = {
+            let mut state = self.state.lock().unwrap();
+            state.stats.record_sent(data.len());
+            state.smp_version
+        };
+
+        let send = |transport: &mut dyn Transport| {
+            self.observed(transport).send_frame_with_version(
+                smp_version,
+                header.write_operation,
+                header.sequence_num,
+                header.group_id,
+                header.command_id,
+                data,
+            )
+        };
+
+        match &self.sender {
+            Some(sender) => send(&mut **sender.lock().unwrap()),
+            None => send(&mut *self.transport.lock().unwrap().transport),
+        }
+    }
+
+    /// Sends a CBOR based SMP command without waiting for its response.
+    fn send_request<R: McuMgrCommand>(&self, request: &R) -> Result<RequestHeader, ExecuteError> {
+        let data = encode_request(request)?;
+        let header = self.start_request(
+            request.is_write_operation(),
+            request.group_id(),
+            request.command_id(),
+        );
+
+        if let Err(e) = self.send_data(header, &data) {
+            self.finish_request(header);
+            return Err(e.into());
+        }
+
+        Ok(header)
+    }
+
+    /// Receives the response frame of the given request.
+    ///
+    /// Only one of the waiting requests reads from the transport at a time;
+    /// it hands over the frames that belong to other requests and wakes them up.
+    fn receive_response(&self, header: RequestHeader) -> Result<Box<[u8]>, ReceiveError> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(frame) = state
+                .in_flight
+                .get_mut(&header.sequence_num)
+                .and_then(Option::take)
+            {
+                return Ok(frame);
+            }
+
+            let deadline = header.timeout.map(|timeout| header.sent_at + timeout);
+            let remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if remaining == Some(Duration::ZERO) {
+                return Err(io::Error::from(io::ErrorKind::TimedOut).into());
+            }
+
+            if state.receiving {
+                state = match remaining {
+                    Some(remaining) => self.state_changed.wait_timeout(state, remaining).unwrap().0,
+                    None => self.state_changed.wait(state).unwrap(),
+                };
+                continue;
+            }
+
+            // Shorten the communication timeout for this read only
+            let timeouts = remaining
+                .zip(state.timeout)
+                .filter(|(remaining, timeout)| remaining < timeout);
+
+            state.receiving = true;
+            drop(state);
+
+            let frame = {
+                let mut transport = self.transport.lock().unwrap();
+                let TransportState {
+                    transport,
+                    transport_buffer,
+                } = &mut *transport;
+                if let Some((remaining, _)) = timeouts {
+                    if let Err(e) = transport.set_timeout(remaining) {
+                        log::debug!("Unable to shorten the timeout: {e}");
+                    }
+                }
+                let frame = self
+                    .observed(&mut **transport)
+                    .recv_raw_frame(transport_buffer)
+                    .map(Box::<[u8]>::from);
+                if let Some((_, timeout)) = timeouts {
+                    if let Err(e) = transport.set_timeout(timeout) {
+                        log::warn!("Unable to restore the timeout: {e}");
+                    }
+                }
+                frame
+            };
+
+            state = self.state.lock().unwrap();
+            state.receiving = false;
+            self.state_changed.notify_all();
+
+            let frame = match frame {
+                // The response might still arrive through a retransmission
+                Err(ReceiveError::CorruptedFrame) => {
+                    log::debug!("Discarding corrupted frame");
+                    state.stats.corrupted_frames += 1;
+                    continue;
+                }
+                frame => frame?,
+            };
+            let Some((header_data, _)) = frame.split_first_chunk::<SMP_HEADER_SIZE>() else {
+                log::debug!("Discarding frame without SMP header");
+                state.stats.discarded_frames += 1;
+                continue;
+            };
+            let sequence_num = SmpHeader::from_bytes(*header_data).sequence_num;
+
+            // Receiving packets with an unknown sequence number is not an error,
+            // they should simply be silently ignored.
+            let State {
+                in_flight,
+                finished,
+                unsolicited_subscribers,
+                stats,
+                ..
+            } = &mut *state;
+            match in_flight.get_mut(&sequence_num) {
+                Some(Some(_)) => {
+                    log::debug!(
+                        "Discarding duplicate response with sequence number {sequence_num}"
+                    );
+                    stats.duplicate_responses += 1;
+                }
+                Some(slot) => *slot = Some(frame),
+                None if finished.contains(&sequence_num) => {
+                    log::debug!("Discarding late response with sequence number {sequence_num}");
+                    stats.late_responses += 1;
+                }
+                None => {
+                    stats.unsolicited_frames += 1;
+                    if unsolicited_subscribers.is_empty() {
+                        log::debug!("Discarding frame with unknown sequence number {sequence_num}");
+                    }
+                    let frame = UnsolicitedFrame {
+                        header: SmpHeader::from_bytes(*header_data),
+                        data: frame[SMP_HEADER_SIZE..].into(),
+                    };
+                    unsolicited_subscribers
+                        .retain(|subscriber| subscriber.send(frame.clone()).is_ok());
+                }
+            }
+        }
+    }
+
+    /// Receives the response of the given request and processes its header and payload.
+    fn receive_processed<T>(
+        &self,
+        header: RequestHeader,
+        process: impl Fn(&SmpHeader, &[u8]) -> Result<T, ExecuteError>,
+    ) -> Result<T, ExecuteError> {
+        let frame = self.receive_response(header)?;
+        let response = response_payload(
+            &frame,
+            header.write_operation,
+            header.group_id,
+            header.command_id,
+        )?;
+        let response_header = frame
+            .first_chunk::<SMP_HEADER_SIZE>()
+            .map(|header| SmpHeader::from_bytes(*header))
+            .ok_or(ReceiveError::UnexpectedResponse)?;
+        let result = process(&response_header, response);
+
+        let mut state = self.state.lock().unwrap();
+        state.stats.record_response(header, response.len());
+        if let Some(version) = SmpVersion::from_header_value(response_header.ver) {
+            state.device_smp_version = Some(version);
+        }
+        result
+    }
+
+    /// Receives and processes the response of the given request,
+    /// retransmitting `request_data` on timeouts and transient device errors
+    /// according to the retry policy.
+    fn receive_with_retries<T>(
+        &self,
+        header: RequestHeader,
+        request_data: &[u8],
+        process: impl Fn(&SmpHeader, &[u8]) -> Result<T, ExecuteError>,
+    ) -> Result<T, ExecuteError> {
+        let RetryPolicy { attempts, backoff } = self.state.lock().unwrap().retry_policy;
+
+        let mut attempt = 1;
+        loop {
+            match self.receive_processed(header, &process) {
+                Err(e) if (is_timeout(&e) || is_device_busy(&e)) && attempt < attempts => {
+                    attempt += 1;
+                    log::debug!("{e}, retransmitting (attempt {attempt}/{attempts})");
+                    std::thread::sleep(backoff);
+                    self.state.lock().unwrap().stats.retransmits += 1;
+                    self.send_data(header, request_data)?;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Switches the sent requests to [`SmpVersion::V1`].
+    ///
+    /// Returns `false` if they already were.
+    fn fall_back_to_smp_v1(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let changed = state.smp_version != SmpVersion::V1;
+        state.smp_version = SmpVersion::V1;
+        changed
+    }
+
+    /// Sends the given data as a request and processes its response.
+    fn execute_data<T>(
+        &self,
+        write_operation: bool,
+        group_id: u16,
+        command_id: u8,
+        data: &[u8],
+        process: impl Fn(&SmpHeader, &[u8]) -> Result<T, ExecuteError>,
+    ) -> Result<T, ExecuteError> {
+        let header = self.start_request(write_operation, group_id, command_id);
+
+        let execute = || {
+            self.send_data(header, data)
+                .map_err(Into::into)
+                .and_then(|()| self.receive_with_retries(header, data, &process))
+        };
+
+        let mut result = execute();
+        if result.as_ref().is_err_and(is_reconnect) {
+            // The request most likely got lost together with the old connection
+            log::debug!("Transport reconnected, sending request again");
+            self.state.lock().unwrap().stats.retransmits += 1;
+            result = execute();
+        }
+        if result.as_ref().is_err_and(is_version_rejected) && self.fall_back_to_smp_v1() {
+            log::warn!("Device does not support SMP version 2, falling back to version 1");
+            result = execute();
+        }
+
+        self.finish_request(header);
+        result
+    }
+}
+
+/// Decodes a CBOR response payload, converting SMP errors into [`ExecuteError::ErrorResponse`].
+fn decode_response<T: for<'a> Deserialize<'a>>(
+    header: &SmpHeader,
+    response: &[u8],
+) -> Result<T, ExecuteError> {
+    log::debug!("RX data: {}", hex::encode(response));
+
+    let err: ErrResponse = ciborium::from_reader(Cursor::new(response))
+        .map_err(|e| ResponseDecodeError::new::<ErrResponse>(response, e))?;
+
+    if let Some(ErrResponseV2 { rc, group }) = err.err {
+        return Err(ExecuteError::ErrorResponse(DeviceError::V2 { group, rc }));
+    }
+
+    if let Some(rc) = err.rc {
+        return Err(ExecuteError::ErrorResponse(DeviceError::V1 {
+            group: header.group_id,
+            rc,
+            rsn: err.rsn,
+        }));
+    }
+
+    ciborium::from_reader(Cursor::new(response))
+        .map_err(|e| ResponseDecodeError::new::<T>(response, e).into())
+}
+
+/// The number of payload bytes per line of the hex dump of [`ResponseDecodeError`].
+const HEX_DUMP_WIDTH: usize = 16;
+
+/// A response payload that could not be decoded into the expected type.
+///
+/// Usually means that the firmware of the device uses a different response schema.
+/// The diagnostic shows a hex dump of the payload, pointing at the offending byte
+/// if it is known.
+#[derive(Error, Debug, Diagnostic)]
+#[error("Failed to decode response as `{expected_type}`")]
+#[diagnostic(
+    code(zephyr_mcumgr::connection::decode_response),
+    help(
+        "The response does not match the expected schema. Check whether the firmware matches this client version."
+    )
+)]
+pub struct ResponseDecodeError {
+    /// The name of the type the payload should have been decoded to.
+    pub expected_type: &'static str,
+    /// The payload that failed to decode.
+    pub payload: Box<[u8]>,
+    #[source]
+    source: ciborium::de::Error<io::Error>,
+    #[source_code]
+    hex_dump: miette::NamedSource<String>,
+    #[label("{reason}")]
+    span: miette::SourceSpan,
+    reason: String,
+}
+
+impl ResponseDecodeError {
+    fn new<T>(payload: &[u8], source: ciborium::de::Error<io::Error>) -> Self {
+        let (offset, reason) = match &source {
+            ciborium::de::Error::Syntax(offset) => (Some(*offset), "invalid CBOR".to_string()),
+            ciborium::de::Error::Semantic(offset, message) => (*offset, message.clone()),
+            ciborium::de::Error::Io(e) => (Some(payload.len()), e.to_string()),
+            ciborium::de::Error::RecursionLimitExceeded => (None, source.to_string()),
+        };
+
+        let hex_dump = payload
+            .chunks(HEX_DUMP_WIDTH)
+            .enumerate()
+            .map(|(line, chunk)| format!("{:04x}: {}\n", line * HEX_DUMP_WIDTH, hex_bytes(chunk)))
+            .collect::<String>();
+
+        // Each line starts with a 6 character offset and holds 3 characters per byte;
+        // without a known offset, the entire payload is marked
+        let span = match offset.filter(|offset| *offset < payload.len()) {
+            Some(offset) => {
+                let line_length = 6 + 3 * HEX_DUMP_WIDTH;
+                let position =
+                    offset / HEX_DUMP_WIDTH * line_length + 6 + offset % HEX_DUMP_WIDTH * 3;
+                miette::SourceSpan::from((position, 2))
+            }
+            None => miette::SourceSpan::from((0, hex_dump.trim_end().len())),
+        };
+
+        Self {
+            expected_type: std::any::type_name::<T>(),
+            payload: payload.into(),
+            source,
+            hex_dump: miette::NamedSource::new("response payload", hex_dump),
+            span,
+            reason,
+        }
+    }
+}
+
+/// Formats bytes as space separated hex values.
+fn hex_bytes(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl From<ResponseDecodeError> for ExecuteError {
+    fn from(err: ResponseDecodeError) -> Self {
+        ExecuteError::DecodeFailed(Box::new(err))
+    }
+}
+
+/// A transport wrapper that reports every frame to a list of [`FrameObserver`]s.
+struct ObservedTransport<'a> {
+    transport: &'a mut dyn Transport,
+    observers: &'a Mutex<FrameObservers>,
+}
+
+impl ObservedTransport<'_> {
+    fn observe(&mut self, direction: FrameDirection, header: [u8; SMP_HEADER_SIZE], data: &[u8]) {
+        let header = SmpHeader::from_bytes(header);
+        for (_, observer) in self.observers.lock().unwrap().observers.iter_mut() {
+            observer(direction, &header, data);
+        }
+    }
+}
+
+impl Transport for ObservedTransport<'_> {
+    fn send_raw_frame(
+        &mut self,
+        header: [u8; SMP_HEADER_SIZE],
+        data: &[u8],
+    ) -> Result<(), SendError> {
+        self.observe(FrameDirection::Sent, header, data);
+        self.transport.send_raw_frame(header, data)
+    }
+
+    fn recv_raw_frame<'a>(
+        &mut self,
+        buffer: &'a mut [u8; SMP_TRANSFER_BUFFER_SIZE],
+    ) -> Result<&'a [u8], ReceiveError> {
+        let frame = self.transport.recv_raw_frame(buffer)?;
+        if let Some((header, data)) = frame.split_first_chunk::<SMP_HEADER_SIZE>() {
+            self.observe(FrameDirection::Received, *header, data);
+        }
+        Ok(frame)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), miette::Report> {
+        self.transport.set_timeout(timeout)
+    }
 }
 
 /// An SMP protocol layer connection to a device.
 ///
 /// In most cases this struct will not be used directly by the user,
 /// but instead it is used indirectly through [`MCUmgrClient`](crate::MCUmgrClient).
+///
+/// Multiple threads can execute commands at the same time; their requests
+/// are interleaved on the transport and the responses are dispatched to the
+/// waiting callers by their sequence number. This allows, for example, to poll
+/// task statistics while a large file upload is in progress.
 pub struct Connection {
-    inner: Mutex<Inner>,
+    transport: Mutex<TransportState>,
+    /// A separate handle for sending, see [`Transport::try_clone_sender`].
+    sender: Option<Mutex<Box<dyn Transport + Send>>>,
+    frame_observers: Mutex<FrameObservers>,
+    state: Mutex<State>,
+    /// Signals changes of [`State::in_flight`] and [`State::receiving`].
+    state_changed: Condvar,
+}
+
+/// The response to [`Connection::execute_raw_command_with_header`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawResponse {
+    /// The header of the response frame, as sent by the device.
+    pub header: SmpHeader,
+    /// The undecoded payload of the response.
+    pub data: Box<[u8]>,
+}
+
+/// A received frame that matched no pending request, see [`Connection::unsolicited_frames`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsolicitedFrame {
+    /// The header of the frame.
+    pub header: SmpHeader,
+    /// The undecoded payload of the frame.
+    pub data: Box<[u8]>,
+}
+
+/// A request that was sent through a [`Pipeline`] and still awaits its response.
+///
+/// `T` is the type the response will be decoded to.
+#[must_use]
+pub struct PendingResponse<T> {
+    header: RequestHeader,
+    _response: PhantomData<fn() -> T>,
+}
+
+/// Allows multiple requests of a [`Connection`] to be in flight at the same time.
+///
+/// Created through [`Connection::pipeline`].
+///
+/// Responses can be received in any order. Requests whose responses were
+/// never received are forgotten when the [`Pipeline`] is dropped.
+pub struct Pipeline<'a> {
+    connection: &'a Connection,
+    in_flight: Vec<RequestHeader>,
+}
+
+impl Pipeline<'_> {
+    /// Sends a CBOR based SMP command without waiting for its response.
+    ///
+    /// Pipelined requests are not retransmitted on timeouts.
+    pub fn send<R: McuMgrCommand>(
+        &mut self,
+        request: &R,
+    ) -> Result<PendingResponse<R::Response>, ExecuteError> {
+        let result = self.connection.send_request(request);
+        if result.is_err() {
+            self.connection.record_outcome(&result);
+        }
+
+        let header = result?;
+        self.in_flight.push(header);
+
+        Ok(PendingResponse {
+            header,
+            _response: PhantomData,
+        })
+    }
+
+    /// Receives the response of a previously sent command.
+    pub fn receive<T: for<'a> Deserialize<'a>>(
+        &mut self,
+        pending: PendingResponse<T>,
+    ) -> Result<T, ExecuteError> {
+        self.receive_within(pending, None)
+    }
+
+    /// Same as [`Pipeline::receive`], but gives up after `timeout` if that is shorter
+    /// than the communication timeout.
+    ///
+    /// Only takes effect if the connection knows its communication timeout, see
+    /// [`Connection::set_timeout`].
+    pub(crate) fn receive_within<T: for<'a> Deserialize<'a>>(
+        &mut self,
+        pending: PendingResponse<T>,
+        timeout: Option<Duration>,
+    ) -> Result<T, ExecuteError> {
+        let header = RequestHeader {
+            timeout,
+            ..pending.header
+        };
+        self.in_flight
+            .retain(|in_flight| in_flight.sequence_num != header.sequence_num);
+
+        let result = self.connection.receive_processed(header, decode_response);
+        self.connection.finish_request(header);
+        self.connection.record_outcome(&result);
+        result
+    }
+}
+
+impl Drop for Pipeline<'_> {
+    fn drop(&mut self) {
+        for header in self.in_flight.drain(..) {
+            self.connection.finish_request(header);
+        }
+    }
 }
 
 /// Errors that can happen on SMP protocol level
@@ -28,35 +827,108 @@ pub struct Connection {
 pub enum ExecuteError {
     /// An error happened on SMP transport level while sending a request
     #[error("Sending failed")]
-    #[diagnostic(code(zephyr_mcumgr::connection::execute::send))]
+    #[diagnostic(code(zephyr_mcumgr::connection::execute::send), forward(0))]
     SendFailed(#[from] SendError),
     /// An error happened on SMP transport level while receiving a response
     #[error("Receiving failed")]
-    #[diagnostic(code(zephyr_mcumgr::connection::execute::receive))]
+    #[diagnostic(code(zephyr_mcumgr::connection::execute::receive), forward(0))]
     ReceiveFailed(#[from] ReceiveError),
     /// An error happened while CBOR encoding the request payload
     #[error("CBOR encoding failed")]
     #[diagnostic(code(zephyr_mcumgr::connection::execute::encode))]
     EncodeFailed(#[source] Box<dyn miette::Diagnostic + Send + Sync>),
     /// An error happened while CBOR decoding the response payload
+    ///
+    /// Usually contains a [`ResponseDecodeError`].
     #[error("CBOR decoding failed")]
     #[diagnostic(code(zephyr_mcumgr::connection::execute::decode))]
-    DecodeFailed(#[source] Box<dyn miette::Diagnostic + Send + Sync>),
+    DecodeFailed(
+        #[source]
+        #[diagnostic_source]
+        Box<dyn miette::Diagnostic + Send + Sync>,
+    ),
     /// The device returned an SMP error
-    #[error("Device returned error code: {0}")]
-    #[diagnostic(code(zephyr_mcumgr::connection::execute::device_error))]
+    #[error("Device returned error code in {group}: {description}", group = .0.group_name(), description = .0.describe())]
+    #[diagnostic(code(zephyr_mcumgr::connection::execute::device_error), forward(0))]
     ErrorResponse(DeviceError),
 }
 
+impl ExecuteError {
+    /// Whether executing the command again might succeed.
+    ///
+    /// True for timeouts, lost connections, frames that got damaged in transit,
+    /// and device errors caused by temporary resource shortages, like
+    /// [`MCUmgrErr::MGMT_ERR_ENOMEM`], [`MCUmgrErr::MGMT_ERR_EBUSY`] or
+    /// [`ImgMgmtErrCode::IMG_MGMT_ERR_NO_FREE_MEMORY`].
+    ///
+    /// Errors caused by the request itself, like encoding failures or
+    /// invalid arguments, are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        let is_transient_io_error = |e: &io::Error| {
+            matches!(
+                e.kind(),
+                io::ErrorKind::TimedOut
+                    | io::ErrorKind::WouldBlock
+                    | io::ErrorKind::Interrupted
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionRefused
+            )
+        };
+
+        match self {
+            ExecuteError::SendFailed(SendError::TransportError(e)) => is_transient_io_error(e),
+            ExecuteError::SendFailed(SendError::DataTooBig) => false,
+            ExecuteError::ReceiveFailed(e) => match e {
+                ReceiveError::TransportError(e) => is_transient_io_error(e),
+                ReceiveError::Base64DecodeError(_) | ReceiveError::CorruptedFrame => true,
+                // Most likely a protocol bug of the device, resending would not help
+                ReceiveError::UnexpectedResponse | ReceiveError::FrameTooBig => false,
+            },
+            ExecuteError::EncodeFailed(_) | ExecuteError::DecodeFailed(_) => false,
+            ExecuteError::ErrorResponse(DeviceError::V1 { rc, .. }) => matches!(
+                MCUmgrErr::from_repr(*rc),
+                Some(
+                    MCUmgrErr::MGMT_ERR_ENOMEM
+                        | MCUmgrErr::MGMT_ERR_ETIMEOUT
+                        | MCUmgrErr::MGMT_ERR_EBUSY
+                )
+            ),
+            ExecuteError::ErrorResponse(e @ DeviceError::V2 { .. }) => matches!(
+                e.group_error(),
+                Some(
+                    GroupError::Image(ImgMgmtErrCode::IMG_MGMT_ERR_NO_FREE_MEMORY)
+                        | GroupError::Enum(
+                            EnumMgmtErrCode::ENUM_MGMT_ERR_INSUFFICIENT_HEAP_FOR_ENTRIES
+                        )
+                )
+            ),
+        }
+    }
+}
+
 impl Connection {
     /// Creates a new SMP
     pub fn new<T: Transport + Send + 'static>(transport: T) -> Self {
         Self {
-            inner: Mutex::new(Inner {
+            sender: transport.try_clone_sender().map(Mutex::new),
+            transport: Mutex::new(TransportState {
                 transport: Box::new(transport),
-                next_seqnum: rand::random(),
                 transport_buffer: Box::new([0; u16::MAX as usize]),
             }),
+            frame_observers: Mutex::default(),
+            state: Mutex::new(State {
+                next_seqnum: rand::random(),
+                in_flight: HashMap::new(),
+                receiving: false,
+                finished: VecDeque::new(),
+                unsolicited_subscribers: vec![],
+                retry_policy: RetryPolicy::default(),
+                smp_version: SmpVersion::default(),
+                device_smp_version: None,
+                timeout: None,
+                stats: ConnectionStats::default(),
+            }),
+            state_changed: Condvar::new(),
         }
     }
 
@@ -65,74 +937,198 @@ impl Connection {
     /// When the device does not respond to packets within the set
     /// duration, an error will be raised.
     pub fn set_timeout(&self, timeout: Duration) -> Result<(), miette::Report> {
-        self.inner.lock().unwrap().transport.set_timeout(timeout)
+        if let Some(sender) = &self.sender {
+            sender.lock().unwrap().set_timeout(timeout)?;
+        }
+        self.transport
+            .lock()
+            .unwrap()
+            .transport
+            .set_timeout(timeout)?;
+        self.assume_timeout(timeout);
+        Ok(())
     }
 
-    /// Executes a given CBOR based SMP command.
-    pub fn execute_command<R: McuMgrCommand>(
-        &self,
-        request: &R,
-    ) -> Result<R::Response, ExecuteError> {
-        let mut lock_guard = self.inner.lock().unwrap();
-        let locked_self: &mut Inner = &mut lock_guard;
+    /// Records the communication timeout the transport was created with.
+    pub(crate) fn assume_timeout(&self, timeout: Duration) {
+        self.state.lock().unwrap().timeout = Some(timeout);
+    }
+
+    /// Registers a callback that gets invoked for every SMP frame sent or received.
+    ///
+    /// Useful for logging, mirroring or asserting on the exact traffic of the connection.
+    /// Received frames are reported even if they get discarded afterwards, for example
+    /// because of a mismatching sequence number.
+    ///
+    /// Any number of observers can be registered; they are invoked in registration order.
+    ///
+    /// **IMPORTANT:** The observer is called while the transport is locked,
+    /// so it must not use the connection itself.
+    ///
+    /// ```no_run
+    /// # use zephyr_mcumgr::MCUmgrClient;
+    /// # fn main() {
+    /// # let serial = serialport::new("COM42", 115200).open().unwrap();
+    /// let client = MCUmgrClient::new_from_serial(serial);
+    ///
+    /// client.add_frame_observer(Box::new(|direction, header, payload| {
+    ///     println!("{direction:?} group={} cmd={}: {payload:02x?}", header.group_id, header.command_id);
+    /// }));
+    /// # }
+    /// ```
+    pub fn add_frame_observer(&self, observer: FrameObserver) -> FrameObserverId {
+        self.frame_observers.lock().unwrap().add(observer)
+    }
 
-        let mut cursor = Cursor::new(locked_self.transport_buffer.as_mut_slice());
-        ciborium::into_writer(request.data(), &mut cursor)
-            .into_diagnostic()
-            .map_err(Into::into)
-            .map_err(ExecuteError::EncodeFailed)?;
-        let data_size = cursor.position() as usize;
-        let data = &locked_self.transport_buffer[..data_size];
+    /// Removes an observer registered through [`Connection::add_frame_observer`].
+    ///
+    /// Returns `false` if the observer was already removed.
+    pub fn remove_frame_observer(&self, id: FrameObserverId) -> bool {
+        let mut frame_observers = self.frame_observers.lock().unwrap();
 
-        log::debug!("TX data: {}", hex::encode(data));
+        let num_observers = frame_observers.observers.len();
+        frame_observers
+            .observers
+            .retain(|(observer_id, _)| *observer_id != id);
 
-        let sequence_num = locked_self.next_seqnum;
-        locked_self.next_seqnum = locked_self.next_seqnum.wrapping_add(1);
+        frame_observers.observers.len() != num_observers
+    }
 
-        let write_operation = request.is_write_operation();
-        let group_id = request.group_id();
-        let command_id = request.command_id();
+    /// Replaces all registered frame observers with the given one.
+    ///
+    /// Pass `None` to remove all observers.
+    ///
+    /// See [`Connection::add_frame_observer`] for more information.
+    pub fn set_frame_observer(&self, observer: Option<FrameObserver>) {
+        // Swap under a single lock, so no frame passes by without an observer
+        let mut frame_observers = self.frame_observers.lock().unwrap();
+        frame_observers.observers.clear();
+        if let Some(observer) = observer {
+            frame_observers.add(observer);
+        }
+    }
 
-        locked_self.transport.send_frame(
-            write_operation,
-            sequence_num,
-            group_id,
-            command_id,
-            data,
-        )?;
+    /// Subscribes to frames that match no pending request.
+    ///
+    /// Returns a channel that receives every frame whose sequence number belongs to
+    /// no request of this connection, for example notifications that firmwares push
+    /// through custom management groups. Late responses to finished requests
+    /// are not reported.
+    ///
+    /// The transport is only read while waiting for responses, so unsolicited frames
+    /// arrive whenever a command gets executed. Use [`mpsc::Receiver::try_iter`]
+    /// to collect the frames received so far.
+    pub fn unsolicited_frames(&self) -> mpsc::Receiver<UnsolicitedFrame> {
+        let (sender, receiver) = mpsc::channel();
+        self.state
+            .lock()
+            .unwrap()
+            .unsolicited_subscribers
+            .push(sender);
+        receiver
+    }
 
-        let response = locked_self.transport.receive_frame(
-            &mut locked_self.transport_buffer,
-            write_operation,
-            sequence_num,
-            group_id,
-            command_id,
-        )?;
+    /// Configures the retransmission of requests whose responses time out.
+    ///
+    /// Only affects [`Connection::execute_command`] and [`Connection::execute_raw_command`];
+    /// requests sent through a [`Pipeline`] are never retransmitted.
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        self.state.lock().unwrap().retry_policy = policy;
+    }
 
-        log::debug!("RX data: {}", hex::encode(response));
+    /// Configures the SMP protocol version of the sent requests.
+    ///
+    /// Defaults to [`SmpVersion::V2`]. Responses are accepted in both versions.
+    /// If the device rejects a request because it does not support [`SmpVersion::V2`],
+    /// the connection falls back to [`SmpVersion::V1`] and sends the request again.
+    pub fn set_smp_version(&self, version: SmpVersion) {
+        self.state.lock().unwrap().smp_version = version;
+    }
 
-        let err: ErrResponse = ciborium::from_reader(Cursor::new(response))
-            .into_diagnostic()
-            .map_err(Into::into)
-            .map_err(ExecuteError::DecodeFailed)?;
+    /// The SMP protocol version the device responded with, or `None` before the first response.
+    ///
+    /// Devices with [`SmpVersion::V1`] answer to [`SmpVersion::V2`] requests with V1 headers
+    /// and do not report group based error codes.
+    pub fn device_smp_version(&self) -> Option<SmpVersion> {
+        self.state.lock().unwrap().device_smp_version
+    }
 
-        if let Some(ErrResponseV2 { rc, group }) = err.err {
-            return Err(ExecuteError::ErrorResponse(DeviceError::V2 { group, rc }));
-        }
+    /// Returns a snapshot of the traffic statistics of this connection.
+    pub fn stats(&self) -> ConnectionStats {
+        self.state.lock().unwrap().stats
+    }
 
-        if let Some(rc) = err.rc {
-            return Err(ExecuteError::ErrorResponse(DeviceError::V1 {
-                rc,
-                rsn: err.rsn,
-            }));
+    /// Resets the traffic statistics of this connection.
+    pub fn reset_stats(&self) {
+        self.state.lock().unwrap().stats = ConnectionStats::default();
+    }
+
+    /// Executes a given CBOR based SMP command.
+    pub fn execute_command<R: McuMgrCommand>(
+        &self,
+        request: &R,
+    ) -> Result<R::Response, ExecuteError> {
+        let result = encode_request(request).and_then(|data| {
+            self.execute_data(
+                request.is_write_operation(),
+                request.group_id(),
+                request.command_id(),
+                &data,
+                decode_response,
+            )
+        });
+        self.record_outcome(&result);
+        result
+    }
+
+    /// Starts pipelined command execution.
+    ///
+    /// Through the returned [`Pipeline`], multiple requests can be sent before
+    /// their responses are received. Other commands can still be executed
+    /// on the connection in the meantime.
+    pub fn pipeline(&self) -> Pipeline<'_> {
+        Pipeline {
+            connection: self,
+            in_flight: vec![],
         }
+    }
 
-        let decoded_response: R::Response = ciborium::from_reader(Cursor::new(response))
-            .into_diagnostic()
-            .map_err(Into::into)
-            .map_err(ExecuteError::DecodeFailed)?;
+    /// Executes multiple CBOR based SMP commands, keeping up to `window_size`
+    /// of them in flight at the same time.
+    ///
+    /// All requests are sent with distinct sequence numbers before their responses
+    /// are awaited, which hides the round trip latency of slow transports.
+    /// `window_size` must not exceed the number of SMP buffers of the device,
+    /// otherwise the device drops requests.
+    ///
+    /// Returns one result per request, in the order of `requests`. A failed request
+    /// does not affect the others. Like all pipelined requests, the requests are
+    /// not retransmitted on timeouts.
+    pub fn execute_batch<R: McuMgrCommand>(
+        &self,
+        requests: &[R],
+        window_size: usize,
+    ) -> Vec<Result<R::Response, ExecuteError>> {
+        let mut pipeline = self.pipeline();
+        let mut in_flight = VecDeque::with_capacity(window_size);
+        let mut requests = requests.iter();
+        let mut results = Vec::with_capacity(requests.len());
+
+        loop {
+            while in_flight.len() < window_size.max(1) {
+                let Some(request) = requests.next() else {
+                    break;
+                };
+                in_flight.push_back(pipeline.send(request));
+            }
 
-        Ok(decoded_response)
+            let Some(pending) = in_flight.pop_front() else {
+                break;
+            };
+            results.push(pending.and_then(|pending| pipeline.receive(pending)));
+        }
+
+        results
     }
 
     /// Executes a raw SMP command.
@@ -151,30 +1147,369 @@ impl Connection {
         command_id: u8,
         data: &[u8],
     ) -> Result<Box<[u8]>, ExecuteError> {
-        let mut lock_guard = self.inner.lock().unwrap();
-        let locked_self: &mut Inner = &mut lock_guard;
-
-        let sequence_num = locked_self.next_seqnum;
-        locked_self.next_seqnum = locked_self.next_seqnum.wrapping_add(1);
+        let result = self.execute_data(
+            write_operation,
+            group_id,
+            command_id,
+            data,
+            |_, response| Ok(response.into()),
+        );
+        self.record_outcome(&result);
+        result
+    }
 
-        locked_self.transport.send_frame(
+    /// Executes a raw SMP command and returns the response together with its header.
+    ///
+    /// Same as [`Connection::execute_raw_command`], but also reports the exact
+    /// operation, flags, SMP version and sequence number the device responded with.
+    pub fn execute_raw_command_with_header(
+        &self,
+        write_operation: bool,
+        group_id: u16,
+        command_id: u8,
+        data: &[u8],
+    ) -> Result<RawResponse, ExecuteError> {
+        let result = self.execute_data(
             write_operation,
-            sequence_num,
             group_id,
             command_id,
             data,
-        )?;
+            |header, response| {
+                Ok(RawResponse {
+                    header: *header,
+                    data: response.into(),
+                })
+            },
+        );
+        self.record_outcome(&result);
+        result
+    }
+}
 
-        locked_self
-            .transport
-            .receive_frame(
-                &mut locked_self.transport_buffer,
-                write_operation,
-                sequence_num,
-                group_id,
-                command_id,
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, net::UdpSocket, sync::Arc};
+
+    use super::*;
+    use crate::{MCUmgrGroup, commands, testing::FakeDevice, transport::udp::UdpTransport};
+
+    #[test]
+    fn dispatches_concurrent_responses_by_sequence_number() {
+        let device = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let connection = Arc::new(Connection::new(
+            UdpTransport::new(device.local_addr().unwrap(), Duration::from_secs(1)).unwrap(),
+        ));
+
+        let requests = [1, 2].map(|payload| {
+            let connection = Arc::clone(&connection);
+            std::thread::spawn(move || connection.execute_raw_command(false, 0, 0, &[payload]))
+        });
+
+        // Answer both requests in reverse order, echoing their payloads
+        let mut received = vec![];
+        for _ in 0..2 {
+            let mut request = [0; 64];
+            let (len, host) = device.recv_from(&mut request).unwrap();
+            received.push((request[..len].to_vec(), host));
+        }
+        for (mut frame, host) in received.into_iter().rev() {
+            frame[0] += 1;
+            device.send_to(&frame, host).unwrap();
+        }
+
+        let [first, second] = requests.map(|request| request.join().unwrap().unwrap());
+        assert_eq!(&*first, &[1]);
+        assert_eq!(&*second, &[2]);
+        assert_eq!(connection.stats().responses, 2);
+    }
+
+    #[test]
+    fn sorts_out_stale_and_unsolicited_frames() {
+        let device = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let connection = Connection::new(
+            UdpTransport::new(device.local_addr().unwrap(), Duration::from_secs(1)).unwrap(),
+        );
+
+        // Turns an echo request into its response;
+        // the keys `d` and `r` have the same encoded length
+        let receive_request = || {
+            let mut frame = [0; 64];
+            let (len, host) = device.recv_from(&mut frame).unwrap();
+            let mut response = frame[..len].to_vec();
+            response[0] += 1;
+            response[SMP_HEADER_SIZE + 2] = b'r';
+            (response, host)
+        };
+
+        let mut pipeline = connection.pipeline();
+        let first = pipeline.send(&commands::os::Echo { d: "a" }).unwrap();
+        let second = pipeline.send(&commands::os::Echo { d: "b" }).unwrap();
+        let (first_response, host) = receive_request();
+        let (second_response, _) = receive_request();
+        for frame in [
+            &first_response,
+            &first_response,
+            &vec![0; 3],
+            &second_response,
+        ] {
+            device.send_to(frame, host).unwrap();
+        }
+        assert_eq!(pipeline.receive(second).unwrap().r, "b");
+        assert_eq!(pipeline.receive(first).unwrap().r, "a");
+
+        let unsolicited = connection.unsolicited_frames();
+        let mut notification = first_response.clone();
+        notification[6] = notification[6].wrapping_add(100);
+
+        let third = pipeline.send(&commands::os::Echo { d: "c" }).unwrap();
+        let (third_response, _) = receive_request();
+        for frame in [&first_response, &notification, &third_response] {
+            device.send_to(frame, host).unwrap();
+        }
+        assert_eq!(pipeline.receive(third).unwrap().r, "c");
+
+        let stats = connection.stats();
+        assert_eq!(stats.responses, 3);
+        assert_eq!(stats.duplicate_responses, 1);
+        assert_eq!(stats.late_responses, 1);
+        assert_eq!(stats.discarded_frames, 1);
+        assert_eq!(stats.unsolicited_frames, 1);
+        assert_eq!(stats.errors, 0);
+
+        let frames = unsolicited.try_iter().collect::<Vec<_>>();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].header.sequence_num, notification[6]);
+        assert_eq!(*frames[0].data, notification[SMP_HEADER_SIZE..]);
+    }
+
+    #[test]
+    fn retryable_errors() {
+        let timeout = ExecuteError::ReceiveFailed(io::Error::from(io::ErrorKind::TimedOut).into());
+        assert!(timeout.is_retryable());
+        assert!(ExecuteError::ReceiveFailed(ReceiveError::CorruptedFrame).is_retryable());
+        assert!(!ExecuteError::ReceiveFailed(ReceiveError::UnexpectedResponse).is_retryable());
+        assert!(!ExecuteError::SendFailed(SendError::DataTooBig).is_retryable());
+        assert!(
+            !ExecuteError::SendFailed(io::Error::from(io::ErrorKind::NotFound).into())
+                .is_retryable()
+        );
+
+        let device_error = |rc: MCUmgrErr| {
+            ExecuteError::ErrorResponse(DeviceError::V1 {
+                group: 0,
+                rc: rc as i32,
+                rsn: None,
+            })
+        };
+        assert!(device_error(MCUmgrErr::MGMT_ERR_EBUSY).is_retryable());
+        assert!(device_error(MCUmgrErr::MGMT_ERR_ENOMEM).is_retryable());
+        assert!(!device_error(MCUmgrErr::MGMT_ERR_EINVAL).is_retryable());
+        assert!(!device_error(MCUmgrErr::MGMT_ERR_ENOTSUP).is_retryable());
+
+        let group_error = |group: MCUmgrGroup, rc: i32| {
+            ExecuteError::ErrorResponse(DeviceError::V2 {
+                group: group as u16,
+                rc,
+            })
+        };
+        assert!(
+            group_error(
+                MCUmgrGroup::MGMT_GROUP_ID_IMAGE,
+                ImgMgmtErrCode::IMG_MGMT_ERR_NO_FREE_MEMORY as i32
             )
-            .map_err(Into::into)
-            .map(|val| val.into())
+            .is_retryable()
+        );
+        assert!(
+            group_error(
+                MCUmgrGroup::MGMT_GROUP_ID_ENUM,
+                EnumMgmtErrCode::ENUM_MGMT_ERR_INSUFFICIENT_HEAP_FOR_ENTRIES as i32
+            )
+            .is_retryable()
+        );
+        assert!(
+            !group_error(
+                MCUmgrGroup::MGMT_GROUP_ID_IMAGE,
+                ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_IMAGE_TOO_LARGE as i32
+            )
+            .is_retryable()
+        );
+        // The same code means something else in another group
+        assert!(
+            !group_error(
+                MCUmgrGroup::MGMT_GROUP_ID_FS,
+                ImgMgmtErrCode::IMG_MGMT_ERR_NO_FREE_MEMORY as i32
+            )
+            .is_retryable()
+        );
+    }
+
+    /// Loses the connection once while waiting for the first response,
+    /// like a [`ReconnectingSerialPort`](crate::transport::reconnect::ReconnectingSerialPort)
+    /// whose device re-enumerated.
+    struct ReconnectingDevice {
+        device: FakeDevice,
+        reconnected: bool,
+    }
+
+    impl Transport for ReconnectingDevice {
+        fn send_raw_frame(
+            &mut self,
+            header: [u8; SMP_HEADER_SIZE],
+            data: &[u8],
+        ) -> Result<(), SendError> {
+            self.device.send_raw_frame(header, data)
+        }
+
+        fn recv_raw_frame<'a>(
+            &mut self,
+            buffer: &'a mut [u8; SMP_TRANSFER_BUFFER_SIZE],
+        ) -> Result<&'a [u8], ReceiveError> {
+            if !self.reconnected {
+                self.reconnected = true;
+                // The response got lost together with the old connection
+                self.device.recv_raw_frame(buffer)?;
+                return Err(io::Error::from(io::ErrorKind::ConnectionReset).into());
+            }
+            self.device.recv_raw_frame(buffer)
+        }
+
+        fn set_timeout(&mut self, timeout: Duration) -> Result<(), miette::Report> {
+            self.device.set_timeout(timeout)
+        }
+    }
+
+    #[test]
+    fn resends_request_after_reconnect() {
+        let connection = Connection::new(ReconnectingDevice {
+            device: FakeDevice::new(),
+            reconnected: false,
+        });
+
+        let sent = Arc::new(Mutex::new(vec![]));
+        connection.add_frame_observer(Box::new({
+            let sent = Arc::clone(&sent);
+            move |direction, header, _| {
+                if direction == FrameDirection::Sent {
+                    sent.lock().unwrap().push(header.sequence_num);
+                }
+            }
+        }));
+
+        let response = connection
+            .execute_command(&commands::os::Echo { d: "hello" })
+            .unwrap();
+        assert_eq!(response.r, "hello");
+
+        // Sent exactly twice, both times with the same sequence number
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0], sent[1]);
+        assert_eq!(connection.stats().retransmits, 1);
+    }
+
+    #[test]
+    fn retries_transient_device_errors() {
+        let device = FakeDevice::new();
+        let connection = Connection::new(device.clone());
+        connection.set_retry_policy(RetryPolicy {
+            attempts: 3,
+            backoff: Duration::ZERO,
+        });
+
+        let mut failures = 2;
+        device.on_command(false, 0, 0, move |_: ciborium::Value| {
+            if failures > 0 {
+                failures -= 1;
+                return Err(MCUmgrErr::MGMT_ERR_EBUSY);
+            }
+            Ok(BTreeMap::from([("r", "hello")]))
+        });
+
+        let response = connection
+            .execute_command(&commands::os::Echo { d: "hello" })
+            .unwrap();
+        assert_eq!(response.r, "hello");
+        assert_eq!(connection.stats().retransmits, 2);
+
+        device.on_command(false, 0, 0, |_: ciborium::Value| {
+            Err::<(), _>(MCUmgrErr::MGMT_ERR_EINVAL)
+        });
+        assert!(
+            connection
+                .execute_command(&commands::os::Echo { d: "hello" })
+                .is_err()
+        );
+        assert_eq!(connection.stats().retransmits, 2);
+    }
+
+    #[test]
+    fn decode_failure_points_at_payload() {
+        let mut payload = vec![];
+        ciborium::into_writer(&BTreeMap::from([("r", 42)]), &mut payload).unwrap();
+        let header = SmpHeader::from_bytes([0; SMP_HEADER_SIZE]);
+
+        let err = decode_response::<commands::os::EchoResponse>(&header, &payload).unwrap_err();
+        assert!(matches!(err, ExecuteError::DecodeFailed(_)));
+
+        let source = ciborium::from_reader::<commands::os::EchoResponse, _>(&*payload).unwrap_err();
+        let err = ResponseDecodeError::new::<commands::os::EchoResponse>(&payload, source);
+        assert!(err.expected_type.ends_with("EchoResponse"));
+        assert_eq!(*err.payload, *payload);
+        assert_eq!(err.hex_dump.inner(), "0000: a1 61 72 18 2a\n");
+        assert_eq!(err.reason, "invalid type: integer `42`, expected string");
+        assert_eq!(err.span, (0, 20).into());
+
+        // Syntax errors point at the offending byte, here a reserved integer encoding
+        let payload = [&[0xa2, 0x70][..], &[0x61; 16], &[0x1c]].concat();
+        let source = ciborium::from_reader::<commands::os::EchoResponse, _>(&*payload).unwrap_err();
+        let err = ResponseDecodeError::new::<commands::os::EchoResponse>(&payload, source);
+        let label = err.labels().unwrap().next().unwrap();
+        assert_eq!(label.offset(), 54 + 6 + 2 * 3);
+        assert_eq!(&err.hex_dump.inner()[label.offset()..][..label.len()], "1c");
+    }
+
+    #[test]
+    fn batch_results_stay_in_order() {
+        let device = FakeDevice::new();
+        let connection = Connection::new(device.clone());
+
+        let messages = ["a", "b", "c", "d"];
+        let requests = messages.map(|d| commands::os::Echo { d });
+
+        // The response of the first request gets lost
+        device.drop_responses(1);
+        let results = connection.execute_batch(&requests, 2);
+
+        assert_eq!(results.len(), 4);
+        assert!(results[0].as_ref().is_err_and(is_timeout));
+        for (result, message) in results[1..].iter().zip(&messages[1..]) {
+            assert_eq!(&result.as_ref().unwrap().r, message);
+        }
+        assert_eq!(connection.stats().responses, 3);
+    }
+
+    #[test]
+    fn raw_command_reports_response_header() {
+        let connection = Connection::new(FakeDevice::new());
+        connection.set_smp_version(SmpVersion::V1);
+        assert_eq!(connection.device_smp_version(), None);
+
+        let mut request = vec![];
+        ciborium::into_writer(&commands::os::Echo { d: "hi" }, &mut request).unwrap();
+        let response = connection
+            .execute_raw_command_with_header(false, 0, 0, &request)
+            .unwrap();
+
+        assert_eq!(response.header.op, 1);
+        assert_eq!(connection.device_smp_version(), Some(SmpVersion::V1));
+        assert_eq!(response.header.ver, 0);
+        assert_eq!(response.header.group_id, 0);
+        assert_eq!(response.header.command_id, 0);
+        assert_eq!(
+            usize::from(response.header.data_length),
+            response.data.len()
+        );
+        let echo: commands::os::EchoResponse = ciborium::from_reader(&*response.data).unwrap();
+        assert_eq!(echo.r, "hi");
     }
 }
@@ -11,8 +11,7 @@ pub use client::MCUmgrClient;
 mod errno;
 pub use errno::Errno;
 
-/// [MCUmgr command group](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_protocol.html#specifications-of-management-groups-supported-by-zephyr) definitions
-pub mod commands;
+pub use zephyr_mcumgr_protocol::{MCUmgrGroup, commands};
 
 /// [SMP protocol layer](https://docs.zephyrproject.org/latest/services/device_mgmt/smp_protocol.html) implementation
 pub mod connection;
@@ -29,39 +28,12 @@ pub mod bootloader;
 /// MCUboot specific algorithms
 pub mod mcuboot;
 
-/// See [`enum mcumgr_group_t`](https://docs.zephyrproject.org/latest/doxygen/html/mgmt__defines_8h.html).
-#[derive(strum::FromRepr, strum::Display, Debug, Copy, Clone, PartialEq, Eq)]
-#[repr(u16)]
-#[allow(non_camel_case_types)]
-#[allow(missing_docs)]
-pub enum MCUmgrGroup {
-    MGMT_GROUP_ID_OS = 0,
-    MGMT_GROUP_ID_IMAGE,
-    MGMT_GROUP_ID_STAT,
-    MGMT_GROUP_ID_SETTINGS,
-    MGMT_GROUP_ID_LOG,
-    MGMT_GROUP_ID_CRASH,
-    MGMT_GROUP_ID_SPLIT,
-    MGMT_GROUP_ID_RUN,
-    MGMT_GROUP_ID_FS,
-    MGMT_GROUP_ID_SHELL,
-    MGMT_GROUP_ID_ENUM,
-    ZEPHYR_MGMT_GRP_BASIC = 63,
-    MGMT_GROUP_ID_PERUSER = 64,
-}
+/// Local hash/checksum computation, compatible with the device's file checksum algorithms
+pub mod checksum;
 
-impl MCUmgrGroup {
-    /// Converts a raw group id to a string
-    pub fn group_id_to_string(group_id: u16) -> String {
-        const PERUSER: MCUmgrGroup = MCUmgrGroup::MGMT_GROUP_ID_PERUSER;
-        if group_id < PERUSER as u16 {
-            if let Some(group_enum) = Self::from_repr(group_id) {
-                format!("{group_enum}")
-            } else {
-                format!("MGMT_GROUP_ID_UNKNOWN({group_id})")
-            }
-        } else {
-            format!("{PERUSER}({group_id})")
-        }
-    }
-}
+/// Capturing of SMP traffic into pcapng files for inspection in Wireshark, or into replayable text logs
+pub mod capture;
+
+/// An in-memory fake device, for testing tools built on this crate without hardware
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
@@ -1,7 +1,18 @@
 use std::io;
 
+mod tlv;
+#[cfg(feature = "verify")]
+mod verify;
+pub use tlv::{
+    ImageDependency, ImageHashAlgorithm, ImageSignatureAlgorithm, ImageTlv, ImageTlvValue,
+};
+#[cfg(feature = "verify")]
+pub use verify::verify_signature;
+
 /// The firmware version
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+///
+/// Versions are ordered semver-style by major, minor, revision and build number.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
 pub struct ImageVersion {
     /// Major version
     pub major: u8,
@@ -22,25 +33,80 @@ impl std::fmt::Display for ImageVersion {
     }
 }
 
+/// The given string is not a valid [`ImageVersion`].
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+#[error("Invalid image version '{0}'")]
+#[diagnostic(code(zephyr_mcumgr::mcuboot::image::version_parse))]
+pub struct ImageVersionParseError(String);
+
+impl std::str::FromStr for ImageVersion {
+    type Err = ImageVersionParseError;
+
+    /// Parses versions in the format `major.minor.revision[.build_num]`,
+    /// as reported by the device in the image state.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ImageVersionParseError(s.to_string());
+
+        let mut parts = s.trim().split('.');
+        let mut next_part = || parts.next().ok_or_else(err);
+
+        let version = ImageVersion {
+            major: next_part()?.parse().map_err(|_| err())?,
+            minor: next_part()?.parse().map_err(|_| err())?,
+            revision: next_part()?.parse().map_err(|_| err())?,
+            build_num: match parts.next() {
+                Some(build_num) => build_num.parse().map_err(|_| err())?,
+                None => 0,
+            },
+        };
+
+        if parts.next().is_some() {
+            return Err(err());
+        }
+
+        Ok(version)
+    }
+}
+
 /// Information about an MCUboot firmware image
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ImageInfo {
     /// Firmware version
     pub version: ImageVersion,
+    /// The address the image gets loaded to, if the `IMAGE_F_RAM_LOAD` flag is set
+    pub load_address: u32,
+    /// The header flags, like `IMAGE_F_ENCRYPTED_AES128` or `IMAGE_F_ROM_FIXED`
+    pub flags: u32,
     /// The identifying hash for the firmware
     ///
     /// Note that this will not be the same as the SHA256 of the whole file, it is the field in the
     /// MCUboot TLV section that contains a hash of the data which is used for signature
     /// verification purposes.
     pub hash: [u8; SHA256_LEN],
+    /// The anti-rollback security counter
+    ///
+    /// Devices with downgrade protection reject images with a counter lower than
+    /// the one of the currently installed image.
+    pub security_counter: Option<u32>,
+    /// The CBOR encoded measured boot record
+    pub boot_record: Option<Box<[u8]>>,
+    /// The minimum versions of other images this image requires
+    ///
+    /// See [`ImageDependency::is_satisfied_by`] for checking them against the device.
+    pub dependencies: Vec<ImageDependency>,
 }
 
-/// Possible error values of [`get_image_info`].
+/// Possible error values of [`get_image_info`] and [`get_image_tlvs`].
 #[derive(thiserror::Error, Debug, miette::Diagnostic)]
 pub enum ImageParseError {
     /// The given image file is not an MCUboot image.
     #[error("Image is not an MCUboot image")]
-    #[diagnostic(code(zephyr_mcumgr::mcuboot::image::unknown_type))]
+    #[diagnostic(
+        code(zephyr_mcumgr::mcuboot::image::unknown_type),
+        help(
+            "Use the signed image, e.g. `zephyr.signed.bin`, instead of `zephyr.bin` or `zephyr.hex`."
+        )
+    )]
     UnknownImageType,
     /// The given image file does not contain TLV entries.
     #[error("Image does not contain TLV entries")]
@@ -56,6 +122,52 @@ pub enum ImageParseError {
     ReadFailed(#[from] std::io::Error),
 }
 
+/// Possible error values of `verify_signature`.
+///
+/// Always available, so that errors wrapping it do not depend on the `verify` feature.
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum ImageVerifyError {
+    /// The given image file is not a valid MCUboot image.
+    #[error("Failed to parse image")]
+    #[diagnostic(code(zephyr_mcumgr::mcuboot::image::verify::parse), forward(0))]
+    ParseFailed(#[from] ImageParseError),
+    /// The identifying hash of the image does not match its content.
+    #[error("Image hash does not match the image content")]
+    #[diagnostic(
+        code(zephyr_mcumgr::mcuboot::image::verify::hash_mismatch),
+        help("The image file is corrupted.")
+    )]
+    HashMismatch,
+    /// The image does not contain a signature with a supported algorithm.
+    #[error("Image does not contain a supported signature")]
+    #[diagnostic(
+        code(zephyr_mcumgr::mcuboot::image::verify::signature_missing),
+        help("Sign the image with an ECDSA-P256, RSA-2048, RSA-3072 or Ed25519 key.")
+    )]
+    SignatureMissing,
+    /// The public key does not match the signature algorithm of the image.
+    #[error("Public key does not match the signature type of the image")]
+    #[diagnostic(
+        code(zephyr_mcumgr::mcuboot::image::verify::key_type_mismatch),
+        help("Provide the public key in DER or PEM format, as exported by `imgtool getpub`.")
+    )]
+    KeyTypeMismatch,
+    /// The image is only signed with an algorithm that cannot be verified.
+    #[error("Signature algorithm {0:?} is not supported")]
+    #[diagnostic(
+        code(zephyr_mcumgr::mcuboot::image::verify::unsupported_algorithm),
+        help("Sign the image with an ECDSA-P256, RSA-2048, RSA-3072 or Ed25519 key.")
+    )]
+    UnsupportedAlgorithm(ImageSignatureAlgorithm),
+    /// The image was not signed with the given public key.
+    #[error("Image signature is not valid for the given public key")]
+    #[diagnostic(
+        code(zephyr_mcumgr::mcuboot::image::verify::signature_invalid),
+        help("The image was signed with a different key.")
+    )]
+    SignatureInvalid,
+}
+
 fn read_u32(data: &mut dyn std::io::Read) -> Result<u32, std::io::Error> {
     let mut bytes = [0u8; 4];
     data.read_exact(&mut bytes)?;
@@ -77,17 +189,26 @@ fn read_u8(data: &mut dyn std::io::Read) -> Result<u8, std::io::Error> {
 /// The identifying header of an MCUboot image
 const IMAGE_MAGIC: u32 = 0x96f3b83d;
 const IMAGE_TLV_INFO_MAGIC: u16 = 0x6907;
-const IMAGE_TLV_SHA256: u8 = 0x10;
+const IMAGE_TLV_PROT_INFO_MAGIC: u16 = 0x6908;
 const SHA256_LEN: usize = 32;
 const TLV_INFO_HEADER_SIZE: u32 = 4;
 const TLV_ELEMENT_HEADER_SIZE: u32 = 4;
 
-/// Extract information from an MCUboot image file
-pub fn get_image_info(
-    mut image_data: impl io::Read + io::Seek,
-) -> Result<ImageInfo, ImageParseError> {
-    let image_data = &mut image_data;
+/// The parsed header and TLV entries of an MCUboot image.
+struct ParsedImage {
+    version: ImageVersion,
+    load_address: u32,
+    flags: u32,
+    /// The size of the header, the image data and the protected TLVs,
+    /// which are covered by the identifying hash
+    #[cfg_attr(not(feature = "verify"), allow(dead_code))]
+    hashed_len: u64,
+    tlvs: Vec<ImageTlv>,
+}
 
+fn parse_image(
+    image_data: &mut (impl io::Read + io::Seek),
+) -> Result<ParsedImage, ImageParseError> {
     let ih_magic = read_u32(image_data)?;
     log::debug!("ih_magic: 0x{ih_magic:08x}");
     if ih_magic != IMAGE_MAGIC {
@@ -118,46 +239,313 @@ pub fn get_image_info(
     log::debug!("ih_ver: {ih_ver:?}");
 
     image_data.seek(io::SeekFrom::Start(
-        u64::from(ih_hdr_size) + u64::from(ih_protect_tlv_size) + u64::from(ih_img_size),
+        u64::from(ih_hdr_size) + u64::from(ih_img_size),
     ))?;
 
+    let mut tlvs = vec![];
+    if ih_protect_tlv_size > 0 {
+        read_tlv_area(image_data, IMAGE_TLV_PROT_INFO_MAGIC, &mut tlvs)?;
+    }
+    read_tlv_area(image_data, IMAGE_TLV_INFO_MAGIC, &mut tlvs)?;
+
+    Ok(ParsedImage {
+        version: ih_ver,
+        load_address: ih_load_addr,
+        flags: ih_flags,
+        hashed_len: u64::from(ih_hdr_size)
+            + u64::from(ih_img_size)
+            + u64::from(ih_protect_tlv_size),
+        tlvs,
+    })
+}
+
+/// Reads a TLV area, starting with its info header.
+///
+/// The protected area is identified by its magic number.
+fn read_tlv_area(
+    image_data: &mut impl io::Read,
+    magic: u16,
+    tlvs: &mut Vec<ImageTlv>,
+) -> Result<(), ImageParseError> {
     let it_magic = read_u16(image_data)?;
     log::debug!("it_magic: 0x{it_magic:04x}");
-    if it_magic != IMAGE_TLV_INFO_MAGIC {
+    if it_magic != magic {
         return Err(ImageParseError::TlvMissing);
     }
 
     let it_tlv_tot = read_u16(image_data)?;
     log::debug!("it_tlv_tot: 0x{it_tlv_tot:04x}");
 
-    let mut id_hash = None;
-    {
-        let mut tlv_read: u32 = 0;
-        // Loop while at least one tlv header can still be read
-        while tlv_read + TLV_INFO_HEADER_SIZE + TLV_ELEMENT_HEADER_SIZE <= u32::from(it_tlv_tot) {
-            let it_type = read_u8(image_data)?;
-            read_u8(image_data)?;
-            let it_len = read_u16(image_data)?;
-
-            if it_type == IMAGE_TLV_SHA256 && usize::from(it_len) == SHA256_LEN {
-                let mut sha256_hash = [0u8; SHA256_LEN];
-                image_data.read_exact(&mut sha256_hash)?;
-                id_hash = Some(sha256_hash);
-            } else {
-                image_data.seek_relative(it_len.into())?;
-            }
+    let mut tlv_read: u32 = 0;
+    // Loop while at least one tlv header can still be read
+    while tlv_read + TLV_INFO_HEADER_SIZE + TLV_ELEMENT_HEADER_SIZE <= u32::from(it_tlv_tot) {
+        let it_type = read_u16(image_data)?;
+        let it_len = read_u16(image_data)?;
 
-            log::debug!("- it_type: 0x{it_type:02x}, it_len: 0x{it_len:02x}");
-            tlv_read += u32::from(it_len) + 4;
-        }
+        let mut data = vec![0u8; it_len.into()].into_boxed_slice();
+        image_data.read_exact(&mut data)?;
+
+        log::debug!("- it_type: 0x{it_type:02x}, it_len: 0x{it_len:02x}");
+        tlv_read += u32::from(it_len) + TLV_ELEMENT_HEADER_SIZE;
+
+        tlvs.push(ImageTlv {
+            tlv_type: it_type,
+            protected: magic == IMAGE_TLV_PROT_INFO_MAGIC,
+            data,
+        });
     }
 
+    Ok(())
+}
+
+/// Extract information from an MCUboot image file
+pub fn get_image_info(
+    mut image_data: impl io::Read + io::Seek,
+) -> Result<ImageInfo, ImageParseError> {
+    let image = parse_image(&mut image_data)?;
+
+    let id_hash = image
+        .tlvs
+        .iter()
+        .filter(|tlv| !tlv.protected && tlv.tlv_type == tlv::IMAGE_TLV_SHA256)
+        .find_map(|tlv| <[u8; SHA256_LEN]>::try_from(tlv.data.as_ref()).ok());
+
+    // Both entries are only valid if they are covered by the signature
+    let security_counter = image
+        .tlvs
+        .iter()
+        .filter(|tlv| tlv.protected)
+        .find_map(|tlv| match tlv.value() {
+            ImageTlvValue::SecurityCounter(counter) => Some(counter),
+            _ => None,
+        });
+    let boot_record = image
+        .tlvs
+        .iter()
+        .filter(|tlv| tlv.protected)
+        .find_map(|tlv| match tlv.value() {
+            ImageTlvValue::BootRecord(boot_record) => Some(boot_record.into()),
+            _ => None,
+        });
+
+    let dependencies = image
+        .tlvs
+        .iter()
+        .filter(|tlv| tlv.protected)
+        .filter_map(|tlv| match tlv.value() {
+            ImageTlvValue::Dependency(dependency) => Some(dependency),
+            _ => None,
+        })
+        .collect();
+
     if let Some(id_hash) = id_hash {
         Ok(ImageInfo {
-            version: ih_ver,
+            version: image.version,
+            load_address: image.load_address,
+            flags: image.flags,
             hash: id_hash,
+            security_counter,
+            boot_record,
+            dependencies,
         })
     } else {
         Err(ImageParseError::IdHashMissing)
     }
 }
+
+/// Read all TLV entries of an MCUboot image file, protected and unprotected.
+///
+/// Use [`ImageTlv::value`] to decode the known entry types.
+pub fn get_image_tlvs(
+    mut image_data: impl io::Read + io::Seek,
+) -> Result<Vec<ImageTlv>, ImageParseError> {
+    parse_image(&mut image_data).map(|image| image.tlvs)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::commands::image::ImageState;
+
+    #[test]
+    fn version_parse() {
+        let version = |major, minor, revision, build_num| ImageVersion {
+            major,
+            minor,
+            revision,
+            build_num,
+        };
+
+        assert_eq!(
+            "1.2.3".parse::<ImageVersion>().unwrap(),
+            version(1, 2, 3, 0)
+        );
+        assert_eq!(
+            "1.2.3.4".parse::<ImageVersion>().unwrap(),
+            version(1, 2, 3, 4)
+        );
+        assert!("1.2".parse::<ImageVersion>().is_err());
+        assert!("1.2.3.4.5".parse::<ImageVersion>().is_err());
+        assert!("1.2.x".parse::<ImageVersion>().is_err());
+
+        for v in [version(1, 2, 3, 0), version(0, 0, 1, 42)] {
+            assert_eq!(v.to_string().parse::<ImageVersion>().unwrap(), v);
+        }
+    }
+
+    #[test]
+    fn version_order() {
+        let parse = |s: &str| s.parse::<ImageVersion>().unwrap();
+
+        assert!(parse("1.2.3") < parse("1.2.4"));
+        assert!(parse("1.2.3") < parse("1.2.3.1"));
+        assert!(parse("1.10.0") > parse("1.9.9"));
+        assert!(parse("2.0.0") > parse("1.255.65535.1"));
+    }
+
+    /// Builds a synthetic image with the given protected and unprotected TLVs.
+    pub(crate) fn build_image(protected: &[(u16, &[u8])], unprotected: &[(u16, &[u8])]) -> Vec<u8> {
+        fn tlv_area(magic: u16, tlvs: &[(u16, &[u8])]) -> Vec<u8> {
+            let mut body = vec![];
+            for (tlv_type, data) in tlvs {
+                body.extend_from_slice(&tlv_type.to_le_bytes());
+                body.extend_from_slice(&(data.len() as u16).to_le_bytes());
+                body.extend_from_slice(data);
+            }
+            let mut area = vec![];
+            area.extend_from_slice(&magic.to_le_bytes());
+            area.extend_from_slice(&((body.len() + 4) as u16).to_le_bytes());
+            area.extend_from_slice(&body);
+            area
+        }
+
+        let protected_area = if protected.is_empty() {
+            vec![]
+        } else {
+            tlv_area(IMAGE_TLV_PROT_INFO_MAGIC, protected)
+        };
+        let hdr_size = 32u16;
+        let payload = [0xaau8; 16];
+
+        let mut image = vec![];
+        image.extend_from_slice(&IMAGE_MAGIC.to_le_bytes());
+        image.extend_from_slice(&0x2000_0000u32.to_le_bytes());
+        image.extend_from_slice(&hdr_size.to_le_bytes());
+        image.extend_from_slice(&(protected_area.len() as u16).to_le_bytes());
+        image.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        image.extend_from_slice(&0x20u32.to_le_bytes());
+        image.extend_from_slice(&[1, 2]);
+        image.extend_from_slice(&3u16.to_le_bytes());
+        image.extend_from_slice(&4u32.to_le_bytes());
+        image.resize(hdr_size.into(), 0);
+        image.extend_from_slice(&payload);
+        image.extend_from_slice(&protected_area);
+        image.extend_from_slice(&tlv_area(IMAGE_TLV_INFO_MAGIC, unprotected));
+        image
+    }
+
+    #[test]
+    fn image_tlvs() {
+        let dependency = [1, 0, 0, 0, 2, 3, 4, 0, 5, 0, 0, 0];
+        let hash = [0x11; SHA256_LEN];
+        let image = build_image(
+            &[(0x50, &7u32.to_le_bytes()), (0x40, &dependency)],
+            &[(0x01, &[0x22; 32]), (0x10, &hash), (0x24, &[0x33; 64])],
+        );
+
+        let info = get_image_info(std::io::Cursor::new(&image)).unwrap();
+        assert_eq!(info.version.to_string(), "1.2.3.4");
+        assert_eq!(info.load_address, 0x2000_0000);
+        assert_eq!(info.flags, 0x20);
+        assert_eq!(info.hash, hash);
+        assert_eq!(info.security_counter, Some(7));
+        assert_eq!(info.boot_record, None);
+        assert_eq!(
+            info.dependencies,
+            [ImageDependency {
+                image_id: 1,
+                min_version: "2.3.4.5".parse().unwrap(),
+            }]
+        );
+
+        let tlvs = get_image_tlvs(std::io::Cursor::new(&image)).unwrap();
+        let protected = tlvs.iter().map(|tlv| tlv.protected).collect::<Vec<_>>();
+        assert_eq!(protected, [true, true, false, false, false]);
+
+        let values = tlvs.iter().map(ImageTlv::value).collect::<Vec<_>>();
+        assert_eq!(values[0], ImageTlvValue::SecurityCounter(7));
+        assert_eq!(
+            values[1],
+            ImageTlvValue::Dependency(ImageDependency {
+                image_id: 1,
+                min_version: "2.3.4.5".parse().unwrap(),
+            })
+        );
+        assert_eq!(values[2], ImageTlvValue::KeyHash(&[0x22; 32]));
+        assert_eq!(
+            values[3],
+            ImageTlvValue::Hash {
+                algorithm: ImageHashAlgorithm::Sha256,
+                hash: &hash,
+            }
+        );
+        assert_eq!(
+            values[4],
+            ImageTlvValue::Signature {
+                algorithm: ImageSignatureAlgorithm::Ed25519,
+                signature: &[0x33; 64],
+            }
+        );
+    }
+
+    #[test]
+    fn image_info_protected_entries() {
+        let image = build_image(
+            &[(0x60, &[0xa0])],
+            &[(0x50, &7u32.to_le_bytes()), (0x10, &[0x11; SHA256_LEN])],
+        );
+
+        let info = get_image_info(std::io::Cursor::new(&image)).unwrap();
+        assert_eq!(info.boot_record.as_deref(), Some(&[0xa0][..]));
+        // Unprotected security counters are not trustworthy
+        assert_eq!(info.security_counter, None);
+    }
+
+    #[test]
+    fn dependency_check() {
+        let state = |image, version: &str, active, pending| ImageState {
+            image,
+            slot: 0,
+            version: version.to_string(),
+            hash: None,
+            bootable: true,
+            pending,
+            confirmed: active,
+            active,
+            permanent: false,
+        };
+        let dependency = ImageDependency {
+            image_id: 1,
+            min_version: "1.2.0".parse().unwrap(),
+        };
+
+        assert!(!dependency.is_satisfied_by(&[]));
+        assert!(!dependency.is_satisfied_by(&[state(0, "2.0.0", true, false)]));
+        assert!(!dependency.is_satisfied_by(&[state(1, "1.1.9", true, false)]));
+        assert!(!dependency.is_satisfied_by(&[state(1, "1.3.0", false, false)]));
+        assert!(dependency.is_satisfied_by(&[state(1, "1.2.0", true, false)]));
+        assert!(dependency.is_satisfied_by(&[
+            state(1, "1.1.0", true, false),
+            state(1, "1.3.0", false, true)
+        ]));
+    }
+
+    #[test]
+    fn image_without_hash() {
+        let image = build_image(&[], &[(0x01, &[0x22; 32])]);
+        assert!(matches!(
+            get_image_info(std::io::Cursor::new(&image)),
+            Err(ImageParseError::IdHashMissing)
+        ));
+    }
+}
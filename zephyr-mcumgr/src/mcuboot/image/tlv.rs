@@ -0,0 +1,184 @@
+use super::ImageVersion;
+use crate::commands::image::ImageState;
+
+const IMAGE_TLV_KEYHASH: u16 = 0x01;
+const IMAGE_TLV_PUBKEY: u16 = 0x02;
+pub(super) const IMAGE_TLV_SHA256: u16 = 0x10;
+const IMAGE_TLV_SHA384: u16 = 0x11;
+const IMAGE_TLV_SHA512: u16 = 0x12;
+const IMAGE_TLV_RSA2048_PSS: u16 = 0x20;
+const IMAGE_TLV_ECDSA224: u16 = 0x21;
+const IMAGE_TLV_ECDSA_SIG: u16 = 0x22;
+const IMAGE_TLV_RSA3072_PSS: u16 = 0x23;
+const IMAGE_TLV_ED25519: u16 = 0x24;
+#[cfg_attr(not(feature = "verify"), allow(dead_code))]
+pub(super) const IMAGE_TLV_SIG_PURE: u16 = 0x25;
+const IMAGE_TLV_ENC_RSA2048: u16 = 0x30;
+const IMAGE_TLV_ENC_KW: u16 = 0x31;
+const IMAGE_TLV_ENC_EC256: u16 = 0x32;
+const IMAGE_TLV_ENC_X25519: u16 = 0x33;
+const IMAGE_TLV_DEPENDENCY: u16 = 0x40;
+const IMAGE_TLV_SEC_CNT: u16 = 0x50;
+const IMAGE_TLV_BOOT_RECORD: u16 = 0x60;
+
+/// A TLV (type-length-value) entry of an MCUboot image.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ImageTlv {
+    /// The type of the entry, as defined in MCUboot's `image.h`
+    pub tlv_type: u16,
+    /// Whether the entry is part of the protected TLV area, which is covered by the signature
+    pub protected: bool,
+    /// The raw data of the entry
+    pub data: Box<[u8]>,
+}
+
+/// Hash algorithms used for the identifying hash of an image.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ImageHashAlgorithm {
+    /// SHA256
+    Sha256,
+    /// SHA384
+    Sha384,
+    /// SHA512
+    Sha512,
+}
+
+/// Signature algorithms MCUboot supports.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ImageSignatureAlgorithm {
+    /// RSA-2048 with PSS padding
+    Rsa2048Pss,
+    /// ECDSA with the P-224 curve
+    Ecdsa224,
+    /// ECDSA with the P-256 or P-384 curve, depending on the key
+    Ecdsa,
+    /// RSA-3072 with PSS padding
+    Rsa3072Pss,
+    /// Ed25519
+    Ed25519,
+}
+
+/// A dependency of an image on the minimum version of another image.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ImageDependency {
+    /// The index of the image that is required
+    pub image_id: u8,
+    /// The minimum version the required image must have
+    pub min_version: ImageVersion,
+}
+
+/// The decoded value of an [`ImageTlv`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ImageTlvValue<'a> {
+    /// Hash of the public key the image was signed with
+    KeyHash(&'a [u8]),
+    /// The public key the image was signed with
+    PublicKey(&'a [u8]),
+    /// The identifying hash of the image
+    Hash {
+        /// The hash algorithm
+        algorithm: ImageHashAlgorithm,
+        /// The hash value
+        hash: &'a [u8],
+    },
+    /// The signature of the image
+    Signature {
+        /// The signature algorithm
+        algorithm: ImageSignatureAlgorithm,
+        /// The raw signature
+        signature: &'a [u8],
+    },
+    /// The encrypted key of an encrypted image
+    EncryptionKey(&'a [u8]),
+    /// A dependency on another image
+    Dependency(ImageDependency),
+    /// The anti-rollback security counter
+    SecurityCounter(u32),
+    /// The CBOR encoded measured boot record
+    BootRecord(&'a [u8]),
+    /// An entry of unknown type, or a known type with invalid length
+    Unknown(&'a [u8]),
+}
+
+impl ImageDependency {
+    /// Decodes an `image_dependency` struct.
+    fn from_bytes(data: &[u8; 12]) -> Self {
+        Self {
+            image_id: data[0],
+            min_version: ImageVersion {
+                major: data[4],
+                minor: data[5],
+                revision: u16::from_le_bytes([data[6], data[7]]),
+                build_num: u32::from_le_bytes([data[8], data[9], data[10], data[11]]),
+            },
+        }
+    }
+
+    /// Checks whether the dependency is fulfilled by the images on the device.
+    ///
+    /// A dependency is fulfilled if the active or pending image with the required index
+    /// has at least the required version.
+    ///
+    /// # Arguments
+    ///
+    /// * `image_states` - The result of [`MCUmgrClient::image_get_state`](crate::MCUmgrClient::image_get_state).
+    ///
+    pub fn is_satisfied_by(&self, image_states: &[ImageState]) -> bool {
+        image_states
+            .iter()
+            .filter(|state| state.image == u64::from(self.image_id))
+            .filter(|state| state.active || state.pending)
+            .any(|state| match state.version.parse::<ImageVersion>() {
+                Ok(version) => version >= self.min_version,
+                Err(e) => {
+                    log::warn!("Unable to check image dependency: {e}");
+                    false
+                }
+            })
+    }
+}
+
+impl ImageTlv {
+    /// Decodes the data of the entry, if its type is known.
+    pub fn value(&self) -> ImageTlvValue<'_> {
+        let data = &self.data;
+
+        let hash = |algorithm| ImageTlvValue::Hash {
+            algorithm,
+            hash: data,
+        };
+        let signature = |algorithm| ImageTlvValue::Signature {
+            algorithm,
+            signature: data,
+        };
+
+        match self.tlv_type {
+            IMAGE_TLV_KEYHASH => ImageTlvValue::KeyHash(data),
+            IMAGE_TLV_PUBKEY => ImageTlvValue::PublicKey(data),
+            IMAGE_TLV_SHA256 => hash(ImageHashAlgorithm::Sha256),
+            IMAGE_TLV_SHA384 => hash(ImageHashAlgorithm::Sha384),
+            IMAGE_TLV_SHA512 => hash(ImageHashAlgorithm::Sha512),
+            IMAGE_TLV_RSA2048_PSS => signature(ImageSignatureAlgorithm::Rsa2048Pss),
+            IMAGE_TLV_ECDSA224 => signature(ImageSignatureAlgorithm::Ecdsa224),
+            IMAGE_TLV_ECDSA_SIG => signature(ImageSignatureAlgorithm::Ecdsa),
+            IMAGE_TLV_RSA3072_PSS => signature(ImageSignatureAlgorithm::Rsa3072Pss),
+            IMAGE_TLV_ED25519 => signature(ImageSignatureAlgorithm::Ed25519),
+            IMAGE_TLV_ENC_RSA2048
+            | IMAGE_TLV_ENC_KW
+            | IMAGE_TLV_ENC_EC256
+            | IMAGE_TLV_ENC_X25519 => ImageTlvValue::EncryptionKey(data),
+            IMAGE_TLV_DEPENDENCY => match data.first_chunk() {
+                Some(dependency) => {
+                    ImageTlvValue::Dependency(ImageDependency::from_bytes(dependency))
+                }
+                None => ImageTlvValue::Unknown(data),
+            },
+            IMAGE_TLV_SEC_CNT => match data.as_ref().try_into() {
+                Ok(counter) => ImageTlvValue::SecurityCounter(u32::from_le_bytes(counter)),
+                Err(_) => ImageTlvValue::Unknown(data),
+            },
+            IMAGE_TLV_BOOT_RECORD => ImageTlvValue::BootRecord(data),
+            _ => ImageTlvValue::Unknown(data),
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use std::io::{self, Read};
+
+use sha2::{Digest, Sha256};
+
+use super::{
+    ImageParseError, ImageSignatureAlgorithm, ImageTlvValue, ImageVerifyError, SHA256_LEN,
+    parse_image,
+    tlv::{IMAGE_TLV_SHA256, IMAGE_TLV_SIG_PURE},
+};
+
+/// Verify the signature of an MCUboot image file against a public key.
+///
+/// The public key has to be in DER or PEM format, as exported by `imgtool getpub`.
+/// Ed25519 keys can additionally be given as the raw 32 key bytes.
+///
+/// Also verifies that the identifying hash of the image matches its content.
+///
+/// Returns the algorithm of the valid signature.
+pub fn verify_signature(
+    mut image_data: impl io::Read + io::Seek,
+    public_key: &[u8],
+) -> Result<ImageSignatureAlgorithm, ImageVerifyError> {
+    let image = parse_image(&mut image_data)?;
+
+    let mut hashed_data = vec![];
+    image_data
+        .seek(io::SeekFrom::Start(0))
+        .map_err(ImageParseError::from)?;
+    (&mut image_data)
+        .take(image.hashed_len)
+        .read_to_end(&mut hashed_data)
+        .map_err(ImageParseError::from)?;
+    if hashed_data.len() as u64 != image.hashed_len {
+        return Err(ImageParseError::from(io::Error::from(io::ErrorKind::UnexpectedEof)).into());
+    }
+
+    let hash: [u8; SHA256_LEN] = Sha256::digest(&hashed_data).into();
+
+    // Images signed in "pure" mode have no hash, the signature covers the data directly
+    let pure = image
+        .tlvs
+        .iter()
+        .any(|tlv| tlv.protected && tlv.tlv_type == IMAGE_TLV_SIG_PURE);
+
+    if !pure {
+        let id_hash = image
+            .tlvs
+            .iter()
+            .find(|tlv| !tlv.protected && tlv.tlv_type == IMAGE_TLV_SHA256)
+            .ok_or(ImageParseError::IdHashMissing)?;
+        if *id_hash.data != hash {
+            return Err(ImageVerifyError::HashMismatch);
+        }
+    }
+
+    let mut result = Err(ImageVerifyError::SignatureMissing);
+
+    for tlv in image.tlvs.iter().filter(|tlv| !tlv.protected) {
+        let ImageTlvValue::Signature {
+            algorithm,
+            signature,
+        } = tlv.value()
+        else {
+            continue;
+        };
+
+        let valid = match algorithm {
+            ImageSignatureAlgorithm::Rsa2048Pss | ImageSignatureAlgorithm::Rsa3072Pss => {
+                verify_rsa_pss(public_key, &hash, signature)
+            }
+            ImageSignatureAlgorithm::Ecdsa => verify_ecdsa_p256(public_key, &hash, signature),
+            ImageSignatureAlgorithm::Ed25519 => {
+                let message = if pure { &hashed_data[..] } else { &hash[..] };
+                verify_ed25519(public_key, message, signature)
+            }
+            ImageSignatureAlgorithm::Ecdsa224 => {
+                if matches!(result, Err(ImageVerifyError::SignatureMissing)) {
+                    result = Err(ImageVerifyError::UnsupportedAlgorithm(algorithm));
+                }
+                continue;
+            }
+        };
+
+        match valid {
+            Some(true) => return Ok(algorithm),
+            Some(false) => result = Err(ImageVerifyError::SignatureInvalid),
+            None => {
+                if matches!(result, Err(ImageVerifyError::SignatureMissing)) {
+                    result = Err(ImageVerifyError::KeyTypeMismatch);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Decodes a public key from either PEM or DER.
+fn decode_key<K>(
+    public_key: &[u8],
+    from_der: impl FnOnce(&[u8]) -> Option<K>,
+    from_pem: impl FnOnce(&str) -> Option<K>,
+) -> Option<K> {
+    match std::str::from_utf8(public_key) {
+        Ok(pem) if pem.trim_start().starts_with("-----BEGIN") => from_pem(pem),
+        _ => from_der(public_key),
+    }
+}
+
+/// Returns `None` if the key is not an RSA key.
+fn verify_rsa_pss(public_key: &[u8], hash: &[u8], signature: &[u8]) -> Option<bool> {
+    use rsa::{pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey};
+
+    let key = decode_key(
+        public_key,
+        |der| {
+            rsa::RsaPublicKey::from_pkcs1_der(der)
+                .or_else(|_| rsa::RsaPublicKey::from_public_key_der(der))
+                .ok()
+        },
+        |pem| {
+            rsa::RsaPublicKey::from_pkcs1_pem(pem)
+                .or_else(|_| rsa::RsaPublicKey::from_public_key_pem(pem))
+                .ok()
+        },
+    )?;
+
+    Some(
+        key.verify(rsa::Pss::new::<Sha256>(), hash, signature)
+            .is_ok(),
+    )
+}
+
+/// Returns `None` if the key is not an ECDSA-P256 key.
+fn verify_ecdsa_p256(public_key: &[u8], hash: &[u8], signature: &[u8]) -> Option<bool> {
+    use p256::{
+        ecdsa::{Signature, VerifyingKey, signature::hazmat::PrehashVerifier},
+        pkcs8::DecodePublicKey,
+    };
+
+    let key = decode_key(
+        public_key,
+        |der| VerifyingKey::from_public_key_der(der).ok(),
+        |pem| VerifyingKey::from_public_key_pem(pem).ok(),
+    )?;
+
+    // Older versions of imgtool pad the DER encoded signature with zeros
+    let signature = match signature {
+        [0x30, len @ 0..0x80, ..] => &signature[..(usize::from(*len) + 2).min(signature.len())],
+        _ => signature,
+    };
+
+    Some(
+        Signature::from_der(signature)
+            .is_ok_and(|signature| key.verify_prehash(hash, &signature).is_ok()),
+    )
+}
+
+/// Returns `None` if the key is not an Ed25519 key.
+fn verify_ed25519(public_key: &[u8], message: &[u8], signature: &[u8]) -> Option<bool> {
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey, pkcs8::DecodePublicKey};
+
+    let key = decode_key(
+        public_key,
+        |der| {
+            VerifyingKey::from_public_key_der(der)
+                .ok()
+                .or_else(|| VerifyingKey::from_bytes(der.try_into().ok()?).ok())
+        },
+        |pem| VerifyingKey::from_public_key_pem(pem).ok(),
+    )?;
+
+    Some(
+        Signature::from_slice(signature)
+            .is_ok_and(|signature| key.verify(message, &signature).is_ok()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::build_image;
+    use super::*;
+
+    /// Builds a signed image, using the given signing function on the image hash.
+    fn build_signed_image(
+        protected: &[(u16, &[u8])],
+        signature_type: u16,
+        sign: impl FnOnce(&[u8], &[u8; SHA256_LEN]) -> Vec<u8>,
+    ) -> Vec<u8> {
+        let unsigned = build_image(protected, &[]);
+        // Strip the empty unprotected TLV area
+        let hashed_data = &unsigned[..unsigned.len() - 4];
+        let hash: [u8; SHA256_LEN] = Sha256::digest(hashed_data).into();
+        let signature = sign(hashed_data, &hash);
+        build_image(
+            protected,
+            &[(IMAGE_TLV_SHA256, &hash), (signature_type, &signature)],
+        )
+    }
+
+    #[test]
+    fn ecdsa_p256() {
+        use p256::{
+            ecdsa::{Signature, SigningKey, signature::hazmat::PrehashSigner},
+            pkcs8::EncodePublicKey,
+        };
+
+        let key = SigningKey::from_slice(&[0x42; 32]).unwrap();
+        let public_key = key.verifying_key().to_public_key_der().unwrap();
+        let image = build_signed_image(&[], 0x22, |_, hash| {
+            let signature: Signature = key.sign_prehash(hash).unwrap();
+            signature.to_der().as_bytes().to_vec()
+        });
+
+        assert_eq!(
+            verify_signature(io::Cursor::new(&image), public_key.as_bytes()).unwrap(),
+            ImageSignatureAlgorithm::Ecdsa
+        );
+
+        let other_key = SigningKey::from_slice(&[0x43; 32]).unwrap();
+        let other_public_key = other_key.verifying_key().to_public_key_der().unwrap();
+        assert!(matches!(
+            verify_signature(io::Cursor::new(&image), other_public_key.as_bytes()),
+            Err(ImageVerifyError::SignatureInvalid)
+        ));
+
+        let mut corrupted = image.clone();
+        corrupted[40] ^= 1;
+        assert!(matches!(
+            verify_signature(io::Cursor::new(&corrupted), public_key.as_bytes()),
+            Err(ImageVerifyError::HashMismatch)
+        ));
+
+        assert!(matches!(
+            verify_signature(io::Cursor::new(&image), &[0x11; 32]),
+            Err(ImageVerifyError::KeyTypeMismatch)
+        ));
+    }
+
+    #[test]
+    fn ed25519() {
+        use ed25519_dalek::{Signer, SigningKey, pkcs8::EncodePublicKey};
+
+        let key = SigningKey::from_bytes(&[0x42; 32]);
+        let public_key_pem = key
+            .verifying_key()
+            .to_public_key_pem(Default::default())
+            .unwrap();
+
+        let image = build_signed_image(&[], 0x24, |_, hash| key.sign(hash).to_vec());
+        assert_eq!(
+            verify_signature(io::Cursor::new(&image), public_key_pem.as_bytes()).unwrap(),
+            ImageSignatureAlgorithm::Ed25519
+        );
+
+        let pure_image = build_signed_image(&[(IMAGE_TLV_SIG_PURE, &[])], 0x24, |data, _| {
+            key.sign(data).to_vec()
+        });
+        assert_eq!(
+            verify_signature(io::Cursor::new(&pure_image), key.verifying_key().as_bytes()).unwrap(),
+            ImageSignatureAlgorithm::Ed25519
+        );
+    }
+
+    /// Verifies the given signature, created with `openssl pkeyutl -sign` in PSS mode
+    /// with a salt length of 32 bytes, like `imgtool` does.
+    fn verify_rsa_fixture(
+        signature_type: u16,
+        algorithm: ImageSignatureAlgorithm,
+        signature: &[u8],
+        public_key: &[u8],
+    ) {
+        let image = build_signed_image(&[], signature_type, |_, _| signature.to_vec());
+        assert_eq!(
+            verify_signature(io::Cursor::new(&image), public_key).unwrap(),
+            algorithm
+        );
+
+        let mut forged_signature = signature.to_vec();
+        forged_signature[10] ^= 1;
+        let forged = build_signed_image(&[], signature_type, |_, _| forged_signature);
+        assert!(matches!(
+            verify_signature(io::Cursor::new(&forged), public_key),
+            Err(ImageVerifyError::SignatureInvalid)
+        ));
+    }
+
+    #[test]
+    fn rsa2048_pss() {
+        verify_rsa_fixture(
+            0x20,
+            ImageSignatureAlgorithm::Rsa2048Pss,
+            include_bytes!("testdata/rsa2048.sig"),
+            include_bytes!("testdata/rsa2048.pub.der"),
+        );
+    }
+
+    #[test]
+    fn rsa3072_pss() {
+        verify_rsa_fixture(
+            0x23,
+            ImageSignatureAlgorithm::Rsa3072Pss,
+            include_bytes!("testdata/rsa3072.sig"),
+            include_bytes!("testdata/rsa3072.pub.pem"),
+        );
+    }
+
+    #[test]
+    fn rsa_key_mismatch() {
+        let image = build_signed_image(&[], 0x23, |_, _| {
+            include_bytes!("testdata/rsa3072.sig").to_vec()
+        });
+        assert!(matches!(
+            verify_signature(
+                io::Cursor::new(&image),
+                include_bytes!("testdata/rsa2048.pub.der")
+            ),
+            Err(ImageVerifyError::SignatureInvalid)
+        ));
+    }
+
+    #[test]
+    fn ecdsa224_is_unsupported() {
+        let image = build_signed_image(&[], 0x21, |_, _| vec![0; 64]);
+        assert!(matches!(
+            verify_signature(io::Cursor::new(&image), &[0x11; 32]),
+            Err(ImageVerifyError::UnsupportedAlgorithm(
+                ImageSignatureAlgorithm::Ecdsa224
+            ))
+        ));
+    }
+
+    #[test]
+    fn unsigned_image() {
+        let image = build_signed_image(&[], 0x01, |_, _| vec![0; 32]);
+        assert!(matches!(
+            verify_signature(io::Cursor::new(&image), &[0x11; 32]),
+            Err(ImageVerifyError::SignatureMissing)
+        ));
+    }
+}
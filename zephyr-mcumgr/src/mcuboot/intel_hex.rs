@@ -0,0 +1,127 @@
+use std::io;
+
+/// Possible error values of [`from_ihex`].
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+pub enum IhexError {
+    /// Failed to read the HEX file
+    #[error("HEX file read failed")]
+    #[diagnostic(code(zephyr_mcumgr::mcuboot::ihex::read))]
+    ReadFailed(#[from] io::Error),
+    /// The HEX file contains an invalid record.
+    #[error("Invalid HEX record")]
+    #[diagnostic(code(zephyr_mcumgr::mcuboot::ihex::parse))]
+    ParseFailed(#[from] ihex::ReaderError),
+    /// The HEX file does not contain any data.
+    #[error("HEX file does not contain any data")]
+    #[diagnostic(code(zephyr_mcumgr::mcuboot::ihex::empty))]
+    Empty,
+    /// The data of the HEX file is not one contiguous region.
+    #[error(
+        "HEX file data is not contiguous, expected address 0x{expected:08x}, found 0x{found:08x}"
+    )]
+    #[diagnostic(
+        code(zephyr_mcumgr::mcuboot::ihex::not_contiguous),
+        help("The HEX file must contain a single image, without gaps between its records.")
+    )]
+    NotContiguous {
+        /// The address following the previous data record
+        expected: u32,
+        /// The address of the data record
+        found: u32,
+    },
+}
+
+/// Checks whether the given data looks like an Intel HEX file.
+///
+/// MCUboot images start with a binary magic number, so this cannot produce
+/// false positives for them.
+pub fn is_ihex(data: &[u8]) -> bool {
+    data.trim_ascii_start().first() == Some(&b':')
+}
+
+/// Converts an Intel HEX file to the contiguous binary it describes.
+///
+/// The data records have to form a single contiguous region, in ascending order.
+/// The load address of the region is discarded.
+pub fn from_ihex(mut reader: impl io::Read) -> Result<Vec<u8>, IhexError> {
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+
+    let mut base_address = 0u32;
+    let mut next_address = None;
+    let mut data = vec![];
+
+    for record in ihex::Reader::new(&content) {
+        match record? {
+            ihex::Record::Data { offset, value } => {
+                let address = base_address.wrapping_add(offset.into());
+                if let Some(expected) = next_address {
+                    if address != expected {
+                        return Err(IhexError::NotContiguous {
+                            expected,
+                            found: address,
+                        });
+                    }
+                }
+                data.extend_from_slice(&value);
+                next_address = Some(address.wrapping_add(value.len() as u32));
+            }
+            ihex::Record::ExtendedSegmentAddress(segment) => {
+                base_address = u32::from(segment) << 4;
+            }
+            ihex::Record::ExtendedLinearAddress(upper) => {
+                base_address = u32::from(upper) << 16;
+            }
+            ihex::Record::EndOfFile => break,
+            ihex::Record::StartSegmentAddress { .. } | ihex::Record::StartLinearAddress(_) => {}
+        }
+    }
+
+    if data.is_empty() {
+        return Err(IhexError::Empty);
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contiguous() {
+        let hex = ":020000040001F9\n\
+                   :04FFFC0001020304F7\n\
+                   :020000040002F8\n\
+                   :020000000506F3\n\
+                   :0400000508000000EF\n\
+                   :00000001FF\n";
+
+        assert!(is_ihex(hex.as_bytes()));
+        assert_eq!(from_ihex(hex.as_bytes()).unwrap(), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn gap() {
+        let hex = ":020000000102FB\n\
+                   :020004000304F3\n\
+                   :00000001FF\n";
+
+        assert!(matches!(
+            from_ihex(hex.as_bytes()),
+            Err(IhexError::NotContiguous {
+                expected: 2,
+                found: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn empty() {
+        assert!(matches!(
+            from_ihex(":00000001FF\n".as_bytes()),
+            Err(IhexError::Empty)
+        ));
+        assert!(!is_ihex(&[0x3d, 0xb8, 0xf3, 0x96]));
+    }
+}
@@ -1,4 +1,20 @@
 /// MCUboot image parser
 mod image;
+/// Intel HEX conversion
+mod intel_hex;
+/// MCUboot slot trailer parser
+mod trailer;
 
-pub use image::{ImageInfo, ImageParseError, ImageVersion, get_image_info};
+#[cfg(test)]
+pub(crate) use image::tests::build_image;
+#[cfg(feature = "verify")]
+pub use image::verify_signature;
+pub use image::{
+    ImageDependency, ImageHashAlgorithm, ImageInfo, ImageParseError, ImageSignatureAlgorithm,
+    ImageTlv, ImageTlvValue, ImageVerifyError, ImageVersion, ImageVersionParseError,
+    get_image_info, get_image_tlvs,
+};
+pub use intel_hex::{IhexError, from_ihex, is_ihex};
+pub use trailer::{
+    BootMagic, SlotTrailer, SwapInfo, SwapType, TrailerFlag, TrailerParseError, parse_trailer,
+};
@@ -0,0 +1,194 @@
+/// The magic number at the very end of a slot that contains a valid trailer
+const BOOT_MAGIC: [u8; 16] = [
+    0x77, 0xc2, 0x95, 0xf3, 0x60, 0xd2, 0xef, 0x7f, 0x35, 0x52, 0x50, 0x0f, 0x2c, 0xb6, 0x79, 0x80,
+];
+const BOOT_MAX_ALIGN: usize = 8;
+const BOOT_FLAG_SET: u8 = 0x01;
+const ERASED_VAL: u8 = 0xff;
+
+/// The size of the part of the trailer that [`parse_trailer`] decodes
+const TRAILER_SIZE: usize = BOOT_MAGIC.len() + 4 * BOOT_MAX_ALIGN;
+
+/// The given slot data is too short to contain an MCUboot trailer.
+#[derive(thiserror::Error, Debug, miette::Diagnostic)]
+#[error(
+    "Slot data is too short to contain an MCUboot trailer, expected at least {TRAILER_SIZE} bytes"
+)]
+#[diagnostic(
+    code(zephyr_mcumgr::mcuboot::trailer::too_short),
+    help("Provide the raw flash content of the whole slot, including its end.")
+)]
+pub struct TrailerParseError;
+
+/// The state of the boot magic of a slot trailer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BootMagic {
+    /// The trailer contains the MCUboot magic number
+    Good,
+    /// The magic number area is erased
+    Unset,
+    /// The magic number area contains unexpected data
+    Bad,
+}
+
+/// The state of a single byte flag of a slot trailer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TrailerFlag {
+    /// The flag is set
+    Set,
+    /// The flag is erased
+    Unset,
+    /// The flag contains an unexpected value
+    Bad,
+}
+
+/// The type of swap operation MCUboot performs or performed.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SwapType {
+    /// No swap
+    None,
+    /// Swap to the secondary image, revert on the next boot
+    Test,
+    /// Swap to the secondary image permanently
+    Permanent,
+    /// Swap back to the previous image
+    Revert,
+    /// The swap failed because the image was invalid
+    Fail,
+    /// An unknown swap type value
+    Unknown(u8),
+}
+
+/// The swap information of a slot trailer.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SwapInfo {
+    /// The swap operation
+    pub swap_type: SwapType,
+    /// The image index the swap operation belongs to
+    pub image: u8,
+}
+
+/// The decoded trailer of an MCUboot slot.
+///
+/// Only the fields at fixed offsets from the end of the slot get decoded;
+/// the swap status area in front of them depends on the device configuration.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SlotTrailer {
+    /// The boot magic, marks that the trailer is valid
+    pub magic: BootMagic,
+    /// The pending or ongoing swap operation, `None` if erased
+    pub swap_info: Option<SwapInfo>,
+    /// Whether the swap operation was completed
+    pub copy_done: TrailerFlag,
+    /// Whether the image in this slot was confirmed
+    pub image_ok: TrailerFlag,
+    /// The size of the swapped area in bytes, `None` if erased
+    pub swap_size: Option<u32>,
+}
+
+impl From<u8> for TrailerFlag {
+    fn from(value: u8) -> Self {
+        match value {
+            BOOT_FLAG_SET => TrailerFlag::Set,
+            ERASED_VAL => TrailerFlag::Unset,
+            _ => TrailerFlag::Bad,
+        }
+    }
+}
+
+impl From<u8> for SwapType {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => SwapType::None,
+            2 => SwapType::Test,
+            3 => SwapType::Permanent,
+            4 => SwapType::Revert,
+            5 => SwapType::Fail,
+            other => SwapType::Unknown(other),
+        }
+    }
+}
+
+/// Decode the MCUboot trailer at the end of a raw slot dump.
+///
+/// `data` has to end with the last byte of the slot. Assumes MCUboot's
+/// default `BOOT_MAX_ALIGN` of 8 bytes and erased flash reading as `0xff`.
+pub fn parse_trailer(data: &[u8]) -> Result<SlotTrailer, TrailerParseError> {
+    let trailer = data.last_chunk::<TRAILER_SIZE>().ok_or(TrailerParseError)?;
+
+    // Every field is padded to BOOT_MAX_ALIGN, in front of the 16 byte magic
+    let field = |index: usize| &trailer[index * BOOT_MAX_ALIGN..];
+
+    let swap_size = u32::from_le_bytes([field(0)[0], field(0)[1], field(0)[2], field(0)[3]]);
+    let swap_info = field(1)[0];
+    let copy_done = field(2)[0];
+    let image_ok = field(3)[0];
+    let magic = &trailer[4 * BOOT_MAX_ALIGN..];
+
+    Ok(SlotTrailer {
+        magic: if magic == BOOT_MAGIC {
+            BootMagic::Good
+        } else if magic.iter().all(|&b| b == ERASED_VAL) {
+            BootMagic::Unset
+        } else {
+            BootMagic::Bad
+        },
+        swap_info: (swap_info != ERASED_VAL).then(|| SwapInfo {
+            swap_type: SwapType::from(swap_info & 0x0f),
+            image: swap_info >> 4,
+        }),
+        copy_done: copy_done.into(),
+        image_ok: image_ok.into(),
+        swap_size: (swap_size != u32::MAX).then_some(swap_size),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn erased() {
+        let trailer = parse_trailer(&[ERASED_VAL; 128]).unwrap();
+        assert_eq!(
+            trailer,
+            SlotTrailer {
+                magic: BootMagic::Unset,
+                swap_info: None,
+                copy_done: TrailerFlag::Unset,
+                image_ok: TrailerFlag::Unset,
+                swap_size: None,
+            }
+        );
+    }
+
+    #[test]
+    fn pending_test_swap() {
+        let mut slot = vec![ERASED_VAL; 128];
+        let trailer_start = slot.len() - TRAILER_SIZE;
+        slot[trailer_start..][..4].copy_from_slice(&0x1234u32.to_le_bytes());
+        slot[trailer_start + 8] = 0x12;
+        slot[trailer_start + 24] = 0x00;
+        slot[trailer_start + 32..].copy_from_slice(&BOOT_MAGIC);
+
+        let trailer = parse_trailer(&slot).unwrap();
+        assert_eq!(
+            trailer,
+            SlotTrailer {
+                magic: BootMagic::Good,
+                swap_info: Some(SwapInfo {
+                    swap_type: SwapType::Test,
+                    image: 1,
+                }),
+                copy_done: TrailerFlag::Unset,
+                image_ok: TrailerFlag::Bad,
+                swap_size: Some(0x1234),
+            }
+        );
+    }
+
+    #[test]
+    fn too_short() {
+        assert!(parse_trailer(&[ERASED_VAL; TRAILER_SIZE - 1]).is_err());
+    }
+}
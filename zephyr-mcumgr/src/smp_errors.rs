@@ -1,4 +1,4 @@
-use strum::{Display, FromRepr};
+use strum::{Display, EnumMessage, FromRepr};
 
 use crate::MCUmgrGroup;
 
@@ -8,13 +8,17 @@ use crate::MCUmgrGroup;
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum DeviceError {
     /// MCUmgr SMP v1 error codes
+    #[non_exhaustive]
     V1 {
+        /// Group id of the failed command
+        group: u16,
         /// Error code
         rc: i32,
         /// Optional string that clarifies reason for an error
         rsn: Option<String>,
     },
     /// MCUmgr SMP v2 error codes
+    #[non_exhaustive]
     V2 {
         /// Group id
         group: u16,
@@ -23,44 +27,290 @@ pub enum DeviceError {
     },
 }
 
-fn v2_err_to_string(group: u16, rc: i32) -> Option<String> {
-    match MCUmgrGroup::from_repr(group)? {
-        MCUmgrGroup::MGMT_GROUP_ID_ENUM => EnumMgmtErrCode::from_repr(rc).map(|x| x.to_string()),
-        MCUmgrGroup::MGMT_GROUP_ID_FS => FsMgmtErrCode::from_repr(rc).map(|x| x.to_string()),
-        MCUmgrGroup::MGMT_GROUP_ID_IMAGE => ImgMgmtErrCode::from_repr(rc).map(|x| x.to_string()),
-        MCUmgrGroup::MGMT_GROUP_ID_OS => OsMgmtErrCode::from_repr(rc).map(|x| x.to_string()),
-        MCUmgrGroup::MGMT_GROUP_ID_SETTINGS => {
-            SettingsMgmtRetCode::from_repr(rc).map(|x| x.to_string())
+/// A decoded, group specific SMP v2 error code.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GroupError {
+    /// Error of the OS management group
+    Os(OsMgmtErrCode),
+    /// Error of the image management group
+    Image(ImgMgmtErrCode),
+    /// Error of the statistics management group
+    Stat(StatMgmtErrCode),
+    /// Error of the settings management group
+    Settings(SettingsMgmtRetCode),
+    /// Error of the file management group
+    Fs(FsMgmtErrCode),
+    /// Error of the shell management group
+    Shell(ShellMgmtErrCode),
+    /// Error of the enumeration management group
+    Enum(EnumMgmtErrCode),
+    /// Error of the Zephyr basic group
+    ZephyrBasic(ZephyrBasicGroupErrCode),
+}
+
+impl GroupError {
+    /// Decodes a group specific error code.
+    ///
+    /// Returns `None` if the group or the error code is unknown.
+    pub fn from_raw(group: u16, rc: i32) -> Option<Self> {
+        Some(match MCUmgrGroup::from_repr(group)? {
+            MCUmgrGroup::MGMT_GROUP_ID_ENUM => Self::Enum(EnumMgmtErrCode::from_repr(rc)?),
+            MCUmgrGroup::MGMT_GROUP_ID_FS => Self::Fs(FsMgmtErrCode::from_repr(rc)?),
+            MCUmgrGroup::MGMT_GROUP_ID_IMAGE => Self::Image(ImgMgmtErrCode::from_repr(rc)?),
+            MCUmgrGroup::MGMT_GROUP_ID_OS => Self::Os(OsMgmtErrCode::from_repr(rc)?),
+            MCUmgrGroup::MGMT_GROUP_ID_SETTINGS => {
+                Self::Settings(SettingsMgmtRetCode::from_repr(rc)?)
+            }
+            MCUmgrGroup::MGMT_GROUP_ID_SHELL => Self::Shell(ShellMgmtErrCode::from_repr(rc)?),
+            MCUmgrGroup::MGMT_GROUP_ID_STAT => Self::Stat(StatMgmtErrCode::from_repr(rc)?),
+            MCUmgrGroup::ZEPHYR_MGMT_GRP_BASIC => {
+                Self::ZephyrBasic(ZephyrBasicGroupErrCode::from_repr(rc)?)
+            }
+            _ => return None,
+        })
+    }
+
+    /// The documentation of the error code, as found in Zephyr's source code.
+    fn documentation(&self) -> Option<&'static str> {
+        match self {
+            Self::Os(e) => e.get_documentation(),
+            Self::Image(e) => e.get_documentation(),
+            Self::Stat(e) => e.get_documentation(),
+            Self::Settings(e) => e.get_documentation(),
+            Self::Fs(e) => e.get_documentation(),
+            Self::Shell(e) => e.get_documentation(),
+            Self::Enum(e) => e.get_documentation(),
+            Self::ZephyrBasic(e) => e.get_documentation(),
         }
-        MCUmgrGroup::MGMT_GROUP_ID_SHELL => ShellMgmtErrCode::from_repr(rc).map(|x| x.to_string()),
-        MCUmgrGroup::MGMT_GROUP_ID_STAT => StatMgmtErrCode::from_repr(rc).map(|x| x.to_string()),
-        MCUmgrGroup::ZEPHYR_MGMT_GRP_BASIC => {
-            ZephyrBasicGroupErrCode::from_repr(rc).map(|x| x.to_string())
+    }
+}
+
+impl std::fmt::Display for GroupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Os(e) => e.fmt(f),
+            Self::Image(e) => e.fmt(f),
+            Self::Stat(e) => e.fmt(f),
+            Self::Settings(e) => e.fmt(f),
+            Self::Fs(e) => e.fmt(f),
+            Self::Shell(e) => e.fmt(f),
+            Self::Enum(e) => e.fmt(f),
+            Self::ZephyrBasic(e) => e.fmt(f),
+        }
+    }
+}
+
+/// Condenses a (possibly multi-line) doc comment into its first sentence.
+fn summarize_documentation(doc: &str) -> String {
+    let text = doc
+        .lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let sentence = match text.find(". ") {
+        Some(pos) => &text[..pos],
+        None => &text,
+    };
+
+    sentence.trim_end_matches('.').to_string()
+}
+
+impl DeviceError {
+    /// The group id of the failed command.
+    pub fn group(&self) -> u16 {
+        match *self {
+            DeviceError::V1 { group, .. } | DeviceError::V2 { group, .. } => group,
+        }
+    }
+
+    /// A human readable name of the group of the failed command,
+    /// e.g. `image management group` or `group 64`.
+    pub fn group_name(&self) -> String {
+        let group = self.group();
+        match MCUmgrGroup::from_repr(group) {
+            Some(MCUmgrGroup::MGMT_GROUP_ID_PERUSER) | None => format!("group {group}"),
+            Some(known_group) => format!("{} group", known_group.name()),
+        }
+    }
+
+    /// The typed error code, if this is a known group specific SMP v2 error.
+    pub fn group_error(&self) -> Option<GroupError> {
+        match *self {
+            DeviceError::V1 { .. } => None,
+            DeviceError::V2 { group, rc } => GroupError::from_raw(group, rc),
         }
-        _ => None,
+    }
+
+    /// Renders a human readable description of the error.
+    ///
+    /// Consists of the symbolic name of the error code, e.g. `IMG_MGMT_ERR_INVALID_IMAGE_TOO_LARGE`,
+    /// followed by a short explanation if the error code is known.
+    pub fn describe(&self) -> String {
+        let (name, documentation) = match self {
+            DeviceError::V1 { rc, .. } => (
+                MCUmgrErr::err_to_string(*rc),
+                MCUmgrErr::from_repr(*rc).and_then(|e| e.get_documentation()),
+            ),
+            DeviceError::V2 { group, rc } => match self.group_error() {
+                Some(e) => (e.to_string(), e.documentation()),
+                None => (format!("group={group},rc={rc}"), None),
+            },
+        };
+
+        let mut description = match documentation {
+            Some(doc) => format!("{name} ({})", summarize_documentation(doc)),
+            None => name,
+        };
+
+        if let DeviceError::V1 { rsn: Some(rsn), .. } = self {
+            description.push_str(": ");
+            description.push_str(rsn);
+        }
+
+        description
     }
 }
 
 impl std::fmt::Display for DeviceError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            DeviceError::V1 { rc, rsn } => {
+            DeviceError::V1 { rc, rsn, .. } => {
                 if let Some(rsn) = rsn {
                     write!(f, "{}: {}", MCUmgrErr::err_to_string(*rc), rsn)
                 } else {
                     write!(f, "{}", MCUmgrErr::err_to_string(*rc))
                 }
             }
-            DeviceError::V2 { group, rc } => match v2_err_to_string(*group, *rc) {
-                Some(msg) => f.write_str(&msg),
+            DeviceError::V2 { group, rc } => match self.group_error() {
+                Some(e) => e.fmt(f),
                 None => write!(f, "group={group},rc={rc}"),
             },
         }
     }
 }
 
+impl std::error::Error for DeviceError {}
+
+impl miette::Diagnostic for DeviceError {
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        if let Some(option) = unsupported_group_option(self) {
+            return Some(Box::new(format!(
+                "The device does not support this command. Enable `{option}` on the device, and the Kconfig option of the command itself if it has one."
+            )));
+        }
+        device_error_help(self).map(|help| Box::new(help) as Box<dyn std::fmt::Display>)
+    }
+}
+
+/// The Kconfig option that enables the given group on the device.
+fn group_kconfig_option(group: u16) -> Option<&'static str> {
+    Some(match MCUmgrGroup::from_repr(group)? {
+        MCUmgrGroup::MGMT_GROUP_ID_OS => "CONFIG_MCUMGR_GRP_OS",
+        MCUmgrGroup::MGMT_GROUP_ID_IMAGE => "CONFIG_MCUMGR_GRP_IMG",
+        MCUmgrGroup::MGMT_GROUP_ID_STAT => "CONFIG_MCUMGR_GRP_STAT",
+        MCUmgrGroup::MGMT_GROUP_ID_SETTINGS => "CONFIG_MCUMGR_GRP_SETTINGS",
+        MCUmgrGroup::MGMT_GROUP_ID_FS => "CONFIG_MCUMGR_GRP_FS",
+        MCUmgrGroup::MGMT_GROUP_ID_SHELL => "CONFIG_MCUMGR_GRP_SHELL",
+        MCUmgrGroup::MGMT_GROUP_ID_ENUM => "CONFIG_MCUMGR_GRP_ENUM",
+        MCUmgrGroup::ZEPHYR_MGMT_GRP_BASIC => "CONFIG_MCUMGR_GRP_ZBASIC",
+        _ => return None,
+    })
+}
+
+/// The Kconfig option of the group, if the device reported that it does not support a command.
+fn unsupported_group_option(err: &DeviceError) -> Option<&'static str> {
+    match *err {
+        DeviceError::V1 { group, rc, .. } if rc == MCUmgrErr::MGMT_ERR_ENOTSUP as i32 => {
+            group_kconfig_option(group)
+        }
+        _ => None,
+    }
+}
+
+/// Actionable hints for the most common device errors.
+fn device_error_help(err: &DeviceError) -> Option<&'static str> {
+    match *err {
+        DeviceError::V1 { rc, .. } => match MCUmgrErr::from_repr(rc)? {
+            MCUmgrErr::MGMT_ERR_ENOTSUP => Some(
+                "The device does not support this command. Make sure the corresponding MCUmgr group is enabled, e.g. `CONFIG_MCUMGR_GRP_OS`, `CONFIG_MCUMGR_GRP_IMG`, `CONFIG_MCUMGR_GRP_FS` or `CONFIG_MCUMGR_GRP_SHELL`.",
+            ),
+            MCUmgrErr::MGMT_ERR_ENOMEM | MCUmgrErr::MGMT_ERR_EMSGSIZE => Some(
+                "The response does not fit into the device's SMP buffer. Increase `CONFIG_MCUMGR_TRANSPORT_NETBUF_SIZE`.",
+            ),
+            MCUmgrErr::MGMT_ERR_EBUSY => {
+                Some("The device is still busy processing another command. Retry later.")
+            }
+            MCUmgrErr::MGMT_ERR_EACCESSDENIED => Some(
+                "The command was rejected by the application, most likely through an MCUmgr command hook (`CONFIG_MCUMGR_MGMT_NOTIFICATION_HOOKS`).",
+            ),
+            MCUmgrErr::MGMT_ERR_UNSUPPORTED_TOO_OLD | MCUmgrErr::MGMT_ERR_UNSUPPORTED_TOO_NEW => {
+                Some("The device does not support the SMP protocol version used by this client.")
+            }
+            _ => None,
+        },
+        DeviceError::V2 { group, rc } => match MCUmgrGroup::from_repr(group)? {
+            MCUmgrGroup::MGMT_GROUP_ID_FS => match FsMgmtErrCode::from_repr(rc)? {
+                FsMgmtErrCode::FS_MGMT_ERR_FILE_INVALID_NAME
+                | FsMgmtErrCode::FS_MGMT_ERR_FILE_NOT_FOUND => Some(
+                    "File paths must be absolute and include the mount point, e.g. `/lfs/file.txt`.",
+                ),
+                FsMgmtErrCode::FS_MGMT_ERR_MOUNT_POINT_NOT_FOUND => Some(
+                    "Make sure the file system is mounted, e.g. through an automounted `zephyr,fstab` devicetree entry.",
+                ),
+                FsMgmtErrCode::FS_MGMT_ERR_READ_ONLY_FILESYSTEM => {
+                    Some("The file system is mounted read-only.")
+                }
+                FsMgmtErrCode::FS_MGMT_ERR_CHECKSUM_HASH_NOT_FOUND => Some(
+                    "Enable the algorithm on the device, e.g. `CONFIG_MCUMGR_GRP_FS_CHECKSUM_IEEE_CRC32` or `CONFIG_MCUMGR_GRP_FS_HASH_SHA256`.",
+                ),
+                FsMgmtErrCode::FS_MGMT_ERR_FILE_OFFSET_NOT_VALID => Some(
+                    "The file on the device changed since the transfer started. Restart the transfer.",
+                ),
+                _ => None,
+            },
+            MCUmgrGroup::MGMT_GROUP_ID_IMAGE => match ImgMgmtErrCode::from_repr(rc)? {
+                ImgMgmtErrCode::IMG_MGMT_ERR_NO_FREE_SLOT => Some(
+                    "There is no slot the image could be uploaded to. Confirm the running image or erase the secondary slot first.",
+                ),
+                ImgMgmtErrCode::IMG_MGMT_ERR_IMAGE_ALREADY_PENDING => Some(
+                    "Another image is already pending for the next boot. Reboot the device or erase the secondary slot first.",
+                ),
+                ImgMgmtErrCode::IMG_MGMT_ERR_CURRENT_VERSION_IS_NEWER => Some(
+                    "The upload was restricted to upgrades, but the image is not newer than the running firmware.",
+                ),
+                ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_IMAGE_HEADER
+                | ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_IMAGE_HEADER_MAGIC => Some(
+                    "The data is not an MCUboot image. Upload the signed image, e.g. `zephyr.signed.bin`, instead of `zephyr.bin`.",
+                ),
+                ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_IMAGE_TOO_LARGE => Some(
+                    "The image is larger than the target slot. Check the flash partition layout.",
+                ),
+                ImgMgmtErrCode::IMG_MGMT_ERR_IMAGE_SETTING_TEST_TO_ACTIVE_DENIED => Some(
+                    "The image is already running. Confirm it instead of marking it for a test boot.",
+                ),
+                _ => None,
+            },
+            MCUmgrGroup::MGMT_GROUP_ID_OS => match OsMgmtErrCode::from_repr(rc)? {
+                OsMgmtErrCode::OS_MGMT_ERR_RTC_NOT_SET => {
+                    Some("The device's RTC has not been set yet. Set the datetime first.")
+                }
+                _ => None,
+            },
+            MCUmgrGroup::MGMT_GROUP_ID_SHELL => match ShellMgmtErrCode::from_repr(rc)? {
+                ShellMgmtErrCode::SHELL_MGMT_ERR_COMMAND_TOO_LONG => {
+                    Some("Increase `CONFIG_SHELL_CMD_BUFF_SIZE` on the device.")
+                }
+                _ => None,
+            },
+            _ => None,
+        },
+    }
+}
+
 /// See [`enum mcumgr_err_t`](https://docs.zephyrproject.org/latest/doxygen/html/mgmt__defines_8h.html).
-#[derive(FromRepr, Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(FromRepr, Display, EnumMessage, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 pub enum MCUmgrErr {
@@ -126,7 +376,7 @@ impl MCUmgrErr {
 }
 
 /// See `enum settings_mgmt_ret_code_t`.
-#[derive(FromRepr, Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(FromRepr, Display, EnumMessage, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 pub enum SettingsMgmtRetCode {
@@ -159,7 +409,7 @@ pub enum SettingsMgmtRetCode {
 }
 
 /// See `enum fs_mgmt_err_code_t`.
-#[derive(FromRepr, Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(FromRepr, Display, EnumMessage, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 pub enum FsMgmtErrCode {
@@ -222,7 +472,7 @@ pub enum FsMgmtErrCode {
 }
 
 /// See `enum img_mgmt_err_code_t`.
-#[derive(FromRepr, Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(FromRepr, Display, EnumMessage, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 pub enum ImgMgmtErrCode {
@@ -316,7 +566,7 @@ pub enum ImgMgmtErrCode {
     /** The image vector table is invalid. */
     IMG_MGMT_ERR_INVALID_IMAGE_VECTOR_TABLE,
 
-    /** The image it too large to fit. */
+    /** The image is too large to fit. */
     IMG_MGMT_ERR_INVALID_IMAGE_TOO_LARGE,
 
     /** The amount of data sent is larger than the provided image size. */
@@ -333,7 +583,7 @@ pub enum ImgMgmtErrCode {
 }
 
 /// See `enum os_mgmt_err_code_t`.
-#[derive(FromRepr, Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(FromRepr, Display, EnumMessage, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 pub enum OsMgmtErrCode {
@@ -360,7 +610,7 @@ pub enum OsMgmtErrCode {
 }
 
 /// See `enum shell_mgmt_err_code_t`.
-#[derive(FromRepr, Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(FromRepr, Display, EnumMessage, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 pub enum ShellMgmtErrCode {
@@ -378,7 +628,7 @@ pub enum ShellMgmtErrCode {
 }
 
 /// See `enum stat_mgmt_err_code_t`.
-#[derive(FromRepr, Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(FromRepr, Display, EnumMessage, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 pub enum StatMgmtErrCode {
@@ -402,7 +652,7 @@ pub enum StatMgmtErrCode {
 }
 
 /// See `enum enum_mgmt_err_code_t`.
-#[derive(FromRepr, Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(FromRepr, Display, EnumMessage, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 pub enum EnumMgmtErrCode {
@@ -423,7 +673,7 @@ pub enum EnumMgmtErrCode {
 }
 
 /// See `enum zephyr_basic_group_err_code_t`.
-#[derive(FromRepr, Display, Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(FromRepr, Display, EnumMessage, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(i32)]
 #[allow(non_camel_case_types)]
 pub enum ZephyrBasicGroupErrCode {
@@ -442,3 +692,110 @@ pub enum ZephyrBasicGroupErrCode {
     /** Erasing the flash area has failed. */
     ZEPHYRBASIC_MGMT_ERR_FLASH_ERASE_FAILED,
 }
+
+#[cfg(test)]
+mod tests {
+    use miette::Diagnostic;
+
+    use super::*;
+    use crate::connection::ExecuteError;
+
+    #[test]
+    fn test_help_known_error() {
+        let err = DeviceError::V2 {
+            group: MCUmgrGroup::MGMT_GROUP_ID_FS as u16,
+            rc: FsMgmtErrCode::FS_MGMT_ERR_CHECKSUM_HASH_NOT_FOUND as i32,
+        };
+        let help = err.help().unwrap().to_string();
+        assert!(help.contains("CONFIG_MCUMGR_GRP_FS_HASH_SHA256"));
+    }
+
+    #[test]
+    fn test_help_unknown_error() {
+        assert!(DeviceError::V2 { group: 42, rc: 1 }.help().is_none());
+        assert!(
+            DeviceError::V1 {
+                group: 0,
+                rc: 1,
+                rsn: None
+            }
+            .help()
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let err = DeviceError::V2 {
+            group: MCUmgrGroup::MGMT_GROUP_ID_IMAGE as u16,
+            rc: ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_IMAGE_TOO_LARGE as i32,
+        };
+        assert_eq!(
+            err.group_error(),
+            Some(GroupError::Image(
+                ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_IMAGE_TOO_LARGE
+            ))
+        );
+        assert_eq!(
+            err.describe(),
+            "IMG_MGMT_ERR_INVALID_IMAGE_TOO_LARGE (The image is too large to fit)"
+        );
+
+        let err = DeviceError::V2 {
+            group: MCUmgrGroup::MGMT_GROUP_ID_FS as u16,
+            rc: FsMgmtErrCode::FS_MGMT_ERR_FILE_OFFSET_NOT_VALID as i32,
+        };
+        assert_eq!(
+            err.describe(),
+            "FS_MGMT_ERR_FILE_OFFSET_NOT_VALID (The specified data offset is not valid, this could indicate that the file on the device has changed since the previous command)"
+        );
+
+        let err = DeviceError::V1 {
+            group: 0,
+            rc: MCUmgrErr::MGMT_ERR_EINVAL as i32,
+            rsn: Some("bad offset".to_string()),
+        };
+        assert_eq!(err.group_error(), None);
+        assert_eq!(
+            err.describe(),
+            "MGMT_ERR_EINVAL (Error in input value): bad offset"
+        );
+        assert_eq!(err.to_string(), "MGMT_ERR_EINVAL: bad offset");
+
+        assert_eq!(
+            DeviceError::V2 { group: 42, rc: 1 }.describe(),
+            "group=42,rc=1"
+        );
+        assert_eq!(
+            DeviceError::V2 { group: 42, rc: 1 }.group_name(),
+            "group 42"
+        );
+        assert_eq!(
+            DeviceError::V2 { group: 65, rc: 1 }.group_name(),
+            "group 65"
+        );
+    }
+
+    #[test]
+    fn test_help_forwarded() {
+        let err = ExecuteError::ErrorResponse(DeviceError::V1 {
+            group: MCUmgrGroup::MGMT_GROUP_ID_IMAGE as u16,
+            rc: MCUmgrErr::MGMT_ERR_ENOTSUP as i32,
+            rsn: None,
+        });
+        assert!(
+            err.help()
+                .unwrap()
+                .to_string()
+                .contains("Enable `CONFIG_MCUMGR_GRP_IMG`")
+        );
+        assert_eq!(
+            err.to_string(),
+            "Device returned error code in image management group: MGMT_ERR_ENOTSUP (Command not supported)"
+        );
+        assert_eq!(
+            err.code().unwrap().to_string(),
+            "zephyr_mcumgr::connection::execute::device_error"
+        );
+    }
+}
@@ -0,0 +1,1304 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use ciborium::{Value, value::Integer};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use zephyr_mcumgr_protocol::header::smp_op;
+
+use crate::{
+    MCUmgrGroup, checksum,
+    commands::fs::FileChecksumData,
+    mcuboot,
+    smp_errors::{FsMgmtErrCode, ImgMgmtErrCode, MCUmgrErr},
+    transport::{
+        ReceiveError, SMP_HEADER_SIZE, SMP_TRANSFER_BUFFER_SIZE, SendError, SmpHeader, SmpVersion,
+        Transport,
+    },
+};
+
+/// The buffer size the device reports through [`MCUmgrClient::os_mcumgr_parameters`](crate::MCUmgrClient::os_mcumgr_parameters).
+const MCUMGR_BUF_SIZE: u64 = 2048;
+
+/// The buffer count the device reports through [`MCUmgrClient::os_mcumgr_parameters`](crate::MCUmgrClient::os_mcumgr_parameters).
+const MCUMGR_BUF_COUNT: u64 = 4;
+
+/// The maximum amount of file data in a single download response.
+const DOWNLOAD_CHUNK_SIZE: usize = 512;
+
+/// A custom command handler, see [`FakeDevice::on_command`].
+type Handler = Box<dyn FnMut(&Value) -> Result<Value, MCUmgrErr> + Send>;
+
+/// An error response of the device.
+enum Failure {
+    /// A generic error, sent as `rc`.
+    Mgmt(MCUmgrErr),
+    /// A group specific error; SMP version 1 requests receive `legacy` instead.
+    Group { rc: i32, legacy: MCUmgrErr },
+}
+
+impl Failure {
+    fn fs(rc: FsMgmtErrCode, legacy: MCUmgrErr) -> Self {
+        Self::Group {
+            rc: rc as i32,
+            legacy,
+        }
+    }
+
+    fn img(rc: ImgMgmtErrCode, legacy: MCUmgrErr) -> Self {
+        Self::Group {
+            rc: rc as i32,
+            legacy,
+        }
+    }
+
+    fn encode(self, header: &SmpHeader) -> Value {
+        match self {
+            Failure::Group { rc, .. } if header.ver == SmpVersion::V2.header_value() => map([(
+                "err",
+                map([("group", header.group_id.into()), ("rc", rc.into())]),
+            )]),
+            Failure::Group { legacy: rc, .. } | Failure::Mgmt(rc) => {
+                map([("rc", (rc as i32).into())])
+            }
+        }
+    }
+}
+
+/// One of the two slots of image 0.
+#[derive(Default)]
+struct Slot {
+    data: Vec<u8>,
+    pending: bool,
+    confirmed: bool,
+    permanent: bool,
+}
+
+/// An image upload that is in progress.
+struct ImageUpload {
+    len: u64,
+    sha: Option<Vec<u8>>,
+    data: Vec<u8>,
+}
+
+#[derive(Default)]
+struct State {
+    files: BTreeMap<String, Vec<u8>>,
+    slots: [Slot; 2],
+    image_upload: Option<ImageUpload>,
+    test_boot: bool,
+    reset_count: usize,
+    dropped_responses: usize,
+    responses: VecDeque<Vec<u8>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    state: Mutex<State>,
+    handlers: Mutex<HashMap<(bool, u16, u8), Handler>>,
+}
+
+/// An in-memory device that answers SMP requests like a Zephyr device would.
+///
+/// Implements the commonly used commands of the `os`, `img` and `fs` groups on top of
+/// a RAM filesystem and two MCUboot slots of image 0, including resumed uploads and
+/// test boots with revert. Everything else can be scripted through [`FakeDevice::on_command`].
+///
+/// All clones share the same device, so one clone can be handed to
+/// [`MCUmgrClient::new_from_transport`](crate::MCUmgrClient::new_from_transport)
+/// while the other one inspects the device state.
+///
+/// ```
+/// # use zephyr_mcumgr::{MCUmgrClient, testing::FakeDevice};
+/// let device = FakeDevice::new();
+/// let client = MCUmgrClient::new_from_transport(device.clone());
+///
+/// client
+///     .fs_file_upload("/lfs/config.txt", &b"answer=42"[..], 9, &Default::default(), None)
+///     .unwrap();
+/// assert_eq!(device.file("/lfs/config.txt").unwrap(), b"answer=42");
+/// ```
+#[derive(Clone, Default)]
+pub struct FakeDevice(Arc<Inner>);
+
+impl FakeDevice {
+    /// Creates a device with an empty filesystem and empty image slots.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers a command through a custom handler instead of the built-in behavior.
+    ///
+    /// Requests that cannot be decoded into `Req` are answered with [`MCUmgrErr::MGMT_ERR_EINVAL`].
+    ///
+    /// # Arguments
+    ///
+    /// * `write_operation` - If the handler answers the write or the read variant of the command.
+    /// * `group_id` - The group ID of the command.
+    /// * `command_id` - The command ID.
+    /// * `handler` - Turns the request payload into the response payload or an error code.
+    ///
+    pub fn on_command<Req, Resp, F>(
+        &self,
+        write_operation: bool,
+        group_id: u16,
+        command_id: u8,
+        mut handler: F,
+    ) where
+        Req: DeserializeOwned,
+        Resp: Serialize,
+        F: FnMut(Req) -> Result<Resp, MCUmgrErr> + Send + 'static,
+    {
+        let handler: Handler = Box::new(move |request: &Value| {
+            let request = request
+                .deserialized()
+                .map_err(|_| MCUmgrErr::MGMT_ERR_EINVAL)?;
+            Value::serialized(&handler(request)?).map_err(|_| MCUmgrErr::MGMT_ERR_EUNKNOWN)
+        });
+        self.0
+            .handlers
+            .lock()
+            .unwrap()
+            .insert((write_operation, group_id, command_id), handler);
+    }
+
+    /// Processes the next `count` requests without responding to them.
+    pub fn drop_responses(&self, count: usize) {
+        self.0.state.lock().unwrap().dropped_responses = count;
+    }
+
+    /// The content of a file, or `None` if it does not exist.
+    pub fn file(&self, name: &str) -> Option<Vec<u8>> {
+        self.0.state.lock().unwrap().files.get(name).cloned()
+    }
+
+    /// Creates or replaces a file.
+    pub fn set_file(&self, name: impl Into<String>, data: impl Into<Vec<u8>>) {
+        let mut state = self.0.state.lock().unwrap();
+        state.files.insert(name.into(), data.into());
+    }
+
+    /// The content of an image slot, or `None` if the slot is empty.
+    ///
+    /// Slot 0 holds the active image, slot 1 receives uploads.
+    pub fn image_slot(&self, slot: u32) -> Option<Vec<u8>> {
+        let state = self.0.state.lock().unwrap();
+        let slot = state.slots.get(slot as usize)?;
+        (!slot.data.is_empty()).then(|| slot.data.clone())
+    }
+
+    /// Replaces the content of an image slot.
+    ///
+    /// An image written to slot 0 is treated as confirmed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slot` is neither 0 nor 1.
+    pub fn set_image_slot(&self, slot: u32, data: impl Into<Vec<u8>>) {
+        let mut state = self.0.state.lock().unwrap();
+        state.slots[slot as usize] = Slot {
+            data: data.into(),
+            confirmed: slot == 0,
+            ..Slot::default()
+        };
+    }
+
+    /// The number of resets requested through [`MCUmgrClient::os_system_reset`](crate::MCUmgrClient::os_system_reset).
+    pub fn reset_count(&self) -> usize {
+        self.0.state.lock().unwrap().reset_count
+    }
+
+    fn process_request(&self, header: &SmpHeader, request: &Value) -> Result<Value, Failure> {
+        let write_operation = header.op == smp_op::WRITE;
+        let key = (write_operation, header.group_id, header.command_id);
+        if let Some(handler) = self.0.handlers.lock().unwrap().get_mut(&key) {
+            return handler(request).map_err(Failure::Mgmt);
+        }
+
+        let request = Request(request);
+        let mut state = self.0.state.lock().unwrap();
+        match (
+            MCUmgrGroup::from_repr(header.group_id),
+            write_operation,
+            header.command_id,
+        ) {
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_OS), false, 0) => os_echo(request),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_OS), true, 5) => Ok(state.reset()),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_OS), false, 6) => Ok(map([
+                ("buf_size", MCUMGR_BUF_SIZE.into()),
+                ("buf_count", MCUMGR_BUF_COUNT.into()),
+            ])),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_IMAGE), false, 0) => Ok(state.image_state()),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_IMAGE), true, 0) => state.image_set_state(request),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_IMAGE), true, 1) => state.image_upload(request),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_IMAGE), true, 5) => state.image_erase(request),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_FS), true, 0) => state.fs_upload(request),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_FS), false, 0) => state.fs_download(request),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_FS), false, 1) => state.fs_status(request),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_FS), false, 2) => state.fs_checksum(request),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_FS), false, 3) => Ok(fs_supported_checksum_types()),
+            (Some(MCUmgrGroup::MGMT_GROUP_ID_FS), true, 4) => Ok(map([])),
+            _ => Err(Failure::Mgmt(MCUmgrErr::MGMT_ERR_ENOTSUP)),
+        }
+    }
+}
+
+impl State {
+    fn reset(&mut self) -> Value {
+        self.reset_count += 1;
+
+        if self.slots[1].pending {
+            // Boot into the new image; it stays only if it was marked permanent
+            self.slots.swap(0, 1);
+            let permanent = self.slots[0].permanent;
+            self.slots[0] = Slot {
+                data: std::mem::take(&mut self.slots[0].data),
+                confirmed: permanent,
+                ..Slot::default()
+            };
+            self.slots[1].confirmed = false;
+            self.test_boot = !permanent;
+        } else if self.test_boot && !self.slots[0].confirmed {
+            // MCUboot reverts test images that did not get confirmed
+            self.slots.swap(0, 1);
+            self.slots[0].confirmed = true;
+            self.test_boot = false;
+        } else {
+            self.test_boot = false;
+        }
+
+        map([])
+    }
+
+    fn image_state(&self) -> Value {
+        let images = self
+            .slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let info = mcuboot::get_image_info(io::Cursor::new(&slot.data)).ok()?;
+                Some(map([
+                    ("image", 0.into()),
+                    ("slot", (index as u64).into()),
+                    ("version", info.version.to_string().into()),
+                    ("hash", Value::Bytes(info.hash.to_vec())),
+                    ("bootable", true.into()),
+                    ("pending", slot.pending.into()),
+                    ("confirmed", slot.confirmed.into()),
+                    ("active", (index == 0).into()),
+                    ("permanent", slot.permanent.into()),
+                ]))
+            })
+            .collect();
+
+        map([("images", Value::Array(images))])
+    }
+
+    fn image_set_state(&mut self, request: Request) -> Result<Value, Failure> {
+        let confirm = request.bool("confirm").unwrap_or(false);
+
+        let slot = match request.bytes("hash") {
+            None if confirm => 0,
+            None => return Err(Failure::Mgmt(MCUmgrErr::MGMT_ERR_EINVAL)),
+            Some(hash) => self
+                .slots
+                .iter()
+                .position(|slot| {
+                    mcuboot::get_image_info(io::Cursor::new(&slot.data))
+                        .is_ok_and(|info| info.hash.as_slice() == hash)
+                })
+                .ok_or(Failure::img(
+                    ImgMgmtErrCode::IMG_MGMT_ERR_NO_IMAGE,
+                    MCUmgrErr::MGMT_ERR_ENOENT,
+                ))?,
+        };
+
+        if slot == 0 {
+            if !confirm {
+                return Err(Failure::img(
+                    ImgMgmtErrCode::IMG_MGMT_ERR_IMAGE_SETTING_TEST_TO_ACTIVE_DENIED,
+                    MCUmgrErr::MGMT_ERR_EBADSTATE,
+                ));
+            }
+            self.slots[0].confirmed = true;
+        } else {
+            self.slots[1].pending = true;
+            self.slots[1].permanent = confirm;
+        }
+
+        Ok(self.image_state())
+    }
+
+    fn image_upload(&mut self, request: Request) -> Result<Value, Failure> {
+        let off = request.integer("off").ok_or(Failure::img(
+            ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_OFFSET,
+            MCUmgrErr::MGMT_ERR_EINVAL,
+        ))?;
+        let data = request
+            .bytes("data")
+            .ok_or(Failure::Mgmt(MCUmgrErr::MGMT_ERR_EINVAL))?;
+
+        if off == 0 {
+            let len = request.integer("len").ok_or(Failure::img(
+                ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_LENGTH,
+                MCUmgrErr::MGMT_ERR_EINVAL,
+            ))?;
+            let sha = request.bytes("sha").map(<[u8]>::to_vec);
+
+            // Uploads of the same image continue where they stopped
+            if let Some(upload) = &self.image_upload {
+                if sha.is_some() && upload.sha == sha && upload.len == len {
+                    return Ok(map([("off", (upload.data.len() as u64).into())]));
+                }
+            }
+
+            self.slots[1] = Slot::default();
+            self.image_upload = Some(ImageUpload {
+                len,
+                sha,
+                data: vec![],
+            });
+        }
+
+        let upload = self
+            .image_upload
+            .as_mut()
+            .ok_or(Failure::Mgmt(MCUmgrErr::MGMT_ERR_EBADSTATE))?;
+
+        // Chunks at unexpected offsets get ignored, the response tells the client where to continue
+        if off != upload.data.len() as u64 {
+            return Ok(map([("off", (upload.data.len() as u64).into())]));
+        }
+
+        if off + data.len() as u64 > upload.len {
+            return Err(Failure::img(
+                ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_IMAGE_DATA_OVERRUN,
+                MCUmgrErr::MGMT_ERR_EINVAL,
+            ));
+        }
+        upload.data.extend_from_slice(data);
+
+        let mut response = vec![("off", (upload.data.len() as u64).into())];
+        if upload.data.len() as u64 == upload.len {
+            if let Some(sha) = &upload.sha {
+                let matches = &Sha256::digest(&upload.data)[..] == sha.as_slice();
+                response.push(("match", matches.into()));
+            }
+            if let Some(upload) = self.image_upload.take() {
+                self.slots[1].data = upload.data;
+            }
+        }
+
+        Ok(map(response))
+    }
+
+    fn image_erase(&mut self, request: Request) -> Result<Value, Failure> {
+        if request.integer("slot").unwrap_or(1) != 1 {
+            return Err(Failure::img(
+                ImgMgmtErrCode::IMG_MGMT_ERR_INVALID_SLOT,
+                MCUmgrErr::MGMT_ERR_EINVAL,
+            ));
+        }
+
+        self.slots[1] = Slot::default();
+        self.image_upload = None;
+        Ok(map([]))
+    }
+
+    fn fs_upload(&mut self, request: Request) -> Result<Value, Failure> {
+        let name = fs_file_name(&request)?;
+        let off = request
+            .integer("off")
+            .ok_or(Failure::Mgmt(MCUmgrErr::MGMT_ERR_EINVAL))?;
+        let data = request
+            .bytes("data")
+            .ok_or(Failure::Mgmt(MCUmgrErr::MGMT_ERR_EINVAL))?;
+
+        if off == 0 {
+            self.files.insert(name.to_string(), vec![]);
+        }
+
+        let file = self.files.get_mut(name).ok_or(Failure::fs(
+            FsMgmtErrCode::FS_MGMT_ERR_FILE_NOT_FOUND,
+            MCUmgrErr::MGMT_ERR_ENOENT,
+        ))?;
+        if off > file.len() as u64 {
+            return Err(Failure::fs(
+                FsMgmtErrCode::FS_MGMT_ERR_FILE_OFFSET_NOT_VALID,
+                MCUmgrErr::MGMT_ERR_EINVAL,
+            ));
+        }
+
+        file.truncate(off as usize);
+        file.extend_from_slice(data);
+        Ok(map([("off", (file.len() as u64).into())]))
+    }
+
+    fn fs_download(&self, request: Request) -> Result<Value, Failure> {
+        let file = self.fs_file(&request)?;
+        let off = request
+            .integer("off")
+            .ok_or(Failure::Mgmt(MCUmgrErr::MGMT_ERR_EINVAL))?;
+        if off > file.len() as u64 {
+            return Err(Failure::fs(
+                FsMgmtErrCode::FS_MGMT_ERR_FILE_OFFSET_LARGER_THAN_FILE,
+                MCUmgrErr::MGMT_ERR_EINVAL,
+            ));
+        }
+
+        let off = off as usize;
+        let end = file.len().min(off + DOWNLOAD_CHUNK_SIZE);
+        let mut response = vec![
+            ("off", (off as u64).into()),
+            ("data", Value::Bytes(file[off..end].to_vec())),
+        ];
+        if off == 0 {
+            response.push(("len", (file.len() as u64).into()));
+        }
+
+        Ok(map(response))
+    }
+
+    fn fs_status(&self, request: Request) -> Result<Value, Failure> {
+        let file = self.fs_file(&request)?;
+        Ok(map([("len", (file.len() as u64).into())]))
+    }
+
+    fn fs_checksum(&self, request: Request) -> Result<Value, Failure> {
+        let file = self.fs_file(&request)?;
+        let algorithm = request.text("type").unwrap_or("crc32");
+        let mut hasher = checksum::hasher_for_algorithm(algorithm).ok_or(Failure::fs(
+            FsMgmtErrCode::FS_MGMT_ERR_CHECKSUM_HASH_NOT_FOUND,
+            MCUmgrErr::MGMT_ERR_EINVAL,
+        ))?;
+
+        let off = request.integer("off").unwrap_or(0);
+        if off > file.len() as u64 {
+            return Err(Failure::fs(
+                FsMgmtErrCode::FS_MGMT_ERR_FILE_OFFSET_LARGER_THAN_FILE,
+                MCUmgrErr::MGMT_ERR_EINVAL,
+            ));
+        }
+        let data = &file[off as usize..];
+        let len = request
+            .integer("len")
+            .unwrap_or(u64::MAX)
+            .min(data.len() as u64);
+        hasher.update(&data[..len as usize]);
+
+        let output = match hasher.finalize() {
+            FileChecksumData::Hash(hash) => Value::Bytes(hash.into_vec()),
+            FileChecksumData::Checksum(checksum) => checksum.into(),
+        };
+
+        Ok(map([
+            ("type", algorithm.into()),
+            ("off", off.into()),
+            ("len", len.into()),
+            ("output", output),
+        ]))
+    }
+
+    fn fs_file(&self, request: &Request) -> Result<&Vec<u8>, Failure> {
+        self.files.get(fs_file_name(request)?).ok_or(Failure::fs(
+            FsMgmtErrCode::FS_MGMT_ERR_FILE_NOT_FOUND,
+            MCUmgrErr::MGMT_ERR_ENOENT,
+        ))
+    }
+}
+
+fn os_echo(request: Request) -> Result<Value, Failure> {
+    let text = request
+        .text("d")
+        .ok_or(Failure::Mgmt(MCUmgrErr::MGMT_ERR_EINVAL))?;
+    Ok(map([("r", text.into())]))
+}
+
+fn fs_supported_checksum_types() -> Value {
+    map([(
+        "types",
+        map([
+            ("sha256", map([("format", 1.into()), ("size", 32.into())])),
+            ("crc32", map([("format", 0.into()), ("size", 4.into())])),
+        ]),
+    )])
+}
+
+fn fs_file_name<'a>(request: &Request<'a>) -> Result<&'a str, Failure> {
+    request.text("name").ok_or(Failure::fs(
+        FsMgmtErrCode::FS_MGMT_ERR_FILE_INVALID_NAME,
+        MCUmgrErr::MGMT_ERR_EINVAL,
+    ))
+}
+
+/// Builds a CBOR map with text keys.
+fn map(entries: impl IntoIterator<Item = (&'static str, Value)>) -> Value {
+    Value::Map(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.into(), value))
+            .collect(),
+    )
+}
+
+/// Typed access to the fields of a request payload.
+struct Request<'a>(&'a Value);
+
+impl<'a> Request<'a> {
+    fn get(&self, key: &str) -> Option<&'a Value> {
+        self.0
+            .as_map()?
+            .iter()
+            .find(|(k, _)| k.as_text() == Some(key))
+            .map(|(_, value)| value)
+    }
+
+    fn text(&self, key: &str) -> Option<&'a str> {
+        self.get(key)?.as_text()
+    }
+
+    fn bytes(&self, key: &str) -> Option<&'a [u8]> {
+        self.get(key)?.as_bytes().map(Vec::as_slice)
+    }
+
+    fn integer(&self, key: &str) -> Option<u64> {
+        self.get(key)?
+            .as_integer()
+            .and_then(|value: Integer| value.try_into().ok())
+    }
+
+    fn bool(&self, key: &str) -> Option<bool> {
+        self.get(key)?.as_bool()
+    }
+}
+
+impl Transport for FakeDevice {
+    fn send_raw_frame(
+        &mut self,
+        header: [u8; SMP_HEADER_SIZE],
+        data: &[u8],
+    ) -> Result<(), SendError> {
+        let mut header = SmpHeader::from_bytes(header);
+
+        let response = match ciborium::from_reader::<Value, _>(data) {
+            Ok(request) => self.process_request(&header, &request),
+            Err(_) => Err(Failure::Mgmt(MCUmgrErr::MGMT_ERR_EINVAL)),
+        }
+        .unwrap_or_else(|failure| failure.encode(&header));
+
+        let mut state = self.0.state.lock().unwrap();
+        if state.dropped_responses > 0 {
+            state.dropped_responses -= 1;
+            return Ok(());
+        }
+
+        let mut payload = vec![];
+        ciborium::into_writer(&response, &mut payload).map_err(io::Error::other)?;
+
+        header.op += 1;
+        header.data_length = payload
+            .len()
+            .try_into()
+            .map_err(|_| SendError::DataTooBig)?;
+        let mut frame = header.to_bytes().to_vec();
+        frame.extend_from_slice(&payload);
+        state.responses.push_back(frame);
+
+        Ok(())
+    }
+
+    fn recv_raw_frame<'a>(
+        &mut self,
+        buffer: &'a mut [u8; SMP_TRANSFER_BUFFER_SIZE],
+    ) -> Result<&'a [u8], ReceiveError> {
+        let frame = self
+            .0
+            .state
+            .lock()
+            .unwrap()
+            .responses
+            .pop_front()
+            .ok_or_else(|| io::Error::from(io::ErrorKind::TimedOut))?;
+
+        let frame_buffer = buffer
+            .get_mut(..frame.len())
+            .ok_or(ReceiveError::FrameTooBig)?;
+        frame_buffer.copy_from_slice(&frame);
+        Ok(frame_buffer)
+    }
+
+    fn set_timeout(&mut self, _: Duration) -> Result<(), miette::Report> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        MCUmgrClient,
+        client::{
+            CancellationToken, FileDownloadError, FileUploadError, FirmwareUpdateError,
+            FirmwareUpdateOutcome, FirmwareUpdateParams, FsUploadOptions, ImageUploadError,
+        },
+        connection::{ExecuteError, FrameDirection, RetryPolicy},
+        mcuboot::build_image,
+    };
+
+    #[test]
+    fn file_transfers() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        let data = (0..3000u32).map(|i| i as u8).collect::<Vec<_>>();
+        client
+            .fs_file_upload(
+                "/lfs/data.bin",
+                &data[..],
+                3000,
+                &FsUploadOptions {
+                    verify: true,
+                    ..Default::default()
+                },
+                None,
+            )
+            .unwrap();
+        assert_eq!(device.file("/lfs/data.bin").unwrap(), data);
+
+        let mut downloaded = vec![];
+        client
+            .fs_file_download("/lfs/data.bin", &mut downloaded, None, None)
+            .unwrap();
+        assert_eq!(downloaded, data);
+
+        // Resumed uploads only transfer the missing part
+        device.set_file("/lfs/data.bin", &data[..1000]);
+        client
+            .fs_file_upload_resume(
+                "/lfs/data.bin",
+                io::Cursor::new(&data),
+                3000,
+                &FsUploadOptions::default(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(device.file("/lfs/data.bin").unwrap(), data);
+
+        assert!(matches!(
+            client.fs_file_status("/lfs/missing.txt"),
+            Err(ExecuteError::ErrorResponse(_))
+        ));
+    }
+
+    #[test]
+    fn resumed_file_download() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        let data = (0..3000u32).map(|i| i as u8).collect::<Vec<_>>();
+        device.set_file("/lfs/data.bin", data.clone());
+
+        let mut reported = vec![];
+        let mut progress = |transferred, total| {
+            reported.push((transferred, total));
+            true
+        };
+        let mut downloaded = data[..1000].to_vec();
+        client
+            .fs_file_download_from(
+                "/lfs/data.bin",
+                1000,
+                &mut downloaded,
+                Some(&mut progress),
+                None,
+            )
+            .unwrap();
+        assert_eq!(downloaded, data);
+
+        // The progress includes the skipped part
+        assert_eq!(reported.first(), Some(&(1000, 3000)));
+        assert_eq!(reported.last(), Some(&(3000, 3000)));
+
+        // Nothing is left to load at the end of the file
+        let mut downloaded = vec![];
+        client
+            .fs_file_download_from("/lfs/data.bin", 3000, &mut downloaded, None, None)
+            .unwrap();
+        assert!(downloaded.is_empty());
+
+        assert!(matches!(
+            client.fs_file_download_from("/lfs/missing.bin", 0, &mut downloaded, None, None),
+            Err(FileDownloadError::ExecuteError(
+                ExecuteError::ErrorResponse(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn file_download_range() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        let data = (0..3000u32).map(|i| i as u8).collect::<Vec<_>>();
+        device.set_file("/lfs/data.bin", data.clone());
+
+        let download = |offset, length| {
+            let mut downloaded = vec![];
+            client
+                .fs_file_download_range(
+                    "/lfs/data.bin",
+                    offset,
+                    length,
+                    &mut downloaded,
+                    None,
+                    None,
+                )
+                .map(|()| downloaded)
+        };
+
+        assert_eq!(download(0, Some(100)).unwrap(), data[..100]);
+        assert_eq!(download(500, Some(1200)).unwrap(), data[500..1700]);
+        assert_eq!(download(1000, None).unwrap(), data[1000..]);
+
+        // The range ends early if the file is shorter
+        assert_eq!(download(2500, Some(1000)).unwrap(), data[2500..]);
+
+        assert!(matches!(
+            download(3001, Some(10)),
+            Err(FileDownloadError::OffsetBeyondEnd)
+        ));
+    }
+
+    #[test]
+    fn resumed_file_upload() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        // The (off, len) pairs of all sent upload chunks
+        let chunks = Arc::new(Mutex::new(vec![]));
+        client.set_frame_observer(Some(Box::new({
+            let chunks = Arc::clone(&chunks);
+            move |direction, header, payload| {
+                if direction == FrameDirection::Sent
+                    && (header.group_id, header.command_id) == (8, 0)
+                {
+                    let request: Value = ciborium::from_reader(payload).unwrap();
+                    let request = Request(&request);
+                    chunks
+                        .lock()
+                        .unwrap()
+                        .push((request.integer("off").unwrap(), request.integer("len")));
+                }
+            }
+        })));
+
+        let data = (0..3000u32).map(|i| i as u8).collect::<Vec<_>>();
+        let options = FsUploadOptions {
+            verify: true,
+            ..Default::default()
+        };
+
+        device.set_file("/lfs/data.bin", &data[..1000]);
+        client
+            .fs_file_upload_resume(
+                "/lfs/data.bin",
+                io::Cursor::new(&data),
+                3000,
+                &options,
+                None,
+            )
+            .unwrap();
+        assert_eq!(device.file("/lfs/data.bin").unwrap(), data);
+
+        let sent = std::mem::take(&mut *chunks.lock().unwrap());
+        assert_eq!(sent[0], (1000, Some(3000)));
+        assert!(
+            sent[1..]
+                .iter()
+                .all(|&(off, len)| off > 1000 && len.is_none())
+        );
+
+        // Existing data that does not match gets uploaded again,
+        // from where the reader started
+        device.set_file("/lfs/data.bin", [0xff; 1000]);
+        let mut reader = io::Cursor::new([&[0xee; 100][..], &data].concat());
+        reader.set_position(100);
+        client
+            .fs_file_upload_resume("/lfs/data.bin", reader, 3000, &options, None)
+            .unwrap();
+        assert_eq!(device.file("/lfs/data.bin").unwrap(), data);
+        assert_eq!(chunks.lock().unwrap()[0], (0, Some(3000)));
+    }
+
+    #[test]
+    fn upload_chunks_are_retransmitted() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+        client.set_window_size(Some(1));
+        client.set_retry_policy(RetryPolicy {
+            attempts: 2,
+            backoff: Duration::ZERO,
+        });
+
+        // The response to the second upload chunk gets lost
+        let chunks = Arc::new(Mutex::new(0));
+        client.set_frame_observer(Some(Box::new({
+            let chunks = Arc::clone(&chunks);
+            let device = device.clone();
+            move |direction, header, _| {
+                if direction == FrameDirection::Sent
+                    && matches!((header.group_id, header.command_id), (1, 1) | (8, 0))
+                {
+                    let mut chunks = chunks.lock().unwrap();
+                    *chunks += 1;
+                    if *chunks == 2 {
+                        device.drop_responses(1);
+                    }
+                }
+            }
+        })));
+
+        let data = (0..3000u32).map(|i| i as u8).collect::<Vec<_>>();
+        client
+            .fs_file_upload(
+                "/lfs/data.bin",
+                &data[..],
+                3000,
+                &FsUploadOptions::default(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(device.file("/lfs/data.bin").unwrap(), data);
+        assert_eq!(client.stats().retransmits, 1);
+
+        *chunks.lock().unwrap() = 0;
+        client
+            .image_upload(&data, None, None, false, None, None)
+            .unwrap();
+        assert_eq!(device.image_slot(1).unwrap(), data);
+        assert_eq!(client.stats().retransmits, 2);
+    }
+
+    #[test]
+    fn builder_chunk_alignment() {
+        let device = FakeDevice::new();
+
+        // The offsets of all sent upload chunks
+        let offsets = Arc::new(Mutex::new(vec![]));
+        let client = MCUmgrClient::builder()
+            .chunk_alignment(64)
+            .frame_observer(Box::new({
+                let offsets = Arc::clone(&offsets);
+                move |direction, header, payload| {
+                    if direction == FrameDirection::Sent
+                        && (header.group_id, header.command_id) == (8, 0)
+                    {
+                        let request: Value = ciborium::from_reader(payload).unwrap();
+                        let offset = Request(&request).integer("off").unwrap();
+                        offsets.lock().unwrap().push(offset);
+                    }
+                }
+            }))
+            .build_from_transport(device.clone())
+            .unwrap();
+
+        let data = vec![0x5a; 3000];
+        client
+            .fs_file_upload(
+                "/lfs/data.bin",
+                &data[..],
+                3000,
+                &FsUploadOptions::default(),
+                None,
+            )
+            .unwrap();
+        assert_eq!(device.file("/lfs/data.bin").unwrap(), data);
+
+        let offsets = offsets.lock().unwrap();
+        assert!(offsets.len() > 1);
+        assert!(offsets.iter().all(|offset| offset % 64 == 0));
+    }
+
+    #[test]
+    fn file_upload_verification_failure() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        // A device whose file system corrupted the upload
+        device.on_command(false, 8, 2, |_: Value| {
+            Ok::<_, MCUmgrErr>(map([
+                ("type", "sha256".into()),
+                ("off", 0.into()),
+                ("len", 3.into()),
+                ("output", Value::Bytes(vec![0; 32])),
+            ]))
+        });
+
+        let result = client.fs_file_upload(
+            "/lfs/a.txt",
+            &b"abc"[..],
+            3,
+            &FsUploadOptions {
+                verify: true,
+                ..Default::default()
+            },
+            None,
+        );
+        assert!(matches!(result, Err(FileUploadError::VerificationFailed)));
+    }
+
+    #[test]
+    fn cancelled_image_upload() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+        // Without cancellation, the second chunk would be delayed by seconds
+        client.set_max_upload_rate(Some(100));
+
+        let sent = Arc::new(Mutex::new(0));
+        client.set_frame_observer(Some(Box::new({
+            let sent = Arc::clone(&sent);
+            move |direction, header, _| {
+                if direction == FrameDirection::Sent {
+                    assert_eq!((header.group_id, header.command_id), (1, 1));
+                    *sent.lock().unwrap() += 1;
+                }
+            }
+        })));
+
+        let cancel = CancellationToken::new();
+        let mut progress = |_, _| {
+            cancel.cancel();
+            true
+        };
+
+        let start = std::time::Instant::now();
+        let result = client.image_upload(
+            [0x55; 5000],
+            None,
+            None,
+            false,
+            Some(&mut progress),
+            Some(&cancel),
+        );
+        assert!(matches!(result, Err(ImageUploadError::Cancelled)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Nothing gets sent after the chunk that was in flight when cancelling
+        assert_eq!(*sent.lock().unwrap(), 1);
+        assert_eq!(device.image_slot(1), None);
+    }
+
+    #[test]
+    fn firmware_update_with_revert() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        let old_image = build_image(&[], &[(0x10, &[0x11; 32])]);
+        let new_image = build_image(&[], &[(0x10, &[0x22; 32])]);
+        device.set_image_slot(0, old_image.clone());
+
+        client
+            .image_upload(&new_image, None, None, false, None, None)
+            .unwrap();
+        assert_eq!(device.image_slot(1).unwrap(), new_image);
+
+        client.image_set_state(Some([0x22; 32]), false).unwrap();
+        client.os_system_reset(false, None).unwrap();
+        let state = client.image_get_state().unwrap();
+        assert_eq!(state[0].hash, Some([0x22; 32]));
+        assert!(state[0].active && !state[0].confirmed);
+
+        // Unconfirmed test images get reverted on the next reset
+        client.os_system_reset(false, None).unwrap();
+        assert_eq!(device.image_slot(0).unwrap(), old_image);
+        assert_eq!(device.reset_count(), 2);
+    }
+
+    #[test]
+    fn firmware_update_of_second_image() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        // Image 0 runs the same version, which must not prevent the update of image 1
+        let old_image = build_image(&[], &[(0x10, &[0x11; 32])]);
+        let new_image = build_image(&[], &[(0x10, &[0x22; 32])]);
+        device.set_image_slot(0, old_image);
+
+        let erased_slots = Arc::new(Mutex::new(vec![]));
+        device.on_command(true, 1, 5, {
+            let erased_slots = Arc::clone(&erased_slots);
+            move |request: Value| {
+                erased_slots
+                    .lock()
+                    .unwrap()
+                    .push(Request(&request).integer("slot"));
+                Ok::<_, MCUmgrErr>(map([]))
+            }
+        });
+
+        // The image numbers of all sent upload chunks
+        let uploaded_images = Arc::new(Mutex::new(vec![]));
+        client.set_frame_observer(Some(Box::new({
+            let uploaded_images = Arc::clone(&uploaded_images);
+            move |direction, header, payload| {
+                if direction == FrameDirection::Sent
+                    && (header.group_id, header.command_id) == (1, 1)
+                {
+                    let request: Value = ciborium::from_reader(payload).unwrap();
+                    uploaded_images
+                        .lock()
+                        .unwrap()
+                        .push(Request(&request).integer("image"));
+                }
+            }
+        })));
+
+        let params = FirmwareUpdateParams {
+            image_index: Some(1),
+            restart_upload: true,
+            skip_if_version_not_newer: true,
+            skip_reboot: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            client.firmware_update(&new_image, &params, None).unwrap(),
+            FirmwareUpdateOutcome::Updated
+        );
+        assert_eq!(device.image_slot(1).unwrap(), new_image);
+
+        // The second slot of image 1 gets erased
+        assert_eq!(*erased_slots.lock().unwrap(), [Some(3)]);
+
+        // Only the first chunk names the image
+        let uploaded_images = uploaded_images.lock().unwrap();
+        assert_eq!(uploaded_images[0], Some(1));
+        assert!(uploaded_images[1..].iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn firmware_update_confirms_new_image() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        let old_image = build_image(&[], &[(0x10, &[0x11; 32])]);
+        let new_image = build_image(&[], &[(0x10, &[0x22; 32])]);
+        device.set_image_slot(0, old_image.clone());
+
+        let params = FirmwareUpdateParams {
+            confirm_timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        };
+        assert_eq!(
+            client.firmware_update(&new_image, &params, None).unwrap(),
+            FirmwareUpdateOutcome::Updated
+        );
+
+        let state = client.image_get_state().unwrap();
+        assert_eq!(state[0].hash, Some([0x22; 32]));
+        assert!(state[0].active && state[0].confirmed);
+        assert_eq!(device.reset_count(), 1);
+
+        // Confirmed images survive further resets
+        client.os_system_reset(false, None).unwrap();
+        assert_eq!(device.image_slot(0).unwrap(), new_image);
+        assert_eq!(device.image_slot(1).unwrap(), old_image);
+    }
+
+    #[test]
+    fn firmware_update_detects_failed_boot() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        let old_image = build_image(&[], &[(0x10, &[0x11; 32])]);
+        let new_image = build_image(&[], &[(0x10, &[0x22; 32])]);
+        device.set_image_slot(0, old_image.clone());
+
+        // MCUboot fails to boot the new image and stays with the old one
+        device.on_command(true, 0, 5, |_: Value| Ok::<_, MCUmgrErr>(map([])));
+
+        let params = FirmwareUpdateParams {
+            confirm_timeout: Some(Duration::from_secs(5)),
+            ..Default::default()
+        };
+        assert!(matches!(
+            client.firmware_update(&new_image, &params, None),
+            Err(FirmwareUpdateError::ImageNotActive)
+        ));
+
+        let state = client.image_get_state().unwrap();
+        assert_eq!(state[0].hash, Some([0x11; 32]));
+        assert!(!state[1].confirmed);
+    }
+
+    #[test]
+    fn firmware_update_outcome() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        let image = build_image(&[], &[(0x10, &[0x33; 32])]);
+        let params = FirmwareUpdateParams {
+            skip_if_version_not_newer: true,
+            skip_reboot: true,
+            ..Default::default()
+        };
+
+        // Without an active image, there is nothing to compare the version to
+        assert_eq!(
+            client.firmware_update(&image, &params, None).unwrap(),
+            FirmwareUpdateOutcome::Updated
+        );
+        assert_eq!(device.image_slot(1).unwrap(), image);
+        assert_eq!(device.reset_count(), 0);
+
+        // The synthetic images all have the same version
+        device.set_image_slot(0, image.clone());
+        device.set_image_slot(1, Vec::new());
+        assert_eq!(
+            client.firmware_update(&image, &params, None).unwrap(),
+            FirmwareUpdateOutcome::Skipped {
+                active_version: "1.2.3.4".parse().unwrap()
+            }
+        );
+        assert_eq!(device.image_slot(1), None);
+    }
+
+    #[test]
+    fn firmware_update_rejects_unverified_image() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        let image = build_image(&[], &[(0x10, &[0x33; 32])]);
+        let params = FirmwareUpdateParams {
+            public_key: Some(vec![0x44; 32]),
+            skip_reboot: true,
+            ..Default::default()
+        };
+
+        let result = client.firmware_update(&image, &params, None);
+        if cfg!(feature = "verify") {
+            assert!(matches!(
+                result,
+                Err(FirmwareUpdateError::SignatureVerificationFailed(_))
+            ));
+        } else {
+            assert!(matches!(
+                result,
+                Err(FirmwareUpdateError::SignatureVerificationUnavailable)
+            ));
+        }
+        assert_eq!(device.image_slot(1), None);
+    }
+
+    #[test]
+    fn verify_active_image_stops_on_permanent_errors() {
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        device.on_command(false, 0, 0, |_: Value| {
+            Err::<(), _>(MCUmgrErr::MGMT_ERR_ENOTSUP)
+        });
+        let start = std::time::Instant::now();
+        assert!(matches!(
+            client.verify_active_image([0x22; 32], Duration::from_secs(60)),
+            Err(ExecuteError::ErrorResponse(_))
+        ));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    /// A client whose device fails the shell command `false` and echoes all others.
+    fn shell_client() -> MCUmgrClient {
+        #[derive(serde::Deserialize)]
+        struct Execute {
+            argv: Vec<String>,
+        }
+
+        #[derive(serde::Serialize)]
+        struct ExecuteResponse {
+            o: String,
+            ret: i32,
+        }
+
+        let device = FakeDevice::new();
+        device.on_command(true, 9, 0, |request: Execute| {
+            Ok(ExecuteResponse {
+                ret: i32::from(request.argv[0] == "false"),
+                o: request.argv.join(" "),
+            })
+        });
+        MCUmgrClient::new_from_transport(device)
+    }
+
+    fn shell_lines(lines: &[&str]) -> Vec<Vec<String>> {
+        lines
+            .iter()
+            .map(|line| line.split(' ').map(str::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn shell_script_stops_on_error() {
+        let client = shell_client();
+
+        let results = client
+            .shell_run_script(&shell_lines(&["echo a", "false", "echo b"]), true)
+            .unwrap();
+        assert_eq!(
+            results,
+            [(0, "echo a".to_string()), (1, "false".to_string())]
+        );
+    }
+
+    #[test]
+    fn shell_script_keeps_going() {
+        let client = shell_client();
+
+        let results = client
+            .shell_run_script(&shell_lines(&["echo a", "false", "echo b"]), false)
+            .unwrap();
+        assert_eq!(
+            results,
+            [
+                (0, "echo a".to_string()),
+                (1, "false".to_string()),
+                (0, "echo b".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn scripted_commands() {
+        #[derive(serde::Deserialize)]
+        struct Echo {
+            d: String,
+        }
+
+        #[derive(serde::Serialize)]
+        struct EchoResponse {
+            r: String,
+        }
+
+        let device = FakeDevice::new();
+        let client = MCUmgrClient::new_from_transport(device.clone());
+
+        device.on_command(false, 0, 0, |echo: Echo| {
+            Ok(EchoResponse {
+                r: echo.d.to_uppercase(),
+            })
+        });
+        assert_eq!(client.os_echo("hello").unwrap(), "HELLO");
+
+        device.on_command(true, 8, 0, |_: Value| {
+            Err::<(), _>(MCUmgrErr::MGMT_ERR_ENOMEM)
+        });
+        assert!(matches!(
+            client.fs_file_upload("/lfs/a.txt", &b"a"[..], 1, &Default::default(), None),
+            Err(FileUploadError::ExecuteError(ExecuteError::ErrorResponse(
+                _
+            )))
+        ));
+    }
+}
@@ -6,54 +6,25 @@ use thiserror::Error;
 /// Serial port based transport
 pub mod serial;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-struct SmpHeader {
-    ver: u8,
-    op: u8,
-    flags: u8,
-    data_length: u16,
-    group_id: u16,
-    sequence_num: u8,
-    command_id: u8,
-}
+/// UDP based transport
+pub mod udp;
 
-impl SmpHeader {
-    fn from_bytes(data: [u8; SMP_HEADER_SIZE]) -> Self {
-        Self {
-            ver: (data[0] >> 3) & 0b11,
-            op: data[0] & 0b111,
-            flags: data[1],
-            data_length: u16::from_be_bytes([data[2], data[3]]),
-            group_id: u16::from_be_bytes([data[4], data[5]]),
-            sequence_num: data[6],
-            command_id: data[7],
-        }
-    }
-    fn to_bytes(self) -> [u8; SMP_HEADER_SIZE] {
-        let [length_0, length_1] = self.data_length.to_be_bytes();
-        let [group_id_0, group_id_1] = self.group_id.to_be_bytes();
-        [
-            ((self.ver & 0b11) << 3) | (self.op & 0b111),
-            self.flags,
-            length_0,
-            length_1,
-            group_id_0,
-            group_id_1,
-            self.sequence_num,
-            self.command_id,
-        ]
-    }
-}
+/// Serial ports bridged over TCP, like `ser2net`
+pub mod remote_serial;
 
-const SMP_HEADER_SIZE: usize = 8;
-const SMP_TRANSFER_BUFFER_SIZE: usize = u16::MAX as usize;
+/// Automatic re-opening of disconnected serial ports
+#[cfg(feature = "serialport")]
+pub mod reconnect;
 
-mod smp_op {
-    pub(super) const READ: u8 = 0;
-    pub(super) const READ_RSP: u8 = 1;
-    pub(super) const WRITE: u8 = 2;
-    pub(super) const WRITE_RSP: u8 = 3;
-}
+pub use zephyr_mcumgr_protocol::header::{SmpHeader, SmpVersion};
+
+pub use zephyr_mcumgr_protocol::header::SMP_HEADER_SIZE;
+use zephyr_mcumgr_protocol::header::smp_op;
+
+/// The size of the buffer that [`Transport::recv_raw_frame`] receives frames into.
+///
+/// Large enough for every frame, as the SMP header limits the payload length to 16 bits.
+pub const SMP_TRANSFER_BUFFER_SIZE: usize = u16::MAX as usize;
 
 /// Error while sending a command request
 #[derive(Error, Debug, Diagnostic)]
@@ -64,7 +35,10 @@ pub enum SendError {
     TransportError(#[from] io::Error),
     /// Unable to send data because it is too big
     #[error("Given data slice was too big")]
-    #[diagnostic(code(zephyr_mcumgr::transport::send::too_big))]
+    #[diagnostic(
+        code(zephyr_mcumgr::transport::send::too_big),
+        help("Reduce the SMP frame size.")
+    )]
     DataTooBig,
 }
 
@@ -73,11 +47,23 @@ pub enum SendError {
 pub enum ReceiveError {
     /// An error occurred in the underlying transport
     #[error("Transport error")]
-    #[diagnostic(code(zephyr_mcumgr::transport::recv::transport))]
+    #[diagnostic(
+        code(zephyr_mcumgr::transport::recv::transport),
+        help(
+            "Make sure the device is running with an MCUmgr transport enabled (e.g. `CONFIG_MCUMGR_TRANSPORT_UART`) and that the baud rate matches. \
+             Timeouts can also be caused by frames exceeding `CONFIG_MCUMGR_TRANSPORT_NETBUF_SIZE`; try a smaller frame size."
+        )
+    )]
     TransportError(#[from] io::Error),
     /// We received a response that did not fit to our request
     #[error("Received unexpected response")]
-    #[diagnostic(code(zephyr_mcumgr::transport::recv::unexpected))]
+    #[diagnostic(
+        code(zephyr_mcumgr::transport::recv::unexpected),
+        help(
+            "This usually happens when the serial port is shared with other output, like the Zephyr console or logging. \
+             Move them to a different UART or disable them."
+        )
+    )]
     UnexpectedResponse,
     /// The response we received is bigger than the configured MTU
     #[error("Received frame that exceeds configured MTU")]
@@ -85,11 +71,84 @@ pub enum ReceiveError {
     FrameTooBig,
     /// The response we received is not base64 encoded
     #[error("Failed to decode base64 data")]
-    #[diagnostic(code(zephyr_mcumgr::transport::recv::base64_decode))]
+    #[diagnostic(
+        code(zephyr_mcumgr::transport::recv::base64_decode),
+        help(
+            "The received data is corrupted. Make sure the serial port is not shared with the Zephyr console or logging."
+        )
+    )]
     Base64DecodeError(#[from] base64::DecodeSliceError),
+    /// The received frame was damaged in transit and got discarded
+    #[error("Received corrupted frame")]
+    #[diagnostic(
+        code(zephyr_mcumgr::transport::recv::corrupted),
+        help(
+            "The frame failed its checksum or framing check. Check the baud rate and the wiring, \
+             and make sure the serial port is not shared with the Zephyr console or logging."
+        )
+    )]
+    CorruptedFrame,
 }
 
 /// Defines the API of the SMP transport layer
+///
+/// Custom transports, for example for a CAN bridge or a proprietary radio, only have to
+/// implement [`Transport::send_raw_frame`], [`Transport::recv_raw_frame`] and
+/// [`Transport::set_timeout`]; the SMP header handling is provided by the other methods.
+/// Pass them to [`MCUmgrClient::new_from_transport`](crate::MCUmgrClient::new_from_transport).
+///
+/// ```no_run
+/// # use std::{sync::mpsc, time::Duration};
+/// # use zephyr_mcumgr::{MCUmgrClient, transport::{
+/// #     ReceiveError, SendError, SMP_HEADER_SIZE, SMP_TRANSFER_BUFFER_SIZE, Transport,
+/// # }};
+/// /// Exchanges complete SMP frames with a radio driver through channels.
+/// struct RadioTransport {
+///     to_radio: mpsc::Sender<Vec<u8>>,
+///     from_radio: mpsc::Receiver<Vec<u8>>,
+///     timeout: Duration,
+/// }
+///
+/// impl Transport for RadioTransport {
+///     fn send_raw_frame(
+///         &mut self,
+///         header: [u8; SMP_HEADER_SIZE],
+///         data: &[u8],
+///     ) -> Result<(), SendError> {
+///         let frame = [header.as_slice(), data].concat();
+///         self.to_radio
+///             .send(frame)
+///             .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe).into())
+///     }
+///
+///     fn recv_raw_frame<'a>(
+///         &mut self,
+///         buffer: &'a mut [u8; SMP_TRANSFER_BUFFER_SIZE],
+///     ) -> Result<&'a [u8], ReceiveError> {
+///         let frame = self
+///             .from_radio
+///             .recv_timeout(self.timeout)
+///             .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))?;
+///         let frame_buffer = buffer.get_mut(..frame.len()).ok_or(ReceiveError::FrameTooBig)?;
+///         frame_buffer.copy_from_slice(&frame);
+///         Ok(frame_buffer)
+///     }
+///
+///     fn set_timeout(&mut self, timeout: Duration) -> Result<(), miette::Report> {
+///         self.timeout = timeout;
+///         Ok(())
+///     }
+/// }
+/// # fn main() {
+/// # let (to_radio, _) = mpsc::channel();
+/// # let (_, from_radio) = mpsc::channel();
+/// let client = MCUmgrClient::new_from_transport(RadioTransport {
+///     to_radio,
+///     from_radio,
+///     timeout: Duration::from_secs(1),
+/// });
+/// # }
+/// ```
 pub trait Transport {
     /// Send a raw SMP frame over the bus.
     ///
@@ -110,7 +169,7 @@ pub trait Transport {
         buffer: &'a mut [u8; SMP_TRANSFER_BUFFER_SIZE],
     ) -> Result<&'a [u8], ReceiveError>;
 
-    /// Send an SMP frame over the bus.
+    /// Send an SMP frame over the bus, using SMP protocol version 2.
     ///
     /// # Arguments
     ///
@@ -129,9 +188,41 @@ pub trait Transport {
         group_id: u16,
         command_id: u8,
         data: &[u8],
+    ) -> Result<(), SendError> {
+        self.send_frame_with_version(
+            SmpVersion::V2,
+            write_operation,
+            sequence_num,
+            group_id,
+            command_id,
+            data,
+        )
+    }
+
+    /// Send an SMP frame over the bus, using the given SMP protocol version.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The SMP protocol version of the frame.
+    /// * `write_operation` - If the frame contains a write or read operation.
+    /// * `sequence_num` - A sequence number. Must be different every time this function is called.
+    /// * `group_id` - The group ID of the command.
+    /// * `command_id` - The command ID.
+    /// * `data` - The payload data of the command, most likely CBOR encoded.
+    ///
+    /// **IMPORTANT:** Be aware that the entire header + data must fit within one SMP protocol frame.
+    ///
+    fn send_frame_with_version(
+        &mut self,
+        version: SmpVersion,
+        write_operation: bool,
+        sequence_num: u8,
+        group_id: u16,
+        command_id: u8,
+        data: &[u8],
     ) -> Result<(), SendError> {
         let header = SmpHeader {
-            ver: 0b01,
+            ver: version.header_value(),
             op: if write_operation {
                 smp_op::WRITE
             } else {
@@ -172,35 +263,23 @@ pub trait Transport {
         command_id: u8,
     ) -> Result<&'a [u8], ReceiveError> {
         let data_size = loop {
-            let frame = self.recv_raw_frame(buffer)?;
+            let frame = match self.recv_raw_frame(buffer) {
+                // The transport already resynchronized, wait for the next frame
+                Err(ReceiveError::CorruptedFrame) => continue,
+                frame => frame?,
+            };
 
-            let (header_data, data) = frame
+            let (header_data, _) = frame
                 .split_first_chunk::<SMP_HEADER_SIZE>()
                 .ok_or(ReceiveError::UnexpectedResponse)?;
 
-            let header = SmpHeader::from_bytes(*header_data);
-
-            let expected_op = if write_operation {
-                smp_op::WRITE_RSP
-            } else {
-                smp_op::READ_RSP
-            };
-
             // Receiving packets with the wrong sequence number is not an error,
             // they should simply be silently ignored.
-            if header.sequence_num != sequence_num {
+            if SmpHeader::from_bytes(*header_data).sequence_num != sequence_num {
                 continue;
             }
 
-            if (header.group_id != group_id)
-                || (header.command_id != command_id)
-                || (header.op != expected_op)
-                || (usize::from(header.data_length) != data.len())
-            {
-                return Err(ReceiveError::UnexpectedResponse);
-            }
-
-            break data.len();
+            break response_payload(frame, write_operation, group_id, command_id)?.len();
         };
 
         Ok(&buffer[SMP_HEADER_SIZE..SMP_HEADER_SIZE + data_size])
@@ -211,4 +290,52 @@ pub trait Transport {
     /// When the device does not respond to packets within the set
     /// duration, an error will be raised.
     fn set_timeout(&mut self, timeout: Duration) -> Result<(), miette::Report>;
+
+    /// Creates a second handle to the same bus that is only used for sending.
+    ///
+    /// This allows a [`Connection`](crate::connection::Connection) to send requests
+    /// while another thread waits for a response. Transports that do not
+    /// support this return `None`, which is the default; sending then has to
+    /// wait until the receiving thread got a frame or timed out.
+    ///
+    /// Of the built-in transports, only [`UdpTransport`](udp::UdpTransport) supports this.
+    /// [`SerialTransport`](serial::SerialTransport) is generic over its port and keeps
+    /// framing state that cannot be shared between two handles, so it returns `None`.
+    fn try_clone_sender(&self) -> Option<Box<dyn Transport + Send>> {
+        None
+    }
+}
+
+/// Checks that a received frame is the response to the given request
+/// and returns its payload.
+///
+/// The sequence number is not checked. Useful for transports that override
+/// [`Transport::receive_frame`], for example to match responses differently.
+pub fn response_payload(
+    frame: &[u8],
+    write_operation: bool,
+    group_id: u16,
+    command_id: u8,
+) -> Result<&[u8], ReceiveError> {
+    let (header_data, data) = frame
+        .split_first_chunk::<SMP_HEADER_SIZE>()
+        .ok_or(ReceiveError::UnexpectedResponse)?;
+
+    let header = SmpHeader::from_bytes(*header_data);
+
+    let expected_op = if write_operation {
+        smp_op::WRITE_RSP
+    } else {
+        smp_op::READ_RSP
+    };
+
+    if (header.group_id != group_id)
+        || (header.command_id != command_id)
+        || (header.op != expected_op)
+        || (usize::from(header.data_length) != data.len())
+    {
+        return Err(ReceiveError::UnexpectedResponse);
+    }
+
+    Ok(data)
 }
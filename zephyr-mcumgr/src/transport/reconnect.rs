@@ -0,0 +1,355 @@
+use std::{
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use serialport::SerialPort;
+
+use super::serial::{ConfigurableTimeout, SerialOptions};
+use crate::client::{UsbSerialPortInfo, UsbSerialPorts};
+
+/// Determines how a disconnected serial port gets re-opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// How long to wait for the port to reappear, for example while the device reboots.
+    pub timeout: Duration,
+    /// How long to wait between two attempts to re-open the port.
+    pub poll_interval: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    /// Waits up to 10 seconds, polling every 200 milliseconds.
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            poll_interval: Duration::from_millis(200),
+        }
+    }
+}
+
+/// How the port is found again after it disconnected.
+enum PortMatcher {
+    Name(String),
+    Usb {
+        vid: u16,
+        pid: u16,
+        interface: Option<u8>,
+        serial_number: Option<String>,
+    },
+}
+
+impl PortMatcher {
+    /// Finds the current name of the port.
+    fn port_name(&self) -> Option<String> {
+        match self {
+            Self::Name(name) => Some(name.clone()),
+            Self::Usb {
+                vid,
+                pid,
+                interface,
+                serial_number,
+            } => UsbSerialPorts::available()
+                .ok()?
+                .0
+                .into_iter()
+                .find(|port| {
+                    let info = &port.port_info;
+                    let same_device = match serial_number {
+                        // The serial number survives firmware updates that change the PID
+                        Some(serial_number) => info.serial_number.as_ref() == Some(serial_number),
+                        None => info.vid == *vid && info.pid == *pid,
+                    };
+                    same_device && info.interface == *interface
+                })
+                .map(|port| port.port_name),
+        }
+    }
+}
+
+/// A serial port that gets re-opened automatically when it disconnects.
+///
+/// USB CDC devices re-enumerate when they reboot, for example during a firmware update,
+/// which invalidates the open port. This wrapper then waits for the port to reappear
+/// according to its [`ReconnectPolicy`] and re-opens it; USB ports are found again
+/// through their USB serial number, even if they got a different name.
+///
+/// The failed operation returns an error of kind [`io::ErrorKind::ConnectionReset`]
+/// after a successful reconnect, which makes the [`Connection`](crate::connection::Connection)
+/// send the pending request again.
+///
+/// ```no_run
+/// # use zephyr_mcumgr::{MCUmgrClient, transport::reconnect::{ReconnectingSerialPort, ReconnectPolicy}};
+/// # use std::time::Duration;
+/// # fn main() {
+/// let serial = ReconnectingSerialPort::open(
+///     "/dev/ttyACM0",
+///     115200,
+///     Duration::from_secs(2),
+///     ReconnectPolicy::default(),
+/// )
+/// .unwrap();
+///
+/// let client = MCUmgrClient::new_from_serial(serial);
+/// # }
+/// ```
+pub struct ReconnectingSerialPort {
+    port: Box<dyn SerialPort>,
+    matcher: PortMatcher,
+    baud_rate: u32,
+    timeout: Duration,
+    options: SerialOptions,
+    policy: ReconnectPolicy,
+}
+
+impl ReconnectingSerialPort {
+    /// Opens a serial port by name; it gets re-opened under the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `port_name` - The name of the port, like `/dev/ttyACM0` or `COM3`.
+    /// * `baud_rate` - The baud rate the port should operate at.
+    /// * `timeout` - The communication timeout.
+    /// * `policy` - How the port gets re-opened after it disconnected.
+    ///
+    pub fn open(
+        port_name: impl Into<String>,
+        baud_rate: u32,
+        timeout: Duration,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, serialport::Error> {
+        Self::open_with(
+            PortMatcher::Name(port_name.into()),
+            baud_rate,
+            timeout,
+            SerialOptions::default(),
+            policy,
+        )
+    }
+
+    /// Opens a USB serial port; it gets re-opened by its USB serial number.
+    ///
+    /// Without a serial number, the port is found again by its VID, PID and interface.
+    ///
+    /// # Arguments
+    ///
+    /// * `port` - The port to open, see [`UsbSerialPorts::available`].
+    /// * `baud_rate` - The baud rate the port should operate at.
+    /// * `timeout` - The communication timeout.
+    /// * `policy` - How the port gets re-opened after it disconnected.
+    ///
+    pub fn open_usb(
+        port: &UsbSerialPortInfo,
+        baud_rate: u32,
+        timeout: Duration,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, serialport::Error> {
+        Self::open_usb_with_options(port, baud_rate, timeout, SerialOptions::default(), policy)
+    }
+
+    /// Same as [`ReconnectingSerialPort::open_usb`], but with custom line settings.
+    pub(crate) fn open_usb_with_options(
+        port: &UsbSerialPortInfo,
+        baud_rate: u32,
+        timeout: Duration,
+        options: SerialOptions,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, serialport::Error> {
+        let info = &port.port_info;
+        let mut serial = Self::open_with(
+            PortMatcher::Name(port.port_name.clone()),
+            baud_rate,
+            timeout,
+            options,
+            policy,
+        )?;
+        serial.matcher = PortMatcher::Usb {
+            vid: info.vid,
+            pid: info.pid,
+            interface: info.interface,
+            serial_number: info.serial_number.clone(),
+        };
+        Ok(serial)
+    }
+
+    fn open_with(
+        matcher: PortMatcher,
+        baud_rate: u32,
+        timeout: Duration,
+        options: SerialOptions,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, serialport::Error> {
+        let port_name = matcher.port_name().ok_or_else(|| {
+            serialport::Error::new(serialport::ErrorKind::NoDevice, "Port not found")
+        })?;
+
+        Ok(Self {
+            port: options.open(&port_name, baud_rate, timeout)?,
+            matcher,
+            baud_rate,
+            timeout,
+            // The device gets reset once, not on every reconnect
+            options: SerialOptions {
+                dtr_pulse: None,
+                ..options
+            },
+            policy,
+        })
+    }
+
+    /// Re-opens the port if the result of an operation indicates a disconnect.
+    fn handle_disconnect<T>(&mut self, result: io::Result<T>) -> io::Result<T> {
+        let Self {
+            port,
+            matcher,
+            baud_rate,
+            timeout,
+            options,
+            policy,
+        } = self;
+
+        handle_disconnect(result, port, policy, || {
+            let port_name = matcher.port_name()?;
+            options
+                .open(&port_name, *baud_rate, *timeout)
+                .inspect_err(|e| log::debug!("Failed to re-open {port_name}: {e}"))
+                .ok()
+        })
+    }
+}
+
+/// Replaces `port` through `reopen` if the result of an operation indicates a disconnect.
+///
+/// Polls `reopen` according to the policy until it returns a port. On success, the
+/// operation fails with [`io::ErrorKind::ConnectionReset`], otherwise with its original error.
+fn handle_disconnect<T, P>(
+    result: io::Result<T>,
+    port: &mut P,
+    policy: &ReconnectPolicy,
+    mut reopen: impl FnMut() -> Option<P>,
+) -> io::Result<T> {
+    let cause = match result {
+        Err(e)
+            if !matches!(
+                e.kind(),
+                io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+            ) =>
+        {
+            e
+        }
+        result => return result,
+    };
+
+    log::warn!("Serial port disconnected ({cause}), trying to re-open it");
+
+    let deadline = Instant::now() + policy.timeout;
+    loop {
+        if let Some(reopened) = reopen() {
+            log::info!("Serial port re-opened");
+            *port = reopened;
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionReset,
+                "Serial port was re-opened",
+            ));
+        }
+
+        if Instant::now() >= deadline {
+            log::warn!("Serial port did not reappear");
+            return Err(cause);
+        }
+
+        std::thread::sleep(policy.poll_interval);
+    }
+}
+
+impl Read for ReconnectingSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = match self.port.read(buf) {
+            // A vanished port reports end-of-file on some platforms
+            Ok(0) if !buf.is_empty() => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Serial port unexpectedly returned end-of-file",
+            )),
+            result => result,
+        };
+        self.handle_disconnect(result)
+    }
+}
+
+impl Write for ReconnectingSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let result = self.port.write(buf);
+        self.handle_disconnect(result)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.port.flush();
+        self.handle_disconnect(result)
+    }
+}
+
+impl ConfigurableTimeout for ReconnectingSerialPort {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), miette::Report> {
+        self.timeout = timeout;
+        ConfigurableTimeout::set_timeout(&mut self.port, timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: ReconnectPolicy = ReconnectPolicy {
+        timeout: Duration::from_millis(200),
+        poll_interval: Duration::from_millis(1),
+    };
+
+    #[test]
+    fn reopens_disconnected_port() {
+        let mut port = "old";
+        let mut attempts = 0;
+        let result = handle_disconnect::<(), _>(
+            Err(io::ErrorKind::BrokenPipe.into()),
+            &mut port,
+            &POLICY,
+            || {
+                attempts += 1;
+                (attempts == 2).then_some("new")
+            },
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::ConnectionReset);
+        assert_eq!(port, "new");
+        assert_eq!(attempts, 2);
+    }
+
+    #[test]
+    fn gives_up_if_port_does_not_reappear() {
+        let mut port = "old";
+        let result = handle_disconnect::<(), _>(
+            Err(io::ErrorKind::BrokenPipe.into()),
+            &mut port,
+            &POLICY,
+            || None,
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(port, "old");
+    }
+
+    #[test]
+    fn ignores_timeouts() {
+        let mut port = "old";
+        let result = handle_disconnect::<(), _>(
+            Err(io::ErrorKind::TimedOut.into()),
+            &mut port,
+            &POLICY,
+            || panic!("timeouts are no disconnects"),
+        );
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(
+            handle_disconnect(Ok(42), &mut port, &POLICY, || None).unwrap(),
+            42
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use std::{
+    io::{self, Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use super::serial::ConfigurableTimeout;
+
+/// Telnet command bytes, see RFC 854.
+mod telnet {
+    pub(super) const IAC: u8 = 255;
+    pub(super) const DONT: u8 = 254;
+    pub(super) const DO: u8 = 253;
+    pub(super) const WONT: u8 = 252;
+    pub(super) const WILL: u8 = 251;
+    pub(super) const SB: u8 = 250;
+    pub(super) const SE: u8 = 240;
+
+    pub(super) const BINARY: u8 = 0;
+    pub(super) const SUPPRESS_GO_AHEAD: u8 = 3;
+    pub(super) const COM_PORT_OPTION: u8 = 44;
+
+    /// RFC 2217 `SET-BAUDRATE`
+    pub(super) const SET_BAUDRATE: u8 = 1;
+    /// RFC 2217 `SET-DATASIZE`
+    pub(super) const SET_DATASIZE: u8 = 2;
+    /// RFC 2217 `SET-PARITY`
+    pub(super) const SET_PARITY: u8 = 3;
+    /// RFC 2217 `SET-STOPSIZE`
+    pub(super) const SET_STOPSIZE: u8 = 4;
+}
+
+/// Possible error values of [`RemoteSerialPort::open`].
+#[derive(Error, Debug, Diagnostic)]
+pub enum RemoteSerialError {
+    /// The URL could not be parsed.
+    #[error("Invalid remote serial URL '{0}'")]
+    #[diagnostic(
+        code(zephyr_mcumgr::transport::remote_serial::invalid_url),
+        help("Use `tcp://<host>:<port>` or `rfc2217://<host>:<port>[?baud=<baud rate>]`.")
+    )]
+    InvalidUrl(String),
+    /// Connecting to the serial bridge failed.
+    #[error("Failed to connect to serial bridge")]
+    #[diagnostic(code(zephyr_mcumgr::transport::remote_serial::connect))]
+    ConnectFailed(#[from] io::Error),
+}
+
+/// The position within a telnet command sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TelnetState {
+    Data,
+    Iac,
+    Negotiation(u8),
+    Subnegotiation,
+    SubnegotiationIac,
+}
+
+/// A serial port that is bridged over TCP, for example through `ser2net`.
+///
+/// Supports raw TCP bridges and bridges that speak the telnet based
+/// [RFC 2217](https://www.rfc-editor.org/rfc/rfc2217) protocol, which
+/// additionally allows configuring the baud rate of the remote port.
+///
+/// ```no_run
+/// # use zephyr_mcumgr::{MCUmgrClient, transport::remote_serial::RemoteSerialPort};
+/// # use std::time::Duration;
+/// # fn main() {
+/// let serial = RemoteSerialPort::open("rfc2217://lab-pi:4000?baud=115200", Duration::from_secs(2))
+///     .unwrap();
+///
+/// let client = MCUmgrClient::new_from_serial(serial);
+/// # }
+/// ```
+pub struct RemoteSerialPort {
+    stream: TcpStream,
+    /// `None` for raw TCP bridges.
+    telnet: Option<TelnetState>,
+}
+
+impl RemoteSerialPort {
+    /// Connects to a serial bridge given by URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The address of the bridge:
+    ///   - `tcp://<host>:<port>` or `<host>:<port>` for raw TCP bridges.
+    ///   - `rfc2217://<host>:<port>` for RFC 2217 bridges; `?baud=<baud rate>` configures the remote port.
+    /// * `timeout` - The communication timeout.
+    ///
+    pub fn open(url: &str, timeout: Duration) -> Result<Self, RemoteSerialError> {
+        let invalid_url = || RemoteSerialError::InvalidUrl(url.to_string());
+
+        if let Some(rest) = url.strip_prefix("rfc2217://") {
+            let (addr, baud_rate) = match rest.split_once('?') {
+                Some((addr, query)) => {
+                    let baud_rate = query
+                        .strip_prefix("baud=")
+                        .and_then(|baud_rate| baud_rate.parse().ok())
+                        .ok_or_else(invalid_url)?;
+                    (addr, Some(baud_rate))
+                }
+                None => (rest, None),
+            };
+            if addr.is_empty() {
+                return Err(invalid_url());
+            }
+            Ok(Self::connect_rfc2217(addr, baud_rate, timeout)?)
+        } else {
+            let addr = url.strip_prefix("tcp://").unwrap_or(url);
+            if addr.is_empty() || addr.contains("://") {
+                return Err(invalid_url());
+            }
+            Ok(Self::connect_raw(addr, timeout)?)
+        }
+    }
+
+    /// Connects to a bridge that forwards the serial data unmodified.
+    pub fn connect_raw(addr: &str, timeout: Duration) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+
+        let mut port = Self {
+            stream,
+            telnet: None,
+        };
+        port.set_stream_timeout(timeout)?;
+        Ok(port)
+    }
+
+    /// Connects to an RFC 2217 bridge.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The `<host>:<port>` of the bridge.
+    /// * `baud_rate` - If set, configures the remote port to this baud rate and 8N1.
+    /// * `timeout` - The communication timeout.
+    ///
+    pub fn connect_rfc2217(
+        addr: &str,
+        baud_rate: Option<u32>,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        let mut port = Self::connect_raw(addr, timeout)?;
+        port.telnet = Some(TelnetState::Data);
+
+        use telnet::*;
+        let mut handshake = [
+            (WILL, BINARY),
+            (DO, BINARY),
+            (WILL, SUPPRESS_GO_AHEAD),
+            (DO, SUPPRESS_GO_AHEAD),
+            (WILL, COM_PORT_OPTION),
+        ]
+        .into_iter()
+        .flat_map(|(command, option)| [IAC, command, option])
+        .collect::<Vec<_>>();
+        if let Some(baud_rate) = baud_rate {
+            let mut set = |command: u8, value: &[u8]| {
+                handshake.extend_from_slice(&[IAC, SB, COM_PORT_OPTION, command]);
+                handshake.extend(escape_iac(value));
+                handshake.extend_from_slice(&[IAC, SE]);
+            };
+            set(SET_BAUDRATE, &baud_rate.to_be_bytes());
+            set(SET_DATASIZE, &[8]);
+            // 1 = no parity, 1 = one stop bit
+            set(SET_PARITY, &[1]);
+            set(SET_STOPSIZE, &[1]);
+        }
+        port.stream.write_all(&handshake)?;
+
+        Ok(port)
+    }
+
+    fn set_stream_timeout(&mut self, timeout: Duration) -> io::Result<()> {
+        // A zero duration means "no timeout" for sockets, but "fail immediately" for serial ports
+        let timeout = Some(timeout.max(Duration::from_millis(1)));
+        self.stream.set_read_timeout(timeout)?;
+        self.stream.set_write_timeout(timeout)
+    }
+
+    /// Removes telnet commands from received data in place and answers negotiations.
+    ///
+    /// Returns the number of remaining data bytes.
+    fn filter_telnet(&mut self, data: &mut [u8]) -> io::Result<usize> {
+        use telnet::*;
+
+        let Some(mut state) = self.telnet else {
+            return Ok(data.len());
+        };
+
+        let mut replies = vec![];
+        let mut len = 0;
+        for index in 0..data.len() {
+            let byte = data[index];
+            state = match (state, byte) {
+                (TelnetState::Data, IAC) => TelnetState::Iac,
+                (TelnetState::Data, _) => {
+                    data[len] = byte;
+                    len += 1;
+                    TelnetState::Data
+                }
+                (TelnetState::Iac, IAC) => {
+                    data[len] = IAC;
+                    len += 1;
+                    TelnetState::Data
+                }
+                (TelnetState::Iac, WILL | WONT | DO | DONT) => TelnetState::Negotiation(byte),
+                (TelnetState::Iac, SB) => TelnetState::Subnegotiation,
+                (TelnetState::Iac, _) => TelnetState::Data,
+                (TelnetState::Negotiation(command), option) => {
+                    let supported = matches!(option, BINARY | SUPPRESS_GO_AHEAD)
+                        || (option == COM_PORT_OPTION && command == DO);
+                    // Requests for our own options acknowledge the handshake
+                    match command {
+                        WILL if !supported => replies.extend_from_slice(&[IAC, DONT, option]),
+                        DO if !supported => replies.extend_from_slice(&[IAC, WONT, option]),
+                        _ => {}
+                    }
+                    TelnetState::Data
+                }
+                // Notifications about the remote port are not needed
+                (TelnetState::Subnegotiation, IAC) => TelnetState::SubnegotiationIac,
+                (TelnetState::Subnegotiation, _) => TelnetState::Subnegotiation,
+                (TelnetState::SubnegotiationIac, SE) => TelnetState::Data,
+                (TelnetState::SubnegotiationIac, _) => TelnetState::Subnegotiation,
+            };
+        }
+        self.telnet = Some(state);
+
+        if !replies.is_empty() {
+            self.stream.write_all(&replies)?;
+        }
+
+        Ok(len)
+    }
+}
+
+/// Doubles all `IAC` bytes, as required for data sent through telnet.
+fn escape_iac(data: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    data.iter().flat_map(|&byte| {
+        let repeat = if byte == telnet::IAC { 2 } else { 1 };
+        std::iter::repeat_n(byte, repeat)
+    })
+}
+
+impl Read for RemoteSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let num_read = self.stream.read(buf)?;
+            if num_read == 0 {
+                return Ok(0);
+            }
+
+            // Reads that only contained telnet commands must not look like end-of-file
+            let len = self.filter_telnet(&mut buf[..num_read])?;
+            if len > 0 {
+                return Ok(len);
+            }
+        }
+    }
+}
+
+impl Write for RemoteSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.telnet.is_some() {
+            let escaped = escape_iac(buf).collect::<Vec<_>>();
+            self.stream.write_all(&escaped)?;
+            Ok(buf.len())
+        } else {
+            self.stream.write(buf)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl ConfigurableTimeout for RemoteSerialPort {
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), miette::Report> {
+        use miette::IntoDiagnostic;
+        self.set_stream_timeout(timeout).into_diagnostic()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn reject_invalid_urls() {
+        for url in [
+            "",
+            "udp://localhost:4000",
+            "rfc2217://",
+            "rfc2217://host:1?baud=x",
+        ] {
+            assert!(
+                matches!(
+                    RemoteSerialPort::open(url, Duration::from_secs(1)),
+                    Err(RemoteSerialError::InvalidUrl(_))
+                ),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn rfc2217_filters_telnet_commands() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let url = format!("rfc2217://{}?baud=115200", listener.local_addr().unwrap());
+
+        let mut port = RemoteSerialPort::open(&url, Duration::from_secs(5)).unwrap();
+        let (mut bridge, _) = listener.accept().unwrap();
+
+        let mut handshake = [0; 15];
+        bridge.read_exact(&mut handshake).unwrap();
+        assert_eq!(&handshake[12..], &[255, 251, 44]);
+        let mut set_baudrate = [0; 10];
+        bridge.read_exact(&mut set_baudrate).unwrap();
+        assert_eq!(
+            set_baudrate,
+            [255, 250, 44, 1, 0, 1, 0xc2, 0, 255, 240],
+            "115200 baud"
+        );
+        let mut other_settings = [0; 21];
+        bridge.read_exact(&mut other_settings).unwrap();
+
+        // Acknowledgement, unsupported ECHO request, notification and escaped data
+        bridge
+            .write_all(&[
+                255, 253, 44, 255, 253, 1, b'a', 255, 250, 44, 107, 0, 255, 240,
+            ])
+            .unwrap();
+        bridge.write_all(&[255, 255, b'b']).unwrap();
+
+        let mut received = vec![];
+        while received.len() < 3 {
+            let mut buf = [0; 16];
+            let len = port.read(&mut buf).unwrap();
+            received.extend_from_slice(&buf[..len]);
+        }
+        assert_eq!(received, [b'a', 255, b'b']);
+
+        let mut reply = [0; 3];
+        bridge.read_exact(&mut reply).unwrap();
+        assert_eq!(reply, [255, 252, 1]);
+
+        port.write_all(&[1, 255, 2]).unwrap();
+        let mut escaped = [0; 4];
+        bridge.read_exact(&mut escaped).unwrap();
+        assert_eq!(escaped, [1, 255, 255, 2]);
+    }
+}
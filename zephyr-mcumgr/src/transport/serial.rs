@@ -1,43 +1,66 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use base64::prelude::*;
-use miette::IntoDiagnostic;
 use ringbuf::{
     LocalRb,
     storage::Heap,
     traits::{Consumer, Observer, Producer},
 };
-use serialport::SerialPort;
+
+use zephyr_mcumgr_protocol::serial::{
+    FRAME_CONTINUATION, FRAME_START, MAX_PACKET_BODY_SIZE, MAX_PACKET_SIZE, PACKET_END,
+    decode_packet, encode_packet, packet_bodies, verify_checksum,
+};
 
 use super::{ReceiveError, SMP_HEADER_SIZE, SMP_TRANSFER_BUFFER_SIZE, SendError, Transport};
 
+/// The maximum length of a console line, in bytes; longer lines get split.
+pub const MAX_CONSOLE_LINE_LENGTH: usize = 1024;
+
+/// A callback that receives text lines the device printed outside of SMP frames.
+pub type ConsoleLineHandler = Box<dyn FnMut(&str) + Send>;
+
+/// Pacing of the data written to slow serial links, see [`SerialTransport::with_pacing`].
+///
+/// Some bridged UARTs lose bytes when SMP transport packets are written back to back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerialPacing {
+    /// The minimum time between two writes to the serial port.
+    pub inter_frame_delay: Duration,
+    /// The maximum number of bytes per write; longer packets are split into multiple writes.
+    ///
+    /// `None` writes every SMP transport packet at once.
+    pub max_write_burst: Option<usize>,
+}
+
+impl SerialPacing {
+    fn is_enabled(&self) -> bool {
+        !self.inter_frame_delay.is_zero() || self.max_write_burst.is_some()
+    }
+}
+
 /// A transport layer implementation for serial ports.
+///
+/// The serial port may be shared with the Zephyr console; everything outside
+/// of SMP frames is collected into text lines and handed to the
+/// [`ConsoleLineHandler`], if one is set.
+///
+/// Frames that fail their checksum or framing check are reported as
+/// [`ReceiveError::CorruptedFrame`]; the remains of their SMP transport packets
+/// get discarded up to the packet end, so that they do not end up in the
+/// console output while the console lines after them still do.
 pub struct SerialTransport<T> {
     transfer_buffer: Box<[u8]>,
     body_buffer: Box<[u8]>,
     serial: T,
-    crc_algo: crc::Crc<u16>,
     read_buffer: LocalRb<Heap<u8>>,
+    console_line: Vec<u8>,
+    console_handler: Option<ConsoleLineHandler>,
+    /// Set while the end of the current SMP transport packet has not been read yet.
+    packet_pending: bool,
+    pacing: SerialPacing,
+    last_write: Option<Instant>,
 }
 
-fn fill_buffer_with_data<'a, I: Iterator<Item = u8>>(
-    buffer: &'a mut [u8],
-    data_iter: &mut I,
-) -> &'a [u8] {
-    for (pos, val) in buffer.iter_mut().enumerate() {
-        if let Some(next) = data_iter.next() {
-            *val = next;
-        } else {
-            return &buffer[..pos];
-        }
-    }
-
-    buffer
-}
-
-/// See Zephyr's [`MCUMGR_SERIAL_MAX_FRAME`](https://github.com/zephyrproject-rtos/zephyr/blob/v4.2.1/include/zephyr/mgmt/mcumgr/transport/serial.h#L18).
-const SERIAL_TRANSPORT_ZEPHYR_MTU: usize = 127;
-
 impl<T> SerialTransport<T>
 where
     T: std::io::Write + std::io::Read,
@@ -49,56 +72,105 @@ where
     /// * `serial` - A serial port object, like [`serialport::SerialPort`].
     ///
     pub fn new(serial: T) -> Self {
-        let mtu = SERIAL_TRANSPORT_ZEPHYR_MTU;
         Self {
             serial,
-            transfer_buffer: vec![0u8; mtu].into_boxed_slice(),
-            body_buffer: vec![0u8; ((mtu - 3) / 4) * 3].into_boxed_slice(),
-            crc_algo: crc::Crc::<u16>::new(&crc::CRC_16_XMODEM),
+            transfer_buffer: vec![0u8; MAX_PACKET_SIZE].into_boxed_slice(),
+            body_buffer: vec![0u8; MAX_PACKET_BODY_SIZE].into_boxed_slice(),
             read_buffer: LocalRb::new(4096),
+            console_line: Vec::new(),
+            console_handler: None,
+            packet_pending: false,
+            pacing: SerialPacing::default(),
+            last_write: None,
         }
     }
 
-    /// Take a raw data stream, split it into SMP transport frames and transmit them.
-    ///
-    /// # Arguments
+    /// Sets the handler for console output that is interleaved with SMP frames.
     ///
-    /// * `data_iter` - An iterator that produces the binary data of the message to send.
+    /// Without a handler, console lines are only logged on debug level.
+    pub fn with_console_handler(mut self, handler: ConsoleLineHandler) -> Self {
+        self.console_handler = Some(handler);
+        self
+    }
+
+    /// Paces the data written to the serial port.
     ///
-    fn send_chunked<I: Iterator<Item = u8>>(&mut self, mut data_iter: I) -> Result<(), SendError> {
-        self.transfer_buffer[0] = 6;
-        self.transfer_buffer[1] = 9;
+    /// Every write gets flushed, so the delay applies to the data on the wire.
+    pub fn with_pacing(mut self, pacing: SerialPacing) -> Self {
+        self.pacing = pacing;
+        self
+    }
 
-        loop {
-            let body = fill_buffer_with_data(&mut self.body_buffer, &mut data_iter);
+    /// Writes data to the serial port, honoring the configured [`SerialPacing`].
+    fn write_paced(&mut self, data: &[u8]) -> std::io::Result<()> {
+        if !self.pacing.is_enabled() {
+            return self.serial.write_all(data);
+        }
+
+        let burst_size = self.pacing.max_write_burst.unwrap_or(usize::MAX).max(1);
+        for burst in data.chunks(burst_size) {
+            if let Some(last_write) = self.last_write {
+                let elapsed = last_write.elapsed();
+                if elapsed < self.pacing.inter_frame_delay {
+                    std::thread::sleep(self.pacing.inter_frame_delay - elapsed);
+                }
+            }
+
+            self.serial.write_all(burst)?;
+            self.serial.flush()?;
+            self.last_write = Some(Instant::now());
+        }
 
-            if body.is_empty() {
-                break Ok(());
+        Ok(())
+    }
+
+    /// Collects a byte that is not part of an SMP frame into the current console line.
+    ///
+    /// Lines longer than [`MAX_CONSOLE_LINE_LENGTH`] get split, so that a device
+    /// that never sends a newline cannot grow the buffer indefinitely.
+    fn push_console_byte(&mut self, byte: u8) {
+        if byte != b'\n' {
+            self.console_line.push(byte);
+            if self.console_line.len() >= MAX_CONSOLE_LINE_LENGTH {
+                self.flush_console_line();
             }
+            return;
+        }
 
-            let base64_len = BASE64_STANDARD
-                .encode_slice(body, &mut self.transfer_buffer[2..])
-                .expect("Transfer buffer overflow; this is a bug. Please report.");
+        self.flush_console_line();
+    }
 
-            self.transfer_buffer[base64_len + 2] = 0x0a;
+    /// Hands the current console line to the handler and starts a new one.
+    fn flush_console_line(&mut self) {
+        let line = String::from_utf8_lossy(&self.console_line);
+        let line = line.trim_end_matches('\r');
+        if !line.is_empty() {
+            match &mut self.console_handler {
+                Some(handler) => handler(line),
+                None => log::debug!("Console: {line}"),
+            }
+        }
+        self.console_line.clear();
+    }
 
-            self.serial
-                .write_all(&self.transfer_buffer[..base64_len + 3])?;
+    /// Split an SMP frame into SMP transport packets and transmit them.
+    fn send_chunked(&mut self, bodies: Vec<Vec<u8>>) -> Result<(), SendError> {
+        for (index, body) in bodies.iter().enumerate() {
+            let first = index == 0;
+            let packet = encode_packet(body, first);
+
+            self.write_paced(&packet)?;
 
             log::debug!(
                 "Sent Chunk ({}, {} bytes raw, {} bytes encoded)",
-                if self.transfer_buffer[0] == 6 {
-                    "initial"
-                } else {
-                    "partial"
-                },
+                if first { "initial" } else { "partial" },
                 body.len(),
-                base64_len,
+                packet.len() - 3,
             );
-
-            self.transfer_buffer[0] = 4;
-            self.transfer_buffer[1] = 20;
+            log::trace!("Sent chunk data: {}", hex::encode(body));
         }
+
+        Ok(())
     }
 
     /// Receive an SMP transport frame and decode it.
@@ -112,8 +184,11 @@ where
     /// The received data
     ///
     fn recv_chunk(&mut self, first: bool) -> Result<&[u8], ReceiveError> {
-        let expected_header_0 = if first { 6 } else { 4 };
-        let expected_header_1 = if first { 9 } else { 20 };
+        let [expected_header_0, expected_header_1] = if first {
+            FRAME_START
+        } else {
+            FRAME_CONTINUATION
+        };
 
         loop {
             while self.read_buffer.occupied_len() < 2 {
@@ -131,11 +206,30 @@ where
             }
 
             let current = self.read_buffer.try_pop().unwrap();
-            let next = self.read_buffer.try_peek().unwrap();
-            if current == expected_header_0 && *next == expected_header_1 {
+            let next = *self.read_buffer.try_peek().unwrap();
+            if current == expected_header_0 && next == expected_header_1 {
                 self.read_buffer.try_pop().unwrap();
+                self.packet_pending = true;
                 break;
             }
+
+            if self.packet_pending {
+                // Remains of a corrupted packet
+                if current == PACKET_END {
+                    self.packet_pending = false;
+                }
+                continue;
+            }
+
+            if first && [current, next] == FRAME_CONTINUATION {
+                // Remaining packets of a corrupted frame
+                self.read_buffer.try_pop().unwrap();
+                self.packet_pending = true;
+                continue;
+            }
+
+            // Not part of an SMP frame, most likely console output on a shared UART
+            self.push_console_byte(current);
         }
 
         let mut base64_data = None;
@@ -158,7 +252,8 @@ where
                 }
             };
 
-            if data == 0x0a {
+            if data == PACKET_END {
+                self.packet_pending = false;
                 base64_data = Some(&self.transfer_buffer[..pos]);
                 break;
             }
@@ -166,12 +261,12 @@ where
             *elem = data;
         }
 
-        if let Some(0x0a) = self.read_buffer.try_peek() {
+        if let Some(&PACKET_END) = self.read_buffer.try_peek() {
             base64_data = Some(&self.transfer_buffer);
         }
 
         if let Some(base64_data) = base64_data {
-            let len = BASE64_STANDARD.decode_slice(base64_data, &mut self.body_buffer)?;
+            let len = decode_packet(base64_data, &mut self.body_buffer)?;
 
             log::debug!(
                 "Received Chunk ({}, {} bytes raw, {} bytes decoded)",
@@ -179,44 +274,18 @@ where
                 base64_data.len(),
                 len
             );
+            log::trace!(
+                "Received chunk data: {}",
+                hex::encode(&self.body_buffer[..len])
+            );
             Ok(&self.body_buffer[..len])
         } else {
             Err(ReceiveError::FrameTooBig)
         }
     }
-}
 
-impl<T> Transport for SerialTransport<T>
-where
-    T: std::io::Write + std::io::Read + ConfigurableTimeout,
-{
-    fn send_raw_frame(
-        &mut self,
-        header: [u8; SMP_HEADER_SIZE],
-        data: &[u8],
-    ) -> Result<(), SendError> {
-        log::debug!("Sending SMP Frame ({} bytes)", data.len());
-
-        let checksum = {
-            let mut digest = self.crc_algo.digest();
-            digest.update(&header);
-            digest.update(data);
-            digest.finalize().to_be_bytes()
-        };
-
-        let size = u16::try_from(header.len() + data.len() + checksum.len())
-            .map_err(|_| SendError::DataTooBig)?
-            .to_be_bytes();
-
-        self.send_chunked(
-            size.into_iter()
-                .chain(header)
-                .chain(data.iter().copied())
-                .chain(checksum),
-        )
-    }
-
-    fn recv_raw_frame<'a>(
+    /// Receive the SMP transport packets of a frame and reassemble them.
+    fn recv_frame<'a>(
         &mut self,
         buffer: &'a mut [u8; SMP_TRANSFER_BUFFER_SIZE],
     ) -> Result<&'a [u8], ReceiveError> {
@@ -251,21 +320,41 @@ where
             current_result_buffer.copy_from_slice(next_chunk);
         }
 
-        let (data, checksum_data) = result_buffer
-            .split_last_chunk::<2>()
-            .ok_or(ReceiveError::UnexpectedResponse)?;
+        let data = verify_checksum(result_buffer).ok_or(ReceiveError::UnexpectedResponse)?;
 
-        let expected_checksum = u16::from_be_bytes(*checksum_data);
+        log::debug!("Received SMP Frame ({} bytes)", data.len());
 
-        let actual_checksum = self.crc_algo.checksum(data);
+        Ok(data)
+    }
+}
 
-        if expected_checksum != actual_checksum {
-            return Err(ReceiveError::UnexpectedResponse);
-        }
+impl<T> Transport for SerialTransport<T>
+where
+    T: std::io::Write + std::io::Read + ConfigurableTimeout,
+{
+    fn send_raw_frame(
+        &mut self,
+        header: [u8; SMP_HEADER_SIZE],
+        data: &[u8],
+    ) -> Result<(), SendError> {
+        log::debug!("Sending SMP Frame ({} bytes)", data.len());
 
-        log::debug!("Received SMP Frame ({} bytes)", data.len());
+        let bodies = packet_bodies(&header, data).map_err(|_| SendError::DataTooBig)?;
+        self.send_chunked(bodies)
+    }
 
-        Ok(data)
+    fn recv_raw_frame<'a>(
+        &mut self,
+        buffer: &'a mut [u8; SMP_TRANSFER_BUFFER_SIZE],
+    ) -> Result<&'a [u8], ReceiveError> {
+        match self.recv_frame(buffer) {
+            Err(ReceiveError::TransportError(e)) => Err(e.into()),
+            Err(e) => {
+                log::debug!("Discarding corrupted frame: {e}");
+                Err(ReceiveError::CorruptedFrame)
+            }
+            frame => frame,
+        }
     }
 
     fn set_timeout(&mut self, timeout: std::time::Duration) -> Result<(), miette::Report> {
@@ -282,8 +371,94 @@ pub trait ConfigurableTimeout {
     fn set_timeout(&mut self, duration: Duration) -> Result<(), miette::Report>;
 }
 
-impl<T: AsMut<dyn SerialPort> + ?Sized> ConfigurableTimeout for T {
+#[cfg(feature = "serialport")]
+impl<T: AsMut<dyn serialport::SerialPort> + ?Sized> ConfigurableTimeout for T {
     fn set_timeout(&mut self, timeout: Duration) -> Result<(), miette::Report> {
-        SerialPort::set_timeout(self.as_mut(), timeout).into_diagnostic()
+        use miette::IntoDiagnostic;
+        serialport::SerialPort::set_timeout(self.as_mut(), timeout).into_diagnostic()
+    }
+}
+
+/// Line settings of serial ports that get opened by this crate,
+/// see [`MCUmgrClientBuilder::serial_options`](crate::client::MCUmgrClientBuilder::serial_options).
+///
+/// Defaults to 8N1 without flow control, leaving the control lines untouched.
+#[cfg(feature = "serialport")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SerialOptions {
+    /// The number of data bits per character.
+    pub data_bits: serialport::DataBits,
+    /// The parity checking mode.
+    pub parity: serialport::Parity,
+    /// The number of stop bits.
+    pub stop_bits: serialport::StopBits,
+    /// The flow control mode, for example RTS/CTS hardware flow control.
+    pub flow_control: serialport::FlowControl,
+    /// The level of the DTR line after opening the port, or `None` to keep the driver's default.
+    pub dtr: Option<bool>,
+    /// The level of the RTS line after opening the port, or `None` to keep the driver's default.
+    ///
+    /// Has no effect with hardware flow control, which drives RTS itself.
+    pub rts: Option<bool>,
+    /// Inverts DTR for the given time after opening the port, then restores its level.
+    ///
+    /// Boards that connect DTR to their reset line use this to exit reset.
+    /// The level defaults to asserted if [`SerialOptions::dtr`] is `None`.
+    pub dtr_pulse: Option<Duration>,
+}
+
+#[cfg(feature = "serialport")]
+impl Default for SerialOptions {
+    fn default() -> Self {
+        Self {
+            data_bits: serialport::DataBits::Eight,
+            parity: serialport::Parity::None,
+            stop_bits: serialport::StopBits::One,
+            flow_control: serialport::FlowControl::None,
+            dtr: None,
+            rts: None,
+            dtr_pulse: None,
+        }
+    }
+}
+
+#[cfg(feature = "serialport")]
+impl SerialOptions {
+    /// Opens a serial port with these settings.
+    ///
+    /// # Arguments
+    ///
+    /// * `port_name` - The name of the port, like `/dev/ttyACM0` or `COM3`.
+    /// * `baud_rate` - The baud rate the port should operate at.
+    /// * `timeout` - The communication timeout.
+    ///
+    pub fn open(
+        &self,
+        port_name: &str,
+        baud_rate: u32,
+        timeout: Duration,
+    ) -> Result<Box<dyn serialport::SerialPort>, serialport::Error> {
+        let mut port = serialport::new(port_name, baud_rate)
+            .data_bits(self.data_bits)
+            .parity(self.parity)
+            .stop_bits(self.stop_bits)
+            .flow_control(self.flow_control)
+            .timeout(timeout)
+            .open()?;
+
+        if let Some(rts) = self.rts {
+            port.write_request_to_send(rts)?;
+        }
+        if let Some(dtr) = self.dtr {
+            port.write_data_terminal_ready(dtr)?;
+        }
+        if let Some(pulse) = self.dtr_pulse {
+            let level = self.dtr.unwrap_or(true);
+            port.write_data_terminal_ready(!level)?;
+            std::thread::sleep(pulse);
+            port.write_data_terminal_ready(level)?;
+        }
+
+        Ok(port)
     }
 }
@@ -0,0 +1,136 @@
+use std::{
+    io,
+    net::{ToSocketAddrs, UdpSocket},
+    time::Duration,
+};
+
+use miette::IntoDiagnostic;
+
+use super::{ReceiveError, SMP_HEADER_SIZE, SMP_TRANSFER_BUFFER_SIZE, SendError, Transport};
+
+/// A transport that exchanges SMP frames as UDP datagrams.
+///
+/// Every datagram contains exactly one SMP frame, as expected by Zephyr's
+/// `CONFIG_MCUMGR_TRANSPORT_UDP`.
+pub struct UdpTransport {
+    socket: UdpSocket,
+}
+
+impl UdpTransport {
+    /// The default port of Zephyr's UDP SMP server.
+    pub const DEFAULT_PORT: u16 = 1337;
+
+    /// Creates a transport that talks to the SMP server at the given address.
+    ///
+    /// # Arguments
+    ///
+    /// * `addr` - The address of the device, e.g. `("192.168.1.42", UdpTransport::DEFAULT_PORT)`.
+    /// * `timeout` - The communication timeout.
+    ///
+    pub fn new(addr: impl ToSocketAddrs, timeout: Duration) -> io::Result<Self> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Address resolved to nothing")
+        })?;
+
+        let socket = if addr.is_ipv4() {
+            UdpSocket::bind(("0.0.0.0", 0))?
+        } else {
+            UdpSocket::bind(("::", 0))?
+        };
+        socket.connect(addr)?;
+        socket.set_read_timeout(Some(timeout))?;
+
+        Ok(Self { socket })
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send_raw_frame(
+        &mut self,
+        header: [u8; SMP_HEADER_SIZE],
+        data: &[u8],
+    ) -> Result<(), SendError> {
+        log::debug!("Sending SMP Frame ({} bytes)", data.len());
+
+        let frame = header
+            .into_iter()
+            .chain(data.iter().copied())
+            .collect::<Vec<_>>();
+        self.socket.send(&frame)?;
+
+        Ok(())
+    }
+
+    fn recv_raw_frame<'a>(
+        &mut self,
+        buffer: &'a mut [u8; SMP_TRANSFER_BUFFER_SIZE],
+    ) -> Result<&'a [u8], ReceiveError> {
+        let len = self.socket.recv(buffer).map_err(|e| {
+            // Unix reports an expired read timeout as `WouldBlock`
+            if e.kind() == io::ErrorKind::WouldBlock {
+                io::Error::new(io::ErrorKind::TimedOut, e)
+            } else {
+                e
+            }
+        })?;
+
+        log::debug!("Received SMP Frame ({} bytes)", len);
+
+        Ok(&buffer[..len])
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), miette::Report> {
+        self.socket
+            .set_read_timeout(Some(timeout))
+            .into_diagnostic()
+    }
+
+    fn try_clone_sender(&self) -> Option<Box<dyn Transport + Send>> {
+        let socket = self.socket.try_clone().ok()?;
+        Some(Box::new(Self { socket }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exchanges_frames_as_datagrams() {
+        let device = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let mut transport =
+            UdpTransport::new(device.local_addr().unwrap(), Duration::from_secs(1)).unwrap();
+
+        transport.send_frame(true, 42, 0, 0, &[1, 2, 3]).unwrap();
+
+        let mut request = [0; 64];
+        let (len, host) = device.recv_from(&mut request).unwrap();
+        assert_eq!(
+            &request[..len],
+            &[0x0a, 0x00, 0x00, 0x03, 0x00, 0x00, 42, 0x00, 1, 2, 3]
+        );
+
+        device
+            .send_to(&[0x0b, 0x00, 0x00, 0x02, 0x00, 0x00, 42, 0x00, 4, 5], host)
+            .unwrap();
+
+        let mut buffer = Box::new([0; SMP_TRANSFER_BUFFER_SIZE]);
+        let response = transport
+            .receive_frame(&mut buffer, true, 42, 0, 0)
+            .unwrap();
+        assert_eq!(response, &[4, 5]);
+    }
+
+    #[test]
+    fn reports_timeouts() {
+        let device = UdpSocket::bind(("127.0.0.1", 0)).unwrap();
+        let mut transport =
+            UdpTransport::new(device.local_addr().unwrap(), Duration::from_millis(10)).unwrap();
+
+        let mut buffer = Box::new([0; SMP_TRANSFER_BUFFER_SIZE]);
+        let err = transport.recv_raw_frame(&mut buffer).unwrap_err();
+        assert!(
+            matches!(err, ReceiveError::TransportError(e) if e.kind() == io::ErrorKind::TimedOut)
+        );
+    }
+}
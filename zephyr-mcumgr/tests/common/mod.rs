@@ -46,6 +46,9 @@ impl Drop for LoopbackSerial {
 pub(crate) struct EchoSerial {
     input_buffer: VecDeque<u8>,
     output_buffer: VecDeque<u8>,
+    dropped_responses: usize,
+    smp_v1_only: bool,
+    corrupted_responses: usize,
 }
 
 const FRAME_START_1: u8 = 6;
@@ -55,6 +58,35 @@ const FRAME_START_CONT_2: u8 = 20;
 const FRAME_END: u8 = 0x0a;
 
 impl EchoSerial {
+    /// Creates an echo device that does not respond to the first `count` requests.
+    pub(crate) fn dropping_responses(count: usize) -> Self {
+        let mut serial = Self::default();
+        serial.dropped_responses = count;
+        serial
+    }
+
+    /// Creates an echo device that rejects all requests that are not SMP version 1,
+    /// like devices running Zephyr 3.4 or older.
+    pub(crate) fn smp_v1_only() -> Self {
+        let mut serial = Self::default();
+        serial.smp_v1_only = true;
+        serial
+    }
+
+    /// Creates an echo device that damages the first `count` responses in transit.
+    pub(crate) fn corrupting_responses(count: usize) -> Self {
+        let mut serial = Self::default();
+        serial.corrupted_responses = count;
+        serial
+    }
+
+    /// Creates an echo device that prints the given console output before its first response.
+    pub(crate) fn with_console_output(output: &[u8]) -> Self {
+        let mut serial = Self::default();
+        serial.output_buffer.extend(output);
+        serial
+    }
+
     fn process_input_data(&mut self) {
         let mut data = vec![];
 
@@ -70,7 +102,8 @@ impl EchoSerial {
                 data.push(next);
             }
 
-            if self.input_buffer.is_empty() {
+            // Multiple frames might be queued up
+            if self.input_buffer.is_empty() || self.input_buffer.front() == Some(&FRAME_START_1) {
                 break;
             }
 
@@ -78,7 +111,17 @@ impl EchoSerial {
             assert_eq!(Some(FRAME_START_CONT_2), self.input_buffer.pop_front());
         }
 
-        let data = self.process_message(&data);
+        let mut data = self.process_message(&data);
+
+        if self.dropped_responses > 0 {
+            self.dropped_responses -= 1;
+            return;
+        }
+
+        if self.corrupted_responses > 0 {
+            self.corrupted_responses -= 1;
+            data[4] = if data[4] == b'A' { b'B' } else { b'A' };
+        }
 
         self.output_buffer.push_back(FRAME_START_1);
         self.output_buffer.push_back(FRAME_START_2);
@@ -109,8 +152,13 @@ impl EchoSerial {
 
         let (header, data): (&[u8; 8], _) = data.split_first_chunk().unwrap();
 
+        let smp_version = (header[0] >> 3) & 0b11;
+
         let mut data: ciborium::Value = ciborium::from_reader(data).unwrap();
-        if let Some(data_map) = data.as_map_mut() {
+        if self.smp_v1_only && smp_version != 0 {
+            // MGMT_ERR_UNSUPPORTED_TOO_NEW
+            data = ciborium::Value::Map(vec![("rc".into(), 13.into())]);
+        } else if let Some(data_map) = data.as_map_mut() {
             for (key, value) in data_map {
                 if let Some(key) = key.as_text_mut() {
                     if key == "d" {
@@ -123,7 +171,12 @@ impl EchoSerial {
         let mut response_smp = vec![];
         response_smp.extend_from_slice(header);
         response_smp[0] |= 1;
+        if self.smp_v1_only {
+            response_smp[0] &= !(0b11 << 3);
+        }
         ciborium::into_writer(&data, &mut response_smp).unwrap();
+        let response_len = (response_smp.len() - header.len()) as u16;
+        response_smp[2..4].copy_from_slice(&response_len.to_be_bytes());
         let new_crc = crc_algo.checksum(&response_smp);
         response_smp.extend_from_slice(&new_crc.to_be_bytes());
 
@@ -141,10 +194,14 @@ impl EchoSerial {
 
 impl Read for EchoSerial {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        if !self.input_buffer.is_empty() {
+        while !self.input_buffer.is_empty() {
             self.process_input_data();
         }
 
+        if self.output_buffer.is_empty() {
+            return Err(std::io::ErrorKind::TimedOut.into());
+        }
+
         self.output_buffer.read(buf)
     }
 }
@@ -1,7 +1,7 @@
 mod common;
 use common::EchoSerial;
 use rand::prelude::*;
-use zephyr_mcumgr::MCUmgrClient;
+use zephyr_mcumgr::{MCUmgrClient, client::FsUploadOptions};
 
 #[test]
 fn echo() {
@@ -19,3 +19,323 @@ fn echo() {
     let response = client.os_echo(&request).unwrap();
     assert_eq!(request, response);
 }
+
+#[test]
+fn retry_on_timeout() {
+    use std::time::Duration;
+    use zephyr_mcumgr::connection::RetryPolicy;
+
+    let client = MCUmgrClient::new_from_serial(EchoSerial::dropping_responses(2));
+    assert!(client.os_echo("Hello world!").is_err());
+
+    let client = MCUmgrClient::new_from_serial(EchoSerial::dropping_responses(2));
+    client.set_retry_policy(RetryPolicy {
+        attempts: 3,
+        backoff: Duration::ZERO,
+    });
+    assert_eq!(client.os_echo("Hello world!").unwrap(), "Hello world!");
+
+    let stats = client.stats();
+    assert_eq!(stats.commands, 1);
+    assert_eq!(stats.errors, 0);
+    assert_eq!(stats.retransmits, 2);
+    assert_eq!(stats.responses, 1);
+    assert_eq!(stats.bytes_sent, 3 * stats.bytes_received);
+}
+
+#[test]
+fn resync_after_corrupted_response() {
+    use std::time::Duration;
+    use zephyr_mcumgr::connection::RetryPolicy;
+
+    let client = MCUmgrClient::new_from_serial(EchoSerial::corrupting_responses(1));
+    client.set_retry_policy(RetryPolicy {
+        attempts: 2,
+        backoff: Duration::ZERO,
+    });
+    let console = client.console_lines();
+    assert_eq!(client.os_echo("Hello world!").unwrap(), "Hello world!");
+
+    // The remains of the corrupted frame must not show up as console output
+    assert_eq!(console.try_iter().count(), 0);
+
+    let stats = client.stats();
+    assert_eq!(stats.corrupted_frames, 1);
+    assert_eq!(stats.retransmits, 1);
+    assert_eq!(stats.errors, 0);
+}
+
+#[test]
+fn connection_stats() {
+    let client = MCUmgrClient::new_from_serial(EchoSerial::dropping_responses(1));
+
+    assert!(client.os_echo("Hello world!").is_err());
+    client.os_echo("Hello world!").unwrap();
+
+    let stats = client.stats();
+    assert_eq!(stats.commands, 2);
+    assert_eq!(stats.errors, 1);
+    assert_eq!(stats.timeouts, 1);
+    assert_eq!(stats.responses, 1);
+    assert!(stats.bytes_sent > 0);
+    assert!(stats.bytes_received > 0);
+    assert!(stats.last_round_trip_time.is_some());
+    assert_eq!(stats.average_round_trip_time(), stats.last_round_trip_time);
+
+    client.reset_stats();
+    assert_eq!(client.stats(), Default::default());
+}
+
+#[test]
+fn falls_back_to_smp_v1() {
+    use std::sync::{Arc, Mutex};
+    use zephyr_mcumgr::connection::FrameDirection;
+
+    let client = MCUmgrClient::new_from_serial(EchoSerial::smp_v1_only());
+
+    let sent_versions = Arc::new(Mutex::new(vec![]));
+    client.set_frame_observer(Some(Box::new({
+        let sent_versions = Arc::clone(&sent_versions);
+        move |direction, header, _| {
+            if direction == FrameDirection::Sent {
+                sent_versions.lock().unwrap().push(header.ver);
+            }
+        }
+    })));
+
+    assert_eq!(client.os_echo("old").unwrap(), "old");
+    // Later requests are sent as V1 right away
+    assert_eq!(client.os_echo("again").unwrap(), "again");
+
+    assert_eq!(*sent_versions.lock().unwrap(), [0b01, 0b00, 0b00]);
+}
+
+#[test]
+fn console_lines() {
+    let client = MCUmgrClient::new_from_serial(EchoSerial::with_console_output(
+        b"*** Booting Zephyr OS ***\r\nHello from the device!\r\n",
+    ));
+
+    let console = client.console_lines();
+    assert_eq!(client.os_echo("Hello world!").unwrap(), "Hello world!");
+
+    assert_eq!(
+        console.try_iter().collect::<Vec<_>>(),
+        ["*** Booting Zephyr OS ***", "Hello from the device!"]
+    );
+}
+
+#[test]
+fn frame_observer() {
+    use std::sync::{Arc, Mutex};
+    use zephyr_mcumgr::connection::FrameDirection;
+
+    let client = MCUmgrClient::new_from_serial(EchoSerial::default());
+
+    let frames = Arc::new(Mutex::new(vec![]));
+    client.set_frame_observer(Some(Box::new({
+        let frames = Arc::clone(&frames);
+        move |direction, header, payload| {
+            frames
+                .lock()
+                .unwrap()
+                .push((direction, *header, payload.to_vec()));
+        }
+    })));
+
+    client.os_echo("Hello world!").unwrap();
+
+    let frames = frames.lock().unwrap();
+    assert_eq!(frames.len(), 2);
+
+    let (tx_direction, tx_header, tx_payload) = &frames[0];
+    let (rx_direction, rx_header, rx_payload) = &frames[1];
+    assert_eq!(*tx_direction, FrameDirection::Sent);
+    assert_eq!(*rx_direction, FrameDirection::Received);
+    assert_eq!((tx_header.op, rx_header.op), (0, 1));
+    assert_eq!(tx_header.group_id, 0);
+    assert_eq!(tx_header.command_id, 0);
+    assert_eq!(tx_header.sequence_num, rx_header.sequence_num);
+    assert_eq!(usize::from(tx_header.data_length), tx_payload.len());
+    assert_eq!(usize::from(rx_header.data_length), rx_payload.len());
+}
+
+#[test]
+fn multiple_frame_observers() {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    let client = MCUmgrClient::new_from_serial(EchoSerial::default());
+
+    let counter = |count: &Arc<AtomicUsize>| -> zephyr_mcumgr::connection::FrameObserver {
+        let count = Arc::clone(count);
+        Box::new(move |_, _, _| {
+            count.fetch_add(1, Ordering::SeqCst);
+        })
+    };
+
+    let first = Arc::new(AtomicUsize::new(0));
+    let second = Arc::new(AtomicUsize::new(0));
+    let first_id = client.add_frame_observer(counter(&first));
+    client.add_frame_observer(counter(&second));
+
+    client.os_echo("Hello world!").unwrap();
+    assert_eq!(first.load(Ordering::SeqCst), 2);
+    assert_eq!(second.load(Ordering::SeqCst), 2);
+
+    assert!(client.remove_frame_observer(first_id));
+    assert!(!client.remove_frame_observer(first_id));
+
+    client.os_echo("Hello world!").unwrap();
+    assert_eq!(first.load(Ordering::SeqCst), 2);
+    assert_eq!(second.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn windowed_file_upload() {
+    use std::sync::{Arc, Mutex};
+    use zephyr_mcumgr::connection::FrameDirection;
+
+    let client = MCUmgrClient::new_from_serial(EchoSerial::default());
+    client.set_window_size(Some(3));
+
+    let directions = Arc::new(Mutex::new(vec![]));
+    client.set_frame_observer(Some(Box::new({
+        let directions = Arc::clone(&directions);
+        move |direction, _, _| directions.lock().unwrap().push(direction)
+    })));
+
+    let data = vec![42u8; 2000];
+    let mut reports = vec![];
+    client
+        .fs_file_upload(
+            "/lfs/test.bin",
+            data.as_slice(),
+            data.len() as u64,
+            &FsUploadOptions::default(),
+            Some(&mut |current, _| {
+                reports.push(current);
+                true
+            }),
+        )
+        .unwrap();
+
+    assert_eq!(reports.last(), Some(&(data.len() as u64)));
+    assert!(reports.is_sorted());
+
+    let directions = directions.lock().unwrap();
+    assert_eq!(
+        &directions[..4],
+        &[
+            FrameDirection::Sent,
+            FrameDirection::Sent,
+            FrameDirection::Sent,
+            FrameDirection::Received
+        ]
+    );
+
+    let mut in_flight = 0;
+    for direction in directions.iter() {
+        match direction {
+            FrameDirection::Sent => in_flight += 1,
+            FrameDirection::Received => in_flight -= 1,
+        }
+        assert!(in_flight <= 3);
+    }
+    assert_eq!(in_flight, 0);
+}
+
+#[test]
+fn aligned_upload_chunks() {
+    let client = MCUmgrClient::new_from_serial(EchoSerial::default());
+    client.set_chunk_alignment(Some(64));
+
+    let data = vec![42u8; 1000];
+    let mut reports = vec![];
+    client
+        .fs_file_upload(
+            "/lfs/test.bin",
+            data.as_slice(),
+            data.len() as u64,
+            &FsUploadOptions::default(),
+            Some(&mut |current, _| {
+                reports.push(current);
+                true
+            }),
+        )
+        .unwrap();
+
+    let (last, chunk_ends) = reports.split_last().unwrap();
+    assert_eq!(*last, data.len() as u64);
+    assert!(!chunk_ends.is_empty());
+    assert!(chunk_ends.iter().all(|offset| offset % 64 == 0));
+
+    client.set_chunk_alignment(Some(4096));
+    let result = client.fs_file_upload(
+        "/lfs/test.bin",
+        data.as_slice(),
+        data.len() as u64,
+        &FsUploadOptions::default(),
+        None,
+    );
+    assert!(matches!(
+        result,
+        Err(zephyr_mcumgr::client::FileUploadError::FrameSizeTooSmall(_))
+    ));
+}
+
+#[test]
+fn custom_transport() {
+    use std::{collections::VecDeque, time::Duration};
+    use zephyr_mcumgr::transport::{
+        ReceiveError, SMP_HEADER_SIZE, SMP_TRANSFER_BUFFER_SIZE, SendError, Transport,
+    };
+
+    /// Answers echo requests without any framing.
+    #[derive(Default)]
+    struct EchoTransport {
+        responses: VecDeque<Vec<u8>>,
+    }
+
+    impl Transport for EchoTransport {
+        fn send_raw_frame(
+            &mut self,
+            mut header: [u8; SMP_HEADER_SIZE],
+            data: &[u8],
+        ) -> Result<(), SendError> {
+            let mut data: ciborium::Value = ciborium::from_reader(data).unwrap();
+            for (key, _) in data.as_map_mut().unwrap() {
+                *key = ciborium::Value::Text("r".into());
+            }
+
+            // Turn the request into a response
+            header[0] |= 1;
+            let mut response = header.to_vec();
+            ciborium::into_writer(&data, &mut response).unwrap();
+            self.responses.push_back(response);
+            Ok(())
+        }
+
+        fn recv_raw_frame<'a>(
+            &mut self,
+            buffer: &'a mut [u8; SMP_TRANSFER_BUFFER_SIZE],
+        ) -> Result<&'a [u8], ReceiveError> {
+            let response = self
+                .responses
+                .pop_front()
+                .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::TimedOut))?;
+            let frame = &mut buffer[..response.len()];
+            frame.copy_from_slice(&response);
+            Ok(frame)
+        }
+
+        fn set_timeout(&mut self, _: Duration) -> Result<(), miette::Report> {
+            Ok(())
+        }
+    }
+
+    let client = MCUmgrClient::new_from_transport(EchoTransport::default());
+    assert_eq!(client.os_echo("Hello world!").unwrap(), "Hello world!");
+}
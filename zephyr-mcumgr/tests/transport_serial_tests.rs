@@ -59,3 +59,179 @@ fn test_chunking_upper_limit() {
         data.len()
     );
 }
+
+#[test]
+fn test_interleaved_console_output() {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+
+    let mut serial = LoopbackSerial::default();
+    serial
+        .write_all(b"*** Booting Zephyr OS ***\r\n\r\n[00:00:00.001] <inf> main: ready\r\n")
+        .unwrap();
+
+    let lines = Arc::new(Mutex::new(vec![]));
+    let mut transport = SerialTransport::new(serial).with_console_handler(Box::new({
+        let lines = Arc::clone(&lines);
+        move |line| lines.lock().unwrap().push(line.to_string())
+    }));
+
+    let header = [1, 2, 3, 4, 5, 6, 7, 8];
+    let data = [42u8; 200];
+    transport.send_raw_frame(header, &data).unwrap();
+
+    let mut recv_buffer = [0u8; u16::MAX as usize];
+    let data_received = transport.recv_raw_frame(&mut recv_buffer).unwrap();
+    assert_eq!(header, &data_received[..8]);
+    assert_eq!(data, &data_received[8..]);
+
+    assert_eq!(
+        *lines.lock().unwrap(),
+        [
+            "*** Booting Zephyr OS ***",
+            "[00:00:00.001] <inf> main: ready"
+        ]
+    );
+}
+
+#[test]
+fn test_console_line_length_is_capped() {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+    use zephyr_mcumgr::transport::serial::MAX_CONSOLE_LINE_LENGTH;
+
+    let mut serial = LoopbackSerial::default();
+    serial
+        .write_all(&vec![b'x'; MAX_CONSOLE_LINE_LENGTH + 10])
+        .unwrap();
+    serial.write_all(b"\r\n").unwrap();
+
+    let lines = Arc::new(Mutex::new(vec![]));
+    let mut transport = SerialTransport::new(serial).with_console_handler(Box::new({
+        let lines = Arc::clone(&lines);
+        move |line| lines.lock().unwrap().push(line.to_string())
+    }));
+
+    let header = [1, 2, 3, 4, 5, 6, 7, 8];
+    transport.send_raw_frame(header, &[42u8; 10]).unwrap();
+
+    let mut recv_buffer = [0u8; u16::MAX as usize];
+    transport.recv_raw_frame(&mut recv_buffer).unwrap();
+
+    assert_eq!(
+        *lines.lock().unwrap(),
+        ["x".repeat(MAX_CONSOLE_LINE_LENGTH), "x".repeat(10)]
+    );
+}
+
+#[test]
+fn test_console_output_after_corrupted_frame() {
+    use std::{
+        io::Write,
+        sync::{Arc, Mutex},
+    };
+    use zephyr_mcumgr::transport::ReceiveError;
+
+    let mut serial = LoopbackSerial::default();
+    // A packet that is not valid base64, followed by console output
+    serial.write_all(b"\x06\x09!!!!\n").unwrap();
+    serial
+        .write_all(b"<err> main: after the bad frame\r\n")
+        .unwrap();
+    // A stray continuation packet of the corrupted frame
+    serial.write_all(b"\x04\x14AAAA\n").unwrap();
+    serial.write_all(b"<inf> main: still there\r\n").unwrap();
+
+    let lines = Arc::new(Mutex::new(vec![]));
+    let mut transport = SerialTransport::new(serial).with_console_handler(Box::new({
+        let lines = Arc::clone(&lines);
+        move |line| lines.lock().unwrap().push(line.to_string())
+    }));
+
+    let mut recv_buffer = [0u8; u16::MAX as usize];
+    assert!(matches!(
+        transport.recv_raw_frame(&mut recv_buffer),
+        Err(ReceiveError::CorruptedFrame)
+    ));
+
+    let header = [1, 2, 3, 4, 5, 6, 7, 8];
+    transport.send_raw_frame(header, &[42u8; 10]).unwrap();
+    let data_received = transport.recv_raw_frame(&mut recv_buffer).unwrap();
+    assert_eq!(header, &data_received[..8]);
+
+    assert_eq!(
+        *lines.lock().unwrap(),
+        ["<err> main: after the bad frame", "<inf> main: still there"]
+    );
+}
+
+#[test]
+fn test_paced_writes() {
+    use std::{
+        io::{Read, Write},
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+    use zephyr_mcumgr::transport::serial::{ConfigurableTimeout, SerialPacing};
+
+    /// Records the time and size of every write.
+    #[derive(Default)]
+    struct RecordingSerial {
+        loopback: LoopbackSerial,
+        writes: Arc<Mutex<Vec<(Instant, usize)>>>,
+    }
+
+    impl Read for RecordingSerial {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.loopback.read(buf)
+        }
+    }
+
+    impl Write for RecordingSerial {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.writes
+                .lock()
+                .unwrap()
+                .push((Instant::now(), buf.len()));
+            self.loopback.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            self.loopback.flush()
+        }
+    }
+
+    impl ConfigurableTimeout for RecordingSerial {
+        fn set_timeout(&mut self, _: Duration) -> Result<(), miette::Report> {
+            Ok(())
+        }
+    }
+
+    let serial = RecordingSerial::default();
+    let writes = Arc::clone(&serial.writes);
+    let mut transport = SerialTransport::new(serial).with_pacing(SerialPacing {
+        inter_frame_delay: Duration::from_millis(2),
+        max_write_burst: Some(64),
+    });
+
+    let header = [1, 2, 3, 4, 5, 6, 7, 8];
+    let data = [42u8; 300];
+    transport.send_raw_frame(header, &data).unwrap();
+
+    let mut recv_buffer = [0u8; u16::MAX as usize];
+    let data_received = transport.recv_raw_frame(&mut recv_buffer).unwrap();
+    assert_eq!(data, &data_received[8..]);
+
+    let writes = writes.lock().unwrap();
+    assert!(writes.len() > 1);
+    assert!(writes.iter().all(|(_, len)| *len <= 64));
+    assert!(
+        writes
+            .windows(2)
+            .all(|w| w[1].0.duration_since(w[0].0) >= Duration::from_millis(2))
+    );
+}